@@ -1,9 +1,12 @@
 use core::{
     context::RContextRef,
-    event::{EventProcessor, EventSender, EventSource},
+    event::{
+        record::{InputPlayer, InputRecorder},
+        EventProcessor, EventSender, EventSource,
+    },
     scene::Scene,
 };
-use std::{any::Any, cell::RefCell, rc::Rc, sync::Arc};
+use std::{any::Any, cell::RefCell, path::PathBuf, rc::Rc, sync::Arc};
 
 use container::Container;
 use plugin::{Plugin, PluginFactory};
@@ -27,6 +30,8 @@ pub struct App {
     container: Arc<Container>,
     plugin_factory_list: Vec<Box<dyn PluginFactory>>,
     processors: Rc<RefCell<Vec<Box<dyn AppEventProcessor>>>>,
+    record_path: Option<PathBuf>,
+    player: RefCell<Option<InputPlayer>>,
 }
 
 impl App {
@@ -36,6 +41,8 @@ impl App {
             container: Arc::new(Container::default()),
             plugin_factory_list: vec![],
             processors: Rc::new(RefCell::new(vec![])),
+            record_path: None,
+            player: RefCell::new(None),
         }
     }
 
@@ -51,6 +58,16 @@ impl App {
         self.processors.borrow_mut().push(ep);
     }
 
+    // the input and resizes of the session are written to `path` at exit
+    pub fn record_input<P: Into<PathBuf>>(&mut self, path: P) {
+        self.record_path = Some(path.into());
+    }
+
+    // feeds a recording back in, the app exits at the end of it
+    pub fn replay_input(&mut self, player: InputPlayer) {
+        *self.player.borrow_mut() = Some(player);
+    }
+
     pub fn run(&self) {
         log::info!("App startup");
         self.container.register_arc(self.context.clone());
@@ -80,6 +97,9 @@ impl App {
         for p in &mut plugins {
             let core_factory_list = p.load_factory();
             factory_list.materials.extend(core_factory_list.materials);
+            factory_list
+                .camera_controllers
+                .extend(core_factory_list.camera_controllers);
         }
 
         for p in &mut plugins {
@@ -91,6 +111,8 @@ impl App {
             processors: Rc<RefCell<Vec<Box<dyn AppEventProcessor>>>>,
             plugins: Vec<Box<dyn Plugin>>,
             container: Arc<Container>,
+            recorder: Option<InputRecorder>,
+            player: Option<InputPlayer>,
         }
 
         impl Runner for ARunner {
@@ -99,31 +121,62 @@ impl App {
             }
         }
 
-        impl EventProcessor for ARunner {
-            fn on_event(
-                &mut self,
-                source: &dyn core::event::EventSource,
-                event: &dyn Any,
-            ) -> core::event::ProcessEventResult {
+        impl ARunner {
+            fn dispatch(&mut self, source: &dyn core::event::EventSource, event: &dyn Any) {
                 let context = &AppEventContext {
                     source,
                     container: &self.container,
                 };
-
                 for p in self.plugins.iter_mut() {
                     p.on_event(context, event);
                 }
                 for p in self.processors.borrow_mut().iter_mut() {
                     p.on_event(context, event);
                 }
+                if let Some(core::event::Event::Resized { logical, physical }) =
+                    event.downcast_ref::<core::event::Event>()
+                {
+                    context
+                        .container
+                        .get::<Scene>()
+                        .unwrap()
+                        .resize(logical, physical);
+                }
+            }
+        }
 
+        impl EventProcessor for ARunner {
+            fn on_event(
+                &mut self,
+                source: &dyn core::event::EventSource,
+                event: &dyn Any,
+            ) -> core::event::ProcessEventResult {
+                let mut replayed = None;
                 if let Some(ev) = event.downcast_ref::<core::event::Event>() {
-                    if let core::event::Event::Resized { logical, physical } = ev {
-                        context
-                            .container
-                            .get::<Scene>()
-                            .unwrap()
-                            .resize(logical, physical);
+                    if let Some(recorder) = &mut self.recorder {
+                        recorder.on_event(ev);
+                    }
+                    if let Some(player) = &mut self.player {
+                        replayed = player.on_event(ev);
+                    }
+                }
+
+                match replayed {
+                    Some(events) => {
+                        for e in &events {
+                            self.dispatch(source, e);
+                        }
+                    }
+                    None => self.dispatch(source, event),
+                }
+
+                // the events of the last update are drawn in this frame
+                if let Some(core::event::Event::PostRender) =
+                    event.downcast_ref::<core::event::Event>()
+                {
+                    if self.player.as_ref().is_some_and(|v| v.is_finished()) {
+                        log::info!("input replay finished");
+                        return core::event::ProcessEventResult::ExitLoop;
                     }
                 }
 
@@ -131,14 +184,28 @@ impl App {
             }
         }
 
-        looper.unwrap().run(
-            &self.container,
-            Rc::new(RefCell::new(ARunner {
-                processors: self.processors.clone(),
-                plugins,
-                container: self.container.clone(),
-            })),
-        );
+        let runner = Rc::new(RefCell::new(ARunner {
+            processors: self.processors.clone(),
+            plugins,
+            container: self.container.clone(),
+            recorder: self.record_path.as_ref().map(|_| InputRecorder::new()),
+            player: self.player.borrow_mut().take(),
+        }));
+        looper.unwrap().run(&self.container, runner.clone());
+
+        if let (Some(path), Some(recorder)) =
+            (&self.record_path, runner.borrow_mut().recorder.take())
+        {
+            let recording = recorder.finish();
+            match recording.save_to_file(path) {
+                Ok(_) => log::info!(
+                    "input of {} frames recorded to {:?}",
+                    recording.frame_count,
+                    path
+                ),
+                Err(e) => log::error!("save input recording fail {}", e),
+            }
+        }
 
         log::info!("App exit");
     }
@@ -20,6 +20,7 @@ pub enum ResourceTy {
     Texture((wgpu::Texture, wgpu::TextureView)),
     SurfaceTexture((Arc<wgpu::SurfaceTexture>, wgpu::TextureView)),
     Sampler(wgpu::Sampler),
+    Buffer(Arc<wgpu::Buffer>),
 }
 
 #[derive(Debug)]
@@ -74,6 +75,12 @@ impl Resource {
             _ => panic!("resource type invalid"),
         }
     }
+    pub fn buffer(&self) -> &wgpu::Buffer {
+        match &self.ty {
+            ResourceTy::Buffer(b) => b,
+            _ => panic!("resource type invalid"),
+        }
+    }
 
     pub fn ty(&self) -> &ResourceTy {
         &self.ty
@@ -91,6 +98,8 @@ pub struct RContext {
     tags: Mutex<StringIdAllocMap<TagId>>,
 
     res_map: DashMap<u64, Arc<Resource>>,
+    // path or asset id of a resource, saved scenes refer to resources by it
+    res_sources: DashMap<u64, String>,
 }
 
 impl std::fmt::Debug for RContext {
@@ -112,6 +121,7 @@ impl RContext {
             last_material_id: AtomicU64::new(1),
             last_camera_id: AtomicU64::new(1),
             res_map: DashMap::default(),
+            res_sources: DashMap::default(),
             tags: Mutex::new(StringIdAllocMap::new_with_begin(1)),
         })
     }
@@ -131,6 +141,11 @@ impl RContext {
         tags.alloc_or_get(name)
     }
 
+    pub fn tag_name(&self, id: TagId) -> Option<String> {
+        let mut tags = self.tags.lock().unwrap();
+        tags.get(id).map(|v| v.to_owned())
+    }
+
     pub fn delete_tag(&self, id: TagId) {
         let mut tags = self.tags.lock().unwrap();
         tags.dealloc(id);
@@ -158,6 +173,13 @@ impl RContext {
         res
     }
 
+    pub fn register_buffer(&self, buffer: Arc<wgpu::Buffer>) -> ResourceRef {
+        let id = self.last_res_id.fetch_add(1, Ordering::SeqCst);
+        let res = Arc::new(Resource::new(ResourceTy::Buffer(buffer), id));
+        self.res_map.insert(id, res.clone());
+        res
+    }
+
     pub fn register_surface_texture(&self, texture: Arc<wgpu::SurfaceTexture>) -> ResourceRef {
         let id = self.last_res_id.fetch_add(1, Ordering::SeqCst);
         let view = texture
@@ -172,11 +194,20 @@ impl RContext {
         res
     }
 
+    pub fn set_resource_source(&self, res: &ResourceRef, source: &str) {
+        self.res_sources.insert(res.id(), source.to_owned());
+    }
+
+    pub fn resource_source(&self, id: u64) -> Option<String> {
+        self.res_sources.get(&id).map(|v| v.clone())
+    }
+
     pub fn deregister_by_id(&self, id: u64) {
         self.res_map.remove(&id);
+        self.res_sources.remove(&id);
     }
     pub fn deregister(&self, res: ResourceRef) {
-        self.res_map.remove(&res.id());
+        self.deregister_by_id(res.id());
     }
 }
 
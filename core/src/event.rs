@@ -3,11 +3,14 @@ use std::fmt::Debug;
 use std::sync::Arc;
 
 use raw_window_handle::RawWindowHandle;
+use serde::{Deserialize, Serialize};
 
 use crate::backends::wgpu_backend::WGPUResource;
 use crate::context::ResourceRef;
 use crate::types::*;
 
+pub mod record;
+
 pub trait EventSender: Send {
     fn send_event(&self, ev: Box<dyn Any + Send>);
 }
@@ -20,8 +23,7 @@ pub enum Modifiers {
     Shift,
 }
 
-#[derive(Debug, Hash, PartialEq, Eq, Clone, Copy)]
-#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Hash, PartialEq, Eq, Clone, Copy, Serialize, Deserialize)]
 pub enum ElementState {
     Pressed,
     Released,
@@ -36,7 +38,7 @@ impl ElementState {
     }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ModifiersState {
     pub ctrl: bool,
     pub win: bool,
@@ -51,7 +53,7 @@ pub struct MouseState {
     pub middle: bool,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Hash, PartialEq, Eq, Clone, Copy, Serialize, Deserialize)]
 pub enum MouseButton {
     Left,
     Right,
@@ -88,13 +90,13 @@ pub enum CustomEvent {
     CanvasResize(Size),
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct KeyboardInput {
     pub state: ElementState,
     pub vk: VirtualKeyCode,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum InputEvent {
     KeyboardInput(KeyboardInput),
     ModifiersChanged(ModifiersState),
@@ -196,9 +198,8 @@ pub trait EventProcessor {
     fn init(&mut self, source: &dyn EventSource) {}
 }
 
-#[derive(Debug, Hash, Ord, PartialOrd, PartialEq, Eq, Clone, Copy)]
+#[derive(Debug, Hash, Ord, PartialOrd, PartialEq, Eq, Clone, Copy, Serialize, Deserialize)]
 #[repr(u32)]
-#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum VirtualKeyCode {
     /// The '1' key over the letters.
     Key1,
@@ -0,0 +1,350 @@
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use crate::types::Size;
+
+use super::{Event, InputEvent};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum RecordedEvent {
+    Input(InputEvent),
+    Resized { logical: Size, physical: Size },
+}
+
+impl RecordedEvent {
+    pub fn from_event(event: &Event) -> Option<Self> {
+        match event {
+            Event::Input(input) => Some(Self::Input(input.clone())),
+            Event::Resized { logical, physical } => Some(Self::Resized {
+                logical: *logical,
+                physical: *physical,
+            }),
+            _ => None,
+        }
+    }
+
+    pub fn to_event(&self) -> Event {
+        match self {
+            Self::Input(input) => Event::Input(input.clone()),
+            Self::Resized { logical, physical } => Event::Resized {
+                logical: *logical,
+                physical: *physical,
+            },
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RecordedFrame {
+    // updates seen before the events arrived
+    pub frame: u64,
+    // events with the seconds of frame time since the start of the recording
+    pub events: Vec<(f64, RecordedEvent)>,
+}
+
+// the input of a session split by frame, stored as ron like the scene files
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct InputRecording {
+    pub frames: Vec<RecordedFrame>,
+    // updates of the session, the replay lasts as long
+    #[serde(default)]
+    pub frame_count: u64,
+    // seconds of every update, the replay runs with them
+    #[serde(default)]
+    pub deltas: Vec<f64>,
+}
+
+impl InputRecording {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // frames are pushed in order
+    pub fn push(&mut self, frame: u64, time: f64, event: RecordedEvent) {
+        match self.frames.last_mut() {
+            Some(last) if last.frame == frame => last.events.push((time, event)),
+            _ => self.frames.push(RecordedFrame {
+                frame,
+                events: vec![(time, event)],
+            }),
+        }
+        self.frame_count = self.frame_count.max(frame);
+    }
+
+    pub fn event_count(&self) -> usize {
+        self.frames.iter().map(|v| v.events.len()).sum()
+    }
+
+    pub fn parse(s: &str) -> anyhow::Result<Self> {
+        ron::from_str(s).map_err(|e| anyhow::anyhow!("invalid input recording: {}", e))
+    }
+
+    pub fn save(&self) -> anyhow::Result<String> {
+        Ok(ron::ser::to_string_pretty(
+            self,
+            ron::ser::PrettyConfig::default(),
+        )?)
+    }
+
+    pub fn load_from_file<P: AsRef<Path>>(path: P) -> anyhow::Result<Self> {
+        let s = std::fs::read_to_string(path)?;
+        Self::parse(&s)
+    }
+
+    pub fn save_to_file<P: AsRef<Path>>(&self, path: P) -> anyhow::Result<()> {
+        let s = self.save()?;
+        std::fs::write(path, s)?;
+        Ok(())
+    }
+}
+
+// feed it every event of the session
+#[derive(Debug, Default)]
+pub struct InputRecorder {
+    recording: InputRecording,
+    frame: u64,
+    time: f64,
+}
+
+impl InputRecorder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn on_event(&mut self, event: &Event) {
+        if let Event::Update(delta) = event {
+            self.frame += 1;
+            self.time += *delta;
+            self.recording.frame_count = self.frame;
+            self.recording.deltas.push(*delta);
+            return;
+        }
+        if let Some(e) = RecordedEvent::from_event(event) {
+            self.recording.push(self.frame, self.time, e);
+        }
+    }
+
+    pub fn recording(&self) -> &InputRecording {
+        &self.recording
+    }
+
+    pub fn finish(self) -> InputRecording {
+        self.recording
+    }
+}
+
+// plays the events of a recording before the update they were recorded before,
+// so they reach the processors in the same frame order. updates take the
+// recorded frame time and live input is dropped until the end
+#[derive(Debug)]
+pub struct InputPlayer {
+    recording: InputRecording,
+    frame: u64,
+    // next frame of the recording to send
+    next: usize,
+}
+
+impl InputPlayer {
+    pub fn new(recording: InputRecording) -> Self {
+        Self {
+            recording,
+            frame: 0,
+            next: 0,
+        }
+    }
+
+    pub fn load_from_file<P: AsRef<Path>>(path: P) -> anyhow::Result<Self> {
+        Ok(Self::new(InputRecording::load_from_file(path)?))
+    }
+
+    // feed it every event, the returned events go to the processors in place
+    // of `event`. None passes `event` on unchanged
+    pub fn on_event(&mut self, event: &Event) -> Option<Vec<Event>> {
+        // seconds of update `frame`, live for recordings without them
+        let delta = |frame: u64, live: f64| {
+            frame
+                .checked_sub(1)
+                .and_then(|v| self.recording.deltas.get(v as usize))
+                .copied()
+                .unwrap_or(live)
+        };
+        match event {
+            // the last update is played
+            Event::PostUpdate(live) => Some(vec![Event::PostUpdate(delta(self.frame, *live))]),
+            _ if self.is_finished() => None,
+            Event::Input(_) | Event::Resized { .. } => Some(vec![]),
+            Event::PreUpdate(live) => Some(vec![Event::PreUpdate(delta(self.frame + 1, *live))]),
+            Event::Update(live) => {
+                let mut res = vec![];
+                // events after the last update, before this one
+                while let Some(f) = self.recording.frames.get(self.next) {
+                    if f.frame > self.frame {
+                        break;
+                    }
+                    res.extend(f.events.iter().map(|(_, e)| e.to_event()));
+                    self.next += 1;
+                }
+                self.frame += 1;
+                res.push(Event::Update(delta(self.frame, *live)));
+                Some(res)
+            }
+            _ => None,
+        }
+    }
+
+    // plays the recording without a window, `f` gets the input and updates in
+    // the order of the session
+    pub fn replay<F: FnMut(&Event)>(&mut self, mut f: F) {
+        self.restart();
+        while !self.is_finished() {
+            for e in self.on_event(&Event::Update(0f64)).unwrap_or_default() {
+                f(&e);
+            }
+        }
+    }
+
+    pub fn frame(&self) -> u64 {
+        self.frame
+    }
+
+    // every update of the session is played, input after the last update
+    // was never seen by an update
+    pub fn is_finished(&self) -> bool {
+        self.frame >= self.recording.frame_count
+    }
+
+    pub fn restart(&mut self) {
+        self.frame = 0;
+        self.next = 0;
+    }
+
+    pub fn recording(&self) -> &InputRecording {
+        &self.recording
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use crate::{
+        event::{ElementState, KeyboardInput, VirtualKeyCode},
+        scene::{
+            controller::{
+                fly::{FlyCameraController, FlyConfig},
+                CameraController,
+            },
+            Camera,
+        },
+        types::{Vec2f, Vec3f},
+    };
+
+    use super::*;
+
+    fn key(vk: VirtualKeyCode, pressed: bool) -> Event {
+        Event::Input(InputEvent::KeyboardInput(KeyboardInput {
+            state: if pressed {
+                ElementState::Pressed
+            } else {
+                ElementState::Released
+            },
+            vk,
+        }))
+    }
+
+    fn cursor(x: f32, y: f32) -> Event {
+        Event::Input(InputEvent::CursorMoved {
+            logical: Vec2f::new(x, y),
+            physical: Vec2f::new(x, y),
+        })
+    }
+
+    fn camera() -> Arc<Camera> {
+        let camera = Arc::new(Camera::new());
+        camera.look_at(Vec3f::new(0f32, 0f32, 5f32), Vec3f::zeros(), Vec3f::y());
+        camera
+    }
+
+    // what the app does with the events of a frame
+    fn drive(controller: &mut FlyCameraController, event: &Event) {
+        match event {
+            Event::Input(input) => controller.on_input(input),
+            Event::Update(delta) => controller.on_update(*delta as f32),
+            _ => (),
+        }
+    }
+
+    // a session with uneven frame times, input arrives between updates
+    fn session() -> (InputRecording, Arc<Camera>) {
+        let camera = camera();
+        let mut controller = FlyCameraController::new(camera.clone(), FlyConfig::default());
+        let mut recorder = InputRecorder::new();
+        let mut frames: Vec<Vec<Event>> = vec![
+            vec![key(VirtualKeyCode::W, true), cursor(10f32, 10f32)],
+            vec![],
+            vec![key(VirtualKeyCode::D, true)],
+            vec![cursor(30f32, 5f32)],
+            vec![key(VirtualKeyCode::W, false)],
+            vec![],
+            vec![key(VirtualKeyCode::D, false)],
+        ];
+        let deltas = [0.016f64, 0.05, 0.008, 0.033, 0.021, 0.1, 0.016];
+        for (events, delta) in frames.drain(..).zip(deltas) {
+            for e in events.iter().chain([&Event::Update(delta)]) {
+                recorder.on_event(e);
+                drive(&mut controller, e);
+            }
+        }
+        (recorder.finish(), camera)
+    }
+
+    #[test]
+    fn replay_reproduces_camera() {
+        let (recording, expected) = session();
+        assert_eq!(recording.frame_count, 7);
+        assert_eq!(recording.event_count(), 6);
+        // the camera moved
+        assert_ne!(expected.from(), camera().from());
+
+        let recording = InputRecording::parse(&recording.save().unwrap()).unwrap();
+        let camera = camera();
+        let mut controller = FlyCameraController::new(camera.clone(), FlyConfig::default());
+        let mut player = InputPlayer::new(recording);
+        player.replay(|e| drive(&mut controller, e));
+        assert!(player.is_finished());
+        assert_eq!(camera.from(), expected.from());
+        assert_eq!(camera.to(), expected.to());
+    }
+
+    #[test]
+    fn player_retimes_updates_and_blocks_input() {
+        let (recording, _) = session();
+        let mut player = InputPlayer::new(recording);
+
+        let events = player.on_event(&Event::PreUpdate(1f64)).unwrap();
+        assert!(matches!(events[..], [Event::PreUpdate(d)] if d == 0.016f64));
+        assert_eq!(
+            player
+                .on_event(&key(VirtualKeyCode::S, true))
+                .unwrap()
+                .len(),
+            0
+        );
+        // the recorded input goes before the update
+        let events = player.on_event(&Event::Update(1f64)).unwrap();
+        assert_eq!(events.len(), 3);
+        assert!(matches!(events[0], Event::Input(_)));
+        assert!(matches!(events[2], Event::Update(d) if d == 0.016f64));
+        assert!(player.on_event(&Event::PreRender).is_none());
+
+        let events = player.on_event(&Event::Update(1f64)).unwrap();
+        assert!(matches!(events[..], [Event::Update(d)] if d == 0.05f64));
+
+        // live input passes once the recording is played
+        while !player.is_finished() {
+            player.on_event(&Event::Update(1f64));
+        }
+        assert!(player.on_event(&key(VirtualKeyCode::S, true)).is_none());
+    }
+}
@@ -1,18 +1,22 @@
 pub mod backend;
+pub mod inspect;
 pub mod pass;
+pub mod pool;
 pub mod present;
 pub mod resource;
+pub use pass::ComputePassBuilder;
 pub use pass::RenderPass;
 pub use pass::RenderPassBuilder;
 use petgraph::dot::Config;
 use petgraph::dot::Dot;
 use petgraph::unionfind::UnionFind;
+use petgraph::visit::EdgeRef;
 use resource::{BufferInfo, ResourceId, ResourceType, ResourceUsage, TextureInfo};
 
 use std::any::Any;
 use std::fmt::Debug;
 use std::{
-    collections::{HashMap, HashSet},
+    collections::{BTreeMap, HashMap, HashSet},
     sync::Arc,
 };
 
@@ -25,17 +29,20 @@ use crate::graph::rdg::pass::ClearPassBuilder;
 use crate::graph::rdg::pass::ColorRenderTargetDescriptor;
 use crate::graph::rdg::pass::PreferAttachment;
 use crate::graph::rdg::pass::RenderTargetDescriptor;
+use crate::scene::DepthConvention;
 use crate::types::{Color, Size, Vec3u};
 
+use self::inspect::GraphCapture;
 use self::pass::DynPass;
 use self::pass::PassConstraint;
 use self::pass::RenderTargetState;
+use self::pool::{PoolKey, ResourcePool, ResourcePoolStats};
 use self::resource::ResourceNode;
 use self::resource::RT_RESOLVE_COLOR_RESOURCE_ID;
 use self::{
     backend::GraphBackend,
     present::PresentNode,
-    resource::{ImportTextureInfo, RT_COLOR_RESOURCE_ID, RT_DEPTH_RESOURCE_ID},
+    resource::{ImportBufferInfo, ImportTextureInfo, RT_COLOR_RESOURCE_ID, RT_DEPTH_RESOURCE_ID},
 };
 
 type Graph = petgraph::graph::DiGraph<Node, ResourceUsage, u32>;
@@ -62,6 +69,19 @@ impl ResourceRegistry {
     }
 
     pub fn import(&mut self, id: ResourceId, res: ResourceRef) {
+        if let Some(ResourceType::ImportBuffer(info)) = self.desc_map.get(&id).map(|v| &v.inner) {
+            let buffer = res.buffer();
+            if buffer.size() < info.size || !buffer.usage().contains(info.usage) {
+                log::warn!(
+                    "imported buffer {} size {} usage {:?}, expect size {} usage {:?}",
+                    id,
+                    buffer.size(),
+                    buffer.usage(),
+                    info.size,
+                    info.usage
+                );
+            }
+        }
         self.underlying_map.insert(id, res);
     }
 }
@@ -100,6 +120,9 @@ pub struct RenderGraph {
     render_jobs_list: Vec<DependencyRenderJobs>,
 
     registry: ResourceRegistry,
+    pool: ResourcePool,
+    lifetimes: HashMap<ResourceId, ResourceLifetime>,
+    capture: GraphCapture,
 }
 
 impl RenderGraph {
@@ -112,13 +135,22 @@ impl RenderGraph {
                     RenderJob::ResourceOperation(op) => match op {
                         ResourceLifetimeOperation::Create(id) => {
                             let res_desc = self.registry.desc_map.get(id).unwrap();
-                            let underlying = backend.create_resource(&res_desc.inner);
+                            let underlying = match PoolKey::new(&res_desc.inner) {
+                                Some(key) => self.pool.acquire(&key, &res_desc.inner, &backend),
+                                None => backend.create_resource(&res_desc.inner),
+                            };
                             self.registry.underlying_map.insert(*id, underlying);
                         }
                         ResourceLifetimeOperation::Destroy(id) => {
-                            let res = self.registry.underlying_map.get(id).unwrap();
-                            backend.remove_resource(res.clone());
-                            self.registry.underlying_map.remove(id);
+                            let res_desc = self.registry.desc_map.get(id).unwrap();
+                            let res = self.registry.underlying_map.remove(id).unwrap();
+                            if self.capture.id == Some(*id) {
+                                self.capture.capture(backend.gpu(), &res);
+                            }
+                            match PoolKey::new(&res_desc.inner) {
+                                Some(key) => self.pool.release(key, res),
+                                None => backend.remove_resource(res),
+                            }
                         }
                     },
                     RenderJob::PassCall((node_index, render_target_state)) => {
@@ -137,6 +169,17 @@ impl RenderGraph {
             }
         }
 
+        self.pool.end_frame();
+
+        // imported resources live until the end of the frame
+        if let Some(res) = self
+            .capture
+            .id
+            .and_then(|id| self.registry.underlying_map.get(&id))
+        {
+            self.capture.capture(backend.gpu(), res);
+        }
+
         for res in self.registry.underlying_map.values() {
             backend.remove_resource(res.clone())
         }
@@ -148,6 +191,15 @@ impl RenderGraph {
     pub fn registry(&mut self) -> &mut ResourceRegistry {
         &mut self.registry
     }
+
+    pub fn resource_pool_stats(&self) -> ResourcePoolStats {
+        self.pool.stats()
+    }
+
+    // keeps the pooled resources for the graph built next
+    pub fn take_resource_pool(&mut self) -> ResourcePool {
+        std::mem::take(&mut self.pool)
+    }
 }
 
 pub struct RenderGraphBuilder {
@@ -158,7 +210,13 @@ pub struct RenderGraphBuilder {
     pass_name_nodes: HashMap<String, usize>,
     present_node: Option<PresentNode>,
     constraints: HashMap<String, Vec<PassConstraint>>,
+    pass_views: HashMap<String, u32>,
+    view: Option<u32>,
+    pass_orders: HashMap<String, i64>,
+    order: Option<i64>,
     msaa: u32,
+    depth: DepthConvention,
+    pool: Option<ResourcePool>,
 }
 
 impl RenderGraphBuilder {
@@ -171,7 +229,13 @@ impl RenderGraphBuilder {
             pass_name_nodes: HashMap::new(),
             present_node: None,
             constraints: HashMap::new(),
+            pass_views: HashMap::new(),
+            view: None,
+            pass_orders: HashMap::new(),
+            order: None,
             msaa: 1,
+            depth: DepthConvention::default(),
+            pool: None,
         }
     }
 
@@ -180,22 +244,81 @@ impl RenderGraphBuilder {
         self.msaa = sampler_count;
     }
 
+    // depth textures of the graph are cleared to the far plane of `depth`
+    pub fn set_depth_convention(&mut self, depth: DepthConvention) {
+        assert!(self.present_node.is_none());
+        self.depth = depth;
+    }
+
+    pub fn depth_convention(&self) -> DepthConvention {
+        self.depth
+    }
+
+    // transient resources are taken from `pool` instead of a new one
+    pub fn set_resource_pool(&mut self, pool: ResourcePool) {
+        self.pool = Some(pool);
+    }
+
     pub fn add_constraint<S: Into<String>>(&mut self, pass_node: S, c: PassConstraint) {
         let cs = self.constraints.entry(pass_node.into()).or_default();
         cs.push(c);
     }
 
+    // passes added after this belong to `view`. passes of a view draw to the
+    // default target after all passes of lower views, First/Last constraints
+    // apply within the view
+    pub fn set_view(&mut self, view: Option<u32>) {
+        self.view = view;
+    }
+
+    // passes of a view added after this run after the passes of the view
+    // with a lower order, passes without an order are not placed
+    pub fn set_pass_order(&mut self, order: Option<i64>) {
+        self.order = order;
+    }
+
     pub fn add_render_pass(&mut self, mut builder: RenderPassBuilder) {
-        let n = self.pass_nodes.len();
         let mut tmp = vec![];
         std::mem::swap(&mut tmp, &mut builder.constraints.constraints);
         let name = builder.name.clone();
+        if let Some(view) = self.view {
+            self.pass_views.insert(name.clone(), view);
+        }
+        if let Some(order) = self.order {
+            self.pass_orders.insert(name.clone(), order);
+        }
+        self.add_pass_node(name, tmp, Node::Pass(Box::new(builder.build())));
+    }
+
+    // names of the render passes added to `view`
+    pub fn view_passes(&self, view: u32) -> Vec<String> {
+        let mut res: Vec<_> = self
+            .pass_views
+            .iter()
+            .filter(|(_, v)| **v == view)
+            .map(|(name, _)| name.clone())
+            .collect();
+        res.sort();
+        res
+    }
+
+    // compute passes don't belong to a view, they are placed by their
+    // resources and constraints
+    pub fn add_compute_pass(&mut self, mut builder: ComputePassBuilder) {
+        let mut tmp = vec![];
+        std::mem::swap(&mut tmp, &mut builder.constraints.constraints);
+        let name = builder.name.clone();
+        self.add_pass_node(name, tmp, Node::Pass(Box::new(builder.build())));
+    }
+
+    fn add_pass_node(&mut self, name: String, constraints: Vec<PassConstraint>, node: Node) {
+        let n = self.pass_nodes.len();
         self.pass_name_nodes.insert(name.clone(), n);
-        self.pass_nodes.push(Node::Pass(Box::new(builder.build())));
+        self.pass_nodes.push(node);
 
-        if tmp.len() > 0 {
+        if !constraints.is_empty() {
             let cs = self.constraints.entry(name).or_default();
-            cs.extend(tmp);
+            cs.extend(constraints);
         }
     }
 
@@ -213,8 +336,9 @@ impl RenderGraphBuilder {
             inner: ResourceType::Texture(TextureInfo {
                 size: Vec3u::new(size.x, size.y, 1),
                 format: wgpu::TextureFormat::Depth32Float,
-                usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
-                clear: Some(ClearValue::Depth(1f32)),
+                usage: wgpu::TextureUsages::RENDER_ATTACHMENT
+                    | wgpu::TextureUsages::TEXTURE_BINDING,
+                clear: Some(ClearValue::Depth(self.depth.far())),
                 sampler_count: self.msaa,
             }),
         };
@@ -296,18 +420,70 @@ impl RenderGraphBuilder {
         id
     }
 
+    // the buffer is bound each frame with `ResourceRegistry::import`
+    pub fn import_buffer(
+        &mut self,
+        name: &str,
+        size: u64,
+        usage: wgpu::BufferUsages,
+    ) -> ResourceId {
+        let id = self.last_id;
+        self.last_id += 1;
+        let resource = ResourceNode {
+            id,
+            name: name.to_owned(),
+            inner: ResourceType::ImportBuffer(ImportBufferInfo { size, usage }),
+        };
+        self.resource_map.insert(id, resource.into());
+        id
+    }
+
     pub fn import_texture(&mut self, name: &str) -> ResourceId {
+        self.import_texture_with_clear(name, None)
+    }
+
+    // `clear` is applied by the first pass drawing to the texture each frame
+    pub fn import_texture_with_clear(
+        &mut self,
+        name: &str,
+        clear: Option<ClearValue>,
+    ) -> ResourceId {
         let id = self.last_id;
         self.last_id += 1;
         let resource = ResourceNode {
             id,
             name: name.to_owned(),
-            inner: ResourceType::ImportTexture(ImportTextureInfo { clear: None }),
+            inner: ResourceType::ImportTexture(ImportTextureInfo { clear }),
         };
         self.resource_map.insert(id, resource.into());
         id
     }
 
+    // passes of a view drawing to an imported color texture, or with the depth
+    // of such a pass, are ordered like passes drawing to the default target
+    fn is_view_target(
+        &self,
+        name: &str,
+        target: &RenderTargetDescriptor,
+        view_depths: &HashSet<ResourceId>,
+    ) -> bool {
+        if !self.pass_views.contains_key(name) {
+            return false;
+        }
+        let imported = target.colors.iter().any(|c| match &c.prefer_attachment {
+            PreferAttachment::Resource(id) => matches!(
+                self.resource_map.get(id).map(|v| &v.inner),
+                Some(ResourceType::ImportTexture(_))
+            ),
+            _ => false,
+        });
+        imported
+            || match target.depth.as_ref().map(|v| &v.prefer_attachment) {
+                Some(PreferAttachment::Resource(id)) => view_depths.contains(id),
+                _ => false,
+            }
+    }
+
     fn link_pass(
         &mut self,
         pass: Box<dyn DynPass>,
@@ -335,6 +511,14 @@ impl RenderGraphBuilder {
         let index = g.add_node(Node::Pass(pass));
 
         for (id, op) in inputs {
+            // a resource has one node, a pass reading and writing it is
+            // linked as a writer, compile orders it after the earlier writers
+            if matches!(
+                op,
+                ResourceUsage::TextureReadAndWrite | ResourceUsage::BufferReadAndWrite
+            ) {
+                continue;
+            }
             let node_id = resource_nodes.get(&id);
             if let Some(node_id) = node_id {
                 g.add_edge(*node_id, index, op.clone());
@@ -362,9 +546,13 @@ impl RenderGraphBuilder {
         }
 
         // link output resource
-        for color in &target.colors {
+        let attachments = target
+            .colors
+            .iter()
+            .flat_map(|c| [&c.prefer_attachment, &c.resolve_attachment]);
+        for attachment in attachments {
             let op = ResourceUsage::TextureWrite;
-            if let PreferAttachment::Resource(id) = &color.prefer_attachment {
+            if let PreferAttachment::Resource(id) = attachment {
                 if *id == RT_RESOLVE_COLOR_RESOURCE_ID {
                     continue;
                 }
@@ -405,6 +593,21 @@ impl RenderGraphBuilder {
         std::mem::swap(&mut pass_nodes, &mut self.pass_nodes);
         pass_nodes.reverse();
 
+        let mut view_depths = HashSet::new();
+        for node in &pass_nodes {
+            if let Node::Pass(pass) = node {
+                let target = pass.inputs_outputs().2;
+                if !self.is_view_target(pass.name(), target, &HashSet::new()) {
+                    continue;
+                }
+                if let Some(PreferAttachment::Resource(id)) =
+                    target.depth.as_ref().map(|v| &v.prefer_attachment)
+                {
+                    view_depths.insert(*id);
+                }
+            }
+        }
+
         let mut g = Graph::new();
 
         let present_index = g.add_node(Node::Present(present));
@@ -412,6 +615,7 @@ impl RenderGraphBuilder {
         // build graph
         let mut resource_nodes = HashMap::new();
         let mut pass_name_map = HashMap::new();
+        let mut linked = vec![];
 
         for node in pass_nodes {
             match node {
@@ -419,11 +623,40 @@ impl RenderGraphBuilder {
                     let name = pass.name().to_owned();
                     let index = self.link_pass(pass, &mut g, &mut resource_nodes);
                     pass_name_map.insert(name, index);
+                    linked.push(index);
                 }
                 _ => {}
             }
         }
 
+        // a pass reading and writing a resource runs after the passes added
+        // before it writing the resource, the edge links the passes directly
+        linked.reverse();
+        let added: HashMap<NodeIndex, usize> =
+            linked.iter().enumerate().map(|(i, v)| (*v, i)).collect();
+        for (pos, index) in linked.iter().enumerate() {
+            let read_writes: Vec<_> = g
+                .edges_directed(*index, petgraph::Direction::Outgoing)
+                .filter(|e| {
+                    matches!(
+                        e.weight(),
+                        ResourceUsage::TextureReadAndWrite | ResourceUsage::BufferReadAndWrite
+                    )
+                })
+                .map(|e| (e.target(), e.weight().clone()))
+                .collect();
+            for (res, op) in read_writes {
+                let writers: Vec<_> = g
+                    .edges_directed(res, petgraph::Direction::Incoming)
+                    .map(|e| e.source())
+                    .filter(|w| added.get(w).is_some_and(|p| *p < pos))
+                    .collect();
+                for writer in writers {
+                    g.add_edge(writer, *index, op.clone());
+                }
+            }
+        }
+
         let mut main_subgraph = SubGraph::new();
         let first_dummy_node = NodeIndex::new(usize::MAX);
         let last_dummy_node = NodeIndex::new(usize::MAX - 1);
@@ -439,33 +672,89 @@ impl RenderGraphBuilder {
         // main_subgraph.add_edge(first_dummy_node, first_dummy_node2, ());
         main_subgraph.add_edge(last_dummy_node, last_dummy_node2, ());
 
+        let mut dummy_nodes: HashSet<NodeIndex> =
+            [first_dummy_node, last_dummy_node, last_dummy_node2].into();
+
+        // each view is fenced by a begin and end node, views are chained in order
+        let mut views: Vec<u32> = self.pass_views.values().cloned().collect();
+        views.sort();
+        views.dedup();
+        let mut view_nodes = HashMap::new();
+        let mut prev_view_end = first_dummy_node;
+        for (index, view) in views.iter().enumerate() {
+            let begin = NodeIndex::new(usize::MAX - 4 - index * 2);
+            let end = NodeIndex::new(usize::MAX - 5 - index * 2);
+            main_subgraph.add_edge(prev_view_end, begin, ());
+            main_subgraph.add_edge(begin, end, ());
+            dummy_nodes.insert(begin);
+            dummy_nodes.insert(end);
+            view_nodes.insert(*view, (begin, end));
+            prev_view_end = end;
+        }
+        main_subgraph.add_edge(prev_view_end, last_dummy_node, ());
+        let mut view_passes: HashMap<u32, Vec<(NodeIndex, bool, Option<i64>)>> = HashMap::new();
+        let mut offscreen_nodes = HashSet::new();
+        let mut compute_nodes = vec![];
+
         for node_index in g.node_indices() {
             let node = g.node_weight(node_index).unwrap();
             match node {
                 Node::Pass(pass) => {
-                    if !pass.inputs_outputs().2.has_default() {
+                    if pass.is_compute() {
+                        compute_nodes.push(node_index);
                         continue;
                     }
-                    if let Some(c) = pass.constraints() {
-                        for c in &c.constraints {
-                            match c {
-                                PassConstraint::Before(name) => {
-                                    if let Some(index) = pass_name_map.get(name) {
-                                        main_subgraph.add_edge(node_index, *index, ());
-                                    }
-                                }
-                                PassConstraint::After(name) => {
-                                    if let Some(index) = pass_name_map.get(name) {
-                                        main_subgraph.add_edge(*index, node_index, ());
-                                    }
-                                }
-                                PassConstraint::First => {
-                                    main_subgraph.add_edge(first_dummy_node, node_index, ());
+                    let target = pass.inputs_outputs().2;
+                    if !target.has_default() {
+                        if !self.is_view_target(pass.name(), target, &view_depths) {
+                            continue;
+                        }
+                        offscreen_nodes.insert(node_index);
+                    }
+                    // constraints from the pass builder are moved here by add_render_pass
+                    let mut constraints = pass
+                        .constraints()
+                        .map(|c| c.constraints.clone())
+                        .unwrap_or_default();
+                    if let Some(c) = self.constraints.get(pass.name()) {
+                        constraints.extend_from_slice(c);
+                    }
+                    let (first, last) = match self
+                        .pass_views
+                        .get(pass.name())
+                        .and_then(|v| view_nodes.get(v).map(|n| (v, n)))
+                    {
+                        Some((view, (begin, end))) => {
+                            main_subgraph.add_edge(*begin, node_index, ());
+                            main_subgraph.add_edge(node_index, *end, ());
+                            let is_first = constraints.contains(&PassConstraint::First);
+                            view_passes.entry(*view).or_insert_with(Vec::new).push((
+                                node_index,
+                                is_first,
+                                self.pass_orders.get(pass.name()).cloned(),
+                            ));
+                            (*begin, *end)
+                        }
+                        None => (first_dummy_node, last_dummy_node),
+                    };
+                    for c in &constraints {
+                        match c {
+                            PassConstraint::Before(name) => {
+                                if let Some(index) = pass_name_map.get(name) {
+                                    main_subgraph.add_edge(node_index, *index, ());
                                 }
-                                PassConstraint::Last => {
-                                    main_subgraph.add_edge(node_index, last_dummy_node, ());
+                            }
+                            PassConstraint::After(name) => {
+                                if let Some(index) = pass_name_map.get(name) {
+                                    main_subgraph.add_edge(*index, node_index, ());
                                 }
                             }
+                            PassConstraint::First => {
+                                main_subgraph.add_edge(first, node_index, ());
+                            }
+                            PassConstraint::Last => {
+                                main_subgraph.add_edge(node_index, last, ());
+                            }
                         }
                     }
                     main_subgraph.add_edge(node_index, last_dummy_node2, ());
@@ -473,6 +762,51 @@ impl RenderGraphBuilder {
                 _ => (),
             }
         }
+        // First passes of a view run before the other passes of the view
+        for passes in view_passes.values() {
+            for (first, ..) in passes.iter().filter(|v| v.1) {
+                for (other, ..) in passes.iter().filter(|v| !v.1) {
+                    main_subgraph.add_edge(*first, *other, ());
+                }
+            }
+            // ordered passes run after every pass of the previous order
+            let mut orders: BTreeMap<i64, Vec<NodeIndex>> = BTreeMap::new();
+            for (node, _, order) in passes {
+                if let Some(order) = order {
+                    orders.entry(*order).or_default().push(*node);
+                }
+            }
+            let orders: Vec<_> = orders.values().collect();
+            for pair in orders.windows(2) {
+                for from in pair[0] {
+                    for to in pair[1] {
+                        main_subgraph.add_edge(*from, *to, ());
+                    }
+                }
+            }
+            // passes reading a texture or buffer run after the passes writing it
+            let nodes: HashSet<NodeIndex> = passes.iter().map(|v| v.0).collect();
+            for from in &nodes {
+                for res in g.edges_directed(*from, petgraph::Direction::Outgoing) {
+                    if nodes.contains(&res.target()) {
+                        // read and write, linked pass to pass
+                        main_subgraph.add_edge(*from, res.target(), ());
+                        continue;
+                    }
+                    for to in g.edges_directed(res.target(), petgraph::Direction::Outgoing) {
+                        if to.target() != *from
+                            && nodes.contains(&to.target())
+                            && matches!(
+                                to.weight(),
+                                ResourceUsage::TextureRead | ResourceUsage::BufferRead
+                            )
+                        {
+                            main_subgraph.add_edge(*from, to.target(), ());
+                        }
+                    }
+                }
+            }
+        }
         if petgraph::algo::is_cyclic_directed(&main_subgraph) {
             let gz = Dot::with_config(&main_subgraph, &[Config::EdgeNoLabel]);
             panic!("cyclic detected in subgraph {:?}", gz);
@@ -482,7 +816,7 @@ impl RenderGraphBuilder {
 
         let mut prev = None;
         main_pass_list.push(present_index);
-        if main_pass_list.len() == 4 {
+        if main_pass_list.len() == dummy_nodes.len() + offscreen_nodes.len() + 1 {
             // add clear pass
             let resolve_attachment = PreferAttachment::None;
             let b = ClearPassBuilder::new("default clear")
@@ -539,11 +873,21 @@ impl RenderGraphBuilder {
             g.add_edge(resource_depth, to, ResourceUsage::RenderTargetTextureRead);
         };
 
+        // offscreen passes don't share the default target, keep their order
+        // with direct edges
+        let mut order_edges = vec![];
+        let mut prev_any: Option<NodeIndex> = None;
         for node_index in &main_pass_list {
-            if *node_index == first_dummy_node
-                || *node_index == last_dummy_node
-                || *node_index == last_dummy_node2
-            {
+            if dummy_nodes.contains(node_index) {
+                continue;
+            }
+            if let Some(p) = prev_any {
+                if offscreen_nodes.contains(&p) || offscreen_nodes.contains(node_index) {
+                    order_edges.push((p, *node_index));
+                }
+            }
+            prev_any = Some(*node_index);
+            if offscreen_nodes.contains(node_index) {
                 continue;
             }
             if prev.is_none() {
@@ -553,16 +897,69 @@ impl RenderGraphBuilder {
             connect(prev.unwrap(), *node_index);
             prev = Some(*node_index);
         }
+        for (from, to) in order_edges {
+            g.add_edge(from, to, ResourceUsage::Order);
+        }
+
+        // compute passes are ordered by the resource edges, constraints add
+        // direct edges to the passes of the main list
+        let main_passes: Vec<NodeIndex> = main_pass_list
+            .iter()
+            .filter(|v| !dummy_nodes.contains(v) && **v != present_index)
+            .cloned()
+            .collect();
+        for node_index in compute_nodes {
+            let pass = match g.node_weight(node_index) {
+                Some(Node::Pass(pass)) => pass,
+                _ => continue,
+            };
+            let mut constraints = pass
+                .constraints()
+                .map(|c| c.constraints.clone())
+                .unwrap_or_default();
+            if let Some(c) = self.constraints.get(pass.name()) {
+                constraints.extend_from_slice(c);
+            }
+            for c in &constraints {
+                let edge = match c {
+                    PassConstraint::Before(name) => {
+                        pass_name_map.get(name).map(|index| (node_index, *index))
+                    }
+                    PassConstraint::After(name) => {
+                        pass_name_map.get(name).map(|index| (*index, node_index))
+                    }
+                    PassConstraint::First => main_passes.first().map(|index| (node_index, *index)),
+                    PassConstraint::Last => {
+                        g.add_edge(node_index, present_index, ResourceUsage::Order);
+                        main_passes.last().map(|index| (*index, node_index))
+                    }
+                };
+                if let Some((from, to)) = edge {
+                    g.add_edge(from, to, ResourceUsage::Order);
+                }
+            }
+        }
 
         if petgraph::algo::is_cyclic_directed(&g) {
             let gz = Dot::with_config(&g, &[Config::EdgeNoLabel]);
             panic!("cyclic detected in render graph {:?}", gz);
         }
 
+        // passes are kept when they reach the present through resources,
+        // writing an imported resource is visible outside of the graph
         let mut union_find = UnionFind::new(g.node_count());
-        for node_index in g.node_indices() {
-            for n in g.neighbors(node_index) {
-                union_find.union(node_index, n);
+        for edge in g.edge_references() {
+            if *edge.weight() == ResourceUsage::Order {
+                continue;
+            }
+            union_find.union(edge.source(), edge.target());
+            if let Some(Node::Resource(res)) = g.node_weight(edge.target()) {
+                if matches!(
+                    res.inner,
+                    ResourceType::ImportTexture(_) | ResourceType::ImportBuffer(_)
+                ) {
+                    union_find.union(edge.target(), present_index);
+                }
             }
         }
 
@@ -588,11 +985,20 @@ impl RenderGraphBuilder {
 
         // lifetime
         let mut imported = HashSet::new();
+        let mut lifetimes = HashMap::new();
         for jobs in &mut render_jobs_list {
             let mut resource_lifetime_map = HashMap::new();
             for (index, job) in jobs.jobs.iter().enumerate() {
                 if let RenderJob::PassCall((node_index, _)) = job {
-                    let resources = g.neighbors_undirected(*node_index);
+                    let resources = g
+                        .edges_directed(*node_index, petgraph::Direction::Incoming)
+                        .map(|e| (e.source(), e.weight()))
+                        .chain(
+                            g.edges_directed(*node_index, petgraph::Direction::Outgoing)
+                                .map(|e| (e.target(), e.weight())),
+                        )
+                        .filter(|(_, usage)| **usage != ResourceUsage::Order)
+                        .map(|(node, _)| node);
                     for resource in resources {
                         if let Node::Resource(resource) = g.node_weight(resource).unwrap() {
                             match resource.inner {
@@ -664,6 +1070,7 @@ impl RenderGraphBuilder {
             }
 
             jobs.jobs = jobs_with_resource;
+            lifetimes.extend(resource_lifetime_map);
         }
 
         let gz = Dot::with_config(&g, &[Config::EdgeNoLabel]);
@@ -678,6 +1085,9 @@ impl RenderGraphBuilder {
                 desc_map: self.resource_map,
                 underlying_map: HashMap::new(),
             },
+            pool: self.pool.unwrap_or_default(),
+            lifetimes,
+            capture: GraphCapture::default(),
         }
     }
 }
@@ -715,3 +1125,75 @@ struct DependencyRenderJobs {
     jobs: Vec<RenderJob>,
     depends_on_jobs: Vec<u32>,
 }
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Mutex;
+
+    use super::backend::{GraphComputeEngine, GraphCopyEngine};
+    use super::pass::{ComputePassExecutor, RenderPassContext};
+    use super::*;
+
+    struct Nop;
+
+    impl ComputePassExecutor for Nop {
+        fn prepare<'b>(
+            &'b mut self,
+            _context: RenderPassContext<'b>,
+            _engine: &mut GraphCopyEngine,
+        ) -> Option<()> {
+            Some(())
+        }
+        fn dispatch<'b>(
+            &'b mut self,
+            _context: RenderPassContext<'b>,
+            _engine: &mut GraphComputeEngine,
+        ) {
+        }
+        fn cleanup<'b>(&'b mut self, _context: RenderPassContext<'b>) {}
+    }
+
+    fn compute(name: &str, f: impl FnOnce(&mut ComputePassBuilder)) -> ComputePassBuilder {
+        let mut builder = ComputePassBuilder::new(name);
+        f(&mut builder);
+        builder.async_execute(Arc::new(Mutex::new(Nop)));
+        builder
+    }
+
+    fn pass_index(graph: &RenderGraph, name: &str) -> NodeIndex {
+        graph
+            .g
+            .node_indices()
+            .find(|v| matches!(graph.g.node_weight(*v), Some(Node::Pass(pass)) if pass.name() == name))
+            .unwrap()
+    }
+
+    // `to` can't run before `from`
+    fn ordered(graph: &RenderGraph, from: &str, to: &str) -> bool {
+        let (from, to) = (pass_index(graph, from), pass_index(graph, to));
+        petgraph::algo::has_path_connecting(&graph.g, from, to, None)
+    }
+
+    #[test]
+    fn read_write_after_writer() {
+        let mut builder = RenderGraphBuilder::new("test");
+        builder.set_present_target(Size::new(4, 4), wgpu::TextureFormat::Rgba8Unorm, None);
+        let usage = wgpu::BufferUsages::STORAGE;
+        let data = builder.allocate_buffer("data".into(), 16, usage);
+        let output = builder.import_buffer("output", 16, usage);
+
+        builder.add_compute_pass(compute("write", |b| b.write_buffer(data)));
+        builder.add_compute_pass(compute("accumulate a", |b| b.read_write_buffer(data)));
+        builder.add_compute_pass(compute("accumulate b", |b| b.read_write_buffer(data)));
+        builder.add_compute_pass(compute("resolve", |b| {
+            b.read_buffer(data);
+            b.write_buffer(output);
+        }));
+
+        let graph = builder.compile();
+        assert!(ordered(&graph, "write", "accumulate a"));
+        assert!(ordered(&graph, "accumulate a", "accumulate b"));
+        assert!(ordered(&graph, "accumulate b", "resolve"));
+        assert!(!ordered(&graph, "accumulate b", "accumulate a"));
+    }
+}
@@ -6,6 +6,8 @@ use std::{
 use crate::{
     backends::wgpu_backend::{ClearValue, ResourceOps, WGPURenderTarget, WGPUResource},
     context::ResourceRef,
+    render::pso::{BindGroupType, PipelineStateObject},
+    types::Vec3u,
 };
 
 use super::{
@@ -13,6 +15,15 @@ use super::{
     resource::*,
     ResourceRegistry,
 };
+// graph textures can be copied out for inspection, multisampled ones can't
+fn copy_src(t: &TextureInfo) -> wgpu::TextureUsages {
+    if t.sampler_count == 1 {
+        t.usage | wgpu::TextureUsages::COPY_SRC
+    } else {
+        t.usage
+    }
+}
+
 pub struct GraphBackend {
     gpu: Arc<WGPUResource>,
     rx: mpsc::Receiver<(wgpu::CommandBuffer, u32)>,
@@ -39,7 +50,7 @@ impl GraphBackend {
                         sample_count: t.sampler_count,
                         dimension: wgpu::TextureDimension::D2,
                         format: t.format,
-                        usage: t.usage,
+                        usage: copy_src(t),
                         view_formats: &[],
                     });
                     self.gpu.context().register_texture(tex)
@@ -55,22 +66,22 @@ impl GraphBackend {
                         sample_count: t.sampler_count,
                         dimension: wgpu::TextureDimension::D3,
                         format: t.format,
-                        usage: t.usage,
+                        usage: copy_src(t),
                         view_formats: &[],
                     });
                     self.gpu.context().register_texture(tex)
                 }
             }
-            ResourceType::Buffer(_b) => {
-                // let buf = self.gpu.device().create_buffer(&wgpu::BufferDescriptor {
-                //     label: None,
-                //     size: b.size,
-                //     usage: b.usage,
-                //     mapped_at_creation: todo!(),
-                // });
-
-                // self.gpu.context().register_texture(buf).id()
-                todo!()
+            ResourceType::Buffer(b) => {
+                // new buffers are zeroed, counters and indirect args start at 0.
+                // pooled buffers are cleared when reused
+                let buf = self.gpu.device().create_buffer(&wgpu::BufferDescriptor {
+                    label: None,
+                    size: b.size,
+                    usage: b.usage | wgpu::BufferUsages::COPY_DST,
+                    mapped_at_creation: false,
+                });
+                self.gpu.context().register_buffer(Arc::new(buf))
             }
             ty => panic!("ty {:?}", ty),
         }
@@ -80,6 +91,17 @@ impl GraphBackend {
         self.gpu.context().deregister(res);
     }
 
+    pub fn clear_buffer(&self, res: &ResourceRef) {
+        let mut encoder =
+            self.gpu
+                .device()
+                .create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                    label: Some("clear buffer"),
+                });
+        encoder.clear_buffer(res.buffer(), 0, None);
+        self.gpu.queue().submit(Some(encoder.finish()));
+    }
+
     pub fn dispatch_render_with_clear<'a>(
         &self,
         name: &str,
@@ -245,15 +267,19 @@ impl GraphBackend {
         }
     }
 
-    pub fn dispatch_compute(&self) {
-        // GraphRenderBackend {
-        //     gpu: self.gpu.clone(),
-        //     tx: self.tx.clone(),
-        //     pass_render_target,
-        //     registry,
-        //     state,
-        todo!()
-        // }
+    pub fn dispatch_compute(&self, name: &str) -> GraphComputeEngine {
+        let w = self
+            .gpu
+            .device()
+            .create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                label: Some(&format!("{} compute engine", name)),
+            });
+        GraphComputeEngine {
+            gpu: self.gpu.clone(),
+            name: name.to_owned(),
+            w: Some(w),
+            cb: vec![],
+        }
     }
 
     pub fn dispatch_copy(&self, name: &str) -> GraphCopyEngine {
@@ -368,6 +394,70 @@ impl Drop for GraphCopyEngine {
     }
 }
 
-pub struct GraphComputeEngine {}
+pub struct GraphComputeEngine {
+    gpu: Arc<WGPUResource>,
+    name: String,
+    pub w: Option<wgpu::CommandEncoder>,
+    pub cb: Vec<wgpu::CommandBuffer>,
+}
 
-impl GraphComputeEngine {}
+impl GraphComputeEngine {
+    pub fn device(&self) -> &wgpu::Device {
+        self.gpu.device()
+    }
+    pub fn gpu(&self) -> &WGPUResource {
+        &self.gpu
+    }
+    pub fn encoder(&mut self) -> &mut wgpu::CommandEncoder {
+        self.w.as_mut().unwrap()
+    }
+
+    pub fn begin(&mut self) -> wgpu::ComputePass {
+        self.w
+            .as_mut()
+            .unwrap()
+            .begin_compute_pass(&wgpu::ComputePassDescriptor {
+                label: Some(&self.name),
+                timestamp_writes: None,
+            })
+    }
+
+    // dispatches enough workgroups of the pipeline to cover `threads`
+    pub fn dispatch(
+        &mut self,
+        pso: &PipelineStateObject,
+        bind_groups: &[(BindGroupType, &wgpu::BindGroup)],
+        threads: Vec3u,
+    ) {
+        let size = pso.workgroup_size();
+        let count = |t: u32, s: u32| t.div_ceil(s.max(1));
+        let mut pass = self.begin();
+        pass.set_pipeline(pso.compute());
+        for (ty, bind_group) in bind_groups {
+            match pso.bind_group_index(*ty) {
+                Some(index) => pass.set_bind_group(index, *bind_group, &[]),
+                None => log::warn!("pass {} has no {:?} bind group", pso.pass_name(), ty),
+            }
+        }
+        pass.dispatch_workgroups(
+            count(threads.x, size[0]),
+            count(threads.y, size[1]),
+            count(threads.z, size[2]),
+        );
+    }
+
+    pub fn insert_command_buffers(&mut self, index: usize, command: wgpu::CommandBuffer) {
+        self.cb.insert(index, command);
+    }
+}
+
+impl Drop for GraphComputeEngine {
+    fn drop(&mut self) {
+        let mut commands = vec![];
+        std::mem::swap(&mut self.cb, &mut commands);
+        if let Some(w) = self.w.take() {
+            commands.push(w.finish());
+        }
+        self.gpu.queue().submit(commands);
+    }
+}
@@ -0,0 +1,622 @@
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+};
+
+use petgraph::{
+    dot::{Config, Dot},
+    graph::NodeIndex,
+    visit::EdgeRef,
+};
+use serde::Serialize;
+
+use crate::{
+    backends::wgpu_backend::WGPUResource,
+    context::{RContext, ResourceRef},
+};
+
+use super::{
+    pool::{PoolKey, ResourcePoolStats},
+    resource::{ResourceId, ResourceType, ResourceUsage},
+    Node, RenderGraph, RenderJob, ResourceLifetimeOperation,
+};
+
+#[derive(Debug, Clone, Serialize)]
+pub struct PassDesc {
+    pub name: String,
+    pub compute: bool,
+    // index in the job list, passes not reaching the present are culled
+    pub order: Option<usize>,
+    pub reads: Vec<(ResourceId, ResourceUsage)>,
+    pub writes: Vec<(ResourceId, ResourceUsage)>,
+    // passes ordered before this one without a resource between them
+    pub after: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ResourceDesc {
+    pub id: ResourceId,
+    pub name: String,
+    pub kind: String,
+    pub imported: bool,
+    pub detail: String,
+    pub bytes: Option<u64>,
+    // first and last pass using it
+    pub lifetime: Option<(u32, u32)>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub enum JobDesc {
+    Create(ResourceId),
+    Destroy(ResourceId),
+    Pass(String),
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct RenderGraphDesc {
+    pub name: String,
+    pub passes: Vec<PassDesc>,
+    pub resources: Vec<ResourceDesc>,
+    pub jobs: Vec<JobDesc>,
+}
+
+impl RenderGraphDesc {
+    pub fn to_json(&self) -> anyhow::Result<String> {
+        Ok(serde_json::to_string_pretty(self)?)
+    }
+
+    pub fn resource(&self, id: ResourceId) -> Option<&ResourceDesc> {
+        self.resources.iter().find(|v| v.id == id)
+    }
+}
+
+fn resource_detail(ty: &ResourceType) -> (String, String, bool) {
+    match ty {
+        ResourceType::Texture(t) => (
+            "texture".to_owned(),
+            format!(
+                "{}x{}x{} {:?} x{}",
+                t.size.x, t.size.y, t.size.z, t.format, t.sampler_count
+            ),
+            false,
+        ),
+        ResourceType::Buffer(b) => (
+            "buffer".to_owned(),
+            format!("{} bytes {:?}", b.size, b.usage),
+            false,
+        ),
+        ResourceType::ImportTexture(_) => ("texture".to_owned(), String::new(), true),
+        ResourceType::ImportBuffer(b) => (
+            "buffer".to_owned(),
+            format!("{} bytes {:?}", b.size, b.usage),
+            true,
+        ),
+        ResourceType::AliasResource(a, b) => ("alias".to_owned(), format!("{} {}", a, b), false),
+    }
+}
+
+fn escape(s: &str) -> String {
+    s.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+impl RenderGraph {
+    fn pass_orders(&self) -> HashMap<NodeIndex, usize> {
+        let mut orders = HashMap::new();
+        if let Some(jobs) = self.render_jobs_list.first() {
+            for job in &jobs.jobs {
+                if let RenderJob::PassCall((node_index, _)) = job {
+                    let n = orders.len();
+                    orders.insert(*node_index, n);
+                }
+            }
+        }
+        orders
+    }
+
+    fn node_name(&self, node_index: NodeIndex) -> Option<String> {
+        match self.g.node_weight(node_index)? {
+            Node::Pass(pass) => Some(pass.name().to_owned()),
+            Node::Present(_) => Some("present".to_owned()),
+            Node::Resource(_) => None,
+        }
+    }
+
+    pub fn describe(&self) -> RenderGraphDesc {
+        let orders = self.pass_orders();
+        let mut passes = vec![];
+        let mut resources = vec![];
+
+        for node_index in self.g.node_indices() {
+            let node = self.g.node_weight(node_index).unwrap();
+            if let Node::Resource(res) = node {
+                let (kind, detail, imported) = resource_detail(&res.inner);
+                resources.push(ResourceDesc {
+                    id: res.id,
+                    name: res.name.clone(),
+                    kind,
+                    imported,
+                    detail,
+                    bytes: PoolKey::new(&res.inner).map(|v| v.bytes()),
+                    lifetime: self.lifetimes.get(&res.id).map(|v| (v.beg, v.end)),
+                });
+                continue;
+            }
+            let (name, compute) = match node {
+                Node::Pass(pass) => (pass.name().to_owned(), pass.is_compute()),
+                _ => ("present".to_owned(), false),
+            };
+            let mut reads = vec![];
+            let mut after = vec![];
+            for e in self
+                .g
+                .edges_directed(node_index, petgraph::Direction::Incoming)
+            {
+                match self.g.node_weight(e.source()).unwrap() {
+                    _ if *e.weight() == ResourceUsage::Order => {
+                        after.extend(self.node_name(e.source()))
+                    }
+                    Node::Resource(res) => reads.push((res.id, e.weight().clone())),
+                    _ => (),
+                }
+            }
+            let writes = self
+                .g
+                .edges_directed(node_index, petgraph::Direction::Outgoing)
+                .filter(|e| *e.weight() != ResourceUsage::Order)
+                .filter_map(|e| match self.g.node_weight(e.target()).unwrap() {
+                    Node::Resource(res) => Some((res.id, e.weight().clone())),
+                    _ => None,
+                })
+                .collect();
+            passes.push(PassDesc {
+                name,
+                compute,
+                order: orders.get(&node_index).cloned(),
+                reads,
+                writes,
+                after,
+            });
+        }
+        passes.sort_by_key(|v| v.order.unwrap_or(usize::MAX));
+        resources.sort_by_key(|v| v.id);
+
+        let jobs = self
+            .render_jobs_list
+            .first()
+            .map(|jobs| {
+                jobs.jobs
+                    .iter()
+                    .map(|job| match job {
+                        RenderJob::ResourceOperation(ResourceLifetimeOperation::Create(id)) => {
+                            JobDesc::Create(*id)
+                        }
+                        RenderJob::ResourceOperation(ResourceLifetimeOperation::Destroy(id)) => {
+                            JobDesc::Destroy(*id)
+                        }
+                        RenderJob::PassCall((node_index, _)) => {
+                            JobDesc::Pass(self.node_name(*node_index).unwrap_or_default())
+                        }
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        RenderGraphDesc {
+            name: self.name.clone(),
+            passes,
+            resources,
+            jobs,
+        }
+    }
+
+    pub fn to_json(&self) -> anyhow::Result<String> {
+        self.describe().to_json()
+    }
+
+    // passes are boxes labeled with their job index, culled passes are dashed.
+    // resources are labeled with their lifetime, imported ones are dashed
+    pub fn to_dot(&self) -> String {
+        let orders = self.pass_orders();
+        let edge = |_, e: petgraph::graph::EdgeReference<ResourceUsage>| {
+            if *e.weight() == ResourceUsage::Order {
+                "style = dotted".to_owned()
+            } else {
+                format!("label = \"{:?}\"", e.weight())
+            }
+        };
+        let node = |_, (node_index, node): (NodeIndex, &Node)| match node {
+            Node::Resource(res) => {
+                let (kind, detail, imported) = resource_detail(&res.inner);
+                let lifetime = self
+                    .lifetimes
+                    .get(&res.id)
+                    .map(|v| format!("[{}, {}]", v.beg, v.end))
+                    .unwrap_or_default();
+                format!(
+                    "label = \"{} #{}\\n{} {}\\n{}\", shape = ellipse{}",
+                    escape(&res.name),
+                    res.id,
+                    kind,
+                    escape(&detail),
+                    lifetime,
+                    if imported { ", style = dashed" } else { "" }
+                )
+            }
+            Node::Present(_) => "label = \"present\", shape = doublecircle".to_owned(),
+            Node::Pass(pass) => {
+                let name = escape(pass.name());
+                match orders.get(&node_index) {
+                    Some(order) => format!("label = \"{}: {}\", shape = box", order, name),
+                    None => format!("label = \"{}\", shape = box, style = dashed", name),
+                }
+            }
+        };
+        let dot = Dot::with_attr_getters(
+            &self.g,
+            &[Config::EdgeNoLabel, Config::NodeNoLabel],
+            &edge,
+            &node,
+        );
+        format!("{:?}", dot)
+    }
+
+    // the resource copied each frame after its last pass, for previews
+    pub fn set_capture(&mut self, id: Option<ResourceId>, context: &RContext) {
+        if self.capture.id != id {
+            self.capture.clear(context);
+        }
+        self.capture.id = id;
+    }
+
+    pub fn captured(&self) -> Option<ResourceRef> {
+        self.capture.texture.clone()
+    }
+}
+
+type MapResult = Arc<Mutex<Option<Result<(), wgpu::BufferAsyncError>>>>;
+
+// textures the ui can't sample are read back and shown as gray, scaled to the
+// range of their texels. r32uint is read as float bits, the way the hi-z
+// pyramid stores depth
+fn read_back_supported(format: wgpu::TextureFormat) -> bool {
+    matches!(
+        format,
+        wgpu::TextureFormat::Depth32Float
+            | wgpu::TextureFormat::R32Float
+            | wgpu::TextureFormat::R32Uint
+    )
+}
+
+fn to_gray(texels: &[u8]) -> Vec<u8> {
+    let values: Vec<f32> = texels
+        .chunks(4)
+        .map(|v| f32::from_le_bytes([v[0], v[1], v[2], v[3]]))
+        .collect();
+    let (min, max) = values
+        .iter()
+        .filter(|v| v.is_finite())
+        .fold((f32::MAX, f32::MIN), |(min, max), v| {
+            (min.min(*v), max.max(*v))
+        });
+    let mut res = Vec::with_capacity(values.len() * 4);
+    for v in values {
+        let v = if !v.is_finite() {
+            0f32
+        } else if max > min {
+            (v - min) / (max - min)
+        } else {
+            v.clamp(0f32, 1f32)
+        };
+        let v = (v * 255f32).round() as u8;
+        res.extend_from_slice(&[v, v, v, 255]);
+    }
+    res
+}
+
+// one texel of 4 bytes per pixel, mapped after the copy is submitted
+#[derive(Debug)]
+struct CaptureReadback {
+    buffer: wgpu::Buffer,
+    size: wgpu::Extent3d,
+    format: wgpu::TextureFormat,
+    bytes_per_row: u32,
+    mapped: Option<MapResult>,
+}
+
+impl CaptureReadback {
+    fn new(device: &wgpu::Device, size: wgpu::Extent3d, format: wgpu::TextureFormat) -> Self {
+        let bytes_per_row =
+            wgpu::util::align_to(size.width * 4, wgpu::COPY_BYTES_PER_ROW_ALIGNMENT);
+        let buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("graph capture readback"),
+            size: (bytes_per_row * size.height) as u64,
+            usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
+            mapped_at_creation: false,
+        });
+        Self {
+            buffer,
+            size,
+            format,
+            bytes_per_row,
+            mapped: None,
+        }
+    }
+
+    fn read(&self) -> Vec<u8> {
+        let view = self.buffer.slice(..).get_mapped_range();
+        let mut res = Vec::with_capacity((self.size.width * self.size.height * 4) as usize);
+        for row in view.chunks(self.bytes_per_row as usize) {
+            res.extend_from_slice(&row[..(self.size.width * 4) as usize]);
+        }
+        drop(view);
+        self.buffer.unmap();
+        res
+    }
+}
+
+// the captured resource of a graph and the texture showing it
+#[derive(Debug, Default)]
+pub(super) struct GraphCapture {
+    pub id: Option<ResourceId>,
+    texture: Option<ResourceRef>,
+    readback: Option<CaptureReadback>,
+}
+
+impl GraphCapture {
+    fn clear(&mut self, context: &RContext) {
+        if let Some(old) = self.texture.take() {
+            context.deregister(old);
+        }
+        self.readback = None;
+    }
+
+    // only single sampled 2d textures are captured. filterable color formats
+    // are copied, the formats of `read_back_supported` are shown a frame late
+    pub fn capture(&mut self, gpu: &WGPUResource, res: &ResourceRef) {
+        let src = match res.ty() {
+            crate::context::ResourceTy::Texture((t, _)) => t,
+            _ => return,
+        };
+        let capturable = src.sample_count() == 1
+            && src.dimension() == wgpu::TextureDimension::D2
+            && src.usage().contains(wgpu::TextureUsages::COPY_SRC);
+        if !capturable {
+            self.clear(gpu.context());
+            return;
+        }
+        match src.format().sample_type(None, None) {
+            Some(wgpu::TextureSampleType::Float { filterable: true }) => {
+                self.readback = None;
+                self.copy(gpu, src);
+            }
+            _ if read_back_supported(src.format()) => self.read_back(gpu, src),
+            _ => self.clear(gpu.context()),
+        }
+    }
+
+    // the texture shown by the ui, recreated when the size or format changes
+    fn texture(
+        &mut self,
+        gpu: &WGPUResource,
+        size: wgpu::Extent3d,
+        format: wgpu::TextureFormat,
+    ) -> &ResourceRef {
+        let reuse = self.texture.as_ref().is_some_and(|v| {
+            let t = v.texture_ref();
+            t.size() == size && t.format() == format
+        });
+        if !reuse {
+            if let Some(old) = self.texture.take() {
+                gpu.context().deregister(old);
+            }
+            let texture = gpu.device().create_texture(&wgpu::TextureDescriptor {
+                label: Some("graph capture"),
+                size,
+                mip_level_count: 1,
+                sample_count: 1,
+                dimension: wgpu::TextureDimension::D2,
+                format,
+                usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST,
+                view_formats: &[],
+            });
+            self.texture = Some(gpu.context().register_texture(texture));
+        }
+        self.texture.as_ref().unwrap()
+    }
+
+    fn copy(&mut self, gpu: &WGPUResource, src: &wgpu::Texture) {
+        let size = src.size();
+        let dst = self.texture(gpu, size, src.format());
+        let mut encoder = gpu
+            .device()
+            .create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                label: Some("graph capture"),
+            });
+        encoder.copy_texture_to_texture(
+            src.as_image_copy(),
+            dst.texture_ref().as_image_copy(),
+            size,
+        );
+        gpu.queue().submit(Some(encoder.finish()));
+    }
+
+    fn read_back(&mut self, gpu: &WGPUResource, src: &wgpu::Texture) {
+        let size = src.size();
+        let format = src.format();
+        if self
+            .readback
+            .as_ref()
+            .is_some_and(|v| v.size != size || v.format != format)
+        {
+            self.readback = None;
+        }
+        let readback = self
+            .readback
+            .get_or_insert_with(|| CaptureReadback::new(gpu.device(), size, format));
+
+        // show the read back of an earlier frame
+        if let Some(mapped) = readback.mapped.clone() {
+            gpu.device().poll(wgpu::Maintain::Poll);
+            let res = match mapped.lock().unwrap().take() {
+                Some(v) => v,
+                None => return,
+            };
+            readback.mapped = None;
+            match res {
+                Ok(_) => {
+                    let data = to_gray(&readback.read());
+                    let dst = self.texture(gpu, size, wgpu::TextureFormat::Rgba8Unorm);
+                    gpu.queue().write_texture(
+                        dst.texture_ref().as_image_copy(),
+                        &data,
+                        wgpu::ImageDataLayout {
+                            offset: 0,
+                            bytes_per_row: Some(size.width * 4),
+                            rows_per_image: None,
+                        },
+                        size,
+                    );
+                }
+                Err(e) => log::warn!("graph capture read back fail {}", e),
+            }
+        }
+
+        let readback = self.readback.as_mut().unwrap();
+        let aspect = if format.is_depth_stencil_format() {
+            wgpu::TextureAspect::DepthOnly
+        } else {
+            wgpu::TextureAspect::All
+        };
+        let mut encoder = gpu
+            .device()
+            .create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                label: Some("graph capture"),
+            });
+        encoder.copy_texture_to_buffer(
+            wgpu::ImageCopyTexture {
+                texture: src,
+                mip_level: 0,
+                origin: wgpu::Origin3d::ZERO,
+                aspect,
+            },
+            wgpu::ImageCopyBuffer {
+                buffer: &readback.buffer,
+                layout: wgpu::ImageDataLayout {
+                    offset: 0,
+                    bytes_per_row: Some(readback.bytes_per_row),
+                    rows_per_image: None,
+                },
+            },
+            size,
+        );
+        gpu.queue().submit(Some(encoder.finish()));
+
+        let mapped: MapResult = Arc::new(Mutex::new(None));
+        let res = mapped.clone();
+        readback
+            .buffer
+            .slice(..)
+            .map_async(wgpu::MapMode::Read, move |v| {
+                *res.lock().unwrap() = Some(v);
+            });
+        readback.mapped = Some(mapped);
+    }
+}
+
+#[derive(Debug, Default)]
+struct InspectorState {
+    graph: Option<Arc<RenderGraphDesc>>,
+    dot: String,
+    capture: Option<ResourceId>,
+    captured: Option<ResourceRef>,
+    pool_stats: ResourcePoolStats,
+}
+
+// shared between the renderer owning the graph and the ui showing it
+#[derive(Debug, Default)]
+pub struct RenderGraphInspector {
+    inner: Mutex<InspectorState>,
+}
+
+impl RenderGraphInspector {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // called by the renderer after each compile
+    pub fn set_graph(&self, g: &RenderGraph) {
+        let graph = g.describe();
+        let dot = g.to_dot();
+        let mut inner = self.inner.lock().unwrap();
+        // ids are reused by the rebuilt graph
+        if inner.capture.is_some_and(|id| graph.resource(id).is_none()) {
+            inner.capture = None;
+            inner.captured = None;
+        }
+        inner.graph = Some(Arc::new(graph));
+        inner.dot = dot;
+    }
+
+    pub fn graph(&self) -> Option<Arc<RenderGraphDesc>> {
+        self.inner.lock().unwrap().graph.clone()
+    }
+
+    pub fn dot(&self) -> String {
+        self.inner.lock().unwrap().dot.clone()
+    }
+
+    pub fn set_capture(&self, id: Option<ResourceId>) {
+        let mut inner = self.inner.lock().unwrap();
+        if inner.capture != id {
+            inner.captured = None;
+        }
+        inner.capture = id;
+    }
+
+    pub fn capture(&self) -> Option<ResourceId> {
+        self.inner.lock().unwrap().capture
+    }
+
+    // called by the renderer after each frame
+    pub fn update(&self, g: &RenderGraph) {
+        let mut inner = self.inner.lock().unwrap();
+        inner.captured = g.captured();
+        inner.pool_stats = g.resource_pool_stats();
+    }
+
+    pub fn captured(&self) -> Option<ResourceRef> {
+        self.inner.lock().unwrap().captured.clone()
+    }
+
+    pub fn pool_stats(&self) -> ResourcePoolStats {
+        self.inner.lock().unwrap().pool_stats
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn texels(values: &[f32]) -> Vec<u8> {
+        values.iter().flat_map(|v| v.to_le_bytes()).collect()
+    }
+
+    #[test]
+    fn gray_follows_range() {
+        // depth close to the far plane still shows its range
+        let res = to_gray(&texels(&[0.9f32, 0.95, 1f32, f32::NAN]));
+        let gray: Vec<u8> = res.chunks(4).map(|v| v[0]).collect();
+        assert_eq!(gray, vec![0, 128, 255, 0]);
+        assert!(res
+            .chunks(4)
+            .all(|v| v[0] == v[1] && v[1] == v[2] && v[3] == 255));
+
+        // a cleared target
+        let res = to_gray(&texels(&[1f32; 2]));
+        assert_eq!(res, vec![255; 8]);
+
+        assert!(read_back_supported(wgpu::TextureFormat::Depth32Float));
+        assert!(read_back_supported(wgpu::TextureFormat::R32Uint));
+        assert!(!read_back_supported(wgpu::TextureFormat::Depth24Plus));
+    }
+}
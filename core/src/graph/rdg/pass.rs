@@ -7,7 +7,7 @@ use std::{
 use crate::backends::wgpu_backend::{ClearValue, ResourceOps, WGPUResource};
 
 use super::{
-    backend::{GraphBackend, GraphCopyEngine, GraphRenderEngine},
+    backend::{GraphBackend, GraphComputeEngine, GraphCopyEngine, GraphRenderEngine},
     resource::{RT_COLOR_RESOURCE_ID, RT_DEPTH_RESOURCE_ID},
     PassParameter, ResourceId, ResourceRegistry, ResourceUsage,
 };
@@ -206,6 +206,16 @@ pub trait RenderPassExecutor {
     fn cleanup<'b>(&'b mut self, context: RenderPassContext<'b>);
 }
 
+pub trait ComputePassExecutor {
+    fn prepare<'b>(
+        &'b mut self,
+        context: RenderPassContext<'b>,
+        engine: &mut GraphCopyEngine,
+    ) -> Option<()>;
+    fn dispatch<'b>(&'b mut self, context: RenderPassContext<'b>, engine: &mut GraphComputeEngine);
+    fn cleanup<'b>(&'b mut self, context: RenderPassContext<'b>);
+}
+
 pub trait DynPass: Debug {
     fn inputs_outputs(&self) -> (&PassParameter, &PassParameter, &RenderTargetDescriptor);
 
@@ -226,6 +236,10 @@ pub trait DynPass: Debug {
     fn constraints(&self) -> Option<&PassConstraints> {
         None
     }
+
+    fn is_compute(&self) -> bool {
+        false
+    }
 }
 
 pub struct RenderPass {
@@ -388,6 +402,12 @@ impl RenderPassBuilder {
         self.inputs.buffers.push((input, ResourceUsage::BufferRead))
     }
 
+    pub fn write_buffer(&mut self, output: ResourceId) {
+        self.outputs
+            .buffers
+            .push((output, ResourceUsage::BufferWrite))
+    }
+
     pub fn read_write_texture(&mut self, t: ResourceId) {
         self.inputs
             .textures
@@ -397,6 +417,15 @@ impl RenderPassBuilder {
             .push((t, ResourceUsage::TextureReadAndWrite));
     }
 
+    pub fn read_write_buffer(&mut self, b: ResourceId) {
+        self.inputs
+            .buffers
+            .push((b, ResourceUsage::BufferReadAndWrite));
+        self.outputs
+            .buffers
+            .push((b, ResourceUsage::BufferReadAndWrite));
+    }
+
     pub fn set_shader_name<S: Into<String>>(&mut self, shader_name: S) {
         self.shader_name = shader_name.into();
     }
@@ -444,6 +473,163 @@ impl RenderPassBuilder {
     }
 }
 
+pub struct ComputePass {
+    inner: Arc<Mutex<dyn ComputePassExecutor>>,
+    pub name: String,
+    pub inputs: PassParameter,
+    pub outputs: PassParameter,
+    // always empty
+    render_targets: RenderTargetDescriptor,
+    pub constraints: PassConstraints,
+}
+
+impl Debug for ComputePass {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ComputePass")
+            .field("name", &self.name)
+            .finish()
+    }
+}
+
+impl DynPass for ComputePass {
+    fn inputs_outputs(&self) -> (&PassParameter, &PassParameter, &RenderTargetDescriptor) {
+        (&self.inputs, &self.outputs, &self.render_targets)
+    }
+
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn execute(
+        &self,
+        registry: &ResourceRegistry,
+        backend: &GraphBackend,
+        _render_target_state: &RenderTargetState,
+        parameter: &dyn Any,
+    ) {
+        profiling::scope!(&self.name);
+
+        let mut c = self.inner.lock().unwrap();
+        let context = RenderPassContext {
+            name: &self.name,
+            parameter,
+            gpu: backend.gpu(),
+            registry,
+        };
+        let copy_commands = {
+            profiling::scope!("copy engine");
+            let mut copy_engine = backend.dispatch_copy(&self.name);
+            // nothing to dispatch this frame
+            if c.prepare(context, &mut copy_engine).is_none() {
+                return;
+            }
+            copy_engine.take_command()
+        };
+        {
+            profiling::scope!("compute engine");
+            let mut compute_engine = backend.dispatch_compute(&self.name);
+            c.dispatch(context, &mut compute_engine);
+            compute_engine.insert_command_buffers(0, copy_commands);
+        }
+        c.cleanup(context);
+    }
+
+    fn constraints(&self) -> Option<&PassConstraints> {
+        Some(&self.constraints)
+    }
+
+    fn is_compute(&self) -> bool {
+        true
+    }
+}
+
+// a compute pass runs after the passes writing the resources it reads and
+// before the passes reading the resources it writes. a pass without such a
+// path to the present target is culled unless constraints order it
+pub struct ComputePassBuilder {
+    pub(crate) name: String,
+    inputs: PassParameter,
+    outputs: PassParameter,
+
+    inner: Option<Arc<Mutex<dyn ComputePassExecutor>>>,
+    pub(crate) constraints: PassConstraints,
+}
+
+impl ComputePassBuilder {
+    pub fn new<S: Into<String>>(name: S) -> Self {
+        Self {
+            name: name.into(),
+            inputs: PassParameter::default(),
+            outputs: PassParameter::default(),
+            inner: None,
+            constraints: PassConstraints::default(),
+        }
+    }
+
+    pub fn add_constraint(&mut self, constraint: PassConstraint) {
+        self.constraints.constraints.push(constraint);
+    }
+
+    pub fn read_texture(&mut self, input: ResourceId) {
+        self.inputs
+            .textures
+            .push((input, ResourceUsage::TextureRead))
+    }
+
+    // bound as a storage texture
+    pub fn write_texture(&mut self, output: ResourceId) {
+        self.outputs
+            .textures
+            .push((output, ResourceUsage::TextureWrite))
+    }
+
+    pub fn read_write_texture(&mut self, t: ResourceId) {
+        self.inputs
+            .textures
+            .push((t, ResourceUsage::TextureReadAndWrite));
+        self.outputs
+            .textures
+            .push((t, ResourceUsage::TextureReadAndWrite));
+    }
+
+    pub fn read_buffer(&mut self, input: ResourceId) {
+        self.inputs.buffers.push((input, ResourceUsage::BufferRead))
+    }
+
+    pub fn write_buffer(&mut self, output: ResourceId) {
+        self.outputs
+            .buffers
+            .push((output, ResourceUsage::BufferWrite))
+    }
+
+    pub fn read_write_buffer(&mut self, b: ResourceId) {
+        self.inputs
+            .buffers
+            .push((b, ResourceUsage::BufferReadAndWrite));
+        self.outputs
+            .buffers
+            .push((b, ResourceUsage::BufferReadAndWrite));
+    }
+
+    pub fn async_execute(&mut self, exec: Arc<Mutex<dyn ComputePassExecutor>>) {
+        self.inner = Some(exec);
+    }
+
+    pub fn build(self) -> ComputePass {
+        ComputePass {
+            inner: self.inner.unwrap(),
+            name: self.name,
+            inputs: self.inputs,
+            outputs: self.outputs,
+            render_targets: RenderTargetDescriptor {
+                colors: smallvec::smallvec![],
+                depth: None,
+            },
+            constraints: self.constraints,
+        }
+    }
+}
+
 pub struct ClearPass {
     name: String,
     inputs: PassParameter,
@@ -0,0 +1,214 @@
+use std::collections::HashMap;
+
+use crate::{
+    context::{RContextRef, ResourceRef},
+    types::Vec3u,
+};
+
+use super::{backend::GraphBackend, resource::ResourceType};
+
+// transient resources with the same key share the underlying resource
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum PoolKey {
+    Texture {
+        size: Vec3u,
+        format: wgpu::TextureFormat,
+        usage: wgpu::TextureUsages,
+        sampler_count: u32,
+    },
+    Buffer {
+        size: u64,
+        usage: wgpu::BufferUsages,
+    },
+}
+
+impl PoolKey {
+    // imported resources are not pooled
+    pub fn new(ty: &ResourceType) -> Option<Self> {
+        match ty {
+            ResourceType::Texture(t) => Some(Self::Texture {
+                size: t.size,
+                format: t.format,
+                usage: t.usage,
+                sampler_count: t.sampler_count,
+            }),
+            ResourceType::Buffer(b) => Some(Self::Buffer {
+                size: b.size,
+                usage: b.usage,
+            }),
+            _ => None,
+        }
+    }
+
+    // estimated, the driver may pad
+    pub fn bytes(&self) -> u64 {
+        match self {
+            Self::Texture {
+                size,
+                format,
+                sampler_count,
+                ..
+            } => {
+                let (bw, bh) = format.block_dimensions();
+                let block = format.block_copy_size(None).unwrap_or(4) as u64;
+                let w = size.x.div_ceil(bw) as u64;
+                let h = size.y.div_ceil(bh) as u64;
+                w * h * size.z as u64 * block * *sampler_count as u64
+            }
+            Self::Buffer { size, .. } => *size,
+        }
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy)]
+pub struct ResourcePoolStats {
+    // bytes of the transient resources of the last frame
+    pub requested_bytes: u64,
+    // created by the last frame
+    pub allocated_bytes: u64,
+    // served by the pool in the last frame
+    pub pooled_bytes: u64,
+    // part of `pooled_bytes` released by a resource of the same frame
+    pub aliased_bytes: u64,
+    // held by the pool
+    pub resident_bytes: u64,
+    pub resident_count: usize,
+}
+
+#[derive(Debug)]
+struct PoolEntry {
+    res: ResourceRef,
+    // frame it was released in
+    frame: u64,
+}
+
+// resources released by the graph are kept across frames and handed to the
+// next resource with the same key. a resource released after its last pass can
+// be acquired by a later pass of the same frame, so resources with disjoint
+// lifetimes alias. wgpu can't place resources of different descriptors in the
+// same memory, aliasing is limited to equal keys
+#[derive(Debug)]
+pub struct ResourcePool {
+    free: HashMap<PoolKey, Vec<PoolEntry>>,
+    frame: u64,
+    max_unused_frames: u64,
+    current: ResourcePoolStats,
+    stats: ResourcePoolStats,
+    context: Option<RContextRef>,
+}
+
+impl Default for ResourcePool {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ResourcePool {
+    pub fn new() -> Self {
+        Self {
+            free: HashMap::new(),
+            frame: 0,
+            max_unused_frames: 8,
+            current: ResourcePoolStats::default(),
+            stats: ResourcePoolStats::default(),
+            context: None,
+        }
+    }
+
+    // free resources unused for more frames are destroyed
+    pub fn set_max_unused_frames(&mut self, frames: u64) {
+        self.max_unused_frames = frames;
+    }
+
+    pub fn acquire(
+        &mut self,
+        key: &PoolKey,
+        ty: &ResourceType,
+        backend: &GraphBackend,
+    ) -> ResourceRef {
+        if self.context.is_none() {
+            self.context = Some(backend.gpu().context_ref());
+        }
+        let bytes = key.bytes();
+        self.current.requested_bytes += bytes;
+
+        if let Some(entry) = self.free.get_mut(key).and_then(|v| v.pop()) {
+            self.current.pooled_bytes += bytes;
+            if entry.frame == self.frame {
+                self.current.aliased_bytes += bytes;
+            }
+            // new buffers are zeroed, reused ones must look the same
+            if let PoolKey::Buffer { .. } = key {
+                backend.clear_buffer(&entry.res);
+            }
+            return entry.res;
+        }
+
+        self.current.allocated_bytes += bytes;
+        self.current.resident_bytes += bytes;
+        self.current.resident_count += 1;
+        backend.create_resource(ty)
+    }
+
+    pub fn release(&mut self, key: PoolKey, res: ResourceRef) {
+        self.free.entry(key).or_default().push(PoolEntry {
+            res,
+            frame: self.frame,
+        });
+    }
+
+    #[profiling::function]
+    pub fn end_frame(&mut self) {
+        let frame = self.frame;
+        let max_unused_frames = self.max_unused_frames;
+        let mut removed = vec![];
+        for (key, entries) in &mut self.free {
+            entries.retain(|e| {
+                let keep = frame - e.frame <= max_unused_frames;
+                if !keep {
+                    removed.push((key.bytes(), e.res.clone()));
+                }
+                keep
+            });
+        }
+        self.free.retain(|_, v| !v.is_empty());
+        for (bytes, res) in removed {
+            self.current.resident_bytes -= bytes;
+            self.current.resident_count -= 1;
+            if let Some(context) = &self.context {
+                context.deregister(res);
+            }
+        }
+
+        self.stats = self.current;
+        self.current = ResourcePoolStats {
+            resident_bytes: self.stats.resident_bytes,
+            resident_count: self.stats.resident_count,
+            ..Default::default()
+        };
+        self.frame += 1;
+    }
+
+    pub fn stats(&self) -> ResourcePoolStats {
+        self.stats
+    }
+
+    // destroys every free resource
+    pub fn clear(&mut self) {
+        for (key, entries) in self.free.drain() {
+            for e in entries {
+                self.current.resident_bytes -= key.bytes();
+                self.current.resident_count -= 1;
+                if let Some(context) = &self.context {
+                    context.deregister(e.res);
+                }
+            }
+        }
+    }
+}
+
+impl Drop for ResourcePool {
+    fn drop(&mut self) {
+        self.clear();
+    }
+}
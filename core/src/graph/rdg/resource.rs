@@ -1,5 +1,7 @@
 use std::fmt::Debug;
 
+use serde::Serialize;
+
 use crate::{backends::wgpu_backend::ClearValue, types::Vec3u};
 
 pub type ResourceId = u32;
@@ -7,15 +9,19 @@ pub const RT_COLOR_RESOURCE_ID: ResourceId = 0;
 pub const RT_RESOLVE_COLOR_RESOURCE_ID: ResourceId = 1;
 pub const RT_DEPTH_RESOURCE_ID: ResourceId = 2;
 
-#[derive(Debug, Clone, Eq, PartialEq)]
+#[derive(Debug, Clone, Eq, PartialEq, Serialize)]
 pub enum ResourceUsage {
     TextureRead,
     TextureWrite,
     TextureReadAndWrite,
     PipelineBuffer,
     BufferRead,
+    BufferWrite,
+    BufferReadAndWrite,
     RenderTargetTextureRead,
     RenderTargetTextureWrite,
+    // orders two passes, no resource is shared
+    Order,
 }
 
 #[derive(Debug)]
@@ -38,11 +44,18 @@ pub struct BufferInfo {
     pub usage: wgpu::BufferUsages,
 }
 
+#[derive(Debug)]
+pub struct ImportBufferInfo {
+    // the imported buffer must have at least this size and usage
+    pub size: u64,
+    pub usage: wgpu::BufferUsages,
+}
+
 pub enum ResourceType {
     Texture(TextureInfo),
     Buffer(BufferInfo),
     ImportTexture(ImportTextureInfo),
-    ImportBuffer(ResourceId),
+    ImportBuffer(ImportBufferInfo),
     AliasResource(ResourceId, ResourceId),
 }
 
@@ -1,36 +1,109 @@
 use std::collections::HashMap;
 
-use crate::scene::Transform;
+use crate::scene::{Scene, Transform};
 
 pub type NodeId = u64;
-type Graph<P> = petgraph::graph::DiGraph<Node<P>, (), u32>;
+
+pub const ROOT_NODE: NodeId = 0;
+
+// something which follows the world transform of a scene node, e.g. a light
+pub trait NodeAttachment: Send + Sync {
+    fn update_transform(&self, world: &Transform);
+
+    // the node holding it was removed from the scene
+    fn detach(&self, _scene: &Scene) {}
+}
 
 pub struct SceneGraph<P> {
     root: NodeId,
     last_node_id: NodeId,
 
     nodes: HashMap<NodeId, Box<Node<P>>>,
+    names: HashMap<String, Vec<NodeId>>,
 }
 
 pub struct Node<P> {
+    name: String,
     object: P,
     local_transform: Transform,
-    word_transform: Transform,
-    children: Vec<u64>,
+    world_transform: Transform,
+    parent: Option<NodeId>,
+    children: Vec<NodeId>,
+    dirty: bool,
 }
 
 impl<P> Node<P> {
     pub fn new(object: P) -> Self {
         Self {
+            name: String::default(),
             object,
             local_transform: Transform::default(),
-            word_transform: Transform::default(),
+            world_transform: Transform::default(),
+            parent: None,
             children: Vec::new(),
+            dirty: true,
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn object(&self) -> &P {
+        &self.object
+    }
+
+    pub fn object_mut(&mut self) -> &mut P {
+        &mut self.object
+    }
+
+    pub fn local_transform(&self) -> &Transform {
+        &self.local_transform
+    }
+
+    pub fn world_transform(&self) -> &Transform {
+        &self.world_transform
+    }
+
+    pub fn parent(&self) -> Option<NodeId> {
+        self.parent
+    }
+
+    pub fn children(&self) -> &[NodeId] {
+        &self.children
+    }
+
+    pub fn is_dirty(&self) -> bool {
+        self.dirty
+    }
+}
+
+impl<P: Default> Default for SceneGraph<P> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<P: Default> SceneGraph<P> {
+    pub fn new() -> Self {
+        let mut nodes = HashMap::new();
+        let mut root = Node::new(P::default());
+        root.name = "root".to_owned();
+        nodes.insert(ROOT_NODE, Box::new(root));
+        Self {
+            root: ROOT_NODE,
+            last_node_id: ROOT_NODE + 1,
+            nodes,
+            names: HashMap::new(),
         }
     }
 }
 
 impl<P> SceneGraph<P> {
+    pub fn root(&self) -> NodeId {
+        self.root
+    }
+
     pub fn root_node(&self) -> &Node<P> {
         self.nodes.get(&self.root).unwrap()
     }
@@ -38,21 +111,38 @@ impl<P> SceneGraph<P> {
         self.nodes.get_mut(&self.root).unwrap()
     }
 
-    pub fn add_to_root(&mut self, object: P) -> NodeId {
-        let node_id = self.last_node_id;
-        self.last_node_id += 1;
-        self.nodes.insert(node_id, Box::new(Node::new(object)));
+    pub fn get(&self, node_id: NodeId) -> Option<&Node<P>> {
+        self.nodes.get(&node_id).map(|v| v.as_ref())
+    }
 
-        let root_node = self.root_node_mut();
-        root_node.children.push(node_id);
+    pub fn get_mut(&mut self, node_id: NodeId) -> Option<&mut Node<P>> {
+        self.nodes.get_mut(&node_id).map(|v| v.as_mut())
+    }
 
-        node_id
+    pub fn len(&self) -> usize {
+        self.nodes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.nodes.len() <= 1
+    }
+
+    pub fn add_to_root(&mut self, object: P) -> NodeId {
+        self.add_to_node(self.root, object)
     }
 
     pub fn add_to_node(&mut self, parent_node_id: NodeId, object: P) -> NodeId {
+        let parent_node_id = if self.nodes.contains_key(&parent_node_id) {
+            parent_node_id
+        } else {
+            log::warn!("parent node {} not found, add to root", parent_node_id);
+            self.root
+        };
         let node_id = self.last_node_id;
         self.last_node_id += 1;
-        self.nodes.insert(node_id, Box::new(Node::new(object)));
+        let mut node = Node::new(object);
+        node.parent = Some(parent_node_id);
+        self.nodes.insert(node_id, Box::new(node));
 
         let parent_node = self.nodes.get_mut(&parent_node_id).unwrap();
         parent_node.children.push(node_id);
@@ -60,5 +150,141 @@ impl<P> SceneGraph<P> {
         node_id
     }
 
-    pub fn update(&mut self) {}
+    pub fn set_name(&mut self, node_id: NodeId, name: &str) {
+        let node = match self.nodes.get_mut(&node_id) {
+            Some(v) => v,
+            None => return,
+        };
+        let old = std::mem::replace(&mut node.name, name.to_owned());
+        if let Some(ids) = self.names.get_mut(&old) {
+            ids.retain(|v| *v != node_id);
+            if ids.is_empty() {
+                self.names.remove(&old);
+            }
+        }
+        if !name.is_empty() {
+            self.names.entry(name.to_owned()).or_default().push(node_id);
+        }
+    }
+
+    pub fn find_by_name(&self, name: &str) -> Option<NodeId> {
+        self.names.get(name).and_then(|v| v.first().cloned())
+    }
+
+    pub fn find_all_by_name(&self, name: &str) -> &[NodeId] {
+        self.names.get(name).map(|v| v.as_slice()).unwrap_or(&[])
+    }
+
+    pub fn set_local_transform(&mut self, node_id: NodeId, transform: Transform) {
+        if let Some(node) = self.nodes.get_mut(&node_id) {
+            node.local_transform = transform;
+            node.dirty = true;
+        }
+    }
+
+    // true if `node_id` is `ancestor` or lives below it
+    pub fn is_descendant(&self, node_id: NodeId, ancestor: NodeId) -> bool {
+        let mut cur = Some(node_id);
+        while let Some(id) = cur {
+            if id == ancestor {
+                return true;
+            }
+            cur = self.nodes.get(&id).and_then(|v| v.parent);
+        }
+        false
+    }
+
+    pub fn attach(&mut self, node_id: NodeId, parent_node_id: NodeId) -> bool {
+        if node_id == self.root
+            || !self.nodes.contains_key(&node_id)
+            || !self.nodes.contains_key(&parent_node_id)
+            || self.is_descendant(parent_node_id, node_id)
+        {
+            return false;
+        }
+        let node = self.nodes.get_mut(&node_id).unwrap();
+        let old_parent = node.parent.replace(parent_node_id);
+        node.dirty = true;
+
+        if let Some(old_parent) = old_parent.and_then(|v| self.nodes.get_mut(&v)) {
+            old_parent.children.retain(|v| *v != node_id);
+        }
+        self.nodes
+            .get_mut(&parent_node_id)
+            .unwrap()
+            .children
+            .push(node_id);
+        true
+    }
+
+    pub fn reparent(&mut self, node_id: NodeId, parent_node_id: NodeId) -> bool {
+        self.attach(node_id, parent_node_id)
+    }
+
+    // move the node back to the root, its local transform is kept
+    pub fn detach(&mut self, node_id: NodeId) -> bool {
+        self.attach(node_id, self.root)
+    }
+
+    // remove the node with all children, returns the objects in depth first order
+    pub fn remove(&mut self, node_id: NodeId) -> Vec<P> {
+        if node_id == self.root || !self.nodes.contains_key(&node_id) {
+            return vec![];
+        }
+        let ids = self.subtree(node_id);
+        if let Some(parent) = self.nodes[&node_id].parent {
+            if let Some(parent) = self.nodes.get_mut(&parent) {
+                parent.children.retain(|v| *v != node_id);
+            }
+        }
+        let mut res = vec![];
+        for id in ids {
+            self.set_name(id, "");
+            if let Some(node) = self.nodes.remove(&id) {
+                res.push(node.object);
+            }
+        }
+        res
+    }
+
+    pub fn subtree(&self, node_id: NodeId) -> Vec<NodeId> {
+        let mut res = vec![];
+        let mut stack = vec![node_id];
+        while let Some(id) = stack.pop() {
+            if let Some(node) = self.nodes.get(&id) {
+                res.push(id);
+                stack.extend(node.children.iter().rev());
+            }
+        }
+        res
+    }
+
+    // propagate local transforms to world transforms, returns nodes whose world transform changed
+    pub fn update(&mut self) -> Vec<NodeId> {
+        let mut changed = vec![];
+        let mut stack = vec![(self.root, false, None)];
+
+        while let Some((id, parent_dirty, parent_world)) = stack.pop() {
+            let node = match self.nodes.get_mut(&id) {
+                Some(v) => v,
+                None => continue,
+            };
+            let dirty = node.dirty || parent_dirty;
+            if dirty {
+                let mut world = node.local_transform.clone();
+                if let Some(parent_world) = &parent_world {
+                    world.mul_mut(parent_world);
+                }
+                node.world_transform = world;
+                node.dirty = false;
+                changed.push(id);
+            }
+            let world = node.world_transform.clone();
+            for child in node.children.iter().rev() {
+                stack.push((*child, dirty, Some(world.clone())));
+            }
+        }
+
+        changed
+    }
 }
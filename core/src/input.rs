@@ -0,0 +1,546 @@
+use std::{
+    collections::{BTreeMap, HashSet},
+    path::Path,
+    sync::{Arc, RwLock},
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    event::{InputEvent, MouseButton, VirtualKeyCode},
+    types::Vec2f,
+};
+
+// actions and axes of the built-in camera controllers
+pub const ACTION_ORBIT: &str = "camera.orbit";
+pub const ACTION_PAN: &str = "camera.pan";
+pub const ACTION_ZOOM: &str = "camera.zoom";
+pub const ACTION_LOOK: &str = "camera.look";
+pub const ACTION_FAST: &str = "camera.fast";
+pub const ACTION_SLOW: &str = "camera.slow";
+pub const AXIS_MOVE_X: &str = "camera.move_x";
+pub const AXIS_MOVE_Y: &str = "camera.move_y";
+pub const AXIS_MOVE_Z: &str = "camera.move_z";
+pub const AXIS_WHEEL: &str = "camera.wheel";
+
+// modifiers that must be held. of the bindings of one input, only the ones
+// with the most modifiers held fire, ctrl + lmb doesn't fire a lmb binding
+// when a ctrl + lmb binding exists
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct Chord {
+    #[serde(default)]
+    pub ctrl: bool,
+    #[serde(default)]
+    pub alt: bool,
+    #[serde(default)]
+    pub shift: bool,
+}
+
+impl Chord {
+    pub fn ctrl() -> Self {
+        Self {
+            ctrl: true,
+            ..Default::default()
+        }
+    }
+
+    pub fn alt() -> Self {
+        Self {
+            alt: true,
+            ..Default::default()
+        }
+    }
+
+    pub fn shift() -> Self {
+        Self {
+            shift: true,
+            ..Default::default()
+        }
+    }
+
+    fn held_in(&self, modifiers: &Chord) -> bool {
+        (!self.ctrl || modifiers.ctrl)
+            && (!self.alt || modifiers.alt)
+            && (!self.shift || modifiers.shift)
+    }
+
+    fn count(&self) -> u32 {
+        self.ctrl as u32 + self.alt as u32 + self.shift as u32
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Input {
+    Key(VirtualKeyCode),
+    Mouse(MouseButton),
+    // a modifier held on its own
+    Ctrl,
+    Alt,
+    Shift,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ActionBinding {
+    pub input: Input,
+    #[serde(default)]
+    pub modifiers: Chord,
+}
+
+impl ActionBinding {
+    pub fn new(input: Input) -> Self {
+        Self {
+            input,
+            modifiers: Chord::default(),
+        }
+    }
+
+    pub fn with(mut self, modifiers: Chord) -> Self {
+        self.modifiers = modifiers;
+        self
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum AxisSource {
+    // -1 while `negative` is held, 1 while `positive`
+    Keys {
+        negative: VirtualKeyCode,
+        positive: VirtualKeyCode,
+    },
+    // pixels moved since the last frame
+    MouseX,
+    MouseY,
+    // wheel steps since the last frame
+    Wheel,
+}
+
+fn one() -> f32 {
+    1f32
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AxisBinding {
+    pub source: AxisSource,
+    #[serde(default)]
+    pub modifiers: Chord,
+    #[serde(default = "one")]
+    pub scale: f32,
+}
+
+impl AxisBinding {
+    pub fn new(source: AxisSource) -> Self {
+        Self {
+            source,
+            modifiers: Chord::default(),
+            scale: 1f32,
+        }
+    }
+
+    pub fn with(mut self, modifiers: Chord) -> Self {
+        self.modifiers = modifiers;
+        self
+    }
+
+    pub fn scale(mut self, scale: f32) -> Self {
+        self.scale = scale;
+        self
+    }
+}
+
+// named actions and axes, an action is held while any of its bindings is and
+// an axis sums its bindings. stored as ron
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct InputMap {
+    #[serde(default)]
+    pub actions: BTreeMap<String, Vec<ActionBinding>>,
+    #[serde(default)]
+    pub axes: BTreeMap<String, Vec<AxisBinding>>,
+}
+
+impl InputMap {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // bindings of the camera controllers
+    pub fn camera() -> Self {
+        let mouse = |b| ActionBinding::new(Input::Mouse(b));
+        let keys = |negative, positive| AxisBinding::new(AxisSource::Keys { negative, positive });
+        Self::new()
+            .action(ACTION_ORBIT, mouse(MouseButton::Left))
+            .action(ACTION_PAN, mouse(MouseButton::Right))
+            .action(ACTION_ZOOM, mouse(MouseButton::Middle))
+            .action(ACTION_LOOK, mouse(MouseButton::Left))
+            .action(ACTION_LOOK, mouse(MouseButton::Right))
+            .action(ACTION_FAST, ActionBinding::new(Input::Shift))
+            .action(ACTION_SLOW, ActionBinding::new(Input::Ctrl))
+            .axis(AXIS_MOVE_X, keys(VirtualKeyCode::A, VirtualKeyCode::D))
+            .axis(AXIS_MOVE_Y, keys(VirtualKeyCode::Q, VirtualKeyCode::E))
+            .axis(AXIS_MOVE_Z, keys(VirtualKeyCode::S, VirtualKeyCode::W))
+            .axis(AXIS_WHEEL, AxisBinding::new(AxisSource::Wheel))
+    }
+
+    pub fn action<S: Into<String>>(mut self, name: S, binding: ActionBinding) -> Self {
+        self.actions.entry(name.into()).or_default().push(binding);
+        self
+    }
+
+    pub fn axis<S: Into<String>>(mut self, name: S, binding: AxisBinding) -> Self {
+        self.axes.entry(name.into()).or_default().push(binding);
+        self
+    }
+
+    // bindings of `other` replace the bindings with the same name
+    pub fn merge(&mut self, other: InputMap) {
+        self.actions.extend(other.actions);
+        self.axes.extend(other.axes);
+    }
+
+    pub fn parse(s: &str) -> anyhow::Result<Self> {
+        ron::from_str(s).map_err(|e| anyhow::anyhow!("invalid input map: {}", e))
+    }
+
+    pub fn save(&self) -> anyhow::Result<String> {
+        Ok(ron::ser::to_string_pretty(
+            self,
+            ron::ser::PrettyConfig::default(),
+        )?)
+    }
+
+    pub fn load_from_file<P: AsRef<Path>>(path: P) -> anyhow::Result<Self> {
+        let s = std::fs::read_to_string(path)?;
+        Self::parse(&s)
+    }
+
+    pub fn save_to_file<P: AsRef<Path>>(&self, path: P) -> anyhow::Result<()> {
+        let s = self.save()?;
+        std::fs::write(path, s)?;
+        Ok(())
+    }
+}
+
+// the bindings of a window, shared by the input states of its controllers.
+// rebinding takes effect at the next query
+#[derive(Debug, Clone)]
+pub struct SharedInputMap(Arc<RwLock<Arc<InputMap>>>);
+
+impl Default for SharedInputMap {
+    fn default() -> Self {
+        Self::new(InputMap::camera())
+    }
+}
+
+impl SharedInputMap {
+    pub fn new(map: InputMap) -> Self {
+        Self(Arc::new(RwLock::new(Arc::new(map))))
+    }
+
+    pub fn get(&self) -> Arc<InputMap> {
+        self.0.read().unwrap().clone()
+    }
+
+    pub fn set(&self, map: InputMap) {
+        log::info!("set input map {:?}", map);
+        *self.0.write().unwrap() = Arc::new(map);
+    }
+
+    // bindings in the file replace the current bindings of the same name
+    pub fn load<P: AsRef<Path>>(&self, path: P) -> anyhow::Result<()> {
+        let file = InputMap::load_from_file(path)?;
+        let mut map = (*self.get()).clone();
+        map.merge(file);
+        self.set(map);
+        Ok(())
+    }
+}
+
+// raw input of a consumer, feed it every input event and call `end_frame`
+// after reading the frame
+#[derive(Debug, Default)]
+pub struct InputState {
+    map: SharedInputMap,
+    keys: HashSet<VirtualKeyCode>,
+    buttons: HashSet<MouseButton>,
+    modifiers: Chord,
+    cursor: Option<Vec2f>,
+    motion: Vec2f,
+    wheel: Vec2f,
+    // actions held at the last `end_frame`
+    last_actions: HashSet<String>,
+}
+
+impl InputState {
+    // reads the camera bindings until `set_map`
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn set_map(&mut self, map: SharedInputMap) {
+        self.map = map;
+    }
+
+    pub fn map(&self) -> &SharedInputMap {
+        &self.map
+    }
+
+    pub fn on_input(&mut self, event: &InputEvent) {
+        match event {
+            InputEvent::KeyboardInput(i) => {
+                if i.state.is_pressed() {
+                    self.keys.insert(i.vk);
+                } else {
+                    self.keys.remove(&i.vk);
+                }
+            }
+            InputEvent::ModifiersChanged(m) => {
+                self.modifiers = Chord {
+                    ctrl: m.ctrl,
+                    alt: m.alt,
+                    shift: m.shift,
+                };
+            }
+            InputEvent::CursorMoved {
+                logical: _,
+                physical,
+            } => {
+                if let Some(cursor) = self.cursor {
+                    self.motion += *physical - cursor;
+                }
+                self.cursor = Some(*physical);
+            }
+            InputEvent::MouseWheel { delta } => {
+                self.wheel += delta.xy();
+            }
+            InputEvent::MouseInput { state, button } => {
+                if state.is_pressed() {
+                    self.buttons.insert(*button);
+                } else {
+                    self.buttons.remove(button);
+                }
+            }
+            // releases are not seen while another widget has the focus
+            InputEvent::CaptureKeyboardInputIn => {
+                self.keys.clear();
+                self.modifiers = Chord::default();
+            }
+            InputEvent::CursorLeft | InputEvent::CaptureMouseInputIn => {
+                self.cursor = None;
+            }
+            _ => (),
+        }
+    }
+
+    fn held(&self, input: &Input) -> bool {
+        match input {
+            Input::Key(k) => self.keys.contains(k),
+            Input::Mouse(b) => self.buttons.contains(b),
+            Input::Ctrl => self.modifiers.ctrl,
+            Input::Alt => self.modifiers.alt,
+            Input::Shift => self.modifiers.shift,
+        }
+    }
+
+    // held modifiers of the most specific binding of `input`
+    fn chord_of(&self, map: &InputMap, input: &Input) -> u32 {
+        map.actions
+            .values()
+            .flatten()
+            .filter(|b| b.input == *input && b.modifiers.held_in(&self.modifiers))
+            .map(|b| b.modifiers.count())
+            .max()
+            .unwrap_or_default()
+    }
+
+    fn pressed_in(&self, map: &InputMap, action: &str) -> bool {
+        map.actions.get(action).is_some_and(|bindings| {
+            bindings.iter().any(|b| {
+                self.held(&b.input)
+                    && b.modifiers.held_in(&self.modifiers)
+                    && b.modifiers.count() == self.chord_of(map, &b.input)
+            })
+        })
+    }
+
+    pub fn pressed(&self, action: &str) -> bool {
+        self.pressed_in(&self.map.get(), action)
+    }
+
+    pub fn just_pressed(&self, action: &str) -> bool {
+        self.pressed(action) && !self.last_actions.contains(action)
+    }
+
+    pub fn just_released(&self, action: &str) -> bool {
+        !self.pressed(action) && self.last_actions.contains(action)
+    }
+
+    pub fn axis(&self, axis: &str) -> f32 {
+        let map = self.map.get();
+        let bindings = match map.axes.get(axis) {
+            Some(v) => v,
+            None => return 0f32,
+        };
+        // same as actions, per source
+        let chord = |source: &AxisSource| {
+            map.axes
+                .values()
+                .flatten()
+                .filter(|b| b.source == *source && b.modifiers.held_in(&self.modifiers))
+                .map(|b| b.modifiers.count())
+                .max()
+                .unwrap_or_default()
+        };
+        let key = |k| self.keys.contains(k) as i32 as f32;
+        bindings
+            .iter()
+            .filter(|b| {
+                b.modifiers.held_in(&self.modifiers) && b.modifiers.count() == chord(&b.source)
+            })
+            .map(|b| {
+                let v = match &b.source {
+                    AxisSource::Keys { negative, positive } => key(positive) - key(negative),
+                    AxisSource::MouseX => self.motion.x,
+                    AxisSource::MouseY => self.motion.y,
+                    AxisSource::Wheel => self.wheel.y,
+                };
+                v * b.scale
+            })
+            .sum()
+    }
+
+    // physical position
+    pub fn cursor(&self) -> Option<Vec2f> {
+        self.cursor
+    }
+
+    // pixels the cursor moved since the last frame
+    pub fn motion(&self) -> Vec2f {
+        self.motion
+    }
+
+    pub fn end_frame(&mut self) {
+        let map = self.map.get();
+        self.last_actions = map
+            .actions
+            .keys()
+            .filter(|name| self.pressed_in(&map, name))
+            .cloned()
+            .collect();
+        self.motion = Vec2f::zeros();
+        self.wheel = Vec2f::zeros();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::event::{ElementState, KeyboardInput, ModifiersState};
+
+    use super::*;
+
+    fn modifiers(ctrl: bool, shift: bool) -> InputEvent {
+        InputEvent::ModifiersChanged(ModifiersState {
+            ctrl,
+            win: false,
+            alt: false,
+            shift,
+        })
+    }
+
+    fn mouse(button: MouseButton, pressed: bool) -> InputEvent {
+        InputEvent::MouseInput {
+            state: if pressed {
+                ElementState::Pressed
+            } else {
+                ElementState::Released
+            },
+            button,
+        }
+    }
+
+    fn key(vk: VirtualKeyCode) -> InputEvent {
+        InputEvent::KeyboardInput(KeyboardInput {
+            state: ElementState::Pressed,
+            vk,
+        })
+    }
+
+    fn state(map: InputMap) -> InputState {
+        let mut state = InputState::new();
+        state.set_map(SharedInputMap::new(map));
+        state
+    }
+
+    #[test]
+    fn specific_chord_wins() {
+        let map = InputMap::camera().action(
+            ACTION_PAN,
+            ActionBinding::new(Input::Mouse(MouseButton::Left)).with(Chord::ctrl()),
+        );
+        let mut s = state(map);
+        s.on_input(&mouse(MouseButton::Left, true));
+        assert!(s.pressed(ACTION_ORBIT));
+        assert!(!s.pressed(ACTION_PAN));
+
+        s.on_input(&modifiers(true, false));
+        assert!(!s.pressed(ACTION_ORBIT));
+        assert!(s.pressed(ACTION_PAN));
+
+        // no binding of lmb uses shift
+        s.on_input(&modifiers(false, true));
+        assert!(s.pressed(ACTION_ORBIT));
+        assert!(s.pressed(ACTION_FAST));
+    }
+
+    #[test]
+    fn axis_chords() {
+        let map = InputMap::camera().axis(
+            AXIS_MOVE_Y,
+            AxisBinding::new(AxisSource::Keys {
+                negative: VirtualKeyCode::S,
+                positive: VirtualKeyCode::W,
+            })
+            .with(Chord::ctrl()),
+        );
+        let mut s = state(map);
+        s.on_input(&key(VirtualKeyCode::W));
+        assert_eq!(s.axis(AXIS_MOVE_Z), 1f32);
+        assert_eq!(s.axis(AXIS_MOVE_Y), 0f32);
+
+        s.on_input(&modifiers(true, false));
+        assert_eq!(s.axis(AXIS_MOVE_Z), 0f32);
+        assert_eq!(s.axis(AXIS_MOVE_Y), 1f32);
+
+        // moving fast keeps the move axis
+        s.on_input(&modifiers(false, true));
+        assert_eq!(s.axis(AXIS_MOVE_Z), 1f32);
+    }
+
+    #[test]
+    fn just_pressed_and_released() {
+        let mut s = state(InputMap::camera());
+        s.on_input(&mouse(MouseButton::Right, true));
+        assert!(s.just_pressed(ACTION_PAN));
+        s.end_frame();
+        assert!(s.pressed(ACTION_PAN) && !s.just_pressed(ACTION_PAN));
+        s.on_input(&mouse(MouseButton::Right, false));
+        assert!(s.just_released(ACTION_PAN));
+    }
+
+    #[test]
+    fn shared_map_rebinds() {
+        let map = SharedInputMap::new(InputMap::camera());
+        let mut s = InputState::new();
+        s.set_map(map.clone());
+        s.on_input(&mouse(MouseButton::Middle, true));
+        assert!(s.pressed(ACTION_ZOOM));
+
+        map.set(InputMap::new().action(ACTION_ZOOM, ActionBinding::new(Input::Ctrl)));
+        assert!(!s.pressed(ACTION_ZOOM));
+        // other states keep their own map
+        let mut other = InputState::new();
+        other.on_input(&mouse(MouseButton::Middle, true));
+        assert!(other.pressed(ACTION_ZOOM));
+
+        let parsed = InputMap::parse(&InputMap::camera().save().unwrap()).unwrap();
+        assert_eq!(parsed, InputMap::camera());
+    }
+}
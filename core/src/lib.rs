@@ -5,12 +5,17 @@ pub mod context;
 pub mod debug;
 pub mod event;
 pub mod graph;
+pub mod input;
 pub mod material;
 pub mod mesh;
+pub mod reflect;
 pub mod render;
 pub mod scene;
 pub mod types;
 pub mod cache;
 pub mod util;
 
+#[cfg(test)]
+mod testing;
+
 pub use wgpu;
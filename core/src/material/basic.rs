@@ -10,7 +10,14 @@ use crate::{
     context::ResourceRef, material::input::*, mesh::builder::{InstancePropertyType, MeshPropertyType, INSTANCE_TRANSFORM}, render::pso::BindGroupType, types::{Color, Vec2f}
 };
 
-use super::{bind::{BindingResourceMap, BindingResourceProvider, ShaderBindingResource}, validate_material_properties, MaterialFace};
+use crate::scene::serialize::{
+    BindingValue, LoadedResources, MaterialDesc, MaterialFaceSerializer,
+};
+
+use super::{
+    bind::{BindingResourceMap, BindingResourceProvider, ShaderBindingResource},
+    validate_material_properties, MaterialBuilder, MaterialFace,
+};
 
 pub struct BasicMaterialFace {
     pub(crate) is_instance: bool,
@@ -33,7 +40,9 @@ impl Debug for BasicMaterialFace {
 impl MaterialFace for BasicMaterialFace {
     fn sort_key(&self) -> u64 {
         let mut hasher = fxhash::FxHasher64::default();
-        let tid = if let ShaderBindingResource::Resource(texture) = &self.resource.query_resource("texture") {
+        let tid = if let ShaderBindingResource::Resource(texture) =
+            &self.resource.query_resource("texture_color")
+        {
             texture.id()
         } else {
             0
@@ -60,6 +69,14 @@ impl MaterialFace for BasicMaterialFace {
     ) -> anyhow::Result<()> {
         validate_material_properties(t, i, &self.properties, &self.instance_properties)
     }
+
+    fn params(&self) -> &[&str] {
+        &["const_color", "alpha_test"]
+    }
+
+    fn set_param(&self, key: &str, value: ShaderBindingResource) -> bool {
+        self.params().contains(&key) && self.resource.update(key, value)
+    }
 }
 
 impl BindingResourceProvider for BasicMaterialFace {
@@ -69,6 +86,12 @@ impl BindingResourceProvider for BasicMaterialFace {
     fn bind_group(&self) -> crate::render::pso::BindGroupType {
         self.resource.bind_group()
     }
+    fn uses_resource(&self, id: u64) -> bool {
+        self.resource.uses_resource(id)
+    }
+    fn version(&self) -> u64 {
+        self.resource.version()
+    }
 }
 
 #[derive(Default, Debug, Clone)]
@@ -152,7 +175,7 @@ impl BasicMaterialFaceBuilder {
                 InputResourceIterItem::Texture(t) => {
                     variants.add_flag("TEXTURE");
                     properties.push(MeshPropertyType::new::<Vec2f>("texture"));
-                    resource.upsert("const_color", t.clone());
+                    resource.upsert("texture_color", t.clone());
                 }
                 InputResourceIterItem::Instance => {
                     // variants.add_flag("INSTANCE");
@@ -161,6 +184,9 @@ impl BasicMaterialFaceBuilder {
                 }
             }
         }
+        if let Some(sampler) = &self.sampler {
+            resource.upsert("sampler_tex", sampler.clone());
+        }
         if self.alpha_test.is_enable() {
             variants.add_flag("ALPHA_TEST");
             resource.upsert("alpha_test", self.alpha_test.cutoff().unwrap());
@@ -180,3 +206,146 @@ impl BasicMaterialFaceBuilder {
         face
     }
 }
+
+pub struct BasicMaterialFaceSerializer;
+
+impl MaterialFaceSerializer for BasicMaterialFaceSerializer {
+    fn name(&self) -> &str {
+        "basic_material"
+    }
+
+    fn keys(&self) -> &[&str] {
+        &["const_color", "texture_color", "sampler_tex", "alpha_test"]
+    }
+
+    fn load(
+        &self,
+        desc: &MaterialDesc,
+        resources: &LoadedResources,
+        builder: MaterialBuilder,
+    ) -> anyhow::Result<MaterialBuilder> {
+        let has = |flag: &str| desc.variants.iter().any(|v| v == flag);
+        let mut face = BasicMaterialFaceBuilder::new();
+        let mut texture = InputResourceBuilder::new();
+
+        if has("CONST_COLOR") {
+            match desc.params.get("const_color") {
+                Some(BindingValue::Float4(c)) => texture.add_constant(Color::from(*c)),
+                _ => anyhow::bail!("material {} missing const_color", desc.name),
+            }
+        }
+        if has("VERTEX_COLOR") {
+            texture.add_pre_vertex();
+        }
+        if has("CONST_COLOR_INSTANCE") {
+            texture.add_instance();
+        }
+        if has("TEXTURE") {
+            match desc.resource("texture_color", resources) {
+                Some(t) => texture.add_texture(t),
+                None => anyhow::bail!("material {} missing texture_color", desc.name),
+            }
+            match desc.resource("sampler_tex", resources) {
+                Some(s) => face.set_sampler(s),
+                None => anyhow::bail!("material {} missing sampler_tex", desc.name),
+            }
+        }
+        face.set_texture(texture.build());
+
+        if has("ALPHA_TEST") {
+            match desc.params.get("alpha_test") {
+                Some(BindingValue::Float(cut)) => face.set_alpha_test(*cut),
+                _ => anyhow::bail!("material {} missing alpha_test", desc.name),
+            }
+        }
+        if has("INSTANCE") {
+            face.set_instance();
+        }
+
+        Ok(builder.face(face.build()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // keys must match the bindings of shaders/basic/forward.wgsl
+    #[test]
+    fn texture_binding_keys() {
+        let device = match crate::testing::gpu() {
+            Some(v) => v.device,
+            None => return,
+        };
+        let context = crate::context::RContext::new();
+        let texture = context.register_texture(device.create_texture(&wgpu::TextureDescriptor {
+            label: None,
+            size: wgpu::Extent3d::default(),
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: wgpu::TextureFormat::Rgba8Unorm,
+            usage: wgpu::TextureUsages::TEXTURE_BINDING,
+            view_formats: &[],
+        }));
+        let sampler =
+            context.register_sampler(device.create_sampler(&wgpu::SamplerDescriptor::default()));
+
+        let mut input = InputResourceBuilder::new();
+        input.add_texture(texture.clone());
+        let face = BasicMaterialFaceBuilder::new()
+            .texture(input.build())
+            .sampler(sampler.clone())
+            .build();
+
+        let id = |key: &str| match face.query_resource(key) {
+            ShaderBindingResource::Resource(r) => Some(r.id()),
+            _ => None,
+        };
+        assert_eq!(id("texture_color"), Some(texture.id()));
+        assert_eq!(id("sampler_tex"), Some(sampler.id()));
+        assert_eq!(id("const_color"), None);
+        assert!(face.uses_resource(texture.id()));
+    }
+
+    #[test]
+    fn params_proxy() {
+        use crate::reflect::ReflectProxy;
+
+        let context = crate::context::RContext::new();
+        let mut input = InputResourceBuilder::new();
+        input.add_constant(Color::new(1f32, 0f32, 0f32, 1f32));
+        let mut material = MaterialBuilder::default()
+            .face(
+                BasicMaterialFaceBuilder::new()
+                    .texture(input.build())
+                    .build(),
+            )
+            .build(&context);
+
+        let mut proxy = material.reflect_proxy();
+        assert_eq!(proxy.params.len(), 1);
+        assert_eq!(
+            proxy.params.get("const_color"),
+            Some(&BindingValue::Float4([1f32, 0f32, 0f32, 1f32]))
+        );
+
+        let version = material.version();
+        proxy.params.insert(
+            "const_color".to_owned(),
+            BindingValue::Float4([0f32, 1f32, 0f32, 1f32]),
+        );
+        material.apply_proxy(&proxy);
+        assert!(matches!(
+            material.query_resource("const_color"),
+            ShaderBindingResource::Float4(c) if c.y == 1f32
+        ));
+        assert_ne!(material.version(), version);
+
+        // a binding keeps its type, missing bindings are not added
+        let face = material.face();
+        assert!(!face.set_param("const_color", ShaderBindingResource::Float(1f32)));
+        assert!(!face.set_param("alpha_test", ShaderBindingResource::Float(0.5f32)));
+        assert!(!material.has_alpha_test());
+    }
+}
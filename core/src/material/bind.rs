@@ -1,4 +1,10 @@
-use std::{collections::HashMap, sync::Mutex};
+use std::{
+    collections::HashMap,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Mutex,
+    },
+};
 
 use crate::{context::ResourceRef, render::pso::BindGroupType, types::{Vec2f, Vec3f, Vec4f}};
 
@@ -101,12 +107,21 @@ impl ShaderBindingResource {
 pub trait BindingResourceProvider {
     fn query_resource(&self, key: &str) -> ShaderBindingResource;
     fn bind_group(&self) -> BindGroupType;
+    // true if a binding is the resource, finds cameras sampling their own target
+    fn uses_resource(&self, _id: u64) -> bool {
+        false
+    }
+    // changes when a binding is updated, cached bind groups are rebuilt
+    fn version(&self) -> u64 {
+        0
+    }
 }
 
 #[derive(Debug)]
 pub struct BindingResourceMap {
     map: Mutex<HashMap<String, ShaderBindingResource>>,
     ty: BindGroupType,
+    version: AtomicU64,
 }
 
 impl BindingResourceMap {
@@ -114,6 +129,7 @@ impl BindingResourceMap {
         Self {
             map: Mutex::new(HashMap::new()),
             ty,
+            version: AtomicU64::new(0),
         }
     }
 
@@ -123,6 +139,17 @@ impl BindingResourceMap {
         r.entry(key.to_string())
             .and_modify(|v| *v = res.clone())
             .or_insert(res);
+        self.version.fetch_add(1, Ordering::Relaxed);
+    }
+
+    // replaces a binding of the same type, other bindings change the shader variant
+    pub fn update(&self, key: &str, res: ShaderBindingResource) -> bool {
+        let same = !matches!(res, ShaderBindingResource::Nothing)
+            && std::mem::discriminant(&self.query_resource(key)) == std::mem::discriminant(&res);
+        if same {
+            self.upsert(key, res);
+        }
+        same
     }
 }
 
@@ -134,4 +161,12 @@ impl BindingResourceProvider for BindingResourceMap {
     fn bind_group(&self) -> BindGroupType {
         self.ty
     }
+    fn uses_resource(&self, id: u64) -> bool {
+        let r = self.map.lock().unwrap();
+        r.values()
+            .any(|v| matches!(v, ShaderBindingResource::Resource(res) if res.id() == id))
+    }
+    fn version(&self) -> u64 {
+        self.version.load(Ordering::Relaxed)
+    }
 }
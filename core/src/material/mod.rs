@@ -1,13 +1,20 @@
 use std::{
-    any::{Any, TypeId}, fmt::Debug, hash::Hash, sync::{Arc, Mutex}
+    any::{Any, TypeId},
+    collections::BTreeMap,
+    fmt::Debug,
+    hash::Hash,
+    sync::{Arc, Mutex},
 };
 
+use bevy_reflect::Reflect;
 use bind::{BindingResourceProvider, ShaderBindingResource};
 use tshader::VariantFlags;
 
 use crate::{
     context::RContext,
     mesh::builder::{InstancePropertyType, MeshPropertyType, PropertiesFrame},
+    reflect::ReflectProxy,
+    scene::serialize::BindingValue,
 };
 
 #[derive(Debug, Hash, Eq, PartialEq, Clone, Copy)]
@@ -37,6 +44,15 @@ pub trait MaterialFace: Any + Sync + Send + Debug + BindingResourceProvider {
     ) -> anyhow::Result<()> {
         Ok(())
     }
+
+    // numeric bindings which can change after the face is built
+    fn params(&self) -> &[&str] {
+        &[]
+    }
+
+    fn set_param(&self, _key: &str, _value: ShaderBindingResource) -> bool {
+        false
+    }
 }
 
 #[derive(Debug)]
@@ -60,6 +76,12 @@ impl BindingResourceProvider for Material {
     fn bind_group(&self) -> crate::render::pso::BindGroupType {
         crate::render::pso::BindGroupType::Material
     }
+    fn uses_resource(&self, id: u64) -> bool {
+        self.face.uses_resource(id)
+    }
+    fn version(&self) -> u64 {
+        self.face.version()
+    }
 }
 
 impl Material {
@@ -187,6 +209,45 @@ impl MaterialBuilder {
     }
 }
 
+// face params of a material, textures and samplers are not edited
+#[derive(Debug, Clone, Default, Reflect)]
+pub struct MaterialProxy {
+    pub params: BTreeMap<String, BindingValue>,
+}
+
+impl ReflectProxy for MaterialArc {
+    type Proxy = MaterialProxy;
+
+    fn reflect_proxy(&self) -> MaterialProxy {
+        let face = self.face();
+        let params = face
+            .params()
+            .iter()
+            .filter_map(|key| {
+                BindingValue::from_value(&face.query_resource(key)).map(|v| (key.to_string(), v))
+            })
+            .collect();
+        MaterialProxy { params }
+    }
+
+    fn apply_proxy(&mut self, proxy: &MaterialProxy) {
+        let face = self.face();
+        for (key, value) in &proxy.params {
+            if BindingValue::from_value(&face.query_resource(key)).as_ref() == Some(value) {
+                continue;
+            }
+            if !face.set_param(key, value.to_value()) {
+                log::warn!(
+                    "material {} can not set {} to {:?}",
+                    self.name(),
+                    key,
+                    value
+                );
+            }
+        }
+    }
+}
+
 pub trait MaterialShader: Any + Sync + Send + Debug + 'static {}
 
 pub mod basic;
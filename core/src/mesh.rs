@@ -1,4 +1,5 @@
 use crate::{scene::Transform, types::*, util::any_as_u8_slice_array};
+use serde::{Deserialize, Serialize};
 use std::{
     fmt::Debug,
     sync::{Arc, Mutex},
@@ -9,9 +10,12 @@ use self::{
     intersect::{IntersectResult, Ray},
 };
 
+pub mod binary;
 pub mod builder;
+pub mod csg;
 pub mod intersect;
 pub mod merge;
+pub mod subdivide;
 
 #[derive(Debug, Default, Clone)]
 pub(crate) enum Indices {
@@ -59,14 +63,25 @@ impl Mesh {
     }
 
     pub fn boundary(&self) -> Boundary {
-        // if self.has_position {
-        //     let mut aabb = BoundBox::default();
-        //     for a in &self.vertices {
-        //         aabb = &aabb + a;
-        //     }
-        //     return Some(aabb);
-        // }
-        Boundary::None
+        let mut aabb = BoundBox::default();
+        match &self.position_vertices {
+            PositionVertices::F3(v) => {
+                for a in v {
+                    aabb = &aabb + a;
+                }
+            }
+            PositionVertices::F4(v) => {
+                for a in v {
+                    aabb = &aabb + &a.xyz();
+                }
+            }
+            _ => (),
+        }
+        if aabb.is_empty() {
+            Boundary::None
+        } else {
+            Boundary::AABB(aabb)
+        }
     }
 
     pub fn clip(&self) -> Option<Rectu> {
@@ -114,12 +129,37 @@ impl Mesh {
         self.vertex_count
     }
 
-    pub fn apply(&mut self, _transform: &Transform) {
-        // let mut tmp = Vec::new();
-        // core::mem::swap(&mut tmp, &mut self.vertices);
+    pub fn apply(&mut self, transform: &Transform) {
+        let mat = transform.mat();
+        if let PositionVertices::F3(vertices) = &mut self.position_vertices {
+            for v in vertices {
+                *v = (mat * Vec4f::new(v.x, v.y, v.z, 1f32)).xyz();
+            }
+        }
 
-        // let vertices = transform.apply_batch(tmp.into_iter()).collect();
-        // self.vertices = vertices;
+        let normal_mat = match mat.fixed_view::<3, 3>(0, 0).try_inverse() {
+            Some(inv) => inv.transpose(),
+            None => return,
+        };
+        let row = self.properties.row_strip_size as usize;
+        for (property, o) in &self.properties.properties_offset {
+            if o.size() != 12 || (property.name != "normal_vertex" && property.name != "normal") {
+                continue;
+            }
+            for i in 0..self.properties.count as usize {
+                let beg = i * row + o.offset() as usize;
+                let data = &mut self.properties.data[beg..beg + 12];
+                let n = Vec3f::from_iterator(
+                    data.chunks_exact(4)
+                        .map(|v| f32::from_ne_bytes(v.try_into().unwrap())),
+                );
+                let n = (normal_mat * n).try_normalize(1e-12).unwrap_or(n);
+                for (dst, v) in data.chunks_exact_mut(4).zip(n.iter()) {
+                    dst.copy_from_slice(&v.to_ne_bytes());
+                }
+            }
+        }
+        self.properties.version += 1;
     }
 }
 
@@ -140,7 +180,7 @@ pub trait Geometry: Send + Sync + Debug {
     fn boundary(&self) -> &Boundary;
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub enum TransformType {
     None,
     Mat4x4,
@@ -178,6 +218,10 @@ impl StaticGeometry {
         self.instance_data = Some(instance);
         self
     }
+    pub fn with_boundary(mut self, boundary: Boundary) -> Self {
+        self.boundary = boundary;
+        self
+    }
 }
 
 impl Geometry for StaticGeometry {
@@ -0,0 +1,407 @@
+use std::{collections::HashSet, sync::Mutex};
+
+use indexmap::{IndexMap, IndexSet};
+
+use crate::types::*;
+
+use super::{
+    builder::{FieldOffset, InstancePropertyType, MeshPropertyType, PropertiesFrame, Property},
+    Indices, Mesh, PositionVertices,
+};
+
+// little endian layout:
+// magic, version, positions, indices, clip, vertex count, properties frame.
+// property rows are little endian too, every property is made of 4 byte
+// components (f32, u32, i32), or 2 or 1 byte ones when its size is not a
+// multiple of 4
+const MESH_MAGIC: &[u8; 4] = b"GMSH";
+const INSTANCE_MAGIC: &[u8; 4] = b"GINS";
+pub const MESH_BINARY_VERSION: u32 = 1;
+
+lazy_static::lazy_static! {
+    static ref PROPERTY_NAMES: Mutex<HashSet<&'static str>> = Mutex::new(HashSet::new());
+}
+
+// property names are &'static str, names read back from a file are leaked once
+fn intern_name(name: &str) -> &'static str {
+    let mut names = PROPERTY_NAMES.lock().unwrap();
+    if let Some(v) = names.get(name) {
+        return v;
+    }
+    let v: &'static str = Box::leak(name.to_owned().into_boxed_str());
+    names.insert(v);
+    v
+}
+
+trait BinaryProperty: Property {
+    fn parts(&self) -> (&'static str, u32, u32);
+    fn from_parts(name: &'static str, size: u32, alignment: u32) -> Self;
+}
+
+impl BinaryProperty for MeshPropertyType {
+    fn parts(&self) -> (&'static str, u32, u32) {
+        (self.name, self.size, self.alignment)
+    }
+    fn from_parts(name: &'static str, size: u32, alignment: u32) -> Self {
+        Self {
+            name,
+            size,
+            alignment,
+        }
+    }
+}
+
+impl BinaryProperty for InstancePropertyType {
+    fn parts(&self) -> (&'static str, u32, u32) {
+        (self.name, self.size, self.alignment)
+    }
+    fn from_parts(name: &'static str, size: u32, alignment: u32) -> Self {
+        Self {
+            name,
+            size,
+            alignment,
+        }
+    }
+}
+
+// swaps the byte order of every property component, only big endian targets
+// need it
+fn swap_components<P: Property>(frame: &PropertiesFrame<P>, data: &mut [u8]) {
+    if frame.row_strip_size == 0 {
+        return;
+    }
+    for row in data.chunks_exact_mut(frame.row_strip_size as usize) {
+        for o in frame.properties_offset.values() {
+            let (offset, size) = (o.offset() as usize, o.size() as usize);
+            let component = match size {
+                v if v % 4 == 0 => 4,
+                v if v % 2 == 0 => 2,
+                _ => 1,
+            };
+            for v in row[offset..offset + size].chunks_exact_mut(component) {
+                v.reverse();
+            }
+        }
+    }
+}
+
+struct Writer {
+    data: Vec<u8>,
+}
+
+impl Writer {
+    fn u8(&mut self, v: u8) {
+        self.data.push(v);
+    }
+    fn u16(&mut self, v: u16) {
+        self.data.extend_from_slice(&v.to_le_bytes());
+    }
+    fn u32(&mut self, v: u32) {
+        self.data.extend_from_slice(&v.to_le_bytes());
+    }
+    fn u64(&mut self, v: u64) {
+        self.data.extend_from_slice(&v.to_le_bytes());
+    }
+    fn f32s<'a>(&mut self, v: impl Iterator<Item = &'a f32>) {
+        for f in v {
+            self.data.extend_from_slice(&f.to_le_bytes());
+        }
+    }
+    fn bytes(&mut self, v: &[u8]) {
+        self.u64(v.len() as u64);
+        self.data.extend_from_slice(v);
+    }
+
+    fn frame<P: BinaryProperty>(&mut self, frame: &PropertiesFrame<P>) {
+        self.u32(frame.properties_offset.len() as u32);
+        for (property, o) in &frame.properties_offset {
+            let (name, size, alignment) = property.parts();
+            self.u16(name.len() as u16);
+            self.data.extend_from_slice(name.as_bytes());
+            self.u32(size);
+            self.u32(alignment);
+            self.u32(o.offset());
+            self.u32(o.size());
+        }
+        self.u32(frame.row_strip_size);
+        self.u32(frame.row_size);
+        self.u64(frame.count);
+        if cfg!(target_endian = "little") {
+            self.bytes(&frame.data);
+        } else {
+            let mut data = frame.data.clone();
+            swap_components(frame, &mut data);
+            self.bytes(&data);
+        }
+    }
+}
+
+struct Reader<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, n: usize) -> anyhow::Result<&'a [u8]> {
+        if self.data.len() - self.pos < n {
+            anyhow::bail!("unexpected end of mesh data at {}", self.pos);
+        }
+        let v = &self.data[self.pos..self.pos + n];
+        self.pos += n;
+        Ok(v)
+    }
+    fn u8(&mut self) -> anyhow::Result<u8> {
+        Ok(self.take(1)?[0])
+    }
+    fn u16(&mut self) -> anyhow::Result<u16> {
+        Ok(u16::from_le_bytes(self.take(2)?.try_into()?))
+    }
+    fn u32(&mut self) -> anyhow::Result<u32> {
+        Ok(u32::from_le_bytes(self.take(4)?.try_into()?))
+    }
+    fn u64(&mut self) -> anyhow::Result<u64> {
+        Ok(u64::from_le_bytes(self.take(8)?.try_into()?))
+    }
+    fn f32s(&mut self, n: usize) -> anyhow::Result<Vec<f32>> {
+        Ok(self
+            .take(n * 4)?
+            .chunks_exact(4)
+            .map(|v| f32::from_le_bytes(v.try_into().unwrap()))
+            .collect())
+    }
+    fn bytes(&mut self) -> anyhow::Result<&'a [u8]> {
+        let n = self.u64()? as usize;
+        self.take(n)
+    }
+
+    fn header(&mut self, magic: &[u8; 4]) -> anyhow::Result<()> {
+        if self.take(4)? != magic {
+            anyhow::bail!("invalid mesh data, magic mismatch");
+        }
+        let version = self.u32()?;
+        if version != MESH_BINARY_VERSION {
+            anyhow::bail!(
+                "unsupported mesh binary version {}, expect {}",
+                version,
+                MESH_BINARY_VERSION
+            );
+        }
+        Ok(())
+    }
+
+    fn frame<P: BinaryProperty>(&mut self) -> anyhow::Result<PropertiesFrame<P>> {
+        let n = self.u32()?;
+        let mut properties = IndexSet::new();
+        let mut properties_offset = IndexMap::new();
+        for _ in 0..n {
+            let len = self.u16()? as usize;
+            let name = std::str::from_utf8(self.take(len)?)?;
+            let size = self.u32()?;
+            let alignment = self.u32()?;
+            let offset = self.u32()?;
+            let len = self.u32()?;
+            let property = P::from_parts(intern_name(name), size, alignment);
+            properties.insert(property);
+            properties_offset.insert(property, FieldOffset::new(offset, len));
+        }
+        let row_strip_size = self.u32()?;
+        let row_size = self.u32()?;
+        let count = self.u64()?;
+        let data = self.bytes()?.to_vec();
+        if data.len() as u64 != row_strip_size as u64 * count {
+            anyhow::bail!(
+                "properties data size {} mismatch, expect {} rows of {}",
+                data.len(),
+                count,
+                row_strip_size
+            );
+        }
+        let mut frame = PropertiesFrame {
+            properties,
+            data,
+            properties_offset,
+            row_strip_size,
+            row_size,
+            count,
+            version: 0,
+        };
+        if cfg!(target_endian = "big") {
+            let mut data = std::mem::take(&mut frame.data);
+            swap_components(&frame, &mut data);
+            frame.data = data;
+        }
+        Ok(frame)
+    }
+}
+
+pub fn write_mesh(mesh: &Mesh) -> Vec<u8> {
+    let mut w = Writer { data: vec![] };
+    w.data.extend_from_slice(MESH_MAGIC);
+    w.u32(MESH_BINARY_VERSION);
+
+    match &mesh.position_vertices {
+        PositionVertices::Unknown => w.u8(0),
+        PositionVertices::None => w.u8(1),
+        PositionVertices::F2(v) => {
+            w.u8(2);
+            w.u32(v.len() as u32);
+            w.f32s(v.iter().flat_map(|v| v.iter()));
+        }
+        PositionVertices::F3(v) => {
+            w.u8(3);
+            w.u32(v.len() as u32);
+            w.f32s(v.iter().flat_map(|v| v.iter()));
+        }
+        PositionVertices::F4(v) => {
+            w.u8(4);
+            w.u32(v.len() as u32);
+            w.f32s(v.iter().flat_map(|v| v.iter()));
+        }
+    }
+
+    match &mesh.indices {
+        Indices::Unknown => w.u8(0),
+        Indices::None => w.u8(1),
+        Indices::U16(v) => {
+            w.u8(2);
+            w.u32(v.len() as u32);
+            for i in v {
+                w.u16(*i);
+            }
+        }
+        Indices::U32(v) => {
+            w.u8(3);
+            w.u32(v.len() as u32);
+            for i in v {
+                w.u32(*i);
+            }
+        }
+    }
+
+    match &mesh.clip {
+        Some(c) => {
+            w.u8(1);
+            for v in c.iter() {
+                w.u32(*v);
+            }
+        }
+        None => w.u8(0),
+    }
+
+    w.u64(mesh.vertex_count);
+    w.frame(&mesh.properties);
+    w.data
+}
+
+pub fn read_mesh(data: &[u8]) -> anyhow::Result<Mesh> {
+    let mut r = Reader { data, pos: 0 };
+    r.header(MESH_MAGIC)?;
+
+    let position_vertices = match r.u8()? {
+        0 => PositionVertices::Unknown,
+        1 => PositionVertices::None,
+        2 => {
+            let n = r.u32()? as usize;
+            PositionVertices::F2(
+                r.f32s(n * 2)?
+                    .chunks_exact(2)
+                    .map(Vec2f::from_column_slice)
+                    .collect(),
+            )
+        }
+        3 => {
+            let n = r.u32()? as usize;
+            PositionVertices::F3(
+                r.f32s(n * 3)?
+                    .chunks_exact(3)
+                    .map(Vec3f::from_column_slice)
+                    .collect(),
+            )
+        }
+        4 => {
+            let n = r.u32()? as usize;
+            PositionVertices::F4(
+                r.f32s(n * 4)?
+                    .chunks_exact(4)
+                    .map(Vec4f::from_column_slice)
+                    .collect(),
+            )
+        }
+        v => anyhow::bail!("invalid position vertices type {}", v),
+    };
+
+    let indices = match r.u8()? {
+        0 => Indices::Unknown,
+        1 => Indices::None,
+        2 => {
+            let n = r.u32()?;
+            Indices::U16((0..n).map(|_| r.u16()).collect::<anyhow::Result<_>>()?)
+        }
+        3 => {
+            let n = r.u32()?;
+            Indices::U32((0..n).map(|_| r.u32()).collect::<anyhow::Result<_>>()?)
+        }
+        v => anyhow::bail!("invalid indices type {}", v),
+    };
+
+    let clip = if r.u8()? != 0 {
+        Some(Rectu::new(r.u32()?, r.u32()?, r.u32()?, r.u32()?))
+    } else {
+        None
+    };
+
+    let vertex_count = r.u64()?;
+    let properties = r.frame()?;
+
+    Ok(Mesh {
+        position_vertices,
+        indices,
+        clip,
+        vertex_count,
+        properties,
+    })
+}
+
+pub fn write_instance(frame: &PropertiesFrame<InstancePropertyType>) -> Vec<u8> {
+    let mut w = Writer { data: vec![] };
+    w.data.extend_from_slice(INSTANCE_MAGIC);
+    w.u32(MESH_BINARY_VERSION);
+    w.frame(frame);
+    w.data
+}
+
+pub fn read_instance(data: &[u8]) -> anyhow::Result<PropertiesFrame<InstancePropertyType>> {
+    let mut r = Reader { data, pos: 0 };
+    r.header(INSTANCE_MAGIC)?;
+    r.frame()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::mesh::builder::MeshPropertiesBuilder;
+
+    use super::*;
+
+    #[test]
+    fn swap_property_components() {
+        let mut builder = MeshPropertiesBuilder::default();
+        let id = MeshPropertyType::new::<[u32; 2]>("id");
+        let half = MeshPropertyType::new::<u16>("half");
+        builder.add_property(id);
+        builder.add_property(half);
+        builder.add_property_data(id, &[[0x0102_0304u32, 0x0506_0708u32]]);
+        builder.add_property_data(half, &[0x0a0bu16]);
+        let frame = builder.build();
+
+        let mut data = frame.data.clone();
+        swap_components(&frame, &mut data);
+        let mut expect = vec![];
+        expect.extend_from_slice(&0x0102_0304u32.swap_bytes().to_ne_bytes());
+        expect.extend_from_slice(&0x0506_0708u32.swap_bytes().to_ne_bytes());
+        expect.extend_from_slice(&0x0a0bu16.swap_bytes().to_ne_bytes());
+        assert_eq!(data, expect);
+
+        swap_components(&frame, &mut data);
+        assert_eq!(data, frame.data);
+    }
+}
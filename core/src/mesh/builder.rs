@@ -15,6 +15,18 @@ pub struct FieldOffset {
     len: u32,
 }
 
+impl FieldOffset {
+    pub(crate) fn new(offset: u32, len: u32) -> Self {
+        Self { offset, len }
+    }
+    pub fn offset(&self) -> u32 {
+        self.offset
+    }
+    pub fn size(&self) -> u32 {
+        self.len
+    }
+}
+
 pub trait Property: Eq + PartialEq + Hash + Clone + Copy + std::fmt::Debug {
     fn size_alignment(&self) -> (u32, u32);
 }
@@ -0,0 +1,689 @@
+use std::collections::HashSet;
+
+use crate::types::{Color, Vec2f, Vec3f};
+
+use super::{
+    builder::{MeshPropertyType, PropertiesFrame},
+    Indices, Mesh, PositionVertices,
+};
+
+const EPSILON: f32 = 1e-5;
+
+const COPLANAR: u8 = 0;
+const FRONT: u8 = 1;
+const BACK: u8 = 2;
+const SPANNING: u8 = 3;
+
+#[derive(Debug, Clone)]
+struct Vertex {
+    pos: Vec3f,
+    // one property row of the source mesh
+    row: Vec<u8>,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Plane {
+    normal: Vec3f,
+    w: f32,
+}
+
+impl Plane {
+    fn from_points(a: Vec3f, b: Vec3f, c: Vec3f) -> Option<Self> {
+        let normal = (b - a).cross(&(c - a)).try_normalize(1e-12)?;
+        Some(Self {
+            normal,
+            w: normal.dot(&a),
+        })
+    }
+
+    fn flip(&mut self) {
+        self.normal = -self.normal;
+        self.w = -self.w;
+    }
+}
+
+#[derive(Debug, Clone)]
+struct Polygon {
+    vertices: Vec<Vertex>,
+    plane: Plane,
+}
+
+#[derive(Debug, Clone)]
+struct Layout {
+    // (offset, size) of the float properties which are blended as packed
+    // f32, the others are copied from the closest endpoint
+    lanes: Vec<(usize, usize)>,
+    normals: Vec<usize>,
+}
+
+impl Layout {
+    fn lerp(&self, a: &Vertex, b: &Vertex, t: f32) -> Vertex {
+        let mut row = if t < 0.5f32 {
+            a.row.clone()
+        } else {
+            b.row.clone()
+        };
+        for (offset, size) in &self.lanes {
+            for beg in (*offset..*offset + *size).step_by(4) {
+                let x = f32::from_ne_bytes(a.row[beg..beg + 4].try_into().unwrap());
+                let y = f32::from_ne_bytes(b.row[beg..beg + 4].try_into().unwrap());
+                row[beg..beg + 4].copy_from_slice(&(x + (y - x) * t).to_ne_bytes());
+            }
+        }
+        Vertex {
+            pos: a.pos + (b.pos - a.pos) * t,
+            row,
+        }
+    }
+
+    fn flip(&self, polygon: &mut Polygon) {
+        polygon.vertices.reverse();
+        for v in &mut polygon.vertices {
+            for offset in &self.normals {
+                for beg in (*offset..*offset + 12).step_by(4) {
+                    let x = f32::from_ne_bytes(v.row[beg..beg + 4].try_into().unwrap());
+                    v.row[beg..beg + 4].copy_from_slice(&(-x).to_ne_bytes());
+                }
+            }
+        }
+        polygon.plane.flip();
+    }
+
+    fn split_polygon(
+        &self,
+        plane: &Plane,
+        polygon: Polygon,
+        coplanar_front: &mut Vec<Polygon>,
+        coplanar_back: &mut Vec<Polygon>,
+        front: &mut Vec<Polygon>,
+        back: &mut Vec<Polygon>,
+    ) {
+        let mut polygon_type = COPLANAR;
+        let mut types = Vec::with_capacity(polygon.vertices.len());
+        for v in &polygon.vertices {
+            let t = plane.normal.dot(&v.pos) - plane.w;
+            let ty = if t < -EPSILON {
+                BACK
+            } else if t > EPSILON {
+                FRONT
+            } else {
+                COPLANAR
+            };
+            polygon_type |= ty;
+            types.push(ty);
+        }
+
+        match polygon_type {
+            COPLANAR => {
+                if plane.normal.dot(&polygon.plane.normal) > 0f32 {
+                    coplanar_front.push(polygon);
+                } else {
+                    coplanar_back.push(polygon);
+                }
+            }
+            FRONT => front.push(polygon),
+            BACK => back.push(polygon),
+            _ => {
+                let mut f = vec![];
+                let mut b = vec![];
+                let n = polygon.vertices.len();
+                for i in 0..n {
+                    let j = (i + 1) % n;
+                    let (ti, tj) = (types[i], types[j]);
+                    let vi = &polygon.vertices[i];
+                    let vj = &polygon.vertices[j];
+                    if ti != BACK {
+                        f.push(vi.clone());
+                    }
+                    if ti != FRONT {
+                        b.push(vi.clone());
+                    }
+                    if (ti | tj) == SPANNING {
+                        let t = (plane.w - plane.normal.dot(&vi.pos))
+                            / plane.normal.dot(&(vj.pos - vi.pos));
+                        let v = self.lerp(vi, vj, t);
+                        f.push(v.clone());
+                        b.push(v);
+                    }
+                }
+                if f.len() >= 3 {
+                    front.push(Polygon {
+                        vertices: f,
+                        plane: polygon.plane,
+                    });
+                }
+                if b.len() >= 3 {
+                    back.push(Polygon {
+                        vertices: b,
+                        plane: polygon.plane,
+                    });
+                }
+            }
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+struct Node {
+    plane: Option<Plane>,
+    front: Option<Box<Node>>,
+    back: Option<Box<Node>>,
+    polygons: Vec<Polygon>,
+}
+
+impl Node {
+    fn new(layout: &Layout, polygons: Vec<Polygon>) -> Self {
+        let mut node = Node::default();
+        node.build(layout, polygons);
+        node
+    }
+
+    // the tree can be as deep as the polygon count, it is walked with an explicit stack
+    fn invert(&mut self, layout: &Layout) {
+        let mut stack = vec![self];
+        while let Some(node) = stack.pop() {
+            for p in &mut node.polygons {
+                layout.flip(p);
+            }
+            if let Some(plane) = &mut node.plane {
+                plane.flip();
+            }
+            std::mem::swap(&mut node.front, &mut node.back);
+            stack.extend(node.front.as_deref_mut());
+            stack.extend(node.back.as_deref_mut());
+        }
+    }
+
+    // remove all polygons inside of this tree
+    fn clip_polygons(&self, layout: &Layout, polygons: Vec<Polygon>) -> Vec<Polygon> {
+        let mut res = vec![];
+        let mut stack = vec![(self, polygons)];
+        while let Some((node, polygons)) = stack.pop() {
+            let plane = match &node.plane {
+                Some(p) => p,
+                None => {
+                    res.extend(polygons);
+                    continue;
+                }
+            };
+            let mut front = vec![];
+            let mut back = vec![];
+            for p in polygons {
+                let mut coplanar_front = vec![];
+                let mut coplanar_back = vec![];
+                layout.split_polygon(
+                    plane,
+                    p,
+                    &mut coplanar_front,
+                    &mut coplanar_back,
+                    &mut front,
+                    &mut back,
+                );
+                front.append(&mut coplanar_front);
+                back.append(&mut coplanar_back);
+            }
+            // front is popped first to keep the front to back order
+            if let Some(n) = &node.back {
+                stack.push((n, back));
+            }
+            match &node.front {
+                Some(n) => stack.push((n, front)),
+                None => res.extend(front),
+            }
+        }
+        res
+    }
+
+    fn clip_to(&mut self, layout: &Layout, bsp: &Node) {
+        let mut stack = vec![self];
+        while let Some(node) = stack.pop() {
+            node.polygons = bsp.clip_polygons(layout, std::mem::take(&mut node.polygons));
+            stack.extend(node.front.as_deref_mut());
+            stack.extend(node.back.as_deref_mut());
+        }
+    }
+
+    fn all_polygons(&self) -> Vec<Polygon> {
+        let mut res = vec![];
+        let mut stack = vec![self];
+        while let Some(node) = stack.pop() {
+            res.extend(node.polygons.iter().cloned());
+            stack.extend(node.back.as_deref());
+            stack.extend(node.front.as_deref());
+        }
+        res
+    }
+
+    fn build(&mut self, layout: &Layout, polygons: Vec<Polygon>) {
+        let mut stack = vec![(self, polygons)];
+        while let Some((node, polygons)) = stack.pop() {
+            if polygons.is_empty() {
+                continue;
+            }
+            let plane = *node.plane.get_or_insert(polygons[0].plane);
+            let mut front = vec![];
+            let mut back = vec![];
+            for p in polygons {
+                let mut coplanar_front = vec![];
+                let mut coplanar_back = vec![];
+                layout.split_polygon(
+                    &plane,
+                    p,
+                    &mut coplanar_front,
+                    &mut coplanar_back,
+                    &mut front,
+                    &mut back,
+                );
+                node.polygons.append(&mut coplanar_front);
+                node.polygons.append(&mut coplanar_back);
+            }
+            if !front.is_empty() {
+                stack.push((node.front.get_or_insert_with(Default::default), front));
+            }
+            if !back.is_empty() {
+                stack.push((node.back.get_or_insert_with(Default::default), back));
+            }
+        }
+    }
+}
+
+impl Drop for Node {
+    // dropping the boxes recursively overflows on deep trees
+    fn drop(&mut self) {
+        let mut stack: Vec<Box<Node>> = self.front.take().into_iter().collect();
+        stack.extend(self.back.take());
+        while let Some(mut node) = stack.pop() {
+            stack.extend(node.front.take());
+            stack.extend(node.back.take());
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct MeshCsg {
+    polygons: Vec<Polygon>,
+    layout: Layout,
+    properties: PropertiesFrame<MeshPropertyType>,
+}
+
+impl MeshCsg {
+    // the mesh must be a closed triangle list
+    pub fn from_mesh(mesh: &Mesh) -> anyhow::Result<Self> {
+        let positions = match &mesh.position_vertices {
+            PositionVertices::F3(v) => v,
+            _ => anyhow::bail!("csg requires 3d position vertices"),
+        };
+        let indices: Vec<u32> = match &mesh.indices {
+            Indices::U32(v) => v.clone(),
+            Indices::U16(v) => v.iter().map(|v| *v as u32).collect(),
+            Indices::None => (0..positions.len() as u32).collect(),
+            _ => anyhow::bail!("csg requires indices"),
+        };
+
+        let mut blend = HashSet::new();
+        blend.insert(MeshPropertyType::new::<Vec3f>("normal_vertex"));
+        blend.insert(MeshPropertyType::new::<Vec3f>("normal"));
+        blend.insert(MeshPropertyType::new::<Color>("color"));
+        blend.insert(MeshPropertyType::new::<Vec2f>("texture"));
+        blend.insert(MeshPropertyType::new::<Vec2f>("texture_coord"));
+        blend.insert(MeshPropertyType::new::<Vec2f>("uv"));
+
+        let row = mesh.properties.row_strip_size as usize;
+        let has_properties = row != 0 && mesh.properties.count != 0;
+        let mut lanes = vec![];
+        let mut normals = vec![];
+        if has_properties {
+            for (property, o) in &mesh.properties.properties_offset {
+                let offset = o.offset() as usize;
+                let size = o.size() as usize;
+                if blend.contains(property) && size.is_multiple_of(4) {
+                    lanes.push((offset, size));
+                }
+                if size == 12 && (property.name == "normal_vertex" || property.name == "normal") {
+                    normals.push(offset);
+                }
+            }
+        }
+
+        let vertex = |i: u32| Vertex {
+            pos: positions[i as usize],
+            row: if has_properties {
+                mesh.properties.data[i as usize * row..(i as usize + 1) * row].to_vec()
+            } else {
+                vec![]
+            },
+        };
+
+        let mut polygons = vec![];
+        for t in indices.chunks_exact(3) {
+            let vertices: Vec<Vertex> = t.iter().map(|i| vertex(*i)).collect();
+            if let Some(plane) =
+                Plane::from_points(vertices[0].pos, vertices[1].pos, vertices[2].pos)
+            {
+                polygons.push(Polygon { vertices, plane });
+            }
+        }
+
+        let mut properties = mesh.properties.clone();
+        properties.data.clear();
+        properties.count = 0;
+        properties.version = 0;
+
+        Ok(Self {
+            polygons,
+            layout: Layout { lanes, normals },
+            properties,
+        })
+    }
+
+    // only float properties can be blended, they are read as packed f32
+    pub fn blend_property(mut self, property: MeshPropertyType) -> Self {
+        if let Some(o) = self.properties.properties_offset.get(&property) {
+            let lane = (o.offset() as usize, o.size() as usize);
+            if lane.1.is_multiple_of(4) && !self.layout.lanes.contains(&lane) {
+                self.layout.lanes.push(lane);
+            }
+        }
+        self
+    }
+
+    pub fn keep_property(mut self, property: MeshPropertyType) -> Self {
+        if let Some(o) = self.properties.properties_offset.get(&property) {
+            let offset = o.offset() as usize;
+            self.layout.lanes.retain(|v| v.0 != offset);
+        }
+        self
+    }
+
+    fn check(&self, other: &MeshCsg) -> anyhow::Result<()> {
+        if self.properties.properties != other.properties.properties {
+            anyhow::bail!("csg meshes have different properties");
+        }
+        Ok(())
+    }
+
+    fn with_polygons(&self, polygons: Vec<Polygon>) -> Self {
+        Self {
+            polygons,
+            layout: self.layout.clone(),
+            properties: self.properties.clone(),
+        }
+    }
+
+    pub fn union(&self, other: &MeshCsg) -> anyhow::Result<Self> {
+        self.check(other)?;
+        let layout = &self.layout;
+        let mut a = Node::new(layout, self.polygons.clone());
+        let mut b = Node::new(layout, other.polygons.clone());
+        a.clip_to(layout, &b);
+        b.clip_to(layout, &a);
+        b.invert(layout);
+        b.clip_to(layout, &a);
+        b.invert(layout);
+        a.build(layout, b.all_polygons());
+        Ok(self.with_polygons(a.all_polygons()))
+    }
+
+    pub fn subtract(&self, other: &MeshCsg) -> anyhow::Result<Self> {
+        self.check(other)?;
+        let layout = &self.layout;
+        let mut a = Node::new(layout, self.polygons.clone());
+        let mut b = Node::new(layout, other.polygons.clone());
+        a.invert(layout);
+        a.clip_to(layout, &b);
+        b.clip_to(layout, &a);
+        b.invert(layout);
+        b.clip_to(layout, &a);
+        b.invert(layout);
+        a.build(layout, b.all_polygons());
+        a.invert(layout);
+        Ok(self.with_polygons(a.all_polygons()))
+    }
+
+    pub fn intersect(&self, other: &MeshCsg) -> anyhow::Result<Self> {
+        self.check(other)?;
+        let layout = &self.layout;
+        let mut a = Node::new(layout, self.polygons.clone());
+        let mut b = Node::new(layout, other.polygons.clone());
+        a.invert(layout);
+        b.clip_to(layout, &a);
+        b.invert(layout);
+        a.clip_to(layout, &b);
+        b.clip_to(layout, &a);
+        a.build(layout, b.all_polygons());
+        a.invert(layout);
+        Ok(self.with_polygons(a.all_polygons()))
+    }
+
+    pub fn inverse(&self) -> Self {
+        let mut polygons = self.polygons.clone();
+        for p in &mut polygons {
+            self.layout.flip(p);
+        }
+        self.with_polygons(polygons)
+    }
+
+    pub fn to_mesh(&self) -> Mesh {
+        let mut positions = vec![];
+        let mut indices = vec![];
+        let mut properties = self.properties.clone();
+
+        for p in &self.polygons {
+            let base = positions.len() as u32;
+            for v in &p.vertices {
+                positions.push(v.pos);
+                properties.data.extend_from_slice(&v.row);
+            }
+            for i in 1..p.vertices.len() as u32 - 1 {
+                indices.extend_from_slice(&[base, base + i, base + i + 1]);
+            }
+        }
+        if properties.row_strip_size != 0 && !properties.data.is_empty() {
+            properties.count = positions.len() as u64;
+        }
+
+        Mesh {
+            vertex_count: positions.len() as u64,
+            position_vertices: PositionVertices::F3(positions),
+            indices: Indices::U32(indices),
+            clip: None,
+            properties,
+        }
+    }
+}
+
+pub fn union(a: &Mesh, b: &Mesh) -> anyhow::Result<Mesh> {
+    Ok(MeshCsg::from_mesh(a)?
+        .union(&MeshCsg::from_mesh(b)?)?
+        .to_mesh())
+}
+
+pub fn subtract(a: &Mesh, b: &Mesh) -> anyhow::Result<Mesh> {
+    Ok(MeshCsg::from_mesh(a)?
+        .subtract(&MeshCsg::from_mesh(b)?)?
+        .to_mesh())
+}
+
+pub fn intersect(a: &Mesh, b: &Mesh) -> anyhow::Result<Mesh> {
+    Ok(MeshCsg::from_mesh(a)?
+        .intersect(&MeshCsg::from_mesh(b)?)?
+        .to_mesh())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        mesh::builder::{MeshBuilder, MeshPropertiesBuilder, PropertiesUpdater},
+        types::Vec4f,
+    };
+
+    use super::*;
+
+    fn cube(min: Vec3f, max: Vec3f) -> Mesh {
+        let mut positions = vec![];
+        for i in 0..8 {
+            positions.push(Vec3f::new(
+                if i & 1 == 0 { min.x } else { max.x },
+                if i & 2 == 0 { min.y } else { max.y },
+                if i & 4 == 0 { min.z } else { max.z },
+            ));
+        }
+        // CCW seen from outside
+        let indices = [
+            0, 2, 3, 0, 3, 1, // -z
+            4, 5, 7, 4, 7, 6, // +z
+            0, 4, 6, 0, 6, 2, // -x
+            1, 3, 7, 1, 7, 5, // +x
+            0, 1, 5, 0, 5, 4, // -y
+            2, 6, 7, 2, 7, 3, // +y
+        ];
+        let mut builder = MeshBuilder::default();
+        builder.add_position_vertices3(&positions);
+        builder.add_indices32(&indices);
+        builder.build().unwrap()
+    }
+
+    fn volume(mesh: &Mesh) -> f32 {
+        let positions = match &mesh.position_vertices {
+            PositionVertices::F3(v) => v,
+            _ => panic!(),
+        };
+        let indices = match &mesh.indices {
+            Indices::U32(v) => v,
+            _ => panic!(),
+        };
+        indices
+            .chunks(3)
+            .map(|t| {
+                let (a, b, c) = (
+                    positions[t[0] as usize],
+                    positions[t[1] as usize],
+                    positions[t[2] as usize],
+                );
+                a.dot(&b.cross(&c)) / 6f32
+            })
+            .sum()
+    }
+
+    fn cubes() -> (Mesh, Mesh) {
+        (
+            cube(Vec3f::new(0f32, 0f32, 0f32), Vec3f::new(2f32, 2f32, 2f32)),
+            cube(Vec3f::new(1f32, 1f32, 1f32), Vec3f::new(3f32, 3f32, 3f32)),
+        )
+    }
+
+    #[test]
+    fn cube_volume() {
+        let (a, _) = cubes();
+        assert!((volume(&a) - 8f32).abs() < 1e-4);
+    }
+
+    #[test]
+    fn union_volume() {
+        let (a, b) = cubes();
+        assert!((volume(&union(&a, &b).unwrap()) - 15f32).abs() < 1e-3);
+    }
+
+    #[test]
+    fn subtract_volume() {
+        let (a, b) = cubes();
+        assert!((volume(&subtract(&a, &b).unwrap()) - 7f32).abs() < 1e-3);
+    }
+
+    #[test]
+    fn intersect_volume() {
+        let (a, b) = cubes();
+        assert!((volume(&intersect(&a, &b).unwrap()) - 1f32).abs() < 1e-3);
+    }
+
+    // cube with a color and an id per corner
+    fn cube_with_properties(min: Vec3f, max: Vec3f, ids: &[u32]) -> Mesh {
+        let mesh = cube(min, max);
+        let mut builder = MeshBuilder::default();
+        builder.add_position_vertices3(match &mesh.position_vertices {
+            PositionVertices::F3(v) => v,
+            _ => panic!(),
+        });
+        builder.add_indices32(match &mesh.indices {
+            Indices::U32(v) => v,
+            _ => panic!(),
+        });
+
+        let mut properties = MeshPropertiesBuilder::default();
+        let color = MeshPropertyType::new::<Color>("color");
+        let id = MeshPropertyType::new::<u32>("id");
+        properties.add_property(color);
+        properties.add_property(id);
+        let colors: Vec<Color> = (0..8)
+            .map(|i| Vec4f::new((i & 1) as f32, ((i >> 1) & 1) as f32, 0f32, 1f32))
+            .collect();
+        properties.add_property_data(color, &colors);
+        properties.add_property_data(id, ids);
+        builder.set_properties(properties.build());
+        builder.build().unwrap()
+    }
+
+    #[test]
+    fn integer_properties_are_copied() {
+        // ids read as f32 are 1.0, 2.0, .., blending them gives new ids
+        let ids: Vec<u32> = (1..=16).map(|i| (i as f32).to_bits()).collect();
+        let a = cube_with_properties(Vec3f::zeros(), Vec3f::new(2f32, 2f32, 2f32), &ids[..8]);
+        let b = cube_with_properties(
+            Vec3f::new(1f32, 1f32, 1f32),
+            Vec3f::new(3f32, 3f32, 3f32),
+            &ids[8..],
+        );
+        let mut mesh = union(&a, &b).unwrap();
+        let count = mesh.vertex_count();
+        assert_eq!(mesh.properties.count, count);
+
+        let color = MeshPropertyType::new::<Color>("color");
+        let id = MeshPropertyType::new::<u32>("id");
+        let mut updater = PropertiesUpdater::new(&mut mesh.properties);
+        for v in updater.get_property::<u32>(id, 0, count) {
+            assert!(ids.contains(&v), "{}", f32::from_bits(v));
+        }
+        // split vertices still blend the colors
+        let colors: Vec<Color> = updater.get_property(color, 0, count);
+        assert!(colors.iter().any(|c| c.x > 0f32 && c.x < 1f32));
+    }
+
+    #[test]
+    fn deep_tree() {
+        // a chain of parallel planes, deeper than a recursive walk can go
+        let depth = 200_000;
+        let layout = Layout {
+            lanes: vec![],
+            normals: vec![],
+        };
+        let mut root = Node::default();
+        let mut node = &mut root;
+        for i in 0..depth {
+            node.plane = Some(Plane {
+                normal: Vec3f::new(0f32, 0f32, 1f32),
+                w: i as f32,
+            });
+            if i + 1 < depth {
+                node = node.front.get_or_insert_with(Default::default);
+            }
+        }
+
+        let z = depth as f32 + 1f32;
+        let vertex = |x: f32, y: f32| Vertex {
+            pos: Vec3f::new(x, y, z),
+            row: vec![],
+        };
+        let polygon = Polygon {
+            vertices: vec![vertex(0f32, 0f32), vertex(1f32, 0f32), vertex(0f32, 1f32)],
+            plane: Plane {
+                normal: Vec3f::new(0f32, 0f32, 1f32),
+                w: z,
+            },
+        };
+        // in front of every plane, it walks down the whole chain
+        assert_eq!(root.clip_polygons(&layout, vec![polygon.clone()]).len(), 1);
+        root.invert(&layout);
+        assert!(root.clip_polygons(&layout, vec![polygon]).is_empty());
+        assert!(root.all_polygons().is_empty());
+    }
+}
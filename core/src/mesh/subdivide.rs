@@ -0,0 +1,623 @@
+use std::collections::{HashMap, HashSet};
+
+use indexmap::IndexMap;
+
+use crate::types::{Color, Vec2f, Vec3f};
+
+use super::{
+    builder::{MeshPropertyType, PropertiesFrame},
+    Indices, Mesh, PositionVertices,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SubdivisionScheme {
+    // triangle meshes
+    Loop,
+    // quad meshes, consecutive triangle pairs sharing an edge are read back as quads
+    CatmullClark,
+}
+
+type Stencil = Vec<(u32, f32)>;
+
+fn edge_key(a: u32, b: u32) -> (u32, u32) {
+    if a < b {
+        (a, b)
+    } else {
+        (b, a)
+    }
+}
+
+struct Topology<'a> {
+    faces: &'a [Vec<u32>],
+    edges: IndexMap<(u32, u32), Vec<usize>>,
+    vertex_neighbors: Vec<Vec<u32>>,
+    vertex_faces: Vec<Vec<usize>>,
+    creases: &'a HashSet<(u32, u32)>,
+}
+
+impl<'a> Topology<'a> {
+    fn new(faces: &'a [Vec<u32>], vertex_count: usize, creases: &'a HashSet<(u32, u32)>) -> Self {
+        let mut edges: IndexMap<(u32, u32), Vec<usize>> = IndexMap::new();
+        let mut vertex_neighbors = vec![vec![]; vertex_count];
+        let mut vertex_faces = vec![vec![]; vertex_count];
+
+        for (index, face) in faces.iter().enumerate() {
+            let n = face.len();
+            for i in 0..n {
+                let a = face[i];
+                let b = face[(i + 1) % n];
+                vertex_faces[a as usize].push(index);
+                if a == b {
+                    continue;
+                }
+                let faces = edges.entry(edge_key(a, b)).or_default();
+                if faces.is_empty() {
+                    vertex_neighbors[a as usize].push(b);
+                    vertex_neighbors[b as usize].push(a);
+                }
+                faces.push(index);
+            }
+        }
+
+        Self {
+            faces,
+            edges,
+            vertex_neighbors,
+            vertex_faces,
+            creases,
+        }
+    }
+
+    // boundary and non-manifold edges are handled like creases
+    fn is_sharp(&self, a: u32, b: u32) -> bool {
+        let key = edge_key(a, b);
+        self.creases.contains(&key) || self.edges.get(&key).is_none_or(|f| f.len() != 2)
+    }
+
+    fn face_stencil(&self, face: usize) -> Stencil {
+        let f = &self.faces[face];
+        let w = 1f32 / f.len() as f32;
+        f.iter().map(|v| (*v, w)).collect()
+    }
+
+    fn sharp_vertex_stencil(&self, v: u32) -> Option<Stencil> {
+        let sharp: Vec<u32> = self.vertex_neighbors[v as usize]
+            .iter()
+            .cloned()
+            .filter(|n| self.is_sharp(v, *n))
+            .collect();
+        match sharp.len() {
+            // smooth or dart
+            0 | 1 => None,
+            2 => Some(vec![
+                (v, 0.75f32),
+                (sharp[0], 0.125f32),
+                (sharp[1], 0.125f32),
+            ]),
+            // corner
+            _ => Some(vec![(v, 1f32)]),
+        }
+    }
+
+    fn loop_vertex_stencil(&self, v: u32) -> Stencil {
+        if let Some(s) = self.sharp_vertex_stencil(v) {
+            return s;
+        }
+        let neighbors = &self.vertex_neighbors[v as usize];
+        let n = neighbors.len();
+        if n == 0 {
+            return vec![(v, 1f32)];
+        }
+        let t = 0.375f32 + 0.25f32 * (std::f32::consts::PI * 2f32 / n as f32).cos();
+        let beta = (0.625f32 - t * t) / n as f32;
+
+        let mut s = vec![(v, 1f32 - n as f32 * beta)];
+        s.extend(neighbors.iter().map(|n| (*n, beta)));
+        s
+    }
+
+    fn loop_edge_stencil(&self, a: u32, b: u32) -> Stencil {
+        if self.is_sharp(a, b) {
+            return vec![(a, 0.5f32), (b, 0.5f32)];
+        }
+        let mut s = vec![(a, 0.375f32), (b, 0.375f32)];
+        for face in &self.edges[&edge_key(a, b)] {
+            for v in &self.faces[*face] {
+                if *v != a && *v != b {
+                    s.push((*v, 0.125f32));
+                }
+            }
+        }
+        s
+    }
+
+    fn catmull_clark_vertex_stencil(&self, v: u32) -> Stencil {
+        if let Some(s) = self.sharp_vertex_stencil(v) {
+            return s;
+        }
+        let neighbors = &self.vertex_neighbors[v as usize];
+        let faces = &self.vertex_faces[v as usize];
+        let n = neighbors.len() as f32;
+        if neighbors.is_empty() || faces.is_empty() {
+            return vec![(v, 1f32)];
+        }
+
+        // (Q + 2R + (n - 3)P) / n
+        let mut s = vec![(v, (n - 3f32) / n + 1f32 / n)];
+        for neighbor in neighbors {
+            s.push((*neighbor, 1f32 / (n * n)));
+        }
+        let wf = 1f32 / (n * faces.len() as f32);
+        for face in faces {
+            for (u, w) in self.face_stencil(*face) {
+                s.push((u, w * wf));
+            }
+        }
+        s
+    }
+
+    fn catmull_clark_edge_stencil(&self, a: u32, b: u32) -> Stencil {
+        if self.is_sharp(a, b) {
+            return vec![(a, 0.5f32), (b, 0.5f32)];
+        }
+        let mut s = vec![(a, 0.25f32), (b, 0.25f32)];
+        for face in &self.edges[&edge_key(a, b)] {
+            for (u, w) in self.face_stencil(*face) {
+                s.push((u, w * 0.25f32));
+            }
+        }
+        s
+    }
+}
+
+fn triangles_to_quads(indices: &[u32]) -> Vec<Vec<u32>> {
+    let mut faces = vec![];
+    let mut i = 0;
+    while i + 3 <= indices.len() {
+        let t0 = &indices[i..i + 3];
+        if i + 6 <= indices.len() {
+            let t1 = &indices[i + 3..i + 6];
+            // rotate t0 so the shared edge is (p2, p0), t1 must walk it as (p0, p2)
+            let mut quad = None;
+            for r in 0..3 {
+                let p0 = t0[r];
+                let p1 = t0[(r + 1) % 3];
+                let p2 = t0[(r + 2) % 3];
+                for k in 0..3 {
+                    if t1[k] == p0 && t1[(k + 1) % 3] == p2 {
+                        let q = t1[(k + 2) % 3];
+                        if q != p1 {
+                            quad = Some(vec![p0, p1, p2, q]);
+                        }
+                    }
+                }
+            }
+            if let Some(q) = quad {
+                faces.push(q);
+                i += 6;
+                continue;
+            }
+        }
+        faces.push(t0.to_vec());
+        i += 3;
+    }
+    faces
+}
+
+fn apply_positions(stencil: &Stencil, positions: &[Vec3f]) -> Vec3f {
+    let mut p = Vec3f::zeros();
+    for (v, w) in stencil {
+        p += positions[*v as usize] * *w;
+    }
+    p
+}
+
+pub struct MeshSubdivider {
+    scheme: SubdivisionScheme,
+    iterations: u32,
+    creases: HashSet<(u32, u32)>,
+    blend: HashSet<MeshPropertyType>,
+    normalize: HashSet<MeshPropertyType>,
+    weld: bool,
+}
+
+impl MeshSubdivider {
+    pub fn new(scheme: SubdivisionScheme) -> Self {
+        let mut normalize = HashSet::new();
+        normalize.insert(MeshPropertyType::new::<Vec3f>("normal_vertex"));
+        normalize.insert(MeshPropertyType::new::<Vec3f>("normal"));
+        let mut blend = normalize.clone();
+        blend.insert(MeshPropertyType::new::<Color>("color"));
+        blend.insert(MeshPropertyType::new::<Vec2f>("texture"));
+        blend.insert(MeshPropertyType::new::<Vec2f>("texture_coord"));
+        blend.insert(MeshPropertyType::new::<Vec2f>("uv"));
+        Self {
+            scheme,
+            iterations: 1,
+            creases: HashSet::new(),
+            blend,
+            normalize,
+            weld: true,
+        }
+    }
+
+    pub fn set_iterations(mut self, iterations: u32) -> Self {
+        self.iterations = iterations;
+        self
+    }
+
+    // edge between two vertex indices of the input mesh
+    pub fn add_crease(mut self, a: u32, b: u32) -> Self {
+        self.creases.insert(edge_key(a, b));
+        self
+    }
+
+    // only float properties can be blended, they are read as packed f32. the
+    // others are copied from the closest source vertex
+    pub fn blend_property(mut self, property: MeshPropertyType) -> Self {
+        self.blend.insert(property);
+        self
+    }
+
+    pub fn keep_property(mut self, property: MeshPropertyType) -> Self {
+        self.blend.remove(&property);
+        self
+    }
+
+    pub fn normalize_property(mut self, property: MeshPropertyType) -> Self {
+        self.blend.insert(property);
+        self.normalize.insert(property);
+        self
+    }
+
+    // vertices split at uv/normal seams share one position when welded, so the surface stays closed
+    pub fn set_weld(mut self, weld: bool) -> Self {
+        self.weld = weld;
+        self
+    }
+
+    pub fn subdivide(&self, mesh: &Mesh) -> anyhow::Result<Mesh> {
+        let mut positions = match &mesh.position_vertices {
+            PositionVertices::F3(v) => v.clone(),
+            _ => anyhow::bail!("subdivision requires 3d position vertices"),
+        };
+        let mut indices: Vec<u32> = match &mesh.indices {
+            Indices::U32(v) => v.clone(),
+            Indices::U16(v) => v.iter().map(|v| *v as u32).collect(),
+            _ => anyhow::bail!("subdivision requires indices"),
+        };
+        if !indices.len().is_multiple_of(3) {
+            anyhow::bail!("subdivision requires a triangle list");
+        }
+        let mut properties = mesh.properties.clone();
+        let mut creases = self.creases.clone();
+
+        for _ in 0..self.iterations {
+            let faces: Vec<Vec<u32>> = match self.scheme {
+                SubdivisionScheme::Loop => indices.chunks(3).map(|v| v.to_vec()).collect(),
+                SubdivisionScheme::CatmullClark => triangles_to_quads(&indices),
+            };
+            let faces: Vec<Vec<u32>> = faces
+                .into_iter()
+                .filter(|f| (0..f.len()).all(|i| f[i] != f[(i + 1) % f.len()]))
+                .collect();
+            let (p, i, frame, c) = self.step(&positions, &faces, &properties, &creases);
+            positions = p;
+            indices = i;
+            properties = frame;
+            creases = c;
+        }
+
+        Ok(Mesh {
+            vertex_count: positions.len() as u64,
+            position_vertices: PositionVertices::F3(positions),
+            indices: Indices::U32(indices),
+            clip: mesh.clip,
+            properties,
+        })
+    }
+
+    fn weld_map(&self, positions: &[Vec3f]) -> (Vec<u32>, Vec<Vec3f>) {
+        let mut map = Vec::with_capacity(positions.len());
+        let mut welded = vec![];
+        let mut ids = HashMap::new();
+        for p in positions {
+            let id = if self.weld {
+                let key = (p.x.to_bits(), p.y.to_bits(), p.z.to_bits());
+                *ids.entry(key).or_insert_with(|| {
+                    welded.push(*p);
+                    welded.len() as u32 - 1
+                })
+            } else {
+                welded.push(*p);
+                welded.len() as u32 - 1
+            };
+            map.push(id);
+        }
+        (map, welded)
+    }
+
+    #[allow(clippy::type_complexity)]
+    fn step(
+        &self,
+        positions: &[Vec3f],
+        faces: &[Vec<u32>],
+        properties: &PropertiesFrame<MeshPropertyType>,
+        creases: &HashSet<(u32, u32)>,
+    ) -> (
+        Vec<Vec3f>,
+        Vec<u32>,
+        PropertiesFrame<MeshPropertyType>,
+        HashSet<(u32, u32)>,
+    ) {
+        // positions are smoothed over the welded topology, properties over the raw one
+        let (weld, welded_positions) = self.weld_map(positions);
+        let welded_faces: Vec<Vec<u32>> = faces
+            .iter()
+            .map(|f| f.iter().map(|v| weld[*v as usize]).collect())
+            .collect();
+        let welded_creases: HashSet<(u32, u32)> = creases
+            .iter()
+            .map(|(a, b)| edge_key(weld[*a as usize], weld[*b as usize]))
+            .collect();
+
+        let raw = Topology::new(faces, positions.len(), creases);
+        let welded = Topology::new(&welded_faces, welded_positions.len(), &welded_creases);
+
+        let is_loop = self.scheme == SubdivisionScheme::Loop;
+        let vertex_count = positions.len();
+        let edge_base = vertex_count as u32;
+        let face_base = edge_base + raw.edges.len() as u32;
+
+        let mut out_positions = vec![];
+        let mut stencils = vec![];
+
+        for v in 0..vertex_count as u32 {
+            let w = weld[v as usize];
+            let (p, s) = if is_loop {
+                (welded.loop_vertex_stencil(w), raw.loop_vertex_stencil(v))
+            } else {
+                (
+                    welded.catmull_clark_vertex_stencil(w),
+                    raw.catmull_clark_vertex_stencil(v),
+                )
+            };
+            out_positions.push(apply_positions(&p, &welded_positions));
+            stencils.push(s);
+        }
+
+        for (a, b) in raw.edges.keys() {
+            let (wa, wb) = (weld[*a as usize], weld[*b as usize]);
+            let (p, s) = if is_loop {
+                (
+                    welded.loop_edge_stencil(wa, wb),
+                    raw.loop_edge_stencil(*a, *b),
+                )
+            } else {
+                (
+                    welded.catmull_clark_edge_stencil(wa, wb),
+                    raw.catmull_clark_edge_stencil(*a, *b),
+                )
+            };
+            out_positions.push(apply_positions(&p, &welded_positions));
+            stencils.push(s);
+        }
+
+        if !is_loop {
+            for face in 0..faces.len() {
+                let s = raw.face_stencil(face);
+                out_positions.push(apply_positions(&s, positions));
+                stencils.push(s);
+            }
+        }
+
+        let edge_index =
+            |a: u32, b: u32| edge_base + raw.edges.get_index_of(&edge_key(a, b)).unwrap() as u32;
+
+        let mut indices = vec![];
+        for (index, face) in faces.iter().enumerate() {
+            let n = face.len();
+            if is_loop {
+                let (a, b, c) = (face[0], face[1], face[2]);
+                let ab = edge_index(a, b);
+                let bc = edge_index(b, c);
+                let ca = edge_index(c, a);
+                indices.extend_from_slice(&[a, ab, ca, ab, b, bc, ca, bc, c, ab, bc, ca]);
+            } else {
+                let f = face_base + index as u32;
+                for i in 0..n {
+                    let v = face[i];
+                    let next = edge_index(v, face[(i + 1) % n]);
+                    let prev = edge_index(face[(i + n - 1) % n], v);
+                    indices.extend_from_slice(&[v, next, f, v, f, prev]);
+                }
+            }
+        }
+
+        let mut out_creases = HashSet::new();
+        for (a, b) in creases {
+            if let Some(e) = raw.edges.get_index_of(&edge_key(*a, *b)) {
+                let e = edge_base + e as u32;
+                out_creases.insert(edge_key(*a, e));
+                out_creases.insert(edge_key(e, *b));
+            }
+        }
+
+        let frame = self.interpolate_properties(properties, &stencils);
+
+        (out_positions, indices, frame, out_creases)
+    }
+
+    fn interpolate_properties(
+        &self,
+        properties: &PropertiesFrame<MeshPropertyType>,
+        stencils: &[Stencil],
+    ) -> PropertiesFrame<MeshPropertyType> {
+        let row = properties.row_strip_size as usize;
+        let mut frame = properties.clone();
+        frame.version = 0;
+        if row == 0 || properties.count == 0 {
+            return frame;
+        }
+
+        let read_f32 = |row_index: u32, offset: usize| {
+            let beg = row_index as usize * row + offset;
+            f32::from_ne_bytes(properties.data[beg..beg + 4].try_into().unwrap())
+        };
+
+        let mut data = vec![0u8; row * stencils.len()];
+        for (out, stencil) in stencils.iter().enumerate() {
+            let dst = &mut data[out * row..(out + 1) * row];
+            for (property, o) in &properties.properties_offset {
+                let offset = o.offset() as usize;
+                let size = o.size() as usize;
+
+                if !self.blend.contains(property) || !size.is_multiple_of(4) {
+                    let (src, _) =
+                        stencil
+                            .iter()
+                            .fold((0u32, f32::MIN), |a, b| if b.1 > a.1 { *b } else { a });
+                    let beg = src as usize * row + offset;
+                    dst[offset..offset + size].copy_from_slice(&properties.data[beg..beg + size]);
+                    continue;
+                }
+
+                let mut lanes = vec![0f32; size / 4];
+                for (src, w) in stencil {
+                    for (lane, value) in lanes.iter_mut().enumerate() {
+                        *value += read_f32(*src, offset + lane * 4) * *w;
+                    }
+                }
+                if self.normalize.contains(property) {
+                    let len = lanes.iter().map(|v| v * v).sum::<f32>().sqrt();
+                    if len > 0f32 {
+                        lanes.iter_mut().for_each(|v| *v /= len);
+                    }
+                }
+                for (lane, value) in lanes.iter().enumerate() {
+                    let beg = offset + lane * 4;
+                    dst[beg..beg + 4].copy_from_slice(&value.to_ne_bytes());
+                }
+            }
+        }
+
+        frame.data = data;
+        frame.count = stencils.len() as u64;
+        frame
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        mesh::builder::{MeshBuilder, MeshPropertiesBuilder, PropertiesUpdater},
+        types::Vec4f,
+    };
+
+    use super::*;
+
+    // unit quad split into two triangles sharing the (0, 2) edge
+    fn quad() -> Mesh {
+        let mut builder = MeshBuilder::default();
+        builder.add_position_vertices3(&[
+            Vec3f::new(0f32, 0f32, 0f32),
+            Vec3f::new(1f32, 0f32, 0f32),
+            Vec3f::new(1f32, 1f32, 0f32),
+            Vec3f::new(0f32, 1f32, 0f32),
+        ]);
+        builder.add_indices32(&[0, 1, 2, 0, 2, 3]);
+
+        let mut properties = MeshPropertiesBuilder::default();
+        let color = MeshPropertyType::new::<Color>("color");
+        let id = MeshPropertyType::new::<u32>("id");
+        properties.add_property(color);
+        properties.add_property(id);
+        properties.add_property_data(
+            color,
+            &[
+                Vec4f::new(1f32, 0f32, 0f32, 1f32),
+                Vec4f::new(0f32, 0f32, 1f32, 1f32),
+                Vec4f::new(0f32, 1f32, 0f32, 1f32),
+                Vec4f::new(0f32, 1f32, 0f32, 1f32),
+            ],
+        );
+        properties.add_property_data(id, &[3u32, 100_000, 7, 1 << 31]);
+        builder.set_properties(properties.build());
+        builder.build().unwrap()
+    }
+
+    fn positions(mesh: &Mesh) -> &[Vec3f] {
+        match &mesh.position_vertices {
+            PositionVertices::F3(v) => v,
+            _ => panic!(),
+        }
+    }
+
+    fn indices(mesh: &Mesh) -> &[u32] {
+        match &mesh.indices {
+            Indices::U32(v) => v,
+            _ => panic!(),
+        }
+    }
+
+    #[test]
+    fn loop_counts() {
+        let mesh = MeshSubdivider::new(SubdivisionScheme::Loop)
+            .subdivide(&quad())
+            .unwrap();
+        // 4 vertices and 5 edge points
+        assert_eq!(positions(&mesh).len(), 9);
+        assert_eq!(mesh.properties.count, 9);
+        assert_eq!(indices(&mesh).len(), 2 * 4 * 3);
+
+        let mesh = MeshSubdivider::new(SubdivisionScheme::Loop)
+            .set_iterations(2)
+            .subdivide(&quad())
+            .unwrap();
+        assert_eq!(indices(&mesh).len(), 2 * 16 * 3);
+    }
+
+    #[test]
+    fn loop_boundary_edge() {
+        let mesh = MeshSubdivider::new(SubdivisionScheme::Loop)
+            .subdivide(&quad())
+            .unwrap();
+        // the first edge point is on the (0, 1) boundary edge
+        let p = positions(&mesh)[4];
+        assert!((p - Vec3f::new(0.5f32, 0f32, 0f32)).norm() < 1e-6);
+        // boundary vertices stay on the boundary
+        for p in positions(&mesh) {
+            assert!(p.z.abs() < 1e-6);
+        }
+    }
+
+    #[test]
+    fn catmull_clark_quad() {
+        let mesh = MeshSubdivider::new(SubdivisionScheme::CatmullClark)
+            .subdivide(&quad())
+            .unwrap();
+        // 4 vertices, 4 edge points and a face point
+        assert_eq!(positions(&mesh).len(), 9);
+        assert_eq!(indices(&mesh).len(), 4 * 2 * 3);
+        let p = positions(&mesh)[8];
+        assert!((p - Vec3f::new(0.5f32, 0.5f32, 0f32)).norm() < 1e-6);
+    }
+
+    #[test]
+    fn blend_float_properties_only() {
+        let mut mesh = MeshSubdivider::new(SubdivisionScheme::Loop)
+            .subdivide(&quad())
+            .unwrap();
+        let count = mesh.properties.count;
+        let mut updater = PropertiesUpdater::new(&mut mesh.properties);
+
+        let colors: Vec<Color> =
+            updater.get_property(MeshPropertyType::new::<Color>("color"), 0, count);
+        assert!((colors[4] - Vec4f::new(0.5f32, 0f32, 0.5f32, 1f32)).norm() < 1e-6);
+
+        let ids: Vec<u32> = updater.get_property(MeshPropertyType::new::<u32>("id"), 0, count);
+        for id in ids {
+            assert!([3u32, 100_000, 7, 1 << 31].contains(&id));
+        }
+    }
+}
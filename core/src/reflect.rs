@@ -0,0 +1,117 @@
+use std::any::TypeId;
+
+pub use bevy_reflect::{self, Reflect};
+use bevy_reflect::{
+    attributes::CustomAttributes, DynamicEnum, DynamicStruct, DynamicTuple, DynamicVariant, Enum,
+    TypeInfo, VariantInfo,
+};
+
+// custom field attributes read by inspectors, e.g. `#[reflect(@Angle)]`.
+// a `RangeInclusive<f32>` attribute limits a number field to a slider.
+// an attribute of the field's own type is the value used when an enum
+// switches into the variant holding that field.
+
+// radians, edited in degrees
+#[derive(Debug, Clone, Copy, Reflect)]
+pub struct Angle;
+
+// slider with a logarithmic scale
+#[derive(Debug, Clone, Copy, Reflect)]
+pub struct Logarithmic;
+
+// [f32; 4] x, y, z, w quaternion, edited as euler angles
+#[derive(Debug, Clone, Copy, Reflect)]
+pub struct Rotation;
+
+// [f32; 3] or [f32; 4] color
+#[derive(Debug, Clone, Copy, Reflect)]
+pub struct ColorEdit;
+
+// types keeping derived state (cached matrices, locks, gpu uniforms) are
+// reflected through a proxy of their editable fields. the edited proxy is
+// written back with the setters of the type
+pub trait ReflectProxy {
+    type Proxy: Reflect;
+
+    fn reflect_proxy(&self) -> Self::Proxy;
+
+    fn apply_proxy(&mut self, proxy: &Self::Proxy);
+}
+
+fn zero_value(ty: TypeId) -> Option<Box<dyn Reflect>> {
+    macro_rules! zero {
+        ($($t: ty),*) => {
+            $(
+                if ty == TypeId::of::<$t>() {
+                    return Some(Box::new(<$t>::default()));
+                }
+            )*
+        };
+    }
+    zero!(f32, f64, i32, i64, u32, u64, usize, bool, String, [f32; 2], [f32; 3], [f32; 4]);
+    None
+}
+
+fn field_value(
+    old: Option<&dyn Reflect>,
+    attributes: &CustomAttributes,
+    ty: TypeId,
+) -> Option<Box<dyn Reflect>> {
+    if let Some(old) = old {
+        if old.get_represented_type_info().map(|v| v.type_id()) == Some(ty) {
+            return Some(old.clone_value());
+        }
+    }
+    if let Some(v) = attributes.get_by_id(ty) {
+        return Some(v.clone_value());
+    }
+    zero_value(ty)
+}
+
+// switch an enum to another variant, fields with the same name (or index for
+// tuple variants) and type are kept. returns false if a field has no value
+pub fn set_variant(value: &mut dyn Enum, name: &str) -> bool {
+    if value.variant_name() == name {
+        return false;
+    }
+    let variant = match value.get_represented_type_info() {
+        Some(TypeInfo::Enum(info)) => match info.variant(name) {
+            Some(v) => v,
+            None => return false,
+        },
+        _ => return false,
+    };
+
+    let dynamic = match variant {
+        VariantInfo::Unit(_) => DynamicVariant::Unit,
+        VariantInfo::Struct(v) => {
+            let mut s = DynamicStruct::default();
+            for field in v.iter() {
+                match field_value(
+                    value.field(field.name()),
+                    field.custom_attributes(),
+                    field.type_id(),
+                ) {
+                    Some(f) => s.insert_boxed(field.name(), f),
+                    None => return false,
+                }
+            }
+            DynamicVariant::Struct(s)
+        }
+        VariantInfo::Tuple(v) => {
+            let mut t = DynamicTuple::default();
+            for field in v.iter() {
+                match field_value(
+                    value.field_at(field.index()),
+                    field.custom_attributes(),
+                    field.type_id(),
+                ) {
+                    Some(f) => t.insert_boxed(f),
+                    None => return false,
+                }
+            }
+            DynamicVariant::Tuple(t)
+        }
+    };
+    value.try_apply(&DynamicEnum::new(name, dynamic)).is_ok()
+}
@@ -17,6 +17,7 @@ pub struct BindGroupKey {
     pub ty: BindGroupType,
     pub s: u64,      // resource_id
     pub idx: String, // pass name
+    pub version: u64, // BindingResourceProvider::version
 }
 
 pub struct ShaderBindGroupCollection {
@@ -43,11 +44,15 @@ impl ShaderBindGroupCollection {
             ty: binding.bind_group(),
             s: id,
             idx: pso.pass_name().to_owned(),
+            version: binding.version(),
         };
 
         if self.bind_groups.contains_key(&key) {
             return;
         }
+        // drop the groups of older binding versions
+        self.bind_groups
+            .retain(|k, _| k.ty != key.ty || k.s != key.s || k.idx != key.idx);
 
         if let Some((layout, uniforms)) = &pso.get_bind_group_layout(key.ty) {
             let mut entries = vec![];
@@ -185,7 +190,50 @@ impl ShaderBindGroupCollection {
                             );
                         }
                     }
-                    _ => (),
+                    tshader::tech::GlobalVariable::StorageBuffer(b) => {
+                        if let ShaderBindingResource::Resource(r) = binding.query_resource(varname)
+                        {
+                            if let crate::context::ResourceTy::Buffer(buffer) = r.ty() {
+                                entries.push(wgpu::BindGroupEntry {
+                                    binding: b.binding,
+                                    resource: wgpu::BindingResource::Buffer(wgpu::BufferBinding {
+                                        buffer: unsafe {
+                                            std::mem::transmute::<&wgpu::Buffer, &wgpu::Buffer>(
+                                                buffer.as_ref(),
+                                            )
+                                        },
+                                        offset: 0,
+                                        size: None,
+                                    }),
+                                });
+                            }
+                        } else {
+                            log::error!(
+                                "unsupported storage buffer material resource type, {} not find",
+                                varname
+                            );
+                        }
+                    }
+                    tshader::tech::GlobalVariable::StorageTexture(t) => {
+                        if let ShaderBindingResource::Resource(r) = binding.query_resource(varname)
+                        {
+                            if let crate::context::ResourceTy::Texture((_, view)) = r.ty() {
+                                entries.push(wgpu::BindGroupEntry {
+                                    binding: t.binding,
+                                    resource: wgpu::BindingResource::TextureView(unsafe {
+                                        std::mem::transmute::<&wgpu::TextureView, &wgpu::TextureView>(view)
+                                    }),
+                                });
+                            }
+                        } else {
+                            log::error!(
+                                "unsupported storage texture material resource type, {} not find",
+                                varname
+                            );
+                        }
+                    }
+                    // set by the renderer, not a binding
+                    tshader::tech::GlobalVariable::PushConstant(_) => (),
                 }
             }
 
@@ -221,6 +269,7 @@ impl ShaderBindGroupCollection {
             ty: binding.bind_group(),
             s: id,
             idx: pso.pass_name().to_owned(),
+            version: binding.version(),
         };
         if let Some(g) = self.bind_groups.get(&key) {
             pass.set_bind_group(g.group, &g.bind_group, &g.offsets)
@@ -2,7 +2,7 @@ use wgpu::util::DeviceExt;
 
 use crate::{
     mesh::{InstanceProperties, Mesh},
-    scene::SceneStorage,
+    scene::SceneObjects,
     cache::FramedCache,
 };
 
@@ -225,7 +225,7 @@ impl MeshBufferCollector {
         }
     }
 
-    pub fn add(&mut self, c: &SceneStorage, object_id: u64, device: &wgpu::Device) {
+    pub fn add(&mut self, c: &SceneObjects, object_id: u64, device: &wgpu::Device) {
         let obj = match c.get(&object_id) {
             Some(v) => v,
             None => return,
@@ -246,7 +246,7 @@ impl MeshBufferCollector {
         }
     }
 
-    pub fn get(&self, _c: &SceneStorage, object_id: u64) -> Option<&ObjectBuffer> {
+    pub fn get(&self, _c: &SceneObjects, object_id: u64) -> Option<&ObjectBuffer> {
         if let Some(v) = self.static_object_buffers.get(&object_id) {
             return Some(v);
         }
@@ -0,0 +1,393 @@
+use std::sync::{Arc, Mutex};
+
+use crate::{
+    backends::wgpu_backend::WGPUResource,
+    context::ResourceRef,
+    graph::rdg::{
+        backend::{GraphComputeEngine, GraphCopyEngine},
+        pass::*,
+        resource::{ResourceId, RT_DEPTH_RESOURCE_ID},
+        ComputePassBuilder, RenderGraphBuilder,
+    },
+    render::pso::{BindGroupType, RenderDescriptorObject},
+    scene::{occlusion::OcclusionCulling, DepthConvention},
+    types::{Mat4x4f, Size, Vec3u, Vec4f},
+};
+
+use super::{
+    tech::ShaderTechCollection,
+    view::{ViewId, ViewTarget},
+};
+use tshader::VariantFlags;
+
+// the pyramid is read back at the first level fitting in this size
+const HIZ_READBACK_SIZE: u32 = 256;
+
+type MapResult = Arc<Mutex<Option<Result<(), wgpu::BufferAsyncError>>>>;
+
+// farthest depth mips of the view depth, stored as float bits in r32uint
+struct HiZPyramid {
+    texture: ResourceRef,
+    views: Vec<wgpu::TextureView>,
+    size: Size,
+}
+
+impl HiZPyramid {
+    fn new(gpu: &WGPUResource, size: Size) -> Self {
+        let device = gpu.device();
+        let mut levels = 1;
+        while (size.x >> (levels - 1)).max(size.y >> (levels - 1)) > HIZ_READBACK_SIZE {
+            levels += 1;
+        }
+        let texture = device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("hiz pyramid"),
+            size: wgpu::Extent3d {
+                width: size.x,
+                height: size.y,
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: levels,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: wgpu::TextureFormat::R32Uint,
+            usage: wgpu::TextureUsages::STORAGE_BINDING
+                | wgpu::TextureUsages::TEXTURE_BINDING
+                | wgpu::TextureUsages::COPY_SRC,
+            view_formats: &[],
+        });
+        let texture = gpu.context().register_texture(texture);
+        let views = (0..levels)
+            .map(|level| {
+                texture
+                    .texture_ref()
+                    .create_view(&wgpu::TextureViewDescriptor {
+                        label: Some("hiz pyramid level"),
+                        base_mip_level: level,
+                        mip_level_count: Some(1),
+                        ..Default::default()
+                    })
+            })
+            .collect();
+        Self {
+            texture,
+            views,
+            size,
+        }
+    }
+
+    fn level(&self) -> u32 {
+        self.views.len() as u32 - 1
+    }
+
+    fn level_size(&self, level: u32) -> Size {
+        Size::new((self.size.x >> level).max(1), (self.size.y >> level).max(1))
+    }
+}
+
+// the last mip of the pyramid, mapped after the frame is submitted
+struct HiZReadback {
+    buffer: wgpu::Buffer,
+    size: Size,
+    bytes_per_row: u32,
+    // camera of the frame the depth was drawn with
+    vp: Mat4x4f,
+    viewport: Vec4f,
+    mapped: Option<MapResult>,
+}
+
+impl HiZReadback {
+    fn new(device: &wgpu::Device, size: Size) -> Self {
+        let bytes_per_row = wgpu::util::align_to(size.x * 4, wgpu::COPY_BYTES_PER_ROW_ALIGNMENT);
+        let buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("hiz readback"),
+            size: (bytes_per_row * size.y) as u64,
+            usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
+            mapped_at_creation: false,
+        });
+        Self {
+            buffer,
+            size,
+            bytes_per_row,
+            vp: Mat4x4f::identity(),
+            viewport: Vec4f::zeros(),
+            mapped: None,
+        }
+    }
+
+    fn read(&self) -> Vec<f32> {
+        let view = self.buffer.slice(..).get_mapped_range();
+        let mut res = Vec::with_capacity((self.size.x * self.size.y) as usize);
+        for row in view.chunks(self.bytes_per_row as usize) {
+            for texel in row[..(self.size.x * 4) as usize].chunks(4) {
+                res.push(f32::from_bits(u32::from_le_bytes([
+                    texel[0], texel[1], texel[2], texel[3],
+                ])));
+            }
+        }
+        drop(view);
+        self.buffer.unmap();
+        res
+    }
+}
+
+struct HiZRenderer {
+    view: ViewId,
+    depth_id: ResourceId,
+    depth: DepthConvention,
+    variants: VariantFlags,
+    occlusion: Arc<OcclusionCulling>,
+    pyramid: HiZPyramid,
+    readback: HiZReadback,
+    // pass name, bind group and size of the dispatches of this frame
+    dispatches: Vec<(&'static str, wgpu::BindGroup, Size)>,
+    // the pyramid of this frame is copied to `readback`
+    recorded: bool,
+    material_shader_collector: Arc<ShaderTechCollection>,
+}
+
+impl HiZRenderer {
+    // hands the finished read back to the sorters, returns false while it's in flight
+    fn poll(&mut self, device: &wgpu::Device) -> bool {
+        let readback = &mut self.readback;
+        let mapped = match &readback.mapped {
+            Some(v) => v.clone(),
+            None => return true,
+        };
+        device.poll(wgpu::Maintain::Poll);
+        let res = match mapped.lock().unwrap().take() {
+            Some(v) => v,
+            None => return false,
+        };
+        readback.mapped = None;
+        match res {
+            Ok(_) => {
+                let pyramid = &self.pyramid;
+                self.occlusion.update(
+                    pyramid.size,
+                    pyramid.level(),
+                    readback.size,
+                    readback.read(),
+                    (readback.vp, readback.viewport),
+                    self.depth,
+                );
+            }
+            Err(e) => log::warn!("hiz read back fail {}", e),
+        }
+        true
+    }
+
+    fn bind_group(
+        &self,
+        device: &wgpu::Device,
+        pass: &str,
+        input: &wgpu::TextureView,
+        output: &wgpu::TextureView,
+    ) -> wgpu::BindGroup {
+        let pso = self
+            .material_shader_collector
+            .get("hiz", &self.variants, self.view as u64, pass);
+        let (layout, uniforms) = pso.get_bind_group_layout(BindGroupType::Material).unwrap();
+        let mut entries = vec![];
+        for (name, variable) in &uniforms.vars {
+            let (binding, view) = match variable {
+                tshader::tech::GlobalVariable::Texture(t) => (t.binding, input),
+                tshader::tech::GlobalVariable::StorageTexture(t) => (t.binding, output),
+                _ => panic!("unexpected hiz variable {}", name),
+            };
+            entries.push(wgpu::BindGroupEntry {
+                binding,
+                resource: wgpu::BindingResource::TextureView(view),
+            });
+        }
+        device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("hiz bind group"),
+            layout: &layout,
+            entries: &entries,
+        })
+    }
+}
+
+impl ComputePassExecutor for HiZRenderer {
+    #[profiling::function]
+    fn prepare<'a>(
+        &'a mut self,
+        context: RenderPassContext<'a>,
+        engine: &mut GraphCopyEngine,
+    ) -> Option<()> {
+        self.recorded = false;
+        self.dispatches.clear();
+        if !self.poll(engine.device()) {
+            return None;
+        }
+        self.occlusion.view()?;
+
+        let depth = context.registry.get(self.depth_id);
+        let texture = depth.texture_ref();
+        let size = Size::new(texture.width(), texture.height());
+        // the graph is rebuilt with a new pyramid on resize
+        if self.pyramid.size != size {
+            return None;
+        }
+
+        let device = engine.device();
+        let pyramid = &self.pyramid;
+        let mut dispatches = vec![(
+            "copy",
+            self.bind_group(device, "copy", depth.texture_view(), &pyramid.views[0]),
+            size,
+        )];
+        for l in 1..=pyramid.level() {
+            dispatches.push((
+                "reduce",
+                self.bind_group(
+                    device,
+                    "reduce",
+                    &pyramid.views[l as usize - 1],
+                    &pyramid.views[l as usize],
+                ),
+                pyramid.level_size(l),
+            ));
+        }
+        self.dispatches = dispatches;
+        Some(())
+    }
+
+    #[profiling::function]
+    fn dispatch<'a>(
+        &'a mut self,
+        _context: RenderPassContext<'a>,
+        engine: &mut GraphComputeEngine,
+    ) {
+        let (vp, viewport) = match self.occlusion.view() {
+            Some(v) => v,
+            None => return,
+        };
+        for (name, bind_group, size) in &self.dispatches {
+            let pso =
+                self.material_shader_collector
+                    .get("hiz", &self.variants, self.view as u64, name);
+            engine.dispatch(
+                &pso,
+                &[(BindGroupType::Material, bind_group)],
+                Vec3u::new(size.x, size.y, 1),
+            );
+        }
+
+        let pyramid = &self.pyramid;
+        let level = pyramid.level();
+        let level_size = pyramid.level_size(level);
+        let readback = &mut self.readback;
+        engine.encoder().copy_texture_to_buffer(
+            wgpu::ImageCopyTexture {
+                texture: pyramid.texture.texture_ref(),
+                mip_level: level,
+                origin: wgpu::Origin3d::ZERO,
+                aspect: wgpu::TextureAspect::All,
+            },
+            wgpu::ImageCopyBuffer {
+                buffer: &readback.buffer,
+                layout: wgpu::ImageDataLayout {
+                    offset: 0,
+                    bytes_per_row: Some(readback.bytes_per_row),
+                    rows_per_image: None,
+                },
+            },
+            wgpu::Extent3d {
+                width: level_size.x,
+                height: level_size.y,
+                depth_or_array_layers: 1,
+            },
+        );
+        readback.vp = vp;
+        readback.viewport = viewport.unwrap_or(Vec4f::new(
+            0f32,
+            0f32,
+            pyramid.size.x as f32,
+            pyramid.size.y as f32,
+        ));
+        self.recorded = true;
+    }
+
+    // the buffer can be mapped once the copy is submitted
+    fn cleanup<'b>(&'b mut self, _context: RenderPassContext<'b>) {
+        self.dispatches.clear();
+        if !self.recorded {
+            return;
+        }
+        let readback = &mut self.readback;
+        let mapped: MapResult = Arc::new(Mutex::new(None));
+        let res = mapped.clone();
+        readback
+            .buffer
+            .slice(..)
+            .map_async(wgpu::MapMode::Read, move |v| {
+                *res.lock().unwrap() = Some(v);
+            });
+        readback.mapped = Some(mapped);
+    }
+}
+
+// builds the depth pyramid of the view after its passes and before the passes
+// of `next_views`, the sorters cull with it in the next frames. `size` is the
+// size of the view target. returns the pyramid, import it every frame
+#[allow(clippy::too_many_arguments)]
+pub fn add_hiz_pass(
+    g: &mut RenderGraphBuilder,
+    gpu: &WGPUResource,
+    view: ViewId,
+    next_views: &[ViewId],
+    target: &ViewTarget,
+    depth: DepthConvention,
+    size: Size,
+    occlusion: &Arc<OcclusionCulling>,
+    shader_tech_collection: &Arc<ShaderTechCollection>,
+    msaa: u32,
+) -> (ResourceId, ResourceRef) {
+    let mut variants = vec![];
+    if msaa > 1 {
+        variants.push("MSAA".to_owned());
+    }
+    if depth.is_reversed() {
+        variants.push("REVERSE_Z".to_owned());
+    }
+    let variants = VariantFlags::new(variants);
+    shader_tech_collection
+        .setup(gpu.device(), "hiz", &variants, view as u64, |_| {
+            RenderDescriptorObject::new()
+        })
+        .unwrap();
+
+    let depth_id = match target.depth {
+        PreferAttachment::Resource(id) => id,
+        _ => RT_DEPTH_RESOURCE_ID,
+    };
+    let pyramid = HiZPyramid::new(gpu, size);
+    let level_size = pyramid.level_size(pyramid.level());
+    let pyramid_id = g.import_texture(&format!("hiz view {} pyramid", view));
+    let res = (pyramid_id, pyramid.texture.clone());
+
+    let mut pass = ComputePassBuilder::new(format!("hiz view {}", view));
+    pass.read_texture(depth_id);
+    pass.write_texture(pyramid_id);
+    // the default depth is not linked to the passes drawing it
+    for name in g.view_passes(view) {
+        pass.add_constraint(PassConstraint::After(name));
+    }
+    for name in next_views.iter().flat_map(|v| g.view_passes(*v)) {
+        pass.add_constraint(PassConstraint::Before(name));
+    }
+    pass.async_execute(Arc::new(Mutex::new(HiZRenderer {
+        view,
+        depth_id,
+        depth,
+        variants,
+        occlusion: occlusion.clone(),
+        pyramid,
+        readback: HiZReadback::new(gpu.device(), level_size),
+        dispatches: vec![],
+        recorded: false,
+        material_shader_collector: shader_tech_collection.clone(),
+    })));
+    g.add_compute_pass(pass);
+    res
+}
@@ -5,13 +5,16 @@ use std::{
 
 use crate::{
     backends::wgpu_backend::WGPUResource,
-    graph::rdg::{pass::RenderPassContext, RenderGraphBuilder},
+    graph::rdg::{backend::GraphRenderEngine, pass::RenderPassContext, RenderGraphBuilder},
     material::{MaterialArc, MaterialFace, MaterialId},
-    scene::{LayerId, Scene},
+    scene::{DepthConvention, LayerId, Scene},
+    types::Vec4f,
 };
 
 use super::{
+    oit::{is_oit_layer, OitTarget},
     tech::ShaderTechCollection,
+    view::{apply_viewport, RenderView, ViewId, ViewTarget},
     GlobalUniform,
 };
 
@@ -38,6 +41,8 @@ pub struct RenderSourceLayer {
     pub material: Vec<RenderSourceIndirectObjects>,
     pub main_camera: Arc<GlobalUniform>,
     pub layer: u32,
+    pub view: ViewId,
+    pub viewport: Option<Vec4f>,
 }
 
 impl Debug for RenderSourceLayer {
@@ -46,6 +51,7 @@ impl Debug for RenderSourceLayer {
             .field("objects", &self.objects)
             .field("material", &self.material)
             .field("layer", &self.layer)
+            .field("view", &self.view)
             .finish()
     }
 }
@@ -54,18 +60,30 @@ impl RenderSourceLayer {
     pub fn objects(&self, r: &RenderSourceIndirectObjects) -> &[u64] {
         &self.objects[r.offset..(r.offset + r.count)]
     }
+
+    // begin a render pass limited to the viewport of the view
+    pub fn begin<'a>(&self, engine: &'a mut GraphRenderEngine) -> wgpu::RenderPass<'a> {
+        let mut pass = engine.begin(self.layer);
+        if let Some(v) = &self.viewport {
+            apply_viewport(v, &mut pass);
+        }
+        pass
+    }
 }
 
 pub struct RenderSource {
     pub gpu: Arc<WGPUResource>,
     pub scene: Arc<Scene>,
     pub list: Vec<RenderSourceLayer>,
-    pub layer_map_index: HashMap<LayerId, usize>,
+    pub layer_map_index: HashMap<(ViewId, LayerId), usize>,
 }
 
 impl RenderSource {
-    pub fn layer(&self, layer: LayerId) -> &RenderSourceLayer {
-        &self.list[self.layer_map_index.get(&layer).cloned().unwrap()]
+    // None if the view draws no object of the layer, e.g. all of them were culled
+    pub fn layer(&self, view: ViewId, layer: LayerId) -> Option<&RenderSourceLayer> {
+        self.layer_map_index
+            .get(&(view, layer))
+            .map(|v| &self.list[*v])
     }
 }
 
@@ -79,14 +97,31 @@ impl Debug for RenderSource {
 
 pub struct RenderMaterialContext {
     pub map: HashMap<TypeId, RenderSource>,
+    pub views: Vec<RenderView>,
 }
 
+// `setup` of a factory is called once per view, pass names should contain the view
 pub struct SetupResource<'a> {
     pub ui_camera: Arc<GlobalUniform>,
     pub main_camera: Arc<GlobalUniform>,
+    pub view: ViewId,
+    // passes of the view render to this target instead of the default one
+    pub target: ViewTarget,
     pub shader_tech_collection: Arc<ShaderTechCollection>,
     pub scene: &'a Scene,
     pub msaa: u32,
+    pub oit: Option<OitTarget>,
+    // depth compare of the passes follows it
+    pub depth: DepthConvention,
+}
+
+impl<'a> SetupResource<'a> {
+    // passes of `layer` draw the `OIT` variant to this target when set
+    pub fn oit_target(&self, layer: LayerId) -> Option<&OitTarget> {
+        self.oit
+            .as_ref()
+            .filter(|_| is_oit_layer(self.scene, layer))
+    }
 }
 
 pub struct RenderMaterialPsoBuilder {
@@ -107,6 +142,17 @@ pub trait MaterialRendererFactory {
         g: &mut RenderGraphBuilder,
         setup_resource: &SetupResource,
     );
+
+    // create pipelines for materials added after `setup`, the passes already exist.
+    // return false to set up the whole graph again instead
+    fn setup_materials(
+        &self,
+        _material_builder: &RenderMaterialPsoBuilder,
+        _gpu: &WGPUResource,
+        _setup_resource: &SetupResource,
+    ) -> bool {
+        false
+    }
 }
 
 pub mod basic;
@@ -1,4 +1,7 @@
-use std::sync::{Arc, Mutex};
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+};
 
 
 use crate::{
@@ -8,17 +11,23 @@ use crate::{
         pass::*,
         RenderPassBuilder,
     },
-    material::basic::*,
+    material::{basic::*, Material, MaterialArc},
     render::{
-        collection::ShaderBindGroupCollection, collector::MeshBufferCollector, pso::{ColorTargetBuilder, RenderDescriptorObject}, tech::ShaderTechCollection
+        collection::ShaderBindGroupCollection,
+        collector::MeshBufferCollector,
+        oit::{oit_variants, OitTarget},
+        pso::{ColorTargetBuilder, RenderDescriptorObject},
+        tech::{layer_instance_id, ShaderTechCollection},
     },
-    scene::LayerId,
+    scene::{DepthConvention, Layer, LayerId},
     util::any_as_u8_slice,
 };
 
 use super::{
     take_rs, MaterialRendererFactory, RenderMaterialPsoBuilder, SetupResource
 };
+use crate::render::view::ViewId;
+use tshader::VariantFlags;
 
 struct BasicMaterialHardwareRendererInner {
     shader_bind_group_collection: ShaderBindGroupCollection,
@@ -29,6 +38,20 @@ struct BasicMaterialHardwareRendererInner {
 pub struct BasicMaterialHardwareRenderer {
     inner: BasicMaterialHardwareRendererInner,
     layer: LayerId,
+    view: ViewId,
+    // variant hash -> `OIT` variant, set when the layer draws to the oit targets
+    oit: Option<HashMap<u64, VariantFlags>>,
+    depth: DepthConvention,
+}
+
+impl BasicMaterialHardwareRenderer {
+    fn variants<'v>(&'v self, material: &'v Material) -> &'v VariantFlags {
+        let variants = material.face().variants();
+        match &self.oit {
+            Some(m) => m.get(&variants.hash_key()).unwrap(),
+            None => variants,
+        }
+    }
 }
 
 impl RenderPassExecutor for BasicMaterialHardwareRenderer {
@@ -41,9 +64,10 @@ impl RenderPassExecutor for BasicMaterialHardwareRenderer {
         self.inner.mesh_buffer_collector.recall();
 
         let rs = take_rs::<BasicMaterialFace>(&context)?;
-        let c = rs.scene.get_container();
+        let c = rs.scene.read_container();
 
-        let layer = rs.layer(self.layer);
+        // nothing of the layer left after culling
+        let layer = rs.layer(self.view, self.layer)?;
         for indirect in &layer.material {
             // create index/vertex buffer
             let objects = layer.objects(indirect);
@@ -61,12 +85,24 @@ impl RenderPassExecutor for BasicMaterialHardwareRenderer {
     #[profiling::function]
     fn queue<'b>(&'b mut self, context: RenderPassContext<'b>, device: &wgpu::Device) {
         let rs = take_rs::<BasicMaterialFace>(&context).unwrap();
-        let layer = rs.layer(self.layer);
+        let layer = match rs.layer(self.view, self.layer) {
+            Some(v) => v,
+            None => return,
+        };
 
         for indirect in &layer.material {
             let material = indirect.material.as_ref();
+            if let Some(m) = &mut self.oit {
+                let variants = material.face().variants();
+                m.entry(variants.hash_key())
+                    .or_insert_with(|| oit_variants(variants, self.depth));
+            }
             let pso = self.inner.material_shader_collector.get(
-                "basic", indirect.material.face().variants(), material.id().id(), "forward");
+                "basic",
+                self.variants(material),
+                layer_instance_id(material, self.layer),
+                "forward",
+            );
 
             self.inner.shader_bind_group_collection.setup(device, material, material.id().id(), pso);
         }
@@ -75,17 +111,24 @@ impl RenderPassExecutor for BasicMaterialHardwareRenderer {
     #[profiling::function]
     fn render<'a>(&'a mut self, context: RenderPassContext<'a>, engine: &mut GraphRenderEngine) {
         let rs = take_rs::<BasicMaterialFace>(&context).unwrap();
-        let c = rs.scene.get_container();
+        let c = rs.scene.read_container();
 
-        let layer = rs.layer(self.layer);
-        let mut pass = engine.begin(layer.layer);
+        let layer = match rs.layer(self.view, self.layer) {
+            Some(v) => v,
+            None => return,
+        };
+        let mut pass = layer.begin(engine);
 
         for indirect in &layer.material {
             let objects = layer.objects(indirect);
             let material = indirect.material.as_ref();
 
             let pso = self.inner.material_shader_collector.get(
-                "basic", indirect.material.face().variants(), material.id().id(), "forward");
+                "basic",
+                self.variants(material),
+                layer_instance_id(material, self.layer),
+                "forward",
+            );
 
             pass.set_pipeline(pso.render());
             pass.set_bind_group(0, &layer.main_camera.bind_group, &[0]); // camera bind group
@@ -104,7 +147,7 @@ impl RenderPassExecutor for BasicMaterialHardwareRenderer {
                 let b = self.inner.mesh_buffer_collector.get(&c, *id).unwrap();
 
                 if b.instance_data.is_none() {
-                    let object_uniform = obj.geometry().transform();
+                    let object_uniform = obj.transform();
 
                     pass.set_push_constants(
                         wgpu::ShaderStages::VERTEX,
@@ -127,25 +170,39 @@ impl RenderPassExecutor for BasicMaterialHardwareRenderer {
     }
 }
 
-#[derive(Default)]
-pub struct BasicMaterialRendererFactory {}
-
-impl MaterialRendererFactory for BasicMaterialRendererFactory {
-    fn setup(
-        &self,
-        materials_map: &RenderMaterialPsoBuilder,
-        gpu: &WGPUResource,
-        g: &mut crate::graph::rdg::RenderGraphBuilder,
-        setup_resource: &SetupResource,
-    ) {
-        for (layer, materials) in &materials_map.map {
-            setup_resource
-                .shader_tech_collection
-                .setup_materials(gpu.device(), materials, "basic", |material, _| {
+fn setup_pso(
+    materials: &[MaterialArc],
+    layer: LayerId,
+    gpu: &WGPUResource,
+    setup_resource: &SetupResource,
+) {
+    let settings = setup_resource
+        .scene
+        .find_layer(layer)
+        .unwrap_or_else(|| Layer::new(layer, "invalid"));
+    let oit = setup_resource.oit_target(layer).is_some();
+    for material in materials {
+        let oit_flags;
+        let variants = if oit {
+            oit_flags = oit_variants(material.face().variants(), setup_resource.depth);
+            &oit_flags
+        } else {
+            material.face().variants()
+        };
+        setup_resource
+            .shader_tech_collection
+            .setup(
+                gpu.device(),
+                "basic",
+                variants,
+                layer_instance_id(material, layer),
+                |_| {
                     let mut rdo = RenderDescriptorObject::new();
                     rdo = rdo.set_msaa(setup_resource.msaa);
 
-                    if let Some(blend) = material.blend() {
+                    if oit {
+                        rdo = OitTarget::add_targets(rdo);
+                    } else if let Some(blend) = material.blend().or(settings.blend.as_ref()) {
                         rdo = rdo.add_target(
                             ColorTargetBuilder::new(gpu.surface_format())
                                 .set_blender(*blend)
@@ -158,13 +215,32 @@ impl MaterialRendererFactory for BasicMaterialRendererFactory {
 
                     rdo = rdo.set_primitive(|p: &mut _| *p = *material.primitive());
                     rdo = rdo.set_depth(depth_format, |depth: &mut _| {
-                        depth.depth_compare = wgpu::CompareFunction::Less;
-                        depth.depth_write_enabled = !material.is_transparent();
+                        depth.depth_compare = settings.depth_compare(setup_resource.depth);
+                        depth.depth_write_enabled =
+                            !oit && settings.writes_depth(material.is_transparent());
                     });
 
                     rdo
-                })
-                .unwrap();
+                },
+            )
+            .unwrap();
+    }
+}
+
+#[derive(Default)]
+pub struct BasicMaterialRendererFactory {}
+
+impl MaterialRendererFactory for BasicMaterialRendererFactory {
+    fn setup(
+        &self,
+        materials_map: &RenderMaterialPsoBuilder,
+        gpu: &WGPUResource,
+        g: &mut crate::graph::rdg::RenderGraphBuilder,
+        setup_resource: &SetupResource,
+    ) {
+        for (layer, materials) in &materials_map.map {
+            setup_pso(materials, *layer, gpu, setup_resource);
+            let oit = setup_resource.oit_target(*layer);
 
             let r = Arc::new(Mutex::new(BasicMaterialHardwareRenderer {
                 inner: BasicMaterialHardwareRendererInner {
@@ -175,15 +251,36 @@ impl MaterialRendererFactory for BasicMaterialRendererFactory {
                         .clone(),
                 },
                 layer: *layer,
+                view: setup_resource.view,
+                oit: oit.map(|_| HashMap::new()),
+                depth: setup_resource.depth,
             }));
 
-            let mut pass = RenderPassBuilder::new(format!("basic render pass layer {}", layer));
-            pass.default_color_depth_render_target();
+            let mut pass = RenderPassBuilder::new(format!(
+                "basic render pass view {} layer {}",
+                setup_resource.view, layer
+            ));
+            match oit {
+                Some(oit) => pass.render_target(oit.render_target()),
+                None => pass.render_target(setup_resource.target.render_target()),
+            }
             pass.async_execute(r.clone());
             pass.add_constraint(PassConstraint::Last);
 
             g.add_render_pass(pass);
         }
     }
+
+    fn setup_materials(
+        &self,
+        materials_map: &RenderMaterialPsoBuilder,
+        gpu: &WGPUResource,
+        setup_resource: &SetupResource,
+    ) -> bool {
+        for (layer, materials) in &materials_map.map {
+            setup_pso(materials, *layer, gpu, setup_resource);
+        }
+        true
+    }
 }
 
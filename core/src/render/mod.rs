@@ -3,7 +3,7 @@ use material::RenderMaterialPsoBuilder;
 use pso::PipelineStateObjectCache;
 use std::{
     any::TypeId,
-    collections::{BTreeMap, HashMap},
+    collections::{BTreeMap, HashMap, HashSet},
     sync::Arc,
 };
 use tech::ShaderTechCollection;
@@ -11,10 +11,16 @@ use tech::ShaderTechCollection;
 use crate::{
     backends::wgpu_backend::WGPUResource,
     graph::rdg::{backend::GraphBackend, RenderGraph, RenderGraphBuilder},
-    material::{basic::BasicMaterialFace, MaterialArc},
+    material::{basic::BasicMaterialFace, MaterialArc, MaterialId},
     render::material::{RenderSourceIndirectObjects, RenderSourceLayer, SetupResource},
-    scene::{layer_str, LayerId, Scene, LAYER_UI},
-    types::{Mat4x4f, Vec4f},
+    render::hiz::add_hiz_pass,
+    render::oit::{add_oit_composite_pass, is_oit_layer, OitTarget},
+    render::view::{add_view_clear_pass, RenderView, ViewId},
+    scene::{
+        CameraTarget, DepthConvention, LayerId, ObjectId, Scene, SceneEvent, SceneObjects,
+        TransparencyMode,
+    },
+    types::{Mat4x4f, Size, Vec4f},
     util::any_as_u8_slice,
 };
 
@@ -30,6 +36,8 @@ pub struct RenderParameter<'a> {
 
 pub struct SetupConfig {
     pub msaa: u32,
+    // render target size in pixels
+    pub size: Size,
 }
 
 pub trait ModuleRenderer {
@@ -40,6 +48,8 @@ pub trait ModuleRenderer {
         scene: &Scene,
         config: &SetupConfig,
     );
+    // apply object changes since the last frame, returns true if `setup` must run again
+    fn update(&mut self, gpu: Arc<WGPUResource>, scene: &Scene, events: &[SceneEvent]) -> bool;
     fn render(&mut self, parameter: RenderParameter);
     fn stop(&mut self);
 }
@@ -49,8 +59,11 @@ pub mod collector;
 pub mod common;
 pub mod collection;
 pub mod material;
+pub mod hiz;
+pub mod oit;
 pub mod pso;
 pub mod tech;
+pub mod view;
 
 #[repr(C)]
 struct GlobalUniform3d {
@@ -94,8 +107,17 @@ impl GlobalUniform {
 }
 
 struct HardwareRendererInner {
-    main_camera: Arc<GlobalUniform>,
+    bind_layout: Arc<wgpu::BindGroupLayout>,
+    // one uniform per camera view
+    cameras: Vec<Arc<GlobalUniform>>,
     ui_camera: Arc<GlobalUniform>,
+    views: Vec<RenderView>,
+
+    // what the current graph was set up with
+    passes: HashSet<(ViewId, TypeId, LayerId)>,
+    materials: HashSet<(MaterialId, LayerId)>,
+    msaa: u32,
+    depth: DepthConvention,
 }
 
 pub struct HardwareRenderer {
@@ -149,29 +171,52 @@ impl HardwareRenderer {
                     }],
                 },
             ));
-            let main = GlobalUniform::new(
-                gpu,
-                bind_layout.clone(),
-                std::mem::size_of::<GlobalUniform3d>() as u32,
-            );
             let ui = GlobalUniform::new(
                 gpu,
                 bind_layout.clone(),
                 std::mem::size_of::<GlobalUniform2d>() as u32,
             );
             HardwareRendererInner {
-                main_camera: Arc::new(main),
+                bind_layout,
+                cameras: vec![],
                 ui_camera: Arc::new(ui),
+                views: vec![],
+                passes: HashSet::new(),
+                materials: HashSet::new(),
+                msaa: 1,
+                depth: DepthConvention::default(),
             }
         });
     }
+
+    fn camera_uniform(&mut self, gpu: &WGPUResource, index: usize) -> Arc<GlobalUniform> {
+        let inner = self.inner.as_mut().unwrap();
+        while inner.cameras.len() <= index {
+            let uniform = GlobalUniform::new(
+                gpu,
+                inner.bind_layout.clone(),
+                std::mem::size_of::<GlobalUniform3d>() as u32,
+            );
+            inner.cameras.push(Arc::new(uniform));
+        }
+        inner.cameras[index].clone()
+    }
+
     fn copy_camera_uniform(&mut self, p: &RenderParameter) {
         let scene = p.scene.clone();
 
         // prepare camera uniform buffer
         let inner = self.inner.as_ref().unwrap();
-        if let Some(camera) = scene.main_camera_ref() {
+        let occlusion = scene.occlusion_culling();
+        for (index, view) in inner.views.iter().enumerate() {
+            let camera = match view.camera.and_then(|v| scene.camera_ref(v)) {
+                Some(v) => v,
+                None => continue,
+            };
             let vp = camera.vp();
+            if let (Some(o), Some(_)) = (&occlusion, &view.hiz) {
+                o.set_view(vp, view.viewport);
+            }
             let direction = (camera.to() - camera.from()).normalize();
             let data = GlobalUniform3d {
                 mat: vp,
@@ -179,7 +224,7 @@ impl HardwareRenderer {
             };
             p.gpu
                 .queue()
-                .write_buffer(&inner.main_camera.buffer, 0, any_as_u8_slice(&data));
+                .write_buffer(&view.uniform.buffer, 0, any_as_u8_slice(&data));
         }
         let size = scene.ui_camera_ref().width_height();
 
@@ -204,69 +249,334 @@ impl ModuleRenderer for HardwareRenderer {
         log::info!("hardware setup");
         self.setup_global_uniform(&gpu);
 
+        // one view per active camera, ui layers are drawn by the last view
+        let mut views = vec![];
+        for (index, (id, camera, settings)) in scene.active_cameras().iter().enumerate() {
+            let uniform = self.camera_uniform(&gpu, index);
+            let depth = camera.depth_convention();
+            // the default depth is cleared once with the convention of the graph
+            if settings.target.texture().is_none()
+                && !settings.clear_depth
+                && depth != g.depth_convention()
+            {
+                log::warn!(
+                    "camera {} doesn't clear the default depth of {:?}",
+                    id,
+                    g.depth_convention()
+                );
+            }
+            views.push((
+                RenderView::new_camera(
+                    g,
+                    index as ViewId,
+                    *id,
+                    uniform,
+                    settings,
+                    depth,
+                    config.size,
+                    config.msaa,
+                ),
+                Some(settings.clone()),
+            ));
+        }
         let inner = self.inner.as_ref().unwrap();
+        views.push((
+            RenderView::new_ui(
+                inner.ui_camera.clone(),
+                scene.ui_camera_ref().depth_convention(),
+            ),
+            None,
+        ));
+
+        let container = scene.read_container();
+        let mut passes = HashSet::new();
+        let mut setup_materials = HashSet::new();
+
+        // every object gets its passes, the old depth doesn't match the new graph
+        let occlusion = scene.occlusion_culling();
+        if let Some(o) = &occlusion {
+            o.clear();
+        }
+        // the sorters are shared by the views, culling needs a single camera
+        let occlusion = occlusion.filter(|_| {
+            let active = scene.occlusion_culling_active();
+            if !active {
+                log::warn!("occlusion culling is off, more than one camera is active");
+            }
+            active
+        });
 
-        let setup_resource = SetupResource {
-            ui_camera: inner.ui_camera.clone(),
-            main_camera: inner.main_camera.clone(),
-            shader_tech_collection: self.shader_tech_collection.clone(),
-            scene: scene,
-            msaa: config.msaa,
-        };
-        let container = scene.get_container();
-
-        // face map
-        let mut material_map: IndexMap<TypeId, BTreeMap<LayerId, Vec<MaterialArc>>> =
-            IndexMap::new();
-
+        let mut layers = vec![];
         for (layer, sorter) in scene.layers() {
             let sort_objects = sorter.lock().unwrap().sort_and_cull();
             log::info!(
                 "setup layer {} {} total {} object sort {:?}",
                 layer,
-                layer_str(layer),
+                scene.layer_str(layer),
                 sort_objects.len(),
                 sort_objects
             );
+            layers.push((layer, sort_objects));
+        }
 
-            for obj_id in sort_objects {
-                let o = container.get(&obj_id).unwrap();
-                let obj = o.o();
-                let mat_face_id = obj.material_arc().face_id();
-                material_map
-                    .entry(mat_face_id)
-                    .and_modify(|v| {
-                        v.entry(layer)
-                            .and_modify(|r| r.push(obj.material_arc()))
-                            .or_insert_with(|| vec![obj.material_arc()]);
-                    })
-                    .or_insert_with(|| {
-                        let mut m = BTreeMap::new();
-                        m.insert(layer, vec![obj.material_arc()]);
-                        m
-                    });
+        let weighted_blended = scene.transparency_mode() == TransparencyMode::WeightedBlended;
+
+        for (view_id, (view, settings)) in views.iter_mut().enumerate() {
+            let view_id = view_id as ViewId;
+
+            // (layer order, layer) -> face map
+            let mut material_map: BTreeMap<(i64, LayerId), IndexMap<TypeId, Vec<MaterialArc>>> =
+                BTreeMap::new();
+
+            for (layer, sort_objects) in &layers {
+                if !view.contains(scene, *layer) {
+                    continue;
+                }
+                let order = scene
+                    .find_layer(*layer)
+                    .map(|v| v.order)
+                    .unwrap_or(*layer as i64);
+                for obj_id in sort_objects {
+                    let o = container.get(obj_id).unwrap();
+                    let obj = o.o();
+                    if view.samples_target(&obj.material_arc()) {
+                        log::warn!(
+                            "view {} skips object {}, its material samples the view target",
+                            view_id,
+                            obj_id
+                        );
+                        continue;
+                    }
+                    let mat_face_id = obj.material_arc().face_id();
+                    passes.insert((view_id, mat_face_id, *layer));
+                    setup_materials.insert((obj.material_arc().id(), *layer));
+                    material_map
+                        .entry((order, *layer))
+                        .or_default()
+                        .entry(mat_face_id)
+                        .or_default()
+                        .push(obj.material_arc());
+                }
             }
-        }
 
-        for (mat_face_id, materials) in material_map {
-            let f = match self.material_renderer_factory.get(&mat_face_id) {
-                Some(v) => v,
-                None => {
-                    log::error!(
-                        "material {:?} renderer factory not exist, check your plugin list",
-                        mat_face_id
+            // the last transparent layer of the view blends the oit targets
+            let oit_layer = material_map
+                .keys()
+                .rev()
+                .find(|(_, layer)| is_oit_layer(scene, *layer))
+                .cloned();
+            if let Some(settings) = settings
+                .as_ref()
+                .filter(|_| weighted_blended && oit_layer.is_some())
+            {
+                let size = match &settings.target {
+                    CameraTarget::Surface => config.size,
+                    CameraTarget::Texture(t) => t.size(),
+                };
+                view.oit = Some(OitTarget::new(g, view_id, &view.target, size, config.msaa));
+            }
+
+            let setup_resource = SetupResource {
+                ui_camera: inner.ui_camera.clone(),
+                main_camera: view.uniform.clone(),
+                view: view_id,
+                target: view.target.clone(),
+                shader_tech_collection: self.shader_tech_collection.clone(),
+                scene: scene,
+                msaa: config.msaa,
+                oit: view.oit.clone(),
+                depth: view.depth,
+            };
+
+            g.set_view(Some(view_id));
+            if let Some(settings) = settings {
+                add_view_clear_pass(
+                    g,
+                    &gpu,
+                    view_id,
+                    &view.target,
+                    settings,
+                    view.depth,
+                    &self.shader_tech_collection,
+                    config.msaa,
+                );
+            }
+
+            for ((order, layer), faces) in material_map {
+                g.set_pass_order(Some(order));
+                for (mat_face_id, materials) in faces {
+                    let f = match self.material_renderer_factory.get(&mat_face_id) {
+                        Some(v) => v,
+                        None => {
+                            log::error!(
+                                "material {:?} renderer factory not exist, check your plugin list",
+                                mat_face_id
+                            );
+                            continue;
+                        }
+                    };
+                    profiling::scope!("material setup", &format!("{:?}", mat_face_id));
+                    f.setup(
+                        &RenderMaterialPsoBuilder::new(BTreeMap::from([(layer, materials)])),
+                        &gpu,
+                        g,
+                        &setup_resource,
+                    );
+                }
+                if let (Some(oit), true) = (&view.oit, oit_layer == Some((order, layer))) {
+                    add_oit_composite_pass(
+                        g,
+                        &gpu,
+                        view_id,
+                        &view.target,
+                        oit,
+                        &self.shader_tech_collection,
+                        config.msaa,
                     );
-                    continue;
                 }
+            }
+            g.set_pass_order(None);
+        }
+        g.set_view(None);
+
+        // the depth of the main camera, drawn by the first view
+        let next_views: Vec<ViewId> = (1..views.len() as ViewId).collect();
+        if let (Some(o), (view, Some(settings))) = (&occlusion, &mut views[0]) {
+            let size = match &settings.target {
+                CameraTarget::Surface => config.size,
+                CameraTarget::Texture(t) => t.size(),
             };
-            profiling::scope!("material setup", &format!("{:?}", mat_face_id));
-            f.setup(
-                &RenderMaterialPsoBuilder::new(materials),
-                &gpu,
+            view.hiz = Some(add_hiz_pass(
                 g,
-                &setup_resource,
-            );
+                &gpu,
+                0,
+                &next_views,
+                &view.target,
+                view.depth,
+                size,
+                o,
+                &self.shader_tech_collection,
+                config.msaa,
+            ));
         }
+
+        let inner = self.inner.as_mut().unwrap();
+        inner.views = views.into_iter().map(|v| v.0).collect();
+        inner.passes = passes;
+        inner.materials = setup_materials;
+        inner.msaa = config.msaa;
+        inner.depth = g.depth_convention();
+    }
+
+    #[profiling::function]
+    fn update(&mut self, gpu: Arc<WGPUResource>, scene: &Scene, events: &[SceneEvent]) -> bool {
+        let inner = match self.inner.as_mut() {
+            Some(v) => v,
+            None => return true,
+        };
+        if scene.depth_convention() != inner.depth {
+            log::info!("rebuild scene because depth convention changed");
+            return true;
+        }
+        for (index, (_, camera, _)) in scene.active_cameras().iter().enumerate() {
+            if inner
+                .views
+                .get(index)
+                .is_some_and(|v| v.depth != camera.depth_convention())
+            {
+                log::info!(
+                    "rebuild scene because depth convention of view {} changed",
+                    index
+                );
+                return true;
+            }
+        }
+        let container = scene.read_container();
+
+        // view -> face -> layer -> materials without pipelines
+        let mut new_materials: BTreeMap<
+            ViewId,
+            IndexMap<TypeId, BTreeMap<LayerId, Vec<MaterialArc>>>,
+        > = BTreeMap::new();
+        let mut found = HashSet::new();
+
+        for event in events {
+            let (id, layer) = match event {
+                SceneEvent::ObjectAdded { id, layer, .. } => (*id, *layer),
+                SceneEvent::MaterialChanged { id, layer, .. } => (*id, *layer),
+                SceneEvent::LayerChanged { id, new, .. } => (*id, *new),
+                // sorters and per frame uniforms pick up the rest
+                _ => continue,
+            };
+            let material = match container.get(&id) {
+                Some(v) => v.o().material_arc(),
+                None => continue,
+            };
+            let face_id = material.face_id();
+
+            for (view_id, view) in inner.views.iter().enumerate() {
+                let view_id = view_id as ViewId;
+                if !view.contains(scene, layer) || view.samples_target(&material) {
+                    continue;
+                }
+                if !inner.passes.contains(&(view_id, face_id, layer)) {
+                    log::info!(
+                        "rebuild scene because view {} layer {} has no pass for object {}",
+                        view_id,
+                        scene.layer_str(layer),
+                        id
+                    );
+                    return true;
+                }
+                if !inner.materials.contains(&(material.id(), layer))
+                    && found.insert((view_id, material.id(), layer))
+                {
+                    new_materials
+                        .entry(view_id)
+                        .or_default()
+                        .entry(face_id)
+                        .or_default()
+                        .entry(layer)
+                        .or_default()
+                        .push(material.clone());
+                }
+            }
+        }
+
+        let mut setup_materials = vec![];
+        for (view_id, faces) in new_materials {
+            let view = &inner.views[view_id as usize];
+            let setup_resource = SetupResource {
+                ui_camera: inner.ui_camera.clone(),
+                main_camera: view.uniform.clone(),
+                view: view_id,
+                target: view.target.clone(),
+                shader_tech_collection: self.shader_tech_collection.clone(),
+                scene,
+                msaa: inner.msaa,
+                oit: view.oit.clone(),
+                depth: view.depth,
+            };
+            for (face_id, materials) in faces {
+                let f = self.material_renderer_factory.get(&face_id).unwrap();
+                for (layer, materials) in &materials {
+                    setup_materials.extend(materials.iter().map(|v| (v.id(), *layer)));
+                }
+                if !f.setup_materials(
+                    &RenderMaterialPsoBuilder::new(materials),
+                    &gpu,
+                    &setup_resource,
+                ) {
+                    log::info!(
+                        "rebuild scene because material {:?} can't be set up alone",
+                        face_id
+                    );
+                    return true;
+                }
+            }
+        }
+        inner.materials.extend(setup_materials);
+        false
     }
 
     #[profiling::function]
@@ -275,45 +585,92 @@ impl ModuleRenderer for HardwareRenderer {
 
         let gpu = p.gpu.clone();
         let scene = p.scene;
-        let storage = scene.get_container();
+        let storage = scene.read_container();
 
-        let mut render_source_map: HashMap<TypeId, RenderSource> = HashMap::new();
+        let inner = self.inner.as_mut().unwrap();
 
-        let inner = self.inner.as_ref().unwrap();
+        for view in &inner.views {
+            if let Some((id, texture)) = &view.texture {
+                p.g.registry().import(*id, texture.texture());
+            }
+            if let Some((id, pyramid)) = &view.hiz {
+                p.g.registry().import(*id, pyramid.clone());
+            }
+        }
 
+        let mut layers = vec![];
         for (layer, sorter) in scene.layers() {
-            let main_camera = if layer >= LAYER_UI {
-                inner.ui_camera.clone()
-            } else {
-                inner.main_camera.clone()
-            };
-
             let sort_objects = sorter.lock().unwrap().sort_and_cull();
             log::info!(
                 "layer {} {} total {} object sort {:?}",
                 layer,
-                layer_str(layer),
+                scene.layer_str(layer),
                 sort_objects.len(),
                 sort_objects
             );
+            layers.push((layer, sort_objects));
+        }
+
+        let render_source_map: HashMap<TypeId, RenderSource> =
+            layer_lists(&inner.views, &layers, &scene, &storage)
+                .into_iter()
+                .map(|(face_id, list)| {
+                    let layer_map_index = list
+                        .iter()
+                        .enumerate()
+                        .map(|(index, v)| ((v.view, v.layer), index))
+                        .collect();
+                    let rs = RenderSource {
+                        gpu: gpu.clone(),
+                        scene: scene.clone(),
+                        list,
+                        layer_map_index,
+                    };
+                    (face_id, rs)
+                })
+                .collect();
+        log::debug!("{:?}", render_source_map);
+
+        let rm_context = RenderMaterialContext {
+            map: render_source_map,
+            views: std::mem::take(&mut inner.views),
+        };
+        let backend = GraphBackend::new(p.gpu.clone());
+        p.g.execute(backend, &rm_context);
+        inner.views = rm_context.views;
+    }
+
+    fn stop(&mut self) {}
+}
 
-            for obj_id in &sort_objects {
+// objects the views draw grouped by material face, in sort order. a layer
+// whose objects were all culled gets no `RenderSourceLayer`
+fn layer_lists(
+    views: &[RenderView],
+    layers: &[(LayerId, Vec<ObjectId>)],
+    scene: &Scene,
+    storage: &SceneObjects,
+) -> HashMap<TypeId, Vec<RenderSourceLayer>> {
+    let mut res: HashMap<TypeId, Vec<RenderSourceLayer>> = HashMap::new();
+    for (view_id, view) in views.iter().enumerate() {
+        let view_id = view_id as ViewId;
+        for (layer, sort_objects) in layers {
+            let layer = *layer;
+            if !view.contains(scene, layer) {
+                continue;
+            }
+
+            for obj_id in sort_objects {
                 let o = storage.get(obj_id).unwrap();
                 let obj = o.o();
+                if view.samples_target(&obj.material_arc()) {
+                    continue;
+                }
                 let mat_id = obj.material_arc().id();
-                let face_id = obj.material_arc().face_id();
-
-                let rs = render_source_map
-                    .entry(face_id)
-                    .or_insert_with(|| RenderSource {
-                        gpu: gpu.clone(),
-                        scene: scene.clone(),
-                        list: vec![],
-                        layer_map_index: HashMap::new(),
-                    });
+                let list = res.entry(obj.material_arc().face_id()).or_default();
 
-                if let Some(rsl) = rs.list.last_mut() {
-                    if rsl.layer == layer {
+                if let Some(rsl) = list.last_mut() {
+                    if rsl.layer == layer && rsl.view == view_id {
                         // append
                         let last_mat = rsl.material.last_mut().unwrap();
                         if last_mat.mat_id != mat_id {
@@ -323,17 +680,15 @@ impl ModuleRenderer for HardwareRenderer {
                                 offset: rsl.objects.len(),
                                 count: 1,
                             });
-                            rsl.objects.push(*obj_id);
                         } else {
                             last_mat.count += 1;
-                            rsl.objects.push(*obj_id);
                         }
-                        rs.layer_map_index.insert(layer, rs.list.len() - 1);
+                        rsl.objects.push(*obj_id);
                         continue;
                     }
                 }
                 // new list
-                rs.list.push(RenderSourceLayer {
+                list.push(RenderSourceLayer {
                     objects: vec![*obj_id],
                     material: vec![RenderSourceIndirectObjects {
                         material: obj.material_arc(),
@@ -341,20 +696,84 @@ impl ModuleRenderer for HardwareRenderer {
                         offset: 0,
                         count: 1,
                     }],
-                    main_camera: main_camera.clone(),
+                    main_camera: view.uniform.clone(),
                     layer,
+                    view: view_id,
+                    viewport: view.viewport,
                 });
-                rs.layer_map_index.insert(layer, rs.list.len() - 1);
             }
         }
-        log::debug!("{:?}", render_source_map);
+    }
+    res
+}
 
-        let rm_context = RenderMaterialContext {
-            map: render_source_map,
-        };
-        let backend = GraphBackend::new(p.gpu.clone());
-        p.g.execute(backend, &rm_context);
+#[cfg(test)]
+mod tests {
+    use crate::{
+        context::RContext,
+        material::{basic::BasicMaterialFaceBuilder, input::InputResourceBuilder, MaterialBuilder},
+        mesh::{builder::MeshBuilder, StaticGeometry},
+        scene::{RenderObject, LAYER_UI},
+        types::{Color, Vec3f},
+    };
+
+    use super::*;
+
+    fn object(scene: &Scene) -> RenderObject {
+        let mut builder = MeshBuilder::default();
+        builder.add_position_vertices3(&[Vec3f::zeros(), Vec3f::x(), Vec3f::y()]);
+        builder.add_indices32(&[0, 1, 2]);
+        let geometry = StaticGeometry::new(Arc::new(builder.build().unwrap()));
+        let mut color = InputResourceBuilder::new();
+        color.add_constant(Color::new(1f32, 1f32, 1f32, 1f32));
+        let material = MaterialBuilder::default()
+            .face(
+                BasicMaterialFaceBuilder::new()
+                    .texture(color.build())
+                    .build(),
+            )
+            .build(&scene.context());
+        RenderObject::new(Box::new(geometry), material).unwrap()
     }
 
-    fn stop(&mut self) {}
+    #[test]
+    fn occluded_layer_has_no_list() {
+        let device = match crate::testing::gpu() {
+            Some(v) => v.device,
+            None => return,
+        };
+        let layout = Arc::new(
+            device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+                label: None,
+                entries: &[],
+            }),
+        );
+        let uniform = Arc::new(GlobalUniform {
+            buffer: device.create_buffer(&wgpu::BufferDescriptor {
+                label: None,
+                size: 16,
+                usage: wgpu::BufferUsages::UNIFORM,
+                mapped_at_creation: false,
+            }),
+            bind_group: device.create_bind_group(&wgpu::BindGroupDescriptor {
+                label: None,
+                layout: &layout,
+                entries: &[],
+            }),
+            bind_group_layout: layout,
+        });
+        let views = vec![RenderView::new_ui(uniform, DepthConvention::Standard)];
+
+        let scene = Scene::new(RContext::new());
+        let a = scene.add_with(object(&scene), LAYER_UI);
+        scene.add_with(object(&scene), LAYER_UI + 1);
+
+        // the sorter of the second layer culled all of its objects
+        let layers = vec![(LAYER_UI, vec![a]), (LAYER_UI + 1, vec![])];
+        let lists = layer_lists(&views, &layers, &scene, &scene.read_container());
+        let list = &lists[&TypeId::of::<BasicMaterialFace>()];
+        assert_eq!(list.len(), 1);
+        assert_eq!(list[0].layer, LAYER_UI);
+        assert_eq!(list[0].objects, vec![a]);
+    }
 }
@@ -0,0 +1,286 @@
+use std::sync::{Arc, Mutex};
+
+use crate::{
+    backends::wgpu_backend::{ClearValue, ResourceOps, WGPUResource},
+    graph::rdg::{
+        backend::{GraphCopyEngine, GraphRenderEngine},
+        pass::*,
+        resource::ResourceId,
+        RenderGraphBuilder, RenderPassBuilder,
+    },
+    render::pso::{BindGroupType, ColorTargetBuilder, RenderDescriptorObject},
+    scene::{DepthConvention, LayerId, Scene, LAYER_TRANSPARENT},
+    types::{Color, Size, Vec3u},
+};
+
+use super::{
+    material::RenderMaterialContext,
+    tech::ShaderTechCollection,
+    view::{ViewId, ViewTarget},
+};
+use tshader::VariantFlags;
+
+pub const OIT_ACCUM_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Rgba16Float;
+pub const OIT_REVEAL_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::R16Float;
+
+// objects of the registered transparent layer are drawn with weighted blended oit
+pub fn is_oit_layer(scene: &Scene, layer: LayerId) -> bool {
+    scene.find_layer(layer).map(|v| v.id) == Some(LAYER_TRANSPARENT)
+}
+
+// the shader variant drawing to the accumulation and revealage targets, the
+// weight of a fragment depends on `depth`
+pub fn oit_variants(variants: &VariantFlags, depth: DepthConvention) -> VariantFlags {
+    let mut flags = variants.flags().to_vec();
+    flags.push("OIT".to_owned());
+    if depth.is_reversed() {
+        flags.push("REVERSE_Z".to_owned());
+    }
+    VariantFlags::new(flags)
+}
+
+// accumulation and revealage textures of a view. transparent passes draw to
+// them with the depth of the view, the composite pass blends them onto the view
+#[derive(Debug, Clone)]
+pub struct OitTarget {
+    pub accum: ResourceId,
+    pub reveal: ResourceId,
+    // multisampled textures, resolved into `accum` and `reveal`
+    accum_msaa: PreferAttachment,
+    reveal_msaa: PreferAttachment,
+    depth: PreferAttachment,
+}
+
+impl OitTarget {
+    pub fn new(
+        g: &mut RenderGraphBuilder,
+        view: ViewId,
+        target: &ViewTarget,
+        size: Size,
+        msaa: u32,
+    ) -> Self {
+        let size = Vec3u::new(size.x, size.y, 1);
+        let usage = wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::TEXTURE_BINDING;
+        let accum_clear = ClearValue::Color(Color::zeros());
+        let reveal_clear = ClearValue::Color(Color::new(1f32, 1f32, 1f32, 1f32));
+
+        let accum = g.allocate_texture(
+            format!("view {} oit accum", view),
+            size,
+            OIT_ACCUM_FORMAT,
+            usage,
+            Some(accum_clear.clone()),
+            1,
+        );
+        let reveal = g.allocate_texture(
+            format!("view {} oit reveal", view),
+            size,
+            OIT_REVEAL_FORMAT,
+            usage,
+            Some(reveal_clear.clone()),
+            1,
+        );
+        let (accum_msaa, reveal_msaa) = if msaa > 1 {
+            (
+                PreferAttachment::Resource(g.allocate_texture(
+                    format!("view {} oit msaa accum", view),
+                    size,
+                    OIT_ACCUM_FORMAT,
+                    wgpu::TextureUsages::RENDER_ATTACHMENT,
+                    Some(accum_clear),
+                    msaa,
+                )),
+                PreferAttachment::Resource(g.allocate_texture(
+                    format!("view {} oit msaa reveal", view),
+                    size,
+                    OIT_REVEAL_FORMAT,
+                    wgpu::TextureUsages::RENDER_ATTACHMENT,
+                    Some(reveal_clear),
+                    msaa,
+                )),
+            )
+        } else {
+            (PreferAttachment::None, PreferAttachment::None)
+        };
+
+        Self {
+            accum,
+            reveal,
+            accum_msaa,
+            reveal_msaa,
+            depth: target.depth.clone(),
+        }
+    }
+
+    // depth is tested against the opaque passes of the view, not written
+    pub fn render_target(&self) -> RenderTargetDescriptor {
+        let ops = ResourceOps {
+            load: None,
+            store: true,
+        };
+        RenderTargetDescriptor {
+            colors: smallvec::smallvec![
+                ColorRenderTargetDescriptor {
+                    prefer_attachment: PreferAttachment::Resource(self.accum),
+                    resolve_attachment: self.accum_msaa.clone(),
+                    ops: ops.clone(),
+                },
+                ColorRenderTargetDescriptor {
+                    prefer_attachment: PreferAttachment::Resource(self.reveal),
+                    resolve_attachment: self.reveal_msaa.clone(),
+                    ops: ops.clone(),
+                },
+            ],
+            depth: Some(DepthRenderTargetDescriptor {
+                prefer_attachment: self.depth.clone(),
+                depth_ops: Some(ops),
+                stencil_ops: None,
+            }),
+        }
+    }
+
+    // color targets of a pipeline drawing the `OIT` variant
+    pub fn add_targets(rdo: RenderDescriptorObject) -> RenderDescriptorObject {
+        let accum = wgpu::BlendComponent {
+            src_factor: wgpu::BlendFactor::One,
+            dst_factor: wgpu::BlendFactor::One,
+            operation: wgpu::BlendOperation::Add,
+        };
+        let reveal = wgpu::BlendComponent {
+            src_factor: wgpu::BlendFactor::Zero,
+            dst_factor: wgpu::BlendFactor::OneMinusSrc,
+            operation: wgpu::BlendOperation::Add,
+        };
+        rdo.add_target(
+            ColorTargetBuilder::new(OIT_ACCUM_FORMAT)
+                .set_blender(wgpu::BlendState {
+                    color: accum,
+                    alpha: accum,
+                })
+                .build(),
+        )
+        .add_target(
+            ColorTargetBuilder::new(OIT_REVEAL_FORMAT)
+                .set_blender(wgpu::BlendState {
+                    color: reveal,
+                    alpha: reveal,
+                })
+                .build(),
+        )
+    }
+}
+
+struct OitCompositeRenderer {
+    view: ViewId,
+    oit: OitTarget,
+    bind_group: Option<wgpu::BindGroup>,
+    material_shader_collector: Arc<ShaderTechCollection>,
+}
+
+impl RenderPassExecutor for OitCompositeRenderer {
+    fn prepare<'a>(
+        &'a mut self,
+        _context: RenderPassContext<'a>,
+        _engine: &mut GraphCopyEngine,
+    ) -> Option<()> {
+        Some(())
+    }
+
+    // the textures are allocated by the graph every frame
+    #[profiling::function]
+    fn queue<'b>(&'b mut self, context: RenderPassContext<'b>, device: &wgpu::Device) {
+        let pso = self.material_shader_collector.get(
+            "oit",
+            &VariantFlags::default(),
+            self.view as u64,
+            "composite",
+        );
+        let (layout, _) = pso.get_bind_group_layout(BindGroupType::Material).unwrap();
+        let accum = context.registry.get(self.oit.accum);
+        let reveal = context.registry.get(self.oit.reveal);
+
+        self.bind_group = Some(device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("oit composite bind group"),
+            layout: &layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: wgpu::BindingResource::TextureView(accum.texture_view()),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: wgpu::BindingResource::TextureView(reveal.texture_view()),
+                },
+            ],
+        }));
+    }
+
+    #[profiling::function]
+    fn render<'a>(&'a mut self, context: RenderPassContext<'a>, engine: &mut GraphRenderEngine) {
+        let rc = context.take::<RenderMaterialContext>();
+        let view = &rc.views[self.view as usize];
+        let pso = self.material_shader_collector.get(
+            "oit",
+            &VariantFlags::default(),
+            self.view as u64,
+            "composite",
+        );
+
+        let mut pass = engine.begin(0);
+        view.apply(&mut pass);
+        pass.set_pipeline(pso.render());
+        pass.set_bind_group(0, self.bind_group.as_ref().unwrap(), &[]);
+        pass.draw(0..3, 0..1);
+    }
+
+    fn cleanup<'b>(&'b mut self, _context: RenderPassContext<'b>) {
+        self.bind_group = None;
+    }
+}
+
+// blends the transparent layer onto the view, runs after the passes drawing to `oit`
+pub fn add_oit_composite_pass(
+    g: &mut RenderGraphBuilder,
+    gpu: &WGPUResource,
+    view: ViewId,
+    target: &ViewTarget,
+    oit: &OitTarget,
+    shader_tech_collection: &Arc<ShaderTechCollection>,
+    msaa: u32,
+) {
+    shader_tech_collection
+        .setup(
+            gpu.device(),
+            "oit",
+            &VariantFlags::default(),
+            view as u64,
+            |_| {
+                RenderDescriptorObject::new()
+                    .vertex_no_split()
+                    .set_msaa(msaa)
+                    .set_primitive(|p: &mut _| p.cull_mode = None)
+                    .add_target(
+                        ColorTargetBuilder::new(gpu.surface_format())
+                            .set_blender(wgpu::BlendState::ALPHA_BLENDING)
+                            .build(),
+                    )
+                    .set_depth(wgpu::TextureFormat::Depth32Float, |depth: &mut _| {
+                        depth.depth_compare = wgpu::CompareFunction::Always;
+                        depth.depth_write_enabled = false;
+                    })
+            },
+        )
+        .unwrap();
+
+    let mut pass = RenderPassBuilder::new(format!("oit composite view {}", view));
+    pass.render_target(target.render_target());
+    pass.read_texture(oit.accum);
+    pass.read_texture(oit.reveal);
+    pass.async_execute(Arc::new(Mutex::new(OitCompositeRenderer {
+        view,
+        oit: oit.clone(),
+        bind_group: None,
+        material_shader_collector: shader_tech_collection.clone(),
+    })));
+    g.add_render_pass(pass);
+}
@@ -27,6 +27,7 @@ pub struct PipelineStateObject {
     inner: PipelineStateObjectInner,
     global_variables: HashMap<BindGroupType, Uniforms>,
     name: String,
+    workgroup_size: [u32; 3],
 }
 
 #[derive(Debug, Hash, PartialEq, Eq, Copy, Clone)]
@@ -48,6 +49,21 @@ impl PipelineStateObject {
             _ => panic!("unsupported pipeline type"),
         }
     }
+    pub fn compute(&self) -> &wgpu::ComputePipeline {
+        match &self.inner {
+            PipelineStateObjectInner::Compute(c) => c,
+            _ => panic!("unsupported pipeline type"),
+        }
+    }
+
+    // zero for render pipelines
+    pub fn workgroup_size(&self) -> [u32; 3] {
+        self.workgroup_size
+    }
+
+    pub fn bind_group_index(&self, ty: BindGroupType) -> Option<u32> {
+        self.global_variables.get(&ty).map(|v| v.group)
+    }
 
     pub fn get_bind_group_layout(
         &self,
@@ -134,6 +150,32 @@ fn build_bind_group_layout_entry(
                     },
                 });
             }
+            tshader::tech::GlobalVariable::StorageTexture(t) => {
+                entries.push(wgpu::BindGroupLayoutEntry {
+                    visibility,
+                    binding: t.binding,
+                    count: None,
+                    ty: wgpu::BindingType::StorageTexture {
+                        access: t.access,
+                        format: t.format,
+                        view_dimension: t.dimension,
+                    },
+                });
+            }
+            tshader::tech::GlobalVariable::StorageBuffer(b) => {
+                entries.push(wgpu::BindGroupLayoutEntry {
+                    visibility,
+                    binding: b.binding,
+                    count: None,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Storage {
+                            read_only: b.read_only,
+                        },
+                        has_dynamic_offset: false,
+                        min_binding_size: wgpu::BufferSize::new(b.size as u64),
+                    },
+                });
+            }
             tshader::tech::GlobalVariable::Sampler(s) => {
                 let ty = if s.comparison {
                     wgpu::SamplerBindingType::Comparison
@@ -163,6 +205,7 @@ fn create_compute_pipeline(
     device: &wgpu::Device,
     pass: &Pass,
     pipeline_layout: &wgpu::PipelineLayout,
+    global_variables: HashMap<BindGroupType, Uniforms>,
 ) -> anyhow::Result<Arc<PipelineStateObject>> {
     //     compute pipeline
     let desc = wgpu::ComputePipelineDescriptor {
@@ -176,8 +219,9 @@ fn create_compute_pipeline(
     let pipeline = device.create_compute_pipeline(&desc);
     Ok(Arc::new(PipelineStateObject {
         inner: PipelineStateObjectInner::Compute(pipeline),
-        global_variables: HashMap::default(),
+        global_variables,
         name: pass.name.clone(),
+        workgroup_size: pass.workgroup_size,
     }))
 }
 
@@ -242,11 +286,14 @@ fn create_render_pipeline(
         }
         let stride = offset;
 
-        vertex_buffer_layouts.push(wgpu::VertexBufferLayout {
-            array_stride: stride,
-            step_mode: wgpu::VertexStepMode::Vertex,
-            attributes: &main_layouts,
-        });
+        // shaders without vertex inputs (e.g. generated from vertex_index) bind no buffer
+        if !main_layouts.is_empty() {
+            vertex_buffer_layouts.push(wgpu::VertexBufferLayout {
+                array_stride: stride,
+                step_mode: wgpu::VertexStepMode::Vertex,
+                attributes: &main_layouts,
+            });
+        }
     }
 
     let mut desc = wgpu::RenderPipelineDescriptor {
@@ -279,6 +326,7 @@ fn create_render_pipeline(
         inner: PipelineStateObjectInner::Render(pipeline),
         global_variables,
         name: pass.name.clone(),
+        workgroup_size: [0; 3],
     }))
 }
 
@@ -312,6 +360,20 @@ pub(crate) fn load_pso(
                     .or_default()
                     .push(name.as_str());
             }
+            tshader::tech::GlobalVariable::StorageTexture(t) => {
+                let bind_group_type = group_name_to_enum.get(&t.group_name.as_str()).unwrap();
+                available_groups
+                    .entry(*bind_group_type)
+                    .or_default()
+                    .push(name.as_str());
+            }
+            tshader::tech::GlobalVariable::StorageBuffer(b) => {
+                let bind_group_type = group_name_to_enum.get(&b.group_name.as_str()).unwrap();
+                available_groups
+                    .entry(*bind_group_type)
+                    .or_default()
+                    .push(name.as_str());
+            }
             tshader::tech::GlobalVariable::Sampler(s) => {
                 let bind_group_type = group_name_to_enum.get(&s.group_name.as_str()).unwrap();
                 available_groups
@@ -395,7 +457,7 @@ pub(crate) fn load_pso(
     
 
     if pass.cs.is_some() {
-        create_compute_pipeline(device, &pass, &pipeline_layout)
+        create_compute_pipeline(device, &pass, &pipeline_layout, global_variables)
     } else {
         create_render_pipeline(device, &pass, &pipeline_layout, rdo, global_variables)
     }
@@ -450,6 +512,11 @@ impl ColorTargetBuilder {
         self.target.blend = None;
         self
     }
+
+    pub fn set_write_mask(mut self, mask: wgpu::ColorWrites) -> Self {
+        self.target.write_mask = mask;
+        self
+    }
 }
 
 pub fn default_blender() -> wgpu::BlendState {
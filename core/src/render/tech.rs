@@ -3,7 +3,7 @@ use std::sync::Arc;
 use dashmap::DashMap;
 use tshader::{ShaderTech, ShaderTechLoader, VariantFlags};
 
-use crate::material::Material;
+use crate::{material::Material, scene::LayerId};
 
 use super::pso::{PipelineStateObject, PipelineStateObjectCache, RenderDescriptorObject};
 
@@ -15,6 +15,11 @@ struct Key {
     name: String,
 }
 
+// pipelines of a material differ per layer, the layer sets depth and blend defaults
+pub fn layer_instance_id(material: &Material, layer: LayerId) -> u64 {
+    ((layer as u64) << 32) | material.id().id()
+}
+
 pub struct ShaderTechCollection {
     loader: Box<dyn ShaderTechLoader>,
     pso_cache: Box<dyn PipelineStateObjectCache>,
@@ -0,0 +1,385 @@
+use std::sync::{Arc, Mutex};
+
+use crate::{
+    backends::wgpu_backend::{ClearValue, ResourceOps, WGPUResource},
+    context::ResourceRef,
+    graph::rdg::{
+        backend::{GraphCopyEngine, GraphRenderEngine},
+        pass::*,
+        resource::ResourceId,
+        RenderGraphBuilder, RenderPassBuilder,
+    },
+    material::{bind::BindingResourceProvider, Material},
+    render::pso::{ColorTargetBuilder, RenderDescriptorObject},
+    scene::{
+        CameraId, CameraSettings, CameraTarget, DepthConvention, LayerId, LayerMask, Scene,
+        LAYER_UI,
+    },
+    types::{Color, Size, Vec3u, Vec4f},
+    util::any_as_u8_slice,
+};
+
+use super::{
+    material::RenderMaterialContext, oit::OitTarget, tech::ShaderTechCollection, GlobalUniform,
+};
+use tshader::VariantFlags;
+
+pub type ViewId = u32;
+
+// a color texture cameras can render into, bind `texture()` in any material
+// to show it. the format is the surface format, so material pipelines work
+// for both targets
+#[derive(Debug)]
+pub struct RenderTexture {
+    texture: ResourceRef,
+    size: Size,
+    format: wgpu::TextureFormat,
+}
+
+impl RenderTexture {
+    pub fn new(gpu: &WGPUResource, label: Option<&'static str>, size: Size) -> Self {
+        let format = gpu.surface_format();
+        let texture = gpu.device().create_texture(&wgpu::TextureDescriptor {
+            label,
+            size: wgpu::Extent3d {
+                width: size.x,
+                height: size.y,
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format,
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT
+                | wgpu::TextureUsages::TEXTURE_BINDING
+                | wgpu::TextureUsages::COPY_SRC,
+            view_formats: &[],
+        });
+        Self {
+            texture: gpu.context().register_texture(texture),
+            size,
+            format,
+        }
+    }
+
+    // `texture` needs the RENDER_ATTACHMENT and TEXTURE_BINDING usages, the
+    // format of the views drawing into it
+    pub fn from_texture(texture: ResourceRef, size: Size, format: wgpu::TextureFormat) -> Self {
+        Self {
+            texture,
+            size,
+            format,
+        }
+    }
+
+    pub fn texture(&self) -> ResourceRef {
+        self.texture.clone()
+    }
+
+    pub fn size(&self) -> Size {
+        self.size
+    }
+
+    pub fn format(&self) -> wgpu::TextureFormat {
+        self.format
+    }
+}
+
+// attachments the passes of a view draw to
+#[derive(Debug, Clone)]
+pub struct ViewTarget {
+    pub color: PreferAttachment,
+    // multisampled color, resolved into `color`
+    pub resolve: PreferAttachment,
+    pub depth: PreferAttachment,
+}
+
+impl Default for ViewTarget {
+    fn default() -> Self {
+        Self {
+            color: PreferAttachment::Default,
+            resolve: PreferAttachment::Default,
+            depth: PreferAttachment::Default,
+        }
+    }
+}
+
+impl ViewTarget {
+    // imports the texture and allocates depth (and msaa color) of its size
+    pub fn new_texture(
+        g: &mut RenderGraphBuilder,
+        view: ViewId,
+        texture: &RenderTexture,
+        clear: Option<Color>,
+        depth: DepthConvention,
+        msaa: u32,
+    ) -> (Self, ResourceId) {
+        let size = Vec3u::new(texture.size.x, texture.size.y, 1);
+        let depth_clear = depth.far();
+        let color = g.import_texture_with_clear(
+            &format!("view {} color", view),
+            clear.map(ClearValue::Color),
+        );
+        let depth = g.allocate_texture(
+            format!("view {} depth", view),
+            size,
+            wgpu::TextureFormat::Depth32Float,
+            // read by the hi-z pass
+            wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::TEXTURE_BINDING,
+            Some(ClearValue::Depth(depth_clear)),
+            msaa,
+        );
+        let resolve = if msaa > 1 {
+            PreferAttachment::Resource(g.allocate_texture(
+                format!("view {} msaa color", view),
+                size,
+                texture.format,
+                wgpu::TextureUsages::RENDER_ATTACHMENT,
+                clear.map(ClearValue::Color),
+                msaa,
+            ))
+        } else {
+            PreferAttachment::None
+        };
+        (
+            Self {
+                color: PreferAttachment::Resource(color),
+                resolve,
+                depth: PreferAttachment::Resource(depth),
+            },
+            color,
+        )
+    }
+
+    // same as `RenderPassBuilder::default_color_depth_render_target` for this view
+    pub fn render_target(&self) -> RenderTargetDescriptor {
+        RenderTargetDescriptor {
+            colors: smallvec::smallvec![self.color_target(ResourceOps {
+                load: None,
+                store: true,
+            })],
+            depth: Some(self.depth_target(Some(ResourceOps {
+                load: None,
+                store: true,
+            }))),
+        }
+    }
+
+    pub fn color_target(&self, ops: ResourceOps) -> ColorRenderTargetDescriptor {
+        ColorRenderTargetDescriptor {
+            prefer_attachment: self.color.clone(),
+            resolve_attachment: self.resolve.clone(),
+            ops,
+        }
+    }
+
+    pub fn depth_target(&self, depth_ops: Option<ResourceOps>) -> DepthRenderTargetDescriptor {
+        DepthRenderTargetDescriptor {
+            prefer_attachment: self.depth.clone(),
+            depth_ops,
+            stencil_ops: None,
+        }
+    }
+}
+
+// a camera drawing into a part of the render target
+pub struct RenderView {
+    pub camera: Option<CameraId>,
+    pub uniform: Arc<GlobalUniform>,
+    // x, y, width, height in pixels, None for the whole target
+    pub viewport: Option<Vec4f>,
+    pub layer_mask: LayerMask,
+    pub target: ViewTarget,
+    // imported into the graph every frame
+    pub texture: Option<(ResourceId, Arc<RenderTexture>)>,
+    // targets of the transparent layer with TransparencyMode::WeightedBlended
+    pub oit: Option<OitTarget>,
+    // depth pyramid of occlusion culling, imported into the graph every frame
+    pub hiz: Option<(ResourceId, ResourceRef)>,
+    // of the camera, depth tests and clears of the passes follow it
+    pub depth: DepthConvention,
+}
+
+impl RenderView {
+    #[allow(clippy::too_many_arguments)]
+    pub fn new_camera(
+        g: &mut RenderGraphBuilder,
+        view: ViewId,
+        camera: CameraId,
+        uniform: Arc<GlobalUniform>,
+        settings: &CameraSettings,
+        depth: DepthConvention,
+        size: Size,
+        msaa: u32,
+    ) -> Self {
+        let (target, texture, size) = match &settings.target {
+            CameraTarget::Surface => (ViewTarget::default(), None, size),
+            CameraTarget::Texture(t) => {
+                let (target, id) =
+                    ViewTarget::new_texture(g, view, t, settings.clear_color, depth, msaa);
+                (target, Some((id, t.clone())), t.size())
+            }
+        };
+        let viewport = if settings.is_fullscreen() {
+            None
+        } else {
+            Some(pixel_viewport(settings.viewport, size))
+        };
+        Self {
+            camera: Some(camera),
+            uniform,
+            viewport,
+            layer_mask: settings.layer_mask.clone(),
+            target,
+            texture,
+            oit: None,
+            hiz: None,
+            depth,
+        }
+    }
+
+    // ui layers are drawn once over the whole target
+    pub fn new_ui(uniform: Arc<GlobalUniform>, depth: DepthConvention) -> Self {
+        Self {
+            camera: None,
+            uniform,
+            viewport: None,
+            layer_mask: LayerMask::ALL,
+            target: ViewTarget::default(),
+            texture: None,
+            oit: None,
+            hiz: None,
+            depth,
+        }
+    }
+
+    pub fn contains(&self, scene: &Scene, layer: LayerId) -> bool {
+        if self.camera.is_none() {
+            layer >= LAYER_UI
+        } else {
+            layer < LAYER_UI && scene.layer_mask_contains(&self.layer_mask, layer)
+        }
+    }
+
+    // sampling the texture the view draws to is a feedback loop, objects doing
+    // it are not drawn by the view
+    pub fn samples_target(&self, material: &Material) -> bool {
+        self.texture
+            .as_ref()
+            .is_some_and(|(_, t)| material.uses_resource(t.texture().id()))
+    }
+
+    pub fn apply(&self, pass: &mut wgpu::RenderPass) {
+        if let Some(v) = &self.viewport {
+            apply_viewport(v, pass);
+        }
+    }
+}
+
+fn pixel_viewport(rect: Vec4f, size: Size) -> Vec4f {
+    let w = size.x as f32;
+    let h = size.y as f32;
+    let x = (rect.x * w).clamp(0f32, w).floor();
+    let y = (rect.y * h).clamp(0f32, h).floor();
+    let right = ((rect.x + rect.z) * w).clamp(x, w).ceil().min(w);
+    let bottom = ((rect.y + rect.w) * h).clamp(y, h).ceil().min(h);
+    Vec4f::new(x, y, (right - x).max(1f32), (bottom - y).max(1f32))
+}
+
+pub fn apply_viewport(v: &Vec4f, pass: &mut wgpu::RenderPass) {
+    pass.set_viewport(v.x, v.y, v.z, v.w, 0f32, 1f32);
+    pass.set_scissor_rect(v.x as u32, v.y as u32, v.z as u32, v.w as u32);
+}
+
+struct ViewClearRenderer {
+    view: ViewId,
+    color: Option<Color>,
+    variants: VariantFlags,
+    material_shader_collector: Arc<ShaderTechCollection>,
+}
+
+impl RenderPassExecutor for ViewClearRenderer {
+    fn prepare<'a>(
+        &'a mut self,
+        _context: RenderPassContext<'a>,
+        _engine: &mut GraphCopyEngine,
+    ) -> Option<()> {
+        Some(())
+    }
+
+    fn queue<'b>(&'b mut self, _context: RenderPassContext<'b>, _device: &wgpu::Device) {}
+
+    #[profiling::function]
+    fn render<'a>(&'a mut self, context: RenderPassContext<'a>, engine: &mut GraphRenderEngine) {
+        let rc = context.take::<RenderMaterialContext>();
+        let view = &rc.views[self.view as usize];
+        let pso =
+            self.material_shader_collector
+                .get("clear", &self.variants, self.view as u64, "clear");
+
+        let mut pass = engine.begin(0);
+        view.apply(&mut pass);
+        pass.set_pipeline(pso.render());
+        let color = self.color.unwrap_or_default();
+        pass.set_push_constants(wgpu::ShaderStages::FRAGMENT, 0, any_as_u8_slice(&color));
+        pass.draw(0..3, 0..1);
+    }
+
+    fn cleanup<'b>(&'b mut self, _context: RenderPassContext<'b>) {}
+}
+
+// clears the viewport of a camera before its passes
+#[allow(clippy::too_many_arguments)]
+pub fn add_view_clear_pass(
+    g: &mut RenderGraphBuilder,
+    gpu: &WGPUResource,
+    view: ViewId,
+    target: &ViewTarget,
+    settings: &CameraSettings,
+    depth: DepthConvention,
+    shader_tech_collection: &Arc<ShaderTechCollection>,
+    msaa: u32,
+) {
+    if settings.clear_color.is_none() && !settings.clear_depth {
+        return;
+    }
+    // the triangle is drawn on the far plane
+    let variants = if depth.is_reversed() {
+        VariantFlags::new(vec!["REVERSE_Z".to_owned()])
+    } else {
+        VariantFlags::default()
+    };
+    shader_tech_collection
+        .setup(gpu.device(), "clear", &variants, view as u64, |_| {
+            let mask = if settings.clear_color.is_some() {
+                wgpu::ColorWrites::ALL
+            } else {
+                wgpu::ColorWrites::empty()
+            };
+            RenderDescriptorObject::new()
+                .vertex_no_split()
+                .set_msaa(msaa)
+                .set_primitive(|p: &mut _| p.cull_mode = None)
+                .add_target(
+                    ColorTargetBuilder::new(gpu.surface_format())
+                        .set_write_mask(mask)
+                        .build(),
+                )
+                .set_depth(wgpu::TextureFormat::Depth32Float, |depth: &mut _| {
+                    depth.depth_compare = wgpu::CompareFunction::Always;
+                    depth.depth_write_enabled = settings.clear_depth;
+                })
+        })
+        .unwrap();
+
+    let mut pass = RenderPassBuilder::new(format!("clear view {}", view));
+    pass.render_target(target.render_target());
+    pass.async_execute(Arc::new(Mutex::new(ViewClearRenderer {
+        view,
+        color: settings.clear_color,
+        variants,
+        material_shader_collector: shader_tech_collection.clone(),
+    })));
+    pass.add_constraint(PassConstraint::First);
+    g.add_render_pass(pass);
+}
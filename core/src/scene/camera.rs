@@ -1,16 +1,91 @@
 use std::{fmt::Debug, io::Write, sync::Mutex};
 
+use bevy_reflect::Reflect;
+
 use crate::{
-    types::{Frustum, Mat4x4f, Vec2f, Vec3f, Vec4f},
+    reflect::{Angle, Logarithmic, ReflectProxy},
+    types::{Frustum, Mat4x4f, Size, Vec2f, Vec3f, Vec4f},
     util::{angle2rad, any_as_u8_slice},
 };
 
+// how depth maps to [0, 1], the far plane is 0 with `Reversed`. depth tests and
+// depth clears of pipelines drawing with the camera follow it
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum DepthConvention {
+    #[default]
+    Standard,
+    Reversed,
+}
+
+impl DepthConvention {
+    pub fn is_reversed(&self) -> bool {
+        *self == Self::Reversed
+    }
+
+    // ndc depth of the near plane
+    pub fn near(&self) -> f32 {
+        match self {
+            Self::Standard => 0f32,
+            Self::Reversed => 1f32,
+        }
+    }
+
+    // ndc depth of the far plane, the depth clear value
+    pub fn far(&self) -> f32 {
+        match self {
+            Self::Standard => 1f32,
+            Self::Reversed => 0f32,
+        }
+    }
+
+    // `compare` is written for standard depth
+    pub fn compare(&self, compare: wgpu::CompareFunction) -> wgpu::CompareFunction {
+        if !self.is_reversed() {
+            return compare;
+        }
+        match compare {
+            wgpu::CompareFunction::Less => wgpu::CompareFunction::Greater,
+            wgpu::CompareFunction::LessEqual => wgpu::CompareFunction::GreaterEqual,
+            wgpu::CompareFunction::Greater => wgpu::CompareFunction::Less,
+            wgpu::CompareFunction::GreaterEqual => wgpu::CompareFunction::LessEqual,
+            v => v,
+        }
+    }
+}
+
+// radical inverse of `index` in `base`, in [0, 1)
+pub fn halton(mut index: u32, base: u32) -> f32 {
+    let mut f = 1f32;
+    let mut res = 0f32;
+    while index > 0 {
+        f /= base as f32;
+        res += f * (index % base) as f32;
+        index /= base;
+    }
+    res
+}
+
+// sub-pixel jitter of frame `index` in ndc for a target of `size` pixels,
+// halton (2, 3) in [-0.5, 0.5) pixels
+pub fn jitter_offset(index: u32, size: Size) -> Vec2f {
+    let index = index % 16 + 1;
+    Vec2f::new(
+        (halton(index, 2) - 0.5f32) * 2f32 / size.x as f32,
+        (halton(index, 3) - 0.5f32) * 2f32 / size.y as f32,
+    )
+}
+
 #[derive(Debug, Clone)]
 struct PerspectiveProject {
     aspect: f32,
     fovy: f32,
     near: f32,
+    // f32::INFINITY for an infinite far plane
     far: f32,
+    // center of the near plane in ndc, zero for a symmetric frustum
+    off_center: Vec2f,
+    // the bounds of `make_perspective_off_center` are kept on resize
+    fixed: bool,
 }
 
 impl Default for PerspectiveProject {
@@ -20,15 +95,29 @@ impl Default for PerspectiveProject {
             fovy: angle2rad(90f32),
             near: 0.1f32,
             far: 100f32,
+            off_center: Vec2f::zeros(),
+            fixed: false,
         }
     }
 }
 
 impl PerspectiveProject {
-    pub fn gen(&self) -> Mat4x4f {
-        let mut res = Mat4x4f::new_perspective(self.aspect, self.fovy, self.near, self.far);
-        res.append_nonuniform_scaling_mut(&Vec3f::new(1f32, 1f32, 0.5f32));
-        res.append_translation_mut(&Vec3f::new(0f32, 0f32, 0.5f32));
+    pub fn gen(&self, depth: DepthConvention) -> Mat4x4f {
+        let f = 1f32 / (self.fovy * 0.5f32).tan();
+        let (n, far) = (self.near, self.far);
+        let (a, b) = match (depth, far.is_infinite()) {
+            (DepthConvention::Standard, false) => (far / (n - far), n * far / (n - far)),
+            (DepthConvention::Reversed, false) => (n / (far - n), n * far / (far - n)),
+            (DepthConvention::Standard, true) => (-1f32, -n),
+            (DepthConvention::Reversed, true) => (0f32, n),
+        };
+        #[rustfmt::skip]
+        let res = Mat4x4f::new(
+            f / self.aspect, 0f32, self.off_center.x, 0f32,
+            0f32, f, self.off_center.y, 0f32,
+            0f32, 0f32, a, b,
+            0f32, 0f32, -1f32, 0f32,
+        );
         res
     }
 }
@@ -51,17 +140,20 @@ impl Default for OrthographicProject {
 }
 
 impl OrthographicProject {
-    pub fn gen(&self) -> Mat4x4f {
-        let mut res = Mat4x4f::new_orthographic(
-            self.rect.x,
-            self.rect.z,
-            self.rect.y,
-            self.rect.w,
-            self.near,
-            self.far,
+    pub fn gen(&self, depth: DepthConvention) -> Mat4x4f {
+        let (l, b, r, t) = (self.rect.x, self.rect.y, self.rect.z, self.rect.w);
+        let (n, f) = (self.near, self.far);
+        let (c, d) = match depth {
+            DepthConvention::Standard => (-1f32 / (f - n), -n / (f - n)),
+            DepthConvention::Reversed => (1f32 / (f - n), f / (f - n)),
+        };
+        #[rustfmt::skip]
+        let res = Mat4x4f::new(
+            2f32 / (r - l), 0f32, 0f32, -(r + l) / (r - l),
+            0f32, 2f32 / (t - b), 0f32, -(t + b) / (t - b),
+            0f32, 0f32, c, d,
+            0f32, 0f32, 0f32, 1f32,
         );
-        res.append_nonuniform_scaling_mut(&Vec3f::new(1f32, 1f32, 0.5f32));
-        res.append_translation_mut(&Vec3f::new(0f32, 0f32, 0.5f32));
         res
     }
 }
@@ -79,11 +171,25 @@ impl Default for Project {
 }
 
 impl Project {
-    pub fn gen(&self) -> Mat4x4f {
-        match self {
-            Project::Perspective(p) => p.gen(),
-            Project::Orthographic(o) => o.gen(),
+    // `jitter` moves the image in ndc
+    pub fn gen(&self, depth: DepthConvention, jitter: Vec2f) -> Mat4x4f {
+        let mut res = match self {
+            Project::Perspective(p) => p.gen(depth),
+            Project::Orthographic(o) => o.gen(depth),
+        };
+        let w = res.row(3).clone_owned();
+        res.set_row(0, &(res.row(0) + w * jitter.x));
+        res.set_row(1, &(res.row(1) + w * jitter.y));
+        res
+    }
+
+    // an infinite far plane is cut for the frustum
+    fn finite(&self) -> Self {
+        let mut res = self.clone();
+        if let Project::Perspective(p) = &mut res {
+            p.far = p.far.min(p.near * 1e4f32);
         }
+        res
     }
 }
 
@@ -108,6 +214,10 @@ struct Inner {
     up: Vec3f,
     view: Mat4x4f,
 
+    depth: DepthConvention,
+    // ndc offset of the projection
+    jitter: Vec2f,
+
     dirty_project: bool,
     dirty_view: bool,
 }
@@ -136,6 +246,8 @@ impl Camera {
                 up: Vec3f::new(0f32, 1f32, 0f32),
 
                 project_var: Project::default(),
+                depth: DepthConvention::default(),
+                jitter: Vec2f::zeros(),
                 dirty_project: true,
                 dirty_view: true,
             }
@@ -152,21 +264,27 @@ impl Camera {
     }
 
     pub fn frustum_worldspace(&self) -> Frustum {
-        let vp = self.vp();
+        let (vp, depth) = {
+            let mut inner = self.inner.lock().unwrap();
+            Self::update(&mut inner);
+            let projection = inner.project_var.finite().gen(inner.depth, inner.jitter);
+            (projection * inner.view, inner.depth)
+        };
         let rev = vp.try_inverse().unwrap();
         let pos = self.from();
         let to = self.to();
         let up = self.up();
+        let (n, f) = (depth.near(), depth.far());
 
-        let nlt = Vec4f::new(-1f32, 1f32, 0f32, 1f32);
-        let nlb = Vec4f::new(-1f32, -1f32, 0f32, 1f32);
-        let nrt = Vec4f::new(1f32, 1f32, 0f32, 1f32);
-        let nrb = Vec4f::new(1f32, -1f32, 0f32, 1f32);
+        let nlt = Vec4f::new(-1f32, 1f32, n, 1f32);
+        let nlb = Vec4f::new(-1f32, -1f32, n, 1f32);
+        let nrt = Vec4f::new(1f32, 1f32, n, 1f32);
+        let nrb = Vec4f::new(1f32, -1f32, n, 1f32);
 
-        let flt = Vec4f::new(-1f32, 1f32, 1f32, 1f32);
-        let flb = Vec4f::new(-1f32, -1f32, 1f32, 1f32);
-        let frt = Vec4f::new(1f32, 1f32, 1f32, 1f32);
-        let frb = Vec4f::new(1f32, -1f32, 1f32, 1f32);
+        let flt = Vec4f::new(-1f32, 1f32, f, 1f32);
+        let flb = Vec4f::new(-1f32, -1f32, f, 1f32);
+        let frt = Vec4f::new(1f32, 1f32, f, 1f32);
+        let frb = Vec4f::new(1f32, -1f32, f, 1f32);
 
         let nlt = rev * nlt;
         let nrt = rev * nrt;
@@ -227,10 +345,9 @@ impl Camera {
         data
     }
 
-    pub fn vp(&self) -> Mat4x4f {
-        let mut inner = self.inner.lock().unwrap();
+    fn update(inner: &mut Inner) {
         if inner.dirty_project {
-            inner.projection = inner.project_var.gen();
+            inner.projection = inner.project_var.gen(inner.depth, inner.jitter);
             inner.dirty_project = false;
         }
         if inner.dirty_view {
@@ -240,9 +357,70 @@ impl Camera {
             inner.view = Mat4x4f::look_at_rh(&from, &to, up);
             inner.dirty_view = false;
         }
+    }
+
+    pub fn vp(&self) -> Mat4x4f {
+        let mut inner = self.inner.lock().unwrap();
+        Self::update(&mut inner);
         inner.projection * inner.view
     }
 
+    pub fn view(&self) -> Mat4x4f {
+        let mut inner = self.inner.lock().unwrap();
+        Self::update(&mut inner);
+        inner.view
+    }
+
+    // with the jitter applied
+    pub fn projection(&self) -> Mat4x4f {
+        let mut inner = self.inner.lock().unwrap();
+        Self::update(&mut inner);
+        inner.projection
+    }
+
+    pub fn inverse_view(&self) -> Mat4x4f {
+        self.view().try_inverse().unwrap_or_else(Mat4x4f::identity)
+    }
+
+    pub fn inverse_projection(&self) -> Mat4x4f {
+        self.projection()
+            .try_inverse()
+            .unwrap_or_else(Mat4x4f::identity)
+    }
+
+    pub fn inverse_vp(&self) -> Mat4x4f {
+        self.vp().try_inverse().unwrap_or_else(Mat4x4f::identity)
+    }
+
+    pub fn set_depth_convention(&self, depth: DepthConvention) {
+        let mut inner = self.inner.lock().unwrap();
+        inner.depth = depth;
+        inner.dirty_project = true;
+    }
+
+    pub fn set_reverse_z(&self, reverse: bool) {
+        self.set_depth_convention(if reverse {
+            DepthConvention::Reversed
+        } else {
+            DepthConvention::Standard
+        });
+    }
+
+    pub fn depth_convention(&self) -> DepthConvention {
+        self.inner.lock().unwrap().depth
+    }
+
+    // offset of the projection in ndc, see `jitter_offset`
+    pub fn set_jitter(&self, jitter: Vec2f) {
+        let mut inner = self.inner.lock().unwrap();
+        inner.jitter = jitter;
+        inner.dirty_project = true;
+    }
+
+    pub fn jitter(&self) -> Vec2f {
+        self.inner.lock().unwrap().jitter
+    }
+
     pub fn make_orthographic(&self, rect: Vec4f, near: f32, far: f32) {
         let mut inner = self.inner.lock().unwrap();
         inner.dirty_project = true;
@@ -257,14 +435,58 @@ impl Camera {
             aspect,
             near,
             far,
+            off_center: Vec2f::zeros(),
+            fixed: false,
         });
     }
 
-    pub fn set_aspect(&self, aspect: f32) -> bool {
+    // asymmetric frustum, the bounds are on the near plane
+    pub fn make_perspective_off_center(
+        &self,
+        left: f32,
+        right: f32,
+        bottom: f32,
+        top: f32,
+        near: f32,
+        far: f32,
+    ) {
         let mut inner = self.inner.lock().unwrap();
         inner.dirty_project = true;
+        inner.project_var = Project::Perspective(PerspectiveProject {
+            fovy: 2f32 * ((top - bottom) * 0.5f32 / near).atan(),
+            aspect: (right - left) / (top - bottom),
+            near,
+            far,
+            off_center: Vec2f::new(
+                (right + left) / (right - left),
+                (top + bottom) / (top - bottom),
+            ),
+            fixed: true,
+        });
+    }
+
+    // for an infinite far plane
+    pub fn make_perspective_infinite(&self, aspect: f32, fovy: f32, near: f32) {
+        self.make_perspective(aspect, fovy, near, f32::INFINITY);
+    }
+
+    pub fn off_center(&self) -> Vec2f {
+        let inner = self.inner.lock().unwrap();
+        match &inner.project_var {
+            Project::Perspective(p) => p.off_center,
+            Project::Orthographic(_o) => Vec2f::zeros(),
+        }
+    }
+
+    // an off-center frustum keeps its bounds
+    pub fn set_aspect(&self, aspect: f32) -> bool {
+        let mut inner = self.inner.lock().unwrap();
         if let Project::Perspective(project) = &mut inner.project_var {
+            if project.fixed {
+                return false;
+            }
             project.aspect = aspect;
+            inner.dirty_project = true;
             return true;
         }
         false
@@ -273,6 +495,9 @@ impl Camera {
     pub fn set_fov(&self, fov: f32) -> bool {
         let mut inner = self.inner.lock().unwrap();
         if let Project::Perspective(project) = &mut inner.project_var {
+            if project.fixed {
+                return false;
+            }
             project.fovy = fov;
             inner.dirty_project = true;
             return true;
@@ -370,6 +595,15 @@ impl Camera {
         }
     }
 
+    pub fn orthographic_rect(&self) -> Option<Vec4f> {
+        let inner = self.inner.lock().unwrap();
+        if let Project::Orthographic(o) = &inner.project_var {
+            Some(o.rect)
+        } else {
+            None
+        }
+    }
+
     pub fn width_height(&self) -> Vec2f {
         let inner = self.inner.lock().unwrap();
         if let Project::Orthographic(o) = &inner.project_var {
@@ -379,3 +613,248 @@ impl Camera {
         }
     }
 }
+
+#[derive(Debug, Clone, Reflect)]
+pub enum ProjectionProxy {
+    Perspective {
+        #[reflect(@0.01f32..=100f32, @Logarithmic)]
+        aspect: f32,
+        #[reflect(@Angle, @0.7f32..=2.1f32)]
+        fovy: f32,
+        near: f32,
+        far: f32,
+    },
+    Orthographic {
+        #[reflect(@[-1f32, -1f32, 1f32, 1f32])]
+        rect: [f32; 4],
+        near: f32,
+        far: f32,
+    },
+}
+
+#[derive(Debug, Clone, Reflect)]
+pub struct CameraProxy {
+    pub projection: ProjectionProxy,
+    pub from: [f32; 3],
+    pub to: [f32; 3],
+    pub up: [f32; 3],
+}
+
+impl ReflectProxy for Camera {
+    type Proxy = CameraProxy;
+
+    fn reflect_proxy(&self) -> CameraProxy {
+        let projection = match self.orthographic_rect() {
+            Some(rect) => ProjectionProxy::Orthographic {
+                rect: rect.into(),
+                near: self.near(),
+                far: self.far(),
+            },
+            None => ProjectionProxy::Perspective {
+                aspect: self.aspect(),
+                fovy: self.fovy(),
+                near: self.near(),
+                far: self.far(),
+            },
+        };
+        CameraProxy {
+            projection,
+            from: self.from().into(),
+            to: self.to().into(),
+            up: self.up().into(),
+        }
+    }
+
+    // an off-center frustum keeps its bounds, only near and far are applied
+    fn apply_proxy(&mut self, proxy: &CameraProxy) {
+        match &proxy.projection {
+            ProjectionProxy::Perspective {
+                aspect,
+                fovy,
+                near,
+                far,
+            } => {
+                if self.is_perspective() {
+                    self.set_aspect(*aspect);
+                    self.set_fov(*fovy);
+                    self.set_near(*near);
+                    self.set_far(*far);
+                } else {
+                    self.make_perspective(*aspect, *fovy, *near, *far);
+                }
+            }
+            ProjectionProxy::Orthographic { rect, near, far } => {
+                self.make_orthographic(Vec4f::from(*rect), *near, *far)
+            }
+        }
+        self.look_at(proxy.from.into(), proxy.to.into(), proxy.up.into());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // ndc of a view space point
+    fn ndc(m: &Mat4x4f, p: Vec3f) -> Vec3f {
+        let c = m * Vec4f::new(p.x, p.y, p.z, 1f32);
+        c.xyz() / c.w
+    }
+
+    fn near_eq(a: f32, b: f32) -> bool {
+        (a - b).abs() < 1e-4
+    }
+
+    #[test]
+    fn perspective_depth_range() {
+        let camera = Camera::new();
+        camera.make_perspective(1f32, angle2rad(90f32), 0.1f32, 100f32);
+        let m = camera.projection();
+        assert!(near_eq(ndc(&m, Vec3f::new(0f32, 0f32, -0.1f32)).z, 0f32));
+        assert!(near_eq(ndc(&m, Vec3f::new(0f32, 0f32, -100f32)).z, 1f32));
+        // 90 degrees, the edge of the near plane is at `near`
+        assert!(near_eq(
+            ndc(&m, Vec3f::new(0.1f32, 0.1f32, -0.1f32)).x,
+            1f32
+        ));
+
+        camera.set_reverse_z(true);
+        let m = camera.projection();
+        assert!(near_eq(ndc(&m, Vec3f::new(0f32, 0f32, -0.1f32)).z, 1f32));
+        assert!(near_eq(ndc(&m, Vec3f::new(0f32, 0f32, -100f32)).z, 0f32));
+    }
+
+    #[test]
+    fn infinite_far_plane() {
+        let camera = Camera::new();
+        camera.make_perspective_infinite(1f32, angle2rad(90f32), 0.1f32);
+        let m = camera.projection();
+        assert!(near_eq(ndc(&m, Vec3f::new(0f32, 0f32, -0.1f32)).z, 0f32));
+        let z = ndc(&m, Vec3f::new(0f32, 0f32, -1e6f32)).z;
+        assert!(z < 1f32 && near_eq(z, 1f32));
+
+        camera.set_depth_convention(DepthConvention::Reversed);
+        let m = camera.projection();
+        assert!(near_eq(ndc(&m, Vec3f::new(0f32, 0f32, -0.1f32)).z, 1f32));
+        let z = ndc(&m, Vec3f::new(0f32, 0f32, -1e6f32)).z;
+        assert!(z > 0f32 && near_eq(z, 0f32));
+        // the frustum is cut at a finite distance
+        camera.look_at(Vec3f::zeros(), -Vec3f::z(), Vec3f::y());
+        camera.frustum_worldspace();
+    }
+
+    #[test]
+    fn orthographic_depth_range() {
+        let camera = Camera::new();
+        camera.make_orthographic(Vec4f::new(0f32, 0f32, 4f32, 2f32), 1f32, 10f32);
+        let m = camera.projection();
+        let p = ndc(&m, Vec3f::new(4f32, 0f32, -1f32));
+        assert!(near_eq(p.x, 1f32) && near_eq(p.y, -1f32) && near_eq(p.z, 0f32));
+        assert!(near_eq(ndc(&m, Vec3f::new(0f32, 0f32, -10f32)).z, 1f32));
+
+        camera.set_reverse_z(true);
+        let m = camera.projection();
+        assert!(near_eq(ndc(&m, Vec3f::new(0f32, 0f32, -1f32)).z, 1f32));
+        assert!(near_eq(ndc(&m, Vec3f::new(0f32, 0f32, -10f32)).z, 0f32));
+    }
+
+    #[test]
+    fn off_center_bounds() {
+        let camera = Camera::new();
+        camera.make_perspective_off_center(-0.1f32, 0.3f32, -0.1f32, 0.1f32, 0.1f32, 100f32);
+        let m = camera.projection();
+        let lb = ndc(&m, Vec3f::new(-0.1f32, -0.1f32, -0.1f32));
+        let rt = ndc(&m, Vec3f::new(0.3f32, 0.1f32, -0.1f32));
+        assert!(near_eq(lb.x, -1f32) && near_eq(lb.y, -1f32));
+        assert!(near_eq(rt.x, 1f32) && near_eq(rt.y, 1f32));
+        assert!(near_eq(camera.off_center().x, 0.5f32));
+
+        // resizing keeps the bounds
+        assert!(!camera.set_aspect(1f32));
+        assert!(!camera.set_fov(angle2rad(60f32)));
+        assert_eq!(camera.projection(), m);
+
+        camera.make_perspective(1f32, angle2rad(90f32), 0.1f32, 100f32);
+        assert!(camera.set_aspect(2f32));
+        assert_eq!(camera.off_center(), Vec2f::zeros());
+    }
+
+    #[test]
+    fn jitter_moves_image() {
+        let camera = Camera::new();
+        camera.make_perspective(1f32, angle2rad(90f32), 0.1f32, 100f32);
+        let p = Vec3f::new(0.2f32, -0.3f32, -5f32);
+        let a = ndc(&camera.projection(), p);
+        camera.set_jitter(Vec2f::new(0.01f32, -0.02f32));
+        let b = ndc(&camera.projection(), p);
+        assert!(near_eq(b.x - a.x, 0.01f32));
+        assert!(near_eq(b.y - a.y, -0.02f32));
+        assert!(near_eq(b.z, a.z));
+    }
+
+    #[test]
+    fn halton_sequence() {
+        assert_eq!(halton(1, 2), 0.5f32);
+        assert_eq!(halton(2, 2), 0.25f32);
+        assert_eq!(halton(3, 2), 0.75f32);
+        assert!(near_eq(halton(1, 3), 1f32 / 3f32));
+        assert!(near_eq(halton(2, 3), 2f32 / 3f32));
+        // within half a pixel
+        let j = jitter_offset(0, Size::new(4, 4));
+        assert!(j.x.abs() <= 0.25f32 && j.y.abs() <= 0.25f32);
+    }
+
+    #[test]
+    fn reversed_compare() {
+        let d = DepthConvention::Reversed;
+        assert_eq!(
+            d.compare(wgpu::CompareFunction::Less),
+            wgpu::CompareFunction::Greater
+        );
+        assert_eq!(
+            d.compare(wgpu::CompareFunction::LessEqual),
+            wgpu::CompareFunction::GreaterEqual
+        );
+        assert_eq!(
+            d.compare(wgpu::CompareFunction::Equal),
+            wgpu::CompareFunction::Equal
+        );
+        let d = DepthConvention::Standard;
+        assert_eq!(
+            d.compare(wgpu::CompareFunction::Less),
+            wgpu::CompareFunction::Less
+        );
+    }
+
+    #[test]
+    fn proxy_round_trip() {
+        let camera = Camera::new();
+        camera.make_perspective(1.5f32, 1f32, 0.1f32, 100f32);
+        camera.set_reverse_z(true);
+
+        let mut edited = camera.clone();
+        let mut proxy = edited.reflect_proxy();
+        proxy.from = [0f32, 2f32, 5f32];
+        if let ProjectionProxy::Perspective { fovy, .. } = &mut proxy.projection {
+            *fovy = 0.8f32;
+        }
+        edited.apply_proxy(&proxy);
+        camera.copy_from(&edited);
+        assert_eq!(camera.from(), Vec3f::new(0f32, 2f32, 5f32));
+        assert!(near_eq(camera.fovy(), 0.8f32));
+        assert!(near_eq(camera.aspect(), 1.5f32));
+        // state outside of the proxy is kept
+        assert!(camera.depth_convention().is_reversed());
+
+        proxy.projection = ProjectionProxy::Orthographic {
+            rect: [-2f32, -1f32, 2f32, 1f32],
+            near: 0.1f32,
+            far: 10f32,
+        };
+        edited.apply_proxy(&proxy);
+        assert_eq!(
+            edited.orthographic_rect(),
+            Some(Vec4f::new(-2f32, -1f32, 2f32, 1f32))
+        );
+    }
+}
@@ -1,6 +1,10 @@
 use std::{cell::RefCell, collections::HashMap, sync::Arc};
 
-use crate::event::InputEvent;
+use crate::{
+    event::InputEvent,
+    input::SharedInputMap,
+    types::{Size, Vec3f},
+};
 
 use self::{orbit::OrbitControllerFactory, trackball::TrackballControllerFactory};
 
@@ -13,19 +17,65 @@ pub trait ControllerFactory: Send + Sync {
 
 pub trait CameraController {
     fn on_input(&mut self, event: &InputEvent);
+    // `delta` is the frame time in seconds, smoothed motion is applied here
+    fn on_update(&mut self, _delta: f32) {}
+    // physical size of the view the camera draws to
+    fn on_resize(&mut self, _size: Size) {}
+    // moves the camera to show the world bounds
+    fn frame(&mut self, _min: &Vec3f, _max: &Vec3f) {}
+    // bindings of the window the controller is created for
+    fn set_input_map(&mut self, _map: SharedInputMap) {}
 }
 
+pub mod editor;
+pub mod fly;
 pub mod orbit;
+pub mod path;
 pub mod trackball;
 
+// exponential smoothing of controller motion. `time` is the seconds it takes
+// to cover ~63% of the remaining motion, zero applies the input at once
+#[derive(Debug, Clone, Copy)]
+pub struct Inertia {
+    pub time: f32,
+}
+
+impl Default for Inertia {
+    fn default() -> Self {
+        Self { time: 0.08f32 }
+    }
+}
+
+impl Inertia {
+    pub fn new(time: f32) -> Self {
+        Self { time }
+    }
+
+    pub fn none() -> Self {
+        Self { time: 0f32 }
+    }
+
+    // part of the remaining motion applied in `delta` seconds
+    pub fn factor(&self, delta: f32) -> f32 {
+        if self.time <= 0f32 {
+            1f32
+        } else {
+            1f32 - (-delta / self.time).exp()
+        }
+    }
+}
+
 pub struct CameraControllerFactory {
     factory: HashMap<String, Box<dyn ControllerFactory>>,
+    // shared by the created controllers
+    input_map: SharedInputMap,
 }
 
 impl CameraControllerFactory {
     pub fn new() -> Self {
         let mut s = Self {
             factory: HashMap::new(),
+            input_map: SharedInputMap::default(),
         };
         s.add_inner(Box::new(OrbitControllerFactory));
         s.add_inner(Box::new(TrackballControllerFactory));
@@ -47,7 +97,13 @@ impl CameraControllerFactory {
         name: &str,
         camera: Arc<Camera>,
     ) -> Option<Box<RefCell<dyn CameraController>>> {
-        self.factory.get(name).map(|v| v.create(camera))
+        let c = self.factory.get(name)?.create(camera);
+        c.borrow_mut().set_input_map(self.input_map.clone());
+        Some(c)
+    }
+
+    pub fn input_map(&self) -> &SharedInputMap {
+        &self.input_map
     }
 
     pub fn list(&self) -> Vec<String> {
@@ -0,0 +1,394 @@
+use std::{cell::RefCell, sync::Arc};
+
+use nalgebra::Unit;
+
+use crate::{
+    event::InputEvent,
+    input::{InputState, SharedInputMap, ACTION_ORBIT, ACTION_PAN, ACTION_ZOOM, AXIS_WHEEL},
+    scene::Camera,
+    types::{Quaternion, Size, Vec2f, Vec3f, Vec4f},
+};
+
+use super::{CameraController, ControllerDriver, ControllerFactory, Inertia};
+
+#[derive(Debug, Clone)]
+pub struct EditorConfig {
+    // radians per pixel
+    pub orbit_speed: f32,
+    // part of the pivot distance per pixel, used until the view size is known
+    pub pan_speed: f32,
+    // part of the pivot distance per wheel step or 10 pixels of zoom drag
+    pub zoom_speed: f32,
+    pub inertia: Inertia,
+}
+
+impl Default for EditorConfig {
+    fn default() -> Self {
+        Self {
+            orbit_speed: 0.005f32,
+            pan_speed: 0.001f32,
+            zoom_speed: 0.1f32,
+            inertia: Inertia::default(),
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct EditorControllerFactory {
+    pub config: EditorConfig,
+}
+
+impl ControllerFactory for EditorControllerFactory {
+    fn create(&self, camera: Arc<Camera>) -> Box<std::cell::RefCell<dyn CameraController>> {
+        Box::new(RefCell::new(EditorCameraController::new(
+            camera,
+            self.config.clone(),
+        )))
+    }
+    fn name(&self) -> String {
+        "Editor".into()
+    }
+}
+
+// the orbit action drags around the pivot, the pan action pans, the wheel and
+// the zoom action dolly to the cursor
+pub struct EditorCameraController {
+    camera: Arc<Camera>,
+    config: EditorConfig,
+    input: InputState,
+    size: Option<Size>,
+
+    // input not applied yet
+    orbit: Vec2f,
+    pan: Vec2f,
+    zoom: f32,
+    zoom_pos: Vec2f,
+    // from and to of a running frame
+    frame_target: Option<(Vec3f, Vec3f)>,
+    driver: ControllerDriver,
+}
+
+impl EditorCameraController {
+    pub fn new(camera: Arc<Camera>, config: EditorConfig) -> Self {
+        Self {
+            camera,
+            config,
+            input: InputState::new(),
+            size: None,
+            orbit: Vec2f::zeros(),
+            pan: Vec2f::zeros(),
+            zoom: 0f32,
+            zoom_pos: Vec2f::zeros(),
+            frame_target: None,
+            driver: ControllerDriver::default(),
+        }
+    }
+
+    pub fn config_mut(&mut self) -> &mut EditorConfig {
+        &mut self.config
+    }
+
+    fn rotate(&mut self, offset: &Vec2f) {
+        let from = self.camera.from();
+        let to = self.camera.to();
+        let up = self.camera.up();
+        let vector = from - to;
+        let dir = -vector.normalize();
+        let right = dir.cross(&up).normalize();
+
+        let yaw = -offset.x * self.config.orbit_speed;
+        let mut pitch = -offset.y * self.config.orbit_speed;
+        // stop before looking straight up or down
+        let theta = dir.dot(&up.normalize()).clamp(-1f32, 1f32).asin();
+        let limit = std::f32::consts::FRAC_PI_2 - 0.01f32;
+        pitch = (theta + pitch).clamp(-limit, limit) - theta;
+
+        let q = Quaternion::from_axis_angle(&Unit::new_normalize(up), yaw)
+            * Quaternion::from_axis_angle(&Unit::new_unchecked(right), pitch);
+        self.camera.look_at(to + q * vector, to, up);
+    }
+
+    // world units per pixel at the pivot
+    fn pixel_size(&self, dist: f32) -> f32 {
+        let size = match self.size {
+            Some(v) if v.y > 0 => v,
+            _ => return self.config.pan_speed * dist,
+        };
+        if self.camera.is_perspective() {
+            2f32 * dist * (self.camera.fovy() * 0.5f32).tan() / size.y as f32
+        } else {
+            self.camera.width_height().y / size.y as f32
+        }
+    }
+
+    fn translate(&mut self, offset: &Vec2f) {
+        let from = self.camera.from();
+        let to = self.camera.to();
+        let up = self.camera.up();
+        let dir = (to - from).normalize();
+        let right = dir.cross(&up).normalize();
+        let screen_up = right.cross(&dir);
+
+        let scale = self.pixel_size((to - from).norm());
+        let o = (-right * offset.x + screen_up * offset.y) * scale;
+        self.camera.look_at(from + o, to + o, up);
+    }
+
+    // world direction through the pixel, None for an unknown size or orthographic camera
+    fn cursor_ray(&self, pos: &Vec2f) -> Option<Vec3f> {
+        let size = self.size.filter(|v| v.x > 0 && v.y > 0)?;
+        if !self.camera.is_perspective() {
+            return None;
+        }
+        let x = pos.x / size.x as f32 * 2f32 - 1f32;
+        let y = 1f32 - pos.y / size.y as f32 * 2f32;
+        let rev = self.camera.inverse_vp();
+        let near = rev * Vec4f::new(x, y, self.camera.depth_convention().near(), 1f32);
+        let mid = rev * Vec4f::new(x, y, 0.5f32, 1f32);
+        let res = mid.xyz() / mid.w - near.xyz() / near.w;
+        res.try_normalize(f32::EPSILON)
+    }
+
+    // moves along the cursor ray, the pivot comes closer by the same part
+    fn dolly(&mut self, amount: f32, pos: &Vec2f) {
+        let from = self.camera.from();
+        let to = self.camera.to();
+        let up = self.camera.up();
+        let dist = (to - from).norm();
+        let dir = (to - from) / dist;
+
+        let k = (amount * self.config.zoom_speed).clamp(-0.5f32, 0.5f32);
+        let ray = self.cursor_ray(pos).unwrap_or(dir);
+        let from = from + ray * (dist * k);
+        let dist = (dist * (1f32 - k)).max(1e-3f32);
+        self.camera.look_at(from, from + dir * dist, up);
+    }
+
+    // reads the input of the frame into the pending motion
+    fn take_input(&mut self) {
+        let input = &self.input;
+        let motion = input.motion();
+        let mut zoom = input.axis(AXIS_WHEEL);
+        if input.pressed(ACTION_ZOOM) {
+            zoom -= motion.y * 0.1f32;
+        }
+        if input.pressed(ACTION_ORBIT) {
+            self.orbit += motion;
+        } else if input.pressed(ACTION_PAN) {
+            self.pan += motion;
+        }
+        if zoom != 0f32 {
+            self.zoom += zoom;
+            if let Some(cursor) = input.cursor() {
+                self.zoom_pos = cursor;
+            }
+        }
+        if zoom != 0f32
+            || [ACTION_ORBIT, ACTION_PAN, ACTION_ZOOM]
+                .iter()
+                .any(|v| input.just_pressed(v))
+        {
+            self.frame_target = None;
+        }
+        self.input.end_frame();
+    }
+}
+
+impl CameraController for EditorCameraController {
+    fn set_input_map(&mut self, map: SharedInputMap) {
+        self.input.set_map(map);
+    }
+
+    fn on_input(&mut self, event: &InputEvent) {
+        if self.driver.on_input(event).is_none() {
+            return;
+        }
+        self.input.on_input(event);
+    }
+
+    fn on_update(&mut self, delta: f32) {
+        self.take_input();
+        let f = self.config.inertia.factor(delta);
+
+        if let Some((from, to)) = self.frame_target {
+            let cur_from = self.camera.from();
+            let cur_to = self.camera.to();
+            let (from, to) =
+                if (from - cur_from).norm() + (to - cur_to).norm() < 1e-3f32 * (to - from).norm() {
+                    self.frame_target = None;
+                    (from, to)
+                } else {
+                    (cur_from + (from - cur_from) * f, cur_to + (to - cur_to) * f)
+                };
+            self.camera.look_at(from, to, self.camera.up());
+        }
+
+        let orbit = self.orbit * f;
+        self.orbit -= orbit;
+        if orbit != Vec2f::zeros() {
+            self.rotate(&orbit);
+        }
+        let pan = self.pan * f;
+        self.pan -= pan;
+        if pan != Vec2f::zeros() {
+            self.translate(&pan);
+        }
+        let zoom = self.zoom * f;
+        self.zoom -= zoom;
+        if zoom != 0f32 {
+            let pos = self.zoom_pos;
+            self.dolly(zoom, &pos);
+        }
+    }
+
+    fn on_resize(&mut self, size: Size) {
+        self.size = Some(size);
+    }
+
+    // keeps the view direction, the bounding sphere fits the smaller fov or the
+    // smaller side of the orthographic rect
+    fn frame(&mut self, min: &Vec3f, max: &Vec3f) {
+        let center = (min + max) * 0.5f32;
+        let radius = ((max - min).norm() * 0.5f32).max(1e-3f32);
+        let dir = (self.camera.to() - self.camera.from()).normalize();
+
+        let dist = if self.camera.is_perspective() {
+            let fovy = self.camera.fovy();
+            let fovx = 2f32 * ((fovy * 0.5f32).tan() * self.camera.aspect()).atan();
+            radius / (fovy.min(fovx) * 0.5f32).sin()
+        } else {
+            let size = self.camera.width_height();
+            let aspect = if size.y > 0f32 { size.x / size.y } else { 1f32 };
+            let half = Vec2f::new(radius * aspect.max(1f32), radius / aspect.min(1f32));
+            let (near, far) = (self.camera.near(), self.camera.far());
+            self.camera
+                .make_orthographic(Vec4f::new(-half.x, -half.y, half.x, half.y), near, far);
+            (far - near) * 0.5f32 + near
+        };
+        self.orbit = Vec2f::zeros();
+        self.pan = Vec2f::zeros();
+        self.zoom = 0f32;
+        self.frame_target = Some((center - dir * dist, center));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::event::{ElementState, MouseButton};
+
+    use super::*;
+
+    fn near_eq(a: Vec3f, b: Vec3f) -> bool {
+        (a - b).norm() < 1e-4
+    }
+
+    // looks from +z at the origin
+    fn controller(inertia: Inertia) -> EditorCameraController {
+        let camera = Arc::new(Camera::new());
+        camera.make_perspective(1f32, std::f32::consts::FRAC_PI_2, 0.1f32, 100f32);
+        camera.look_at(Vec3f::new(0f32, 0f32, 10f32), Vec3f::zeros(), Vec3f::y());
+        let config = EditorConfig {
+            inertia,
+            ..Default::default()
+        };
+        let mut c = EditorCameraController::new(camera, config);
+        c.on_resize(Size::new(100, 100));
+        c
+    }
+
+    fn cursor(pos: Vec2f) -> InputEvent {
+        InputEvent::CursorMoved {
+            logical: pos,
+            physical: pos,
+        }
+    }
+
+    fn wheel(steps: f32) -> InputEvent {
+        InputEvent::MouseWheel {
+            delta: Vec3f::new(0f32, steps, 0f32),
+        }
+    }
+
+    #[test]
+    fn dolly_to_cursor() {
+        let mut c = controller(Inertia::none());
+        c.on_input(&cursor(Vec2f::new(50f32, 50f32)));
+        c.on_input(&wheel(1f32));
+        c.on_update(0.016f32);
+        // the center of the view moves straight in
+        assert!(near_eq(c.camera.from(), Vec3f::new(0f32, 0f32, 9f32)));
+        assert!(near_eq(c.camera.to(), Vec3f::zeros()));
+
+        let mut c = controller(Inertia::none());
+        c.on_input(&cursor(Vec2f::new(100f32, 50f32)));
+        c.on_input(&wheel(1f32));
+        c.on_update(0.016f32);
+        // along the ray through the right edge, 45 degrees to the right
+        let from = c.camera.from();
+        assert!((from.x - (10f32 - from.z)).abs() < 1e-4);
+        assert!(from.x > 0f32 && from.y.abs() < 1e-4);
+        // the view direction is kept
+        let dir = (c.camera.to() - from).normalize();
+        assert!(near_eq(dir, -Vec3f::z()));
+    }
+
+    #[test]
+    fn inertia_smooths_orbit() {
+        let press = InputEvent::MouseInput {
+            state: ElementState::Pressed,
+            button: MouseButton::Left,
+        };
+        let mut at_once = controller(Inertia::none());
+        let mut smooth = controller(Inertia::new(0.1f32));
+        for c in [&mut at_once, &mut smooth] {
+            c.on_input(&cursor(Vec2f::new(50f32, 50f32)));
+            c.on_input(&press);
+            c.on_input(&cursor(Vec2f::new(150f32, 50f32)));
+            c.on_update(0.1f32);
+        }
+        let angle = |c: &EditorCameraController| {
+            let from = c.camera.from();
+            from.x.atan2(from.z).abs()
+        };
+        let total = 100f32 * EditorConfig::default().orbit_speed;
+        assert!((angle(&at_once) - total).abs() < 1e-4);
+        // ~63% of the motion after `time`
+        let part = 1f32 - (-1f32).exp();
+        assert!((angle(&smooth) - total * part).abs() < 1e-4);
+
+        for _ in 0..100 {
+            smooth.on_update(0.1f32);
+        }
+        assert!((angle(&smooth) - total).abs() < 1e-4);
+        // orbiting keeps the pivot and the distance
+        assert!(near_eq(smooth.camera.to(), Vec3f::zeros()));
+        assert!((smooth.camera.from().norm() - 10f32).abs() < 1e-3);
+    }
+
+    #[test]
+    fn frame_fits_bounds() {
+        let mut c = controller(Inertia::none());
+        let min = Vec3f::new(4f32, -1f32, -1f32);
+        let max = Vec3f::new(6f32, 1f32, 1f32);
+        c.frame(&min, &max);
+        c.on_update(0.016f32);
+        let radius = 3f32.sqrt();
+        let center = Vec3f::new(5f32, 0f32, 0f32);
+        assert!(near_eq(c.camera.to(), center));
+        let dist = radius / std::f32::consts::FRAC_PI_4.sin();
+        assert!(near_eq(c.camera.from(), center + Vec3f::z() * dist));
+
+        // the rect keeps its aspect, the smaller side fits the sphere
+        let mut c = controller(Inertia::none());
+        c.camera
+            .make_orthographic(Vec4f::new(-1f32, -0.5f32, 1f32, 0.5f32), 0.1f32, 100f32);
+        c.frame(&min, &max);
+        c.on_update(0.016f32);
+        assert!(near_eq(c.camera.to(), center));
+        let size = c.camera.width_height();
+        assert!((size.x - radius * 4f32).abs() < 1e-4);
+        assert!((size.y - radius * 2f32).abs() < 1e-4);
+        assert_eq!(c.camera.near(), 0.1f32);
+        assert_eq!(c.camera.far(), 100f32);
+    }
+}
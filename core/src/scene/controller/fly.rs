@@ -0,0 +1,267 @@
+use std::{cell::RefCell, sync::Arc};
+
+use nalgebra::Unit;
+
+use crate::{
+    event::InputEvent,
+    input::{
+        InputState, SharedInputMap, ACTION_FAST, ACTION_LOOK, ACTION_SLOW, AXIS_MOVE_X,
+        AXIS_MOVE_Y, AXIS_MOVE_Z, AXIS_WHEEL,
+    },
+    scene::Camera,
+    types::{Quaternion, Vec2f, Vec3f},
+};
+
+use super::{CameraController, ControllerDriver, ControllerFactory, Inertia};
+
+#[derive(Debug, Clone)]
+pub struct FlyConfig {
+    // units per second
+    pub speed: f32,
+    // speed multipliers while the fast / slow action is held
+    pub fast: f32,
+    pub slow: f32,
+    // radians per pixel
+    pub sensitivity: f32,
+    pub inertia: Inertia,
+}
+
+impl Default for FlyConfig {
+    fn default() -> Self {
+        Self {
+            speed: 2f32,
+            fast: 4f32,
+            slow: 0.25f32,
+            sensitivity: 0.003f32,
+            inertia: Inertia::default(),
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct FlyControllerFactory {
+    pub config: FlyConfig,
+}
+
+impl ControllerFactory for FlyControllerFactory {
+    fn create(&self, camera: Arc<Camera>) -> Box<std::cell::RefCell<dyn CameraController>> {
+        Box::new(RefCell::new(FlyCameraController::new(
+            camera,
+            self.config.clone(),
+        )))
+    }
+    fn name(&self) -> String {
+        "Fly".into()
+    }
+}
+
+// the move axes fly, dragging with the look action looks around and the wheel
+// changes the speed. by default wasd moves, q / e goes down / up
+pub struct FlyCameraController {
+    camera: Arc<Camera>,
+    config: FlyConfig,
+    input: InputState,
+    // mouse offset not applied yet
+    look: Vec2f,
+    velocity: Vec3f,
+    driver: ControllerDriver,
+}
+
+impl FlyCameraController {
+    pub fn new(camera: Arc<Camera>, config: FlyConfig) -> Self {
+        Self {
+            camera,
+            config,
+            input: InputState::new(),
+            look: Vec2f::zeros(),
+            velocity: Vec3f::zeros(),
+            driver: ControllerDriver::default(),
+        }
+    }
+
+    pub fn config_mut(&mut self) -> &mut FlyConfig {
+        &mut self.config
+    }
+
+    fn rotate(&mut self, offset: &Vec2f) {
+        let from = self.camera.from();
+        let to = self.camera.to();
+        let up = self.camera.up();
+        let vector = to - from;
+        let dist = vector.norm();
+        let dir = vector / dist;
+        let right = dir.cross(&up).normalize();
+
+        let yaw = -offset.x * self.config.sensitivity;
+        let mut pitch = -offset.y * self.config.sensitivity;
+        // stop before looking straight up or down
+        let theta = dir.dot(&up.normalize()).clamp(-1f32, 1f32).asin();
+        let limit = std::f32::consts::FRAC_PI_2 - 0.01f32;
+        pitch = (theta + pitch).clamp(-limit, limit) - theta;
+
+        let q = Quaternion::from_axis_angle(&Unit::new_normalize(up), yaw)
+            * Quaternion::from_axis_angle(&Unit::new_unchecked(right), pitch);
+        let dir = q * dir;
+        self.camera.look_at(from, from + dir * dist, up);
+    }
+
+    fn target_velocity(&self) -> Vec3f {
+        let local = Vec3f::new(
+            self.input.axis(AXIS_MOVE_X),
+            self.input.axis(AXIS_MOVE_Y),
+            self.input.axis(AXIS_MOVE_Z),
+        );
+        if local == Vec3f::zeros() {
+            return local;
+        }
+        let from = self.camera.from();
+        let up = self.camera.up().normalize();
+        let dir = (self.camera.to() - from).normalize();
+        let right = dir.cross(&up).normalize();
+
+        let mut speed = self.config.speed;
+        if self.input.pressed(ACTION_FAST) {
+            speed *= self.config.fast;
+        }
+        if self.input.pressed(ACTION_SLOW) {
+            speed *= self.config.slow;
+        }
+        (right * local.x + up * local.y + dir * local.z).normalize() * speed
+    }
+}
+
+impl CameraController for FlyCameraController {
+    fn set_input_map(&mut self, map: SharedInputMap) {
+        self.input.set_map(map);
+    }
+
+    fn on_input(&mut self, event: &InputEvent) {
+        if self.driver.on_input(event).is_none() {
+            return;
+        }
+        self.input.on_input(event);
+    }
+
+    fn on_update(&mut self, delta: f32) {
+        let f = self.config.inertia.factor(delta);
+
+        if self.input.pressed(ACTION_LOOK) {
+            self.look += self.input.motion();
+        }
+        let wheel = self.input.axis(AXIS_WHEEL);
+        if wheel != 0f32 {
+            self.config.speed = (self.config.speed * 1.1f32.powf(wheel)).max(0.01f32);
+        }
+
+        let look = self.look * f;
+        self.look -= look;
+        if look != Vec2f::zeros() {
+            self.rotate(&look);
+        }
+
+        let target = self.target_velocity();
+        self.input.end_frame();
+        self.velocity += (target - self.velocity) * f;
+        if self.velocity.norm() < 1e-4f32 {
+            self.velocity = Vec3f::zeros();
+            return;
+        }
+        let offset = self.velocity * delta;
+        self.camera.look_at(
+            self.camera.from() + offset,
+            self.camera.to() + offset,
+            self.camera.up(),
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::event::{ElementState, KeyboardInput, ModifiersState, VirtualKeyCode};
+
+    use super::*;
+
+    // looks from the origin down -z
+    fn controller(inertia: Inertia) -> FlyCameraController {
+        let camera = Arc::new(Camera::new());
+        camera.make_perspective(1f32, std::f32::consts::FRAC_PI_2, 0.1f32, 100f32);
+        camera.look_at(Vec3f::zeros(), -Vec3f::z(), Vec3f::y());
+        let config = FlyConfig {
+            inertia,
+            ..Default::default()
+        };
+        FlyCameraController::new(camera, config)
+    }
+
+    fn key(vk: VirtualKeyCode) -> InputEvent {
+        InputEvent::KeyboardInput(KeyboardInput {
+            state: ElementState::Pressed,
+            vk,
+        })
+    }
+
+    fn modifiers(ctrl: bool, shift: bool) -> InputEvent {
+        InputEvent::ModifiersChanged(ModifiersState {
+            ctrl,
+            win: false,
+            alt: false,
+            shift,
+        })
+    }
+
+    // distance flown forward in one second
+    fn forward(c: &mut FlyCameraController) -> f32 {
+        let from = c.camera.from();
+        c.on_update(1f32);
+        -(c.camera.from() - from).z
+    }
+
+    #[test]
+    fn speed_modifiers() {
+        let mut c = controller(Inertia::none());
+        c.on_input(&key(VirtualKeyCode::W));
+        assert!((forward(&mut c) - 2f32).abs() < 1e-4);
+
+        c.on_input(&modifiers(false, true));
+        assert!((forward(&mut c) - 8f32).abs() < 1e-4);
+        c.on_input(&modifiers(true, false));
+        assert!((forward(&mut c) - 0.5f32).abs() < 1e-4);
+
+        // the wheel changes the base speed
+        c.on_input(&modifiers(false, false));
+        c.on_input(&InputEvent::MouseWheel {
+            delta: Vec3f::new(0f32, 1f32, 0f32),
+        });
+        assert!((forward(&mut c) - 2.2f32).abs() < 1e-4);
+        assert!((forward(&mut c) - 2.2f32).abs() < 1e-4);
+        // moving keeps the view direction
+        assert!(((c.camera.to() - c.camera.from()) + Vec3f::z()).norm() < 1e-4);
+    }
+
+    #[test]
+    fn inertia_ramps_velocity() {
+        let mut c = controller(Inertia::new(0.1f32));
+        c.on_input(&key(VirtualKeyCode::W));
+        c.on_update(0.1f32);
+        let part = 1f32 - (-1f32).exp();
+        assert!((c.velocity.norm() - 2f32 * part).abs() < 1e-4);
+        assert!((c.camera.from().z + 0.2f32 * part).abs() < 1e-4);
+
+        for _ in 0..100 {
+            c.on_update(0.1f32);
+        }
+        assert!((c.velocity.norm() - 2f32).abs() < 1e-4);
+
+        // slows down after the release
+        c.on_input(&InputEvent::KeyboardInput(KeyboardInput {
+            state: ElementState::Released,
+            vk: VirtualKeyCode::W,
+        }));
+        c.on_update(0.1f32);
+        assert!((c.velocity.norm() - 2f32 * (1f32 - part)).abs() < 1e-4);
+        for _ in 0..100 {
+            c.on_update(0.1f32);
+        }
+        assert_eq!(c.velocity, Vec3f::zeros());
+    }
+}
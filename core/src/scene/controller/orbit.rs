@@ -4,6 +4,7 @@ use nalgebra::Unit;
 
 use crate::{
     event::InputEvent,
+    input::{InputState, SharedInputMap, ACTION_ORBIT, ACTION_PAN, ACTION_ZOOM, AXIS_WHEEL},
     scene::Camera,
     types::{Quaternion, Vec2f},
 };
@@ -21,19 +22,9 @@ impl ControllerFactory for OrbitControllerFactory {
     }
 }
 
-#[derive(Debug, Eq, PartialEq)]
-enum State {
-    None,
-    Orbiting,
-    Zooming,
-    Panning,
-}
-
 pub struct OrbitCameraController {
     camera: Arc<Camera>,
-    down_pos: Option<Vec2f>,
-    state: State,
-    last_pos: Vec2f,
+    input: InputState,
     driver: ControllerDriver,
 }
 
@@ -41,9 +32,7 @@ impl OrbitCameraController {
     pub fn new(camera: Arc<Camera>) -> Self {
         Self {
             camera,
-            down_pos: None,
-            state: State::None,
-            last_pos: Vec2f::default(),
+            input: InputState::new(),
             driver: ControllerDriver::default(),
         }
     }
@@ -110,74 +99,41 @@ impl OrbitCameraController {
 }
 
 impl CameraController for OrbitCameraController {
+    fn set_input_map(&mut self, map: SharedInputMap) {
+        self.input.set_map(map);
+    }
+
     fn on_input(&mut self, event: &InputEvent) {
         if self.driver.on_input(event).is_none() {
             return;
         }
-        match event {
-            crate::event::InputEvent::CursorMoved {
-                logical: _,
-                physical,
-            } => {
-                if State::None == self.state {
-                    self.last_pos = *physical;
-                    return;
-                }
-                let offset = *physical - self.last_pos;
-                self.last_pos = *physical;
-                match self.state {
-                    State::Orbiting => {
-                        self.orbit(&offset);
-                    }
-                    State::Zooming => {
-                        self.zoom(&offset);
-                    }
-                    State::Panning => {
-                        self.pan(&offset);
-                    }
-                    _ => {}
-                }
+        self.input.on_input(event);
+    }
+
+    fn on_update(&mut self, _delta: f32) {
+        let motion = self.input.motion();
+        if motion != Vec2f::zeros() {
+            if self.input.pressed(ACTION_ORBIT) {
+                self.orbit(&motion);
+            } else if self.input.pressed(ACTION_PAN) {
+                self.pan(&motion);
+            } else if self.input.pressed(ACTION_ZOOM) {
+                self.zoom(&motion);
             }
-            crate::event::InputEvent::MouseWheel { delta } => {
-                let from = self.camera.from();
-                let to = self.camera.to();
-                let up = self.camera.up();
+        }
 
-                let vector = from - to;
-                let new_offset = delta.y;
-                let dist = (new_offset * 0.05f32).max(-0.5f32).min(0.5f32);
-                let new_from = from - (vector * dist);
+        let wheel = self.input.axis(AXIS_WHEEL);
+        if wheel != 0f32 {
+            let from = self.camera.from();
+            let to = self.camera.to();
+            let up = self.camera.up();
 
-                self.camera.look_at(new_from, to, up);
-            }
-            crate::event::InputEvent::MouseInput { state, button } => {
-                if button.is_left() {
-                    if state.is_pressed() {
-                        self.state = State::Orbiting;
-                        self.down_pos = Some(self.last_pos);
-                    } else {
-                        self.state = State::None;
-                        self.down_pos = None;
-                    }
-                } else if button.is_right() {
-                    if state.is_pressed() {
-                        self.state = State::Panning;
-                        self.down_pos = Some(self.last_pos);
-                    } else {
-                        self.state = State::None;
-                        self.down_pos = None;
-                    }
-                } else if button.is_middle() {
-                    if state.is_pressed() {
-                        self.state = State::Zooming;
-                        self.down_pos = Some(self.last_pos);
-                    } else {
-                        self.state = State::None;
-                        self.down_pos = None;
-                    }
-                }
-            }
-            _ => (),
+            let vector = from - to;
+            let dist = (wheel * 0.05f32).clamp(-0.5f32, 0.5f32);
+            let new_from = from - (vector * dist);
+
+            self.camera.look_at(new_from, to, up);
         }
+        self.input.end_frame();
     }
 }
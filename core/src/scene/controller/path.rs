@@ -0,0 +1,424 @@
+use std::{path::Path, sync::Arc};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{event::InputEvent, scene::Camera, types::Vec3f};
+
+use super::CameraController;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Ease {
+    #[default]
+    Linear,
+    In,
+    Out,
+    InOut,
+    // holds the keyframe until the next one
+    Step,
+}
+
+impl Ease {
+    pub fn apply(&self, t: f32) -> f32 {
+        let t = t.clamp(0f32, 1f32);
+        match self {
+            Ease::Linear => t,
+            Ease::In => t * t,
+            Ease::Out => 1f32 - (1f32 - t) * (1f32 - t),
+            Ease::InOut => t * t * (3f32 - 2f32 * t),
+            Ease::Step => {
+                if t < 1f32 {
+                    0f32
+                } else {
+                    1f32
+                }
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum PathInterpolation {
+    Linear,
+    // passes through every keyframe with a smooth tangent
+    #[default]
+    CatmullRom,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CameraKeyframe {
+    // seconds from the start of the path
+    pub time: f32,
+    pub from: [f32; 3],
+    pub to: [f32; 3],
+    // vertical fov in radians, None keeps the fov of the camera
+    #[serde(default)]
+    pub fovy: Option<f32>,
+    // ease of the segment starting at this keyframe
+    #[serde(default)]
+    pub ease: Ease,
+}
+
+impl CameraKeyframe {
+    pub fn from_camera(time: f32, camera: &Camera) -> Self {
+        Self {
+            time,
+            from: camera.from().into(),
+            to: camera.to().into(),
+            fovy: camera.is_perspective().then(|| camera.fovy()),
+            ease: Ease::default(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct CameraPathSample {
+    pub from: Vec3f,
+    pub to: Vec3f,
+    pub fovy: Option<f32>,
+}
+
+fn catmull_rom(p0: Vec3f, p1: Vec3f, p2: Vec3f, p3: Vec3f, t: f32) -> Vec3f {
+    let t2 = t * t;
+    let t3 = t2 * t;
+    (p1 * 2f32
+        + (p2 - p0) * t
+        + (p0 * 2f32 - p1 * 5f32 + p2 * 4f32 - p3) * t2
+        + (p1 * 3f32 - p0 - p2 * 3f32 + p3) * t3)
+        * 0.5f32
+}
+
+// keyframes of a camera tour, stored as ron like the scene files
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct CameraPath {
+    pub keyframes: Vec<CameraKeyframe>,
+    #[serde(default)]
+    pub interpolation: PathInterpolation,
+    // playback starts over at the end, the last keyframe should repeat the first
+    #[serde(default)]
+    pub looped: bool,
+}
+
+impl CameraPath {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // keeps the keyframes ordered by time
+    pub fn add_keyframe(&mut self, keyframe: CameraKeyframe) {
+        let index = self.keyframes.partition_point(|v| v.time <= keyframe.time);
+        self.keyframes.insert(index, keyframe);
+    }
+
+    pub fn duration(&self) -> f32 {
+        self.keyframes.last().map(|v| v.time).unwrap_or_default()
+    }
+
+    pub fn sample(&self, time: f32) -> Option<CameraPathSample> {
+        let k = &self.keyframes;
+        let first = k.first()?;
+        let last = k.last()?;
+        if k.len() == 1 || time <= first.time {
+            return Some(Self::keyframe_sample(first));
+        }
+        if time >= last.time {
+            return Some(Self::keyframe_sample(last));
+        }
+        let i = k.partition_point(|v| v.time <= time) - 1;
+        let (a, b) = (&k[i], &k[i + 1]);
+        let span = b.time - a.time;
+        let t = if span > 0f32 {
+            a.ease.apply((time - a.time) / span)
+        } else {
+            1f32
+        };
+
+        // neighbours out of the path are clamped, a looped path wraps over the
+        // last keyframe, which is the first one
+        let last = k.len() - 1;
+        let prev = match i {
+            0 if self.looped => last - 1,
+            i => i.saturating_sub(1),
+        };
+        let next = match i + 2 {
+            n if n > last && self.looped => n - last,
+            n => n.min(last),
+        };
+        let point =
+            |index: usize, f: fn(&CameraKeyframe) -> [f32; 3]| -> Vec3f { f(&k[index]).into() };
+        let interpolate = |f: fn(&CameraKeyframe) -> [f32; 3]| match self.interpolation {
+            PathInterpolation::Linear => point(i, f).lerp(&point(i + 1, f), t),
+            PathInterpolation::CatmullRom => catmull_rom(
+                point(prev, f),
+                point(i, f),
+                point(i + 1, f),
+                point(next, f),
+                t,
+            ),
+        };
+        let fovy = match (a.fovy, b.fovy) {
+            (Some(x), Some(y)) => Some(x + (y - x) * t),
+            (x, y) => x.or(y),
+        };
+        Some(CameraPathSample {
+            from: interpolate(|v| v.from),
+            to: interpolate(|v| v.to),
+            fovy,
+        })
+    }
+
+    fn keyframe_sample(k: &CameraKeyframe) -> CameraPathSample {
+        CameraPathSample {
+            from: k.from.into(),
+            to: k.to.into(),
+            fovy: k.fovy,
+        }
+    }
+
+    // keyframes are sorted by time, like `add_keyframe` keeps them
+    pub fn parse(s: &str) -> anyhow::Result<Self> {
+        let mut path: Self =
+            ron::from_str(s).map_err(|e| anyhow::anyhow!("invalid camera path: {}", e))?;
+        if let Some(k) = path.keyframes.iter().find(|v| !v.time.is_finite()) {
+            anyhow::bail!("invalid camera path: keyframe time {}", k.time);
+        }
+        path.keyframes.sort_by(|a, b| a.time.total_cmp(&b.time));
+        Ok(path)
+    }
+
+    pub fn save(&self) -> anyhow::Result<String> {
+        Ok(ron::ser::to_string_pretty(
+            self,
+            ron::ser::PrettyConfig::default(),
+        )?)
+    }
+
+    pub fn load_from_file<P: AsRef<Path>>(path: P) -> anyhow::Result<Self> {
+        let s = std::fs::read_to_string(path)?;
+        Self::parse(&s)
+    }
+
+    pub fn save_to_file<P: AsRef<Path>>(&self, path: P) -> anyhow::Result<()> {
+        let s = self.save()?;
+        std::fs::write(path, s)?;
+        Ok(())
+    }
+}
+
+// plays a path on the camera, driven by `on_update`
+pub struct CameraPathController {
+    camera: Arc<Camera>,
+    path: CameraPath,
+    time: f32,
+    playing: bool,
+    pub speed: f32,
+}
+
+impl CameraPathController {
+    pub fn new(camera: Arc<Camera>, path: CameraPath) -> Self {
+        Self {
+            camera,
+            path,
+            time: 0f32,
+            playing: false,
+            speed: 1f32,
+        }
+    }
+
+    pub fn path(&self) -> &CameraPath {
+        &self.path
+    }
+
+    // the camera is moved at the next seek or update
+    pub fn path_mut(&mut self) -> &mut CameraPath {
+        &mut self.path
+    }
+
+    pub fn play(&mut self) {
+        if self.time >= self.path.duration() {
+            self.time = 0f32;
+        }
+        self.playing = true;
+    }
+
+    pub fn pause(&mut self) {
+        self.playing = false;
+    }
+
+    pub fn is_playing(&self) -> bool {
+        self.playing
+    }
+
+    pub fn time(&self) -> f32 {
+        self.time
+    }
+
+    pub fn duration(&self) -> f32 {
+        self.path.duration()
+    }
+
+    pub fn seek(&mut self, time: f32) {
+        self.time = time.clamp(0f32, self.path.duration());
+        self.apply();
+    }
+
+    fn apply(&self) {
+        let sample = match self.path.sample(self.time) {
+            Some(v) => v,
+            None => return,
+        };
+        self.camera
+            .look_at(sample.from, sample.to, self.camera.up());
+        if let Some(fovy) = sample.fovy {
+            self.camera.set_fov(fovy);
+        }
+    }
+}
+
+impl CameraController for CameraPathController {
+    fn on_input(&mut self, _event: &InputEvent) {}
+
+    fn on_update(&mut self, delta: f32) {
+        if !self.playing {
+            return;
+        }
+        let duration = self.path.duration();
+        self.time += delta * self.speed;
+        if self.time >= duration {
+            if self.path.looped && duration > 0f32 {
+                self.time %= duration;
+            } else {
+                self.time = duration;
+                self.playing = false;
+            }
+        }
+        self.apply();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn keyframe(time: f32, from: [f32; 3]) -> CameraKeyframe {
+        CameraKeyframe {
+            time,
+            from,
+            to: [0f32; 3],
+            fovy: None,
+            ease: Ease::Linear,
+        }
+    }
+
+    fn near_eq(a: Vec3f, b: Vec3f) -> bool {
+        (a - b).norm() < 1e-4
+    }
+
+    // a square around the origin, closed by repeating the first keyframe
+    fn square(looped: bool) -> CameraPath {
+        let mut path = CameraPath::new();
+        for (time, from) in [
+            [1f32, 0f32, 0f32],
+            [0f32, 0f32, 1f32],
+            [-1f32, 0f32, 0f32],
+            [0f32, 0f32, -1f32],
+            [1f32, 0f32, 0f32],
+        ]
+        .into_iter()
+        .enumerate()
+        {
+            path.add_keyframe(keyframe(time as f32, from));
+        }
+        path.looped = looped;
+        path
+    }
+
+    fn velocity(path: &CameraPath, time: f32) -> Vec3f {
+        let e = 1e-3f32;
+        (path.sample(time + e).unwrap().from - path.sample(time - e).unwrap().from) / (2f32 * e)
+    }
+
+    #[test]
+    fn sample_passes_keyframes() {
+        let path = square(false);
+        for (i, k) in path.keyframes.iter().enumerate() {
+            let s = path.sample(i as f32).unwrap();
+            assert!(near_eq(s.from, k.from.into()));
+        }
+        // clamped out of the path
+        assert!(near_eq(path.sample(-1f32).unwrap().from, Vec3f::x()));
+        assert!(near_eq(path.sample(10f32).unwrap().from, Vec3f::x()));
+
+        let mut path = path;
+        path.interpolation = PathInterpolation::Linear;
+        let s = path.sample(0.5f32).unwrap();
+        assert!(near_eq(s.from, Vec3f::new(0.5f32, 0f32, 0.5f32)));
+    }
+
+    #[test]
+    fn looped_path_is_smooth() {
+        let path = square(true);
+        let e = 1e-2f32;
+        let start = velocity(&path, e);
+        let end = velocity(&path, path.duration() - e);
+        assert!((start - end).norm() < 0.1f32);
+
+        // the ends of an open path follow their own segment
+        let path = square(false);
+        let start = velocity(&path, e);
+        let end = velocity(&path, path.duration() - e);
+        assert!((start - end).norm() > 0.5f32);
+    }
+
+    #[test]
+    fn ease_and_fovy() {
+        let mut path = CameraPath::new();
+        let mut a = keyframe(0f32, [0f32; 3]);
+        a.fovy = Some(1f32);
+        a.ease = Ease::Step;
+        let mut b = keyframe(2f32, [2f32, 0f32, 0f32]);
+        b.fovy = Some(2f32);
+        path.add_keyframe(b);
+        path.add_keyframe(a);
+        path.interpolation = PathInterpolation::Linear;
+
+        let s = path.sample(1.5f32).unwrap();
+        assert!(near_eq(s.from, Vec3f::zeros()));
+        assert_eq!(s.fovy, Some(1f32));
+
+        path.keyframes[0].ease = Ease::Linear;
+        let s = path.sample(1f32).unwrap();
+        assert!(near_eq(s.from, Vec3f::x()));
+        assert_eq!(s.fovy, Some(1.5f32));
+    }
+
+    #[test]
+    fn parse_sorts_keyframes() {
+        let mut path = CameraPath::new();
+        path.keyframes = vec![keyframe(2f32, [2f32; 3]), keyframe(0f32, [0f32; 3])];
+        let path = CameraPath::parse(&path.save().unwrap()).unwrap();
+        let times: Vec<_> = path.keyframes.iter().map(|v| v.time).collect();
+        assert_eq!(times, vec![0f32, 2f32]);
+        assert!(near_eq(path.sample(0f32).unwrap().from, Vec3f::zeros()));
+
+        let mut path = CameraPath::new();
+        path.keyframes = vec![keyframe(f32::NAN, [0f32; 3])];
+        let s = path.save().unwrap();
+        assert!(CameraPath::parse(&s).is_err());
+    }
+
+    #[test]
+    fn controller_wraps_looped_path() {
+        let camera = Arc::new(Camera::new());
+        let mut controller = CameraPathController::new(camera.clone(), square(true));
+        controller.play();
+        controller.on_update(4.5f32);
+        assert!(controller.is_playing());
+        assert!((controller.time() - 0.5f32).abs() < 1e-4);
+
+        let mut controller = CameraPathController::new(camera.clone(), square(false));
+        controller.play();
+        controller.on_update(4.5f32);
+        assert!(!controller.is_playing());
+        assert!(near_eq(camera.from(), Vec3f::x()));
+    }
+}
@@ -0,0 +1,79 @@
+use std::sync::{
+    mpsc::{channel, Receiver, Sender},
+    Mutex,
+};
+
+use crate::material::MaterialId;
+
+use super::{LayerId, ObjectId};
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum SceneEvent {
+    ObjectAdded {
+        id: ObjectId,
+        layer: LayerId,
+        material: MaterialId,
+    },
+    ObjectRemoved {
+        id: ObjectId,
+        layer: LayerId,
+        material: MaterialId,
+    },
+    MaterialChanged {
+        id: ObjectId,
+        layer: LayerId,
+        old: MaterialId,
+        new: MaterialId,
+    },
+    LayerChanged {
+        id: ObjectId,
+        old: LayerId,
+        new: LayerId,
+        material: MaterialId,
+    },
+    TransformChanged {
+        id: ObjectId,
+    },
+    VisibilityChanged {
+        id: ObjectId,
+        visible: bool,
+    },
+}
+
+impl SceneEvent {
+    pub fn object(&self) -> ObjectId {
+        match self {
+            SceneEvent::ObjectAdded { id, .. } => *id,
+            SceneEvent::ObjectRemoved { id, .. } => *id,
+            SceneEvent::MaterialChanged { id, .. } => *id,
+            SceneEvent::LayerChanged { id, .. } => *id,
+            SceneEvent::TransformChanged { id } => *id,
+            SceneEvent::VisibilityChanged { id, .. } => *id,
+        }
+    }
+}
+
+pub type SceneEventReceiver = Receiver<SceneEvent>;
+
+// every subscriber gets its own queue, dropped receivers are removed on the next send
+#[derive(Debug, Default)]
+pub(crate) struct SceneEventDispatcher {
+    senders: Mutex<Vec<Sender<SceneEvent>>>,
+}
+
+impl SceneEventDispatcher {
+    pub fn subscribe(&self) -> SceneEventReceiver {
+        let (tx, rx) = channel();
+        self.senders.lock().unwrap().push(tx);
+        rx
+    }
+
+    pub fn send(&self, event: SceneEvent) {
+        let mut senders = self.senders.lock().unwrap();
+        if senders.is_empty() {
+            return;
+        }
+        log::trace!("scene event {:?}", event);
+        senders.retain(|s| s.send(event.clone()).is_ok());
+    }
+}
@@ -0,0 +1,267 @@
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    sync::Arc,
+};
+
+use bevy_reflect::Reflect;
+use serde::{Deserialize, Serialize};
+
+use super::{
+    camera::DepthConvention,
+    sort::{DepthOrder, DistanceSorterFactory, SorterFactory, ZOrderSorterFactory},
+};
+
+pub type LayerId = u32;
+
+pub const LAYER_NORMAL: LayerId = 4_000;
+pub const LAYER_BACKGROUND: LayerId = 10_000;
+pub const LAYER_TRANSPARENT: LayerId = 20_000;
+pub const LAYER_ALPHA_TEST: LayerId = 30_000;
+// blended ui placed in the world, hidden by geometry in front of it
+pub const LAYER_WORLD_UI: LayerId = 50_000;
+// gizmos drawn over the world, ignores depth
+pub const LAYER_OVERLAY_GIZMO: LayerId = 90_000;
+pub const LAYER_UI: LayerId = 100_000;
+
+// render settings of a layer. a registered layer covers the ids from the
+// previous registered layer up to its own id, an exact layer only its own id
+#[derive(Clone, Reflect)]
+pub struct Layer {
+    pub id: LayerId,
+    pub name: String,
+    pub depth_test: bool,
+    // None writes depth for materials without blending
+    pub depth_write: Option<bool>,
+    // used by materials without a blend state
+    #[reflect(ignore)]
+    pub blend: Option<wgpu::BlendState>,
+    // None uses the scene default of the layer range
+    #[reflect(ignore)]
+    pub sorter: Option<Arc<dyn SorterFactory>>,
+    // passes of a view are placed by order, lower first
+    pub order: i64,
+    pub exact: bool,
+}
+
+impl std::fmt::Debug for Layer {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Layer")
+            .field("id", &self.id)
+            .field("name", &self.name)
+            .field("depth_test", &self.depth_test)
+            .field("depth_write", &self.depth_write)
+            .field("blend", &self.blend)
+            .field("sorter", &self.sorter.is_some())
+            .field("order", &self.order)
+            .field("exact", &self.exact)
+            .finish()
+    }
+}
+
+impl Layer {
+    pub fn new<S: Into<String>>(id: LayerId, name: S) -> Self {
+        Self {
+            id,
+            name: name.into(),
+            depth_test: true,
+            depth_write: None,
+            blend: None,
+            sorter: None,
+            order: id as i64,
+            exact: false,
+        }
+    }
+
+    pub fn exact(mut self) -> Self {
+        self.exact = true;
+        self
+    }
+
+    pub fn depth_test(mut self, test: bool) -> Self {
+        self.depth_test = test;
+        self
+    }
+
+    pub fn depth_write(mut self, write: bool) -> Self {
+        self.depth_write = Some(write);
+        self
+    }
+
+    pub fn blend(mut self, blend: wgpu::BlendState) -> Self {
+        self.blend = Some(blend);
+        self
+    }
+
+    pub fn sorter(mut self, sorter: Arc<dyn SorterFactory>) -> Self {
+        self.sorter = Some(sorter);
+        self
+    }
+
+    pub fn order(mut self, order: i64) -> Self {
+        self.order = order;
+        self
+    }
+
+    pub fn depth_compare(&self, depth: DepthConvention) -> wgpu::CompareFunction {
+        if self.depth_test {
+            depth.compare(wgpu::CompareFunction::Less)
+        } else {
+            wgpu::CompareFunction::Always
+        }
+    }
+
+    pub fn writes_depth(&self, transparent: bool) -> bool {
+        self.depth_write.unwrap_or(!transparent)
+    }
+}
+
+// layers of a scene
+#[derive(Debug)]
+pub struct LayerRegistry {
+    layers: BTreeMap<LayerId, Layer>,
+}
+
+impl LayerRegistry {
+    pub fn new() -> Self {
+        let mut r = Self {
+            layers: BTreeMap::new(),
+        };
+        r.register(Layer::new(LAYER_NORMAL, "normal"));
+        r.register(Layer::new(LAYER_BACKGROUND, "background"));
+        r.register(Layer::new(LAYER_TRANSPARENT, "transparent"));
+        r.register(Layer::new(LAYER_ALPHA_TEST, "alpha_test"));
+        r.register(
+            Layer::new(LAYER_WORLD_UI, "world_ui")
+                .exact()
+                .depth_write(false)
+                .blend(wgpu::BlendState::ALPHA_BLENDING)
+                .sorter(Arc::new(DistanceSorterFactory(DepthOrder::BackToFront))),
+        );
+        r.register(
+            Layer::new(LAYER_OVERLAY_GIZMO, "overlay_gizmo")
+                .exact()
+                .depth_test(false)
+                .depth_write(false)
+                .sorter(Arc::new(ZOrderSorterFactory)),
+        );
+        r.register(Layer::new(LAYER_UI, "ui"));
+        r
+    }
+
+    // declare a layer or replace the settings of one
+    pub fn register(&mut self, layer: Layer) -> LayerId {
+        let id = layer.id;
+        self.layers.insert(id, layer);
+        id
+    }
+
+    // the registered layer covering `id`
+    pub fn find(&self, id: LayerId) -> Option<&Layer> {
+        if let Some(v) = self.layers.get(&id) {
+            return Some(v);
+        }
+        self.layers.range(id..).map(|v| v.1).find(|v| !v.exact)
+    }
+
+    pub fn by_name(&self, name: &str) -> Option<&Layer> {
+        self.layers.values().find(|v| v.name == name)
+    }
+
+    pub fn layers(&self) -> impl Iterator<Item = &Layer> {
+        self.layers.values()
+    }
+}
+
+impl Default for LayerRegistry {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// layers are matched by their covering registered layer
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct LayerMask {
+    // None for all layers
+    only: Option<BTreeSet<LayerId>>,
+    excluded: BTreeSet<LayerId>,
+}
+
+impl LayerMask {
+    pub const ALL: LayerMask = LayerMask {
+        only: None,
+        excluded: BTreeSet::new(),
+    };
+    pub const NONE: LayerMask = LayerMask {
+        only: Some(BTreeSet::new()),
+        excluded: BTreeSet::new(),
+    };
+
+    pub fn only(layers: &[LayerId]) -> Self {
+        layers.iter().fold(Self::NONE, |m, v| m.with(*v))
+    }
+
+    pub fn with(mut self, layer: LayerId) -> Self {
+        self.excluded.remove(&layer);
+        if let Some(only) = &mut self.only {
+            only.insert(layer);
+        }
+        self
+    }
+
+    pub fn without(mut self, layer: LayerId) -> Self {
+        if let Some(only) = &mut self.only {
+            only.remove(&layer);
+        }
+        self.excluded.insert(layer);
+        self
+    }
+
+    // `covering` maps a layer to the id of its registered layer
+    pub fn contains<F: Fn(LayerId) -> LayerId>(&self, layer: LayerId, covering: F) -> bool {
+        let id = covering(layer);
+        if self.excluded.iter().any(|v| covering(*v) == id) {
+            return false;
+        }
+        match &self.only {
+            Some(only) => only.iter().any(|v| covering(*v) == id),
+            None => true,
+        }
+    }
+}
+
+impl Default for LayerMask {
+    fn default() -> Self {
+        Self::ALL
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn exact_layers_keep_ui_range() {
+        let r = LayerRegistry::new();
+        let name = |id| r.find(id).map(|v| v.name.as_str());
+        assert_eq!(name(4_000), Some("normal"));
+        assert_eq!(name(30_001), Some("ui"));
+        assert_eq!(name(LAYER_WORLD_UI), Some("world_ui"));
+        assert_eq!(name(LAYER_WORLD_UI + 1), Some("ui"));
+        assert_eq!(name(LAYER_OVERLAY_GIZMO), Some("overlay_gizmo"));
+        assert_eq!(name(100_001), None);
+    }
+
+    #[test]
+    fn mask_matches_covering_layer() {
+        let r = LayerRegistry::new();
+        let covering = |id| r.find(id).map(|v| v.id).unwrap_or(id);
+        let mask = LayerMask::ALL.without(LAYER_BACKGROUND);
+        assert!(!mask.contains(9_000, covering));
+        assert!(mask.contains(LAYER_NORMAL, covering));
+
+        let mask = LayerMask::only(&[LAYER_NORMAL]);
+        assert!(mask.contains(100, covering));
+        assert!(!mask.contains(LAYER_TRANSPARENT, covering));
+        assert!(mask.with(LAYER_TRANSPARENT).contains(15_000, covering));
+    }
+}
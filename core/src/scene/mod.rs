@@ -1,8 +1,15 @@
 pub mod camera;
+pub mod event;
+mod layer;
+pub mod occlusion;
+mod query;
 mod scene;
+pub mod serialize;
 pub mod transform;
 
-pub use camera::Camera;
+pub use camera::{Camera, DepthConvention};
+pub use event::{SceneEvent, SceneEventReceiver};
+pub use layer::*;
 pub use scene::*;
 pub use transform::Transform;
 pub use transform::TransformBuilder;
@@ -0,0 +1,298 @@
+use std::sync::Mutex;
+
+use crate::types::{Mat4x4f, Size, Vec3f, Vec4f};
+
+use super::{query::world_bounds, DepthConvention, RenderObject};
+
+// farthest depth pyramid of a frame, `levels[0]` is the level read back from the gpu
+#[derive(Debug)]
+struct DepthPyramid {
+    // pixels of the depth texture
+    size: Size,
+    // mip level of the depth texture `levels[0]` was read from
+    base_level: u32,
+    levels: Vec<(Size, Vec<f32>)>,
+    vp: Mat4x4f,
+    // x, y, width, height in pixels
+    viewport: Vec4f,
+    depth: DepthConvention,
+}
+
+// same rule as shaders/hiz/reduce.wgsl, the last texel of an odd size takes
+// the extra row or column
+fn texel(v: u32, size: u32) -> u32 {
+    (v / 2).min((size / 2).max(1) - 1)
+}
+
+fn farthest(depth: DepthConvention, a: f32, b: f32) -> f32 {
+    if depth.is_reversed() {
+        a.min(b)
+    } else {
+        a.max(b)
+    }
+}
+
+fn reduce(size: Size, data: &[f32], depth: DepthConvention) -> (Size, Vec<f32>) {
+    let res_size = Size::new((size.x / 2).max(1), (size.y / 2).max(1));
+    let mut res = vec![depth.near(); (res_size.x * res_size.y) as usize];
+    for y in 0..size.y {
+        let ty = texel(y, size.y);
+        for x in 0..size.x {
+            let tx = texel(x, size.x);
+            let v = &mut res[(ty * res_size.x + tx) as usize];
+            *v = farthest(depth, *v, data[(y * size.x + x) as usize]);
+        }
+    }
+    (res_size, res)
+}
+
+// hierarchical depth of the main camera, read back one frame late. objects
+// behind the depth of the last frame are culled by the layer sorters
+#[derive(Debug, Default)]
+pub struct OcclusionCulling {
+    // camera of the frame being drawn, vp and viewport in pixels
+    view: Mutex<Option<(Mat4x4f, Option<Vec4f>)>>,
+    pyramid: Mutex<Option<DepthPyramid>>,
+}
+
+impl OcclusionCulling {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn set_view(&self, vp: Mat4x4f, viewport: Option<Vec4f>) {
+        *self.view.lock().unwrap() = Some((vp, viewport));
+    }
+
+    pub fn view(&self) -> Option<(Mat4x4f, Option<Vec4f>)> {
+        *self.view.lock().unwrap()
+    }
+
+    // `data` is mip `level` of the farthest depth of `size` pixels drawn with `vp`
+    // to `viewport`
+    pub fn update(
+        &self,
+        size: Size,
+        level: u32,
+        level_size: Size,
+        data: Vec<f32>,
+        (vp, viewport): (Mat4x4f, Vec4f),
+        depth: DepthConvention,
+    ) {
+        let mut levels = vec![(level_size, data)];
+        loop {
+            let (size, data) = levels.last().unwrap();
+            if size.x == 1 && size.y == 1 {
+                break;
+            }
+            let next = reduce(*size, data, depth);
+            levels.push(next);
+        }
+        *self.pyramid.lock().unwrap() = Some(DepthPyramid {
+            size,
+            base_level: level,
+            levels,
+            vp,
+            viewport,
+            depth,
+        });
+    }
+
+    // nothing is culled until the next read back
+    pub fn clear(&self) {
+        *self.view.lock().unwrap() = None;
+        *self.pyramid.lock().unwrap() = None;
+    }
+
+    pub fn is_occluded(&self, o: &RenderObject) -> bool {
+        // the bounds don't cover the instances
+        if o.geometry().instance().is_some() {
+            return false;
+        }
+        let (min, max) = match world_bounds(o) {
+            Some(v) => v,
+            None => return false,
+        };
+        let p = self.pyramid.lock().unwrap();
+        let p = match p.as_ref() {
+            Some(v) => v,
+            None => return false,
+        };
+
+        let mut lo = Vec3f::repeat(f32::MAX);
+        let mut hi = Vec3f::repeat(f32::MIN);
+        for i in 0..8 {
+            let c = Vec4f::new(
+                if i & 1 == 0 { min.x } else { max.x },
+                if i & 2 == 0 { min.y } else { max.y },
+                if i & 4 == 0 { min.z } else { max.z },
+                1f32,
+            );
+            let c = p.vp * c;
+            // crosses the near plane
+            if c.w <= f32::EPSILON {
+                return false;
+            }
+            let c = c.xyz() / c.w;
+            lo = lo.inf(&c);
+            hi = hi.sup(&c);
+        }
+        // nearest depth of the bounds, in front of the near plane is visible
+        let (nearest, before_near) = if p.depth.is_reversed() {
+            (hi.z, hi.z > 1f32)
+        } else {
+            (lo.z, lo.z < 0f32)
+        };
+        // outside of the camera, left to frustum culling
+        if hi.x < -1f32 || lo.x > 1f32 || hi.y < -1f32 || lo.y > 1f32 || before_near {
+            return false;
+        }
+
+        // ndc y points up, pixels down
+        let v = &p.viewport;
+        let x0 = v.x + (lo.x.max(-1f32) * 0.5 + 0.5) * v.z;
+        let x1 = v.x + (hi.x.min(1f32) * 0.5 + 0.5) * v.z;
+        let y0 = v.y + (0.5 - hi.y.min(1f32) * 0.5) * v.w;
+        let y1 = v.y + (0.5 - lo.y.max(-1f32) * 0.5) * v.w;
+
+        let (base_size, _) = &p.levels[0];
+        let to_base = |v: f32, size: u32, base_size: u32| {
+            let pixel = (v.floor().max(0f32) as u32).min(size - 1);
+            (pixel >> p.base_level).min(base_size - 1)
+        };
+        let mut rect = [
+            to_base(x0, p.size.x, base_size.x),
+            to_base(x1, p.size.x, base_size.x),
+            to_base(y0, p.size.y, base_size.y),
+            to_base(y1, p.size.y, base_size.y),
+        ];
+
+        // the coarsest test reads a few texels
+        let mut level = 0;
+        while (rect[1] - rect[0] > 3 || rect[3] - rect[2] > 3) && level + 1 < p.levels.len() {
+            let size = p.levels[level].0;
+            rect = [
+                texel(rect[0], size.x),
+                texel(rect[1], size.x),
+                texel(rect[2], size.y),
+                texel(rect[3], size.y),
+            ];
+            level += 1;
+        }
+
+        let (size, data) = &p.levels[level];
+        let mut depth = p.depth.near();
+        for y in rect[2]..=rect[3] {
+            for x in rect[0]..=rect[1] {
+                depth = farthest(p.depth, depth, data[(y * size.x + x) as usize]);
+            }
+        }
+        if p.depth.is_reversed() {
+            nearest < depth
+        } else {
+            nearest > depth
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use crate::{
+        context::RContext,
+        material::{basic::BasicMaterialFaceBuilder, input::InputResourceBuilder, MaterialBuilder},
+        mesh::{builder::MeshBuilder, StaticGeometry},
+        types::Color,
+    };
+
+    use super::*;
+
+    // a quad over the center of the ndc, `z` to `z + 0.05`
+    fn object(z: f32) -> RenderObject {
+        let mut builder = MeshBuilder::default();
+        builder.add_position_vertices3(&[
+            Vec3f::new(-0.5f32, -0.5f32, z),
+            Vec3f::new(0.5f32, -0.5f32, z),
+            Vec3f::new(0.5f32, 0.5f32, z + 0.05f32),
+            Vec3f::new(-0.5f32, 0.5f32, z + 0.05f32),
+        ]);
+        builder.add_indices32(&[0, 1, 2, 0, 2, 3]);
+        let geometry = StaticGeometry::new(Arc::new(builder.build().unwrap()));
+        let mut color = InputResourceBuilder::new();
+        color.add_constant(Color::new(1f32, 1f32, 1f32, 1f32));
+        let material = MaterialBuilder::default()
+            .face(
+                BasicMaterialFaceBuilder::new()
+                    .texture(color.build())
+                    .build(),
+            )
+            .build(&RContext::new());
+        RenderObject::new(Box::new(geometry), material).unwrap()
+    }
+
+    // 8x8 pixels drawn with an identity vp, ndc is world space
+    fn culling(data: Vec<f32>, depth: DepthConvention) -> OcclusionCulling {
+        let c = OcclusionCulling::new();
+        let size = Size::new(8, 8);
+        c.update(
+            size,
+            0,
+            size,
+            data,
+            (Mat4x4f::identity(), Vec4f::new(0f32, 0f32, 8f32, 8f32)),
+            depth,
+        );
+        c
+    }
+
+    #[test]
+    fn reduce_keeps_farthest() {
+        let data = [0.1f32, 0.2, 0.3, 0.4, 0.5, 0.6, 0.7, 0.8, 0.9];
+        // the last texel of an odd size takes the extra row and column
+        let (size, res) = reduce(Size::new(3, 3), &data, DepthConvention::Standard);
+        assert_eq!(size, Size::new(1, 1));
+        assert_eq!(res, vec![0.9f32]);
+        let (_, res) = reduce(Size::new(3, 3), &data, DepthConvention::Reversed);
+        assert_eq!(res, vec![0.1f32]);
+
+        let (size, res) = reduce(Size::new(4, 2), &[0.5f32; 8], DepthConvention::Standard);
+        assert_eq!(size, Size::new(2, 1));
+        assert_eq!(res, vec![0.5f32, 0.5]);
+    }
+
+    #[test]
+    fn behind_depth_is_occluded() {
+        let c = culling(vec![0.5f32; 64], DepthConvention::Standard);
+        assert!(c.is_occluded(&object(0.8f32)));
+        assert!(!c.is_occluded(&object(0.2f32)));
+
+        let c = culling(vec![0.5f32; 64], DepthConvention::Reversed);
+        assert!(c.is_occluded(&object(0.2f32)));
+        assert!(!c.is_occluded(&object(0.8f32)));
+    }
+
+    #[test]
+    fn hole_in_depth_is_visible() {
+        let mut data = vec![0.5f32; 64];
+        // a far pixel inside of the bounds
+        data[3 * 8 + 4] = 1f32;
+        let c = culling(data, DepthConvention::Standard);
+        assert!(!c.is_occluded(&object(0.8f32)));
+
+        // outside of the bounds
+        let mut data = vec![0.5f32; 64];
+        data[0] = 1f32;
+        let c = culling(data, DepthConvention::Standard);
+        assert!(c.is_occluded(&object(0.8f32)));
+    }
+
+    #[test]
+    fn nothing_culled_without_read_back() {
+        let c = OcclusionCulling::new();
+        assert!(!c.is_occluded(&object(0.8f32)));
+        let c = culling(vec![0.5f32; 64], DepthConvention::Standard);
+        c.clear();
+        assert!(!c.is_occluded(&object(0.8f32)));
+    }
+}
@@ -0,0 +1,464 @@
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+use crate::{
+    context::TagId,
+    material::MaterialId,
+    types::{BoundBox, Boundary, Vec3f, Vec4f},
+};
+
+use super::{LayerId, ObjectId, ObjectWrapper, RenderObject, SceneStorage};
+
+const CELL_SIZE: f32 = 8f32;
+// objects covering more cells are kept in `large`
+const MAX_OBJECT_CELLS: i64 = 64;
+// larger queries test every object with bounds
+const MAX_QUERY_CELLS: i64 = 4096;
+
+type Cell = (i32, i32, i32);
+
+#[derive(Debug, PartialEq)]
+pub(crate) struct IndexEntry {
+    name: String,
+    tags: Vec<TagId>,
+    layer: LayerId,
+    material: MaterialId,
+    bounds: Option<(Vec3f, Vec3f)>,
+}
+
+impl IndexEntry {
+    fn new(w: &ObjectWrapper) -> Self {
+        let o = w.o();
+        let mut tags: Vec<_> = o.tags().collect();
+        tags.sort();
+        Self {
+            name: o.name().to_owned(),
+            tags,
+            layer: w.layer,
+            material: o.material_arc().id(),
+            bounds: world_bounds(o),
+        }
+    }
+}
+
+// world space aabb of the geometry boundary
+pub(crate) fn world_bounds(o: &RenderObject) -> Option<(Vec3f, Vec3f)> {
+    let aabb = match o.geometry().boundary() {
+        Boundary::AABB(v) if !v.is_empty() => v,
+        _ => return None,
+    };
+    let mat = o.transform().mat();
+    let (a, b) = (aabb.min(), aabb.max());
+    let mut res = BoundBox::default();
+    for i in 0..8 {
+        let p = Vec3f::new(
+            if i & 1 == 0 { a.x } else { b.x },
+            if i & 2 == 0 { a.y } else { b.y },
+            if i & 4 == 0 { a.z } else { b.z },
+        );
+        let p = (mat * Vec4f::new(p.x, p.y, p.z, 1f32)).xyz();
+        res = &res + &p;
+    }
+    Some((*res.min(), *res.max()))
+}
+
+fn overlap(a: &(Vec3f, Vec3f), min: &Vec3f, max: &Vec3f) -> bool {
+    a.0.x <= max.x
+        && a.1.x >= min.x
+        && a.0.y <= max.y
+        && a.1.y >= min.y
+        && a.0.z <= max.z
+        && a.1.z >= min.z
+}
+
+fn cell_range(min: &Vec3f, max: &Vec3f) -> (Cell, Cell, i64) {
+    let c = |v: f32| (v / CELL_SIZE).floor() as i32;
+    let beg = (c(min.x), c(min.y), c(min.z));
+    let end = (c(max.x), c(max.y), c(max.z));
+    let count = (end.0 as i64 - beg.0 as i64 + 1)
+        * (end.1 as i64 - beg.1 as i64 + 1)
+        * (end.2 as i64 - beg.2 as i64 + 1);
+    (beg, end, count)
+}
+
+fn cells(beg: Cell, end: Cell) -> impl Iterator<Item = Cell> {
+    (beg.0..=end.0).flat_map(move |x| {
+        (beg.1..=end.1).flat_map(move |y| (beg.2..=end.2).map(move |z| (x, y, z)))
+    })
+}
+
+fn sorted(ids: Option<&HashSet<ObjectId>>) -> Vec<ObjectId> {
+    let mut res: Vec<_> = ids.map(|v| v.iter().cloned().collect()).unwrap_or_default();
+    res.sort();
+    res
+}
+
+// lookup tables of the scene objects, kept in sync by `Scene`
+#[derive(Debug, Default)]
+pub(crate) struct SceneIndex {
+    entries: HashMap<ObjectId, IndexEntry>,
+    names: HashMap<String, HashSet<ObjectId>>,
+    tags: HashMap<TagId, HashSet<ObjectId>>,
+    layers: BTreeMap<LayerId, HashSet<ObjectId>>,
+    materials: HashMap<MaterialId, HashSet<ObjectId>>,
+
+    // uniform grid over world bounds
+    grid: HashMap<Cell, HashSet<ObjectId>>,
+    large: HashSet<ObjectId>,
+}
+
+impl SceneIndex {
+    // re-index the object if anything indexed changed
+    pub fn update(&mut self, id: ObjectId, w: &ObjectWrapper) {
+        self.update_entry(id, IndexEntry::new(w));
+    }
+
+    // entries of every stored object, taken without holding the index
+    pub fn snapshot(storage: &SceneStorage) -> Vec<(ObjectId, IndexEntry)> {
+        storage
+            .iter()
+            .map(|v| (*v.key(), IndexEntry::new(v.value())))
+            .collect()
+    }
+
+    // re-index from a snapshot, objects missing from it are dropped
+    pub fn sync(&mut self, entries: Vec<(ObjectId, IndexEntry)>) {
+        let ids: HashSet<_> = entries.iter().map(|v| v.0).collect();
+        let removed: Vec<_> = self
+            .entries
+            .keys()
+            .filter(|id| !ids.contains(id))
+            .cloned()
+            .collect();
+        for id in removed {
+            self.remove(id);
+        }
+        for (id, entry) in entries {
+            self.update_entry(id, entry);
+        }
+    }
+
+    fn update_entry(&mut self, id: ObjectId, entry: IndexEntry) {
+        if self.entries.get(&id) == Some(&entry) {
+            return;
+        }
+        self.remove(id);
+
+        self.names.entry(entry.name.clone()).or_default().insert(id);
+        for tag in &entry.tags {
+            self.tags.entry(*tag).or_default().insert(id);
+        }
+        self.layers.entry(entry.layer).or_default().insert(id);
+        self.materials.entry(entry.material).or_default().insert(id);
+        if let Some((min, max)) = &entry.bounds {
+            let (beg, end, count) = cell_range(min, max);
+            if count > MAX_OBJECT_CELLS {
+                self.large.insert(id);
+            } else {
+                for cell in cells(beg, end) {
+                    self.grid.entry(cell).or_default().insert(id);
+                }
+            }
+        }
+        self.entries.insert(id, entry);
+    }
+
+    pub fn remove(&mut self, id: ObjectId) {
+        let entry = match self.entries.remove(&id) {
+            Some(v) => v,
+            None => return,
+        };
+        fn take<K: std::hash::Hash + Eq>(
+            map: &mut HashMap<K, HashSet<ObjectId>>,
+            key: K,
+            id: ObjectId,
+        ) {
+            if let Some(set) = map.get_mut(&key) {
+                set.remove(&id);
+                if set.is_empty() {
+                    map.remove(&key);
+                }
+            }
+        }
+        take(&mut self.names, entry.name, id);
+        for tag in entry.tags {
+            take(&mut self.tags, tag, id);
+        }
+        if let Some(set) = self.layers.get_mut(&entry.layer) {
+            set.remove(&id);
+            if set.is_empty() {
+                self.layers.remove(&entry.layer);
+            }
+        }
+        take(&mut self.materials, entry.material, id);
+        if let Some((min, max)) = &entry.bounds {
+            if !self.large.remove(&id) {
+                let (beg, end, _) = cell_range(min, max);
+                for cell in cells(beg, end) {
+                    take(&mut self.grid, cell, id);
+                }
+            }
+        }
+    }
+
+    pub fn clear(&mut self) {
+        *self = Self::default();
+    }
+
+    pub fn by_name(&self, name: &str) -> Vec<ObjectId> {
+        sorted(self.names.get(name))
+    }
+
+    pub fn by_tag(&self, tag: TagId) -> Vec<ObjectId> {
+        sorted(self.tags.get(&tag))
+    }
+
+    pub fn by_layer(&self, layer: LayerId) -> Vec<ObjectId> {
+        sorted(self.layers.get(&layer))
+    }
+
+    pub fn by_material(&self, material: MaterialId) -> Vec<ObjectId> {
+        sorted(self.materials.get(&material))
+    }
+
+    // union of the bounds of `ids`, every object if None
+    pub fn bounds(&self, ids: Option<&[ObjectId]>) -> Option<(Vec3f, Vec3f)> {
+        let bounds: Vec<_> = match ids {
+            Some(ids) => ids
+                .iter()
+                .filter_map(|id| self.entries.get(id)?.bounds)
+                .collect(),
+            None => self.entries.values().filter_map(|v| v.bounds).collect(),
+        };
+        bounds
+            .into_iter()
+            .reduce(|a, b| (a.0.inf(&b.0), a.1.sup(&b.1)))
+    }
+
+    pub fn in_bounds(&self, min: &Vec3f, max: &Vec3f) -> Vec<ObjectId> {
+        let (beg, end, count) = cell_range(min, max);
+        let mut candidates = HashSet::new();
+        if count > MAX_QUERY_CELLS {
+            candidates.extend(self.entries.keys().cloned());
+        } else {
+            for cell in cells(beg, end) {
+                if let Some(set) = self.grid.get(&cell) {
+                    candidates.extend(set.iter().cloned());
+                }
+            }
+            candidates.extend(self.large.iter().cloned());
+        }
+        let mut res: Vec<_> = candidates
+            .into_iter()
+            .filter(|id| {
+                self.entries
+                    .get(id)
+                    .and_then(|v| v.bounds.as_ref())
+                    .map(|b| overlap(b, min, max))
+                    .unwrap_or_default()
+            })
+            .collect();
+        res.sort();
+        res
+    }
+
+    pub fn in_sphere(&self, center: &Vec3f, radius: f32) -> Vec<ObjectId> {
+        let r = Vec3f::new(radius, radius, radius);
+        let mut res = self.in_bounds(&(center - r), &(center + r));
+        res.retain(|id| {
+            let (min, max) = self.entries[id].bounds.unwrap();
+            let closest = center.sup(&min).inf(&max);
+            (closest - center).norm_squared() <= radius * radius
+        });
+        res
+    }
+
+    // objects whose bounds the ray hits, nearest first
+    pub fn ray(&self, origin: &Vec3f, dir: &Vec3f, max_distance: f32) -> Vec<(ObjectId, f32)> {
+        let dir = dir.normalize();
+        let mut res: Vec<_> = self
+            .entries
+            .iter()
+            .filter_map(|(id, v)| {
+                let (min, max) = v.bounds.as_ref()?;
+                let mut near = 0f32;
+                let mut far = max_distance;
+                for i in 0..3 {
+                    if dir[i].abs() < f32::EPSILON {
+                        if origin[i] < min[i] || origin[i] > max[i] {
+                            return None;
+                        }
+                        continue;
+                    }
+                    let t0 = (min[i] - origin[i]) / dir[i];
+                    let t1 = (max[i] - origin[i]) / dir[i];
+                    near = near.max(t0.min(t1));
+                    far = far.min(t0.max(t1));
+                    if near > far {
+                        return None;
+                    }
+                }
+                Some((*id, near))
+            })
+            .collect();
+        res.sort_by(|a, b| a.1.total_cmp(&b.1).then(a.0.cmp(&b.0)));
+        res
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use crate::{
+        context::RContext,
+        material::{
+            basic::BasicMaterialFaceBuilder, input::InputResourceBuilder, MaterialArc,
+            MaterialBuilder,
+        },
+        mesh::{builder::MeshBuilder, StaticGeometry},
+        scene::{Scene, Transform, LAYER_NORMAL, LAYER_TRANSPARENT},
+        types::{Color, Quaternion},
+    };
+
+    use super::*;
+
+    fn material(scene: &Scene) -> MaterialArc {
+        let mut color = InputResourceBuilder::new();
+        color.add_constant(Color::new(1f32, 1f32, 1f32, 1f32));
+        MaterialBuilder::default()
+            .face(
+                BasicMaterialFaceBuilder::new()
+                    .texture(color.build())
+                    .build(),
+            )
+            .build(&scene.context())
+    }
+
+    // bounds from `pos` to `pos + size`
+    fn object(material: &MaterialArc, name: &str, pos: Vec3f, size: f32) -> RenderObject {
+        let mut builder = MeshBuilder::default();
+        builder.add_position_vertices3(&[Vec3f::zeros(), Vec3f::new(1f32, 1f32, 1f32), Vec3f::x()]);
+        builder.add_indices32(&[0, 1, 2]);
+        let geometry = StaticGeometry::new(Arc::new(builder.build().unwrap()));
+        let mut object = RenderObject::new(Box::new(geometry), material.clone()).unwrap();
+        object.set_name(name);
+        object.set_transform(Transform::new(
+            pos,
+            Quaternion::identity(),
+            Vec3f::new(size, size, size),
+        ));
+        object
+    }
+
+    #[test]
+    fn lookups() {
+        let scene = Scene::new(RContext::new());
+        let m1 = material(&scene);
+        let m2 = material(&scene);
+        let t1 = scene.context().new_tag("t1");
+        let t2 = scene.context().new_tag("t2");
+
+        let a = scene.add_with_tags(object(&m1, "a", Vec3f::zeros(), 1f32), LAYER_NORMAL, &[t1]);
+        let b = scene.add_with_tags(
+            object(&m2, "b", Vec3f::zeros(), 1f32),
+            LAYER_TRANSPARENT,
+            &[t1, t2],
+        );
+        let c = scene.add_with(object(&m1, "a", Vec3f::zeros(), 1f32), LAYER_NORMAL);
+
+        assert_eq!(scene.objects_by_name("a"), vec![a, c]);
+        assert_eq!(scene.objects_with_tag(t1), vec![a, b]);
+        assert_eq!(scene.objects_with_tag(t2), vec![b]);
+        assert_eq!(scene.layer_objects(LAYER_NORMAL), vec![a, c]);
+        assert_eq!(scene.layer_objects(LAYER_TRANSPARENT), vec![b]);
+        assert_eq!(scene.objects_with_material(m1.id()), vec![a, c]);
+        assert_eq!(scene.objects_with_material(m2.id()), vec![b]);
+        assert!(scene.objects_by_name("d").is_empty());
+
+        scene.set_object_name(c, "c");
+        scene.remove_object_tag(b, t1);
+        scene.set_object_material(a, m2.clone()).unwrap();
+        assert_eq!(scene.objects_by_name("a"), vec![a]);
+        assert_eq!(scene.objects_by_name("c"), vec![c]);
+        assert_eq!(scene.objects_with_tag(t1), vec![a]);
+        assert_eq!(scene.objects_with_material(m1.id()), vec![c]);
+        assert_eq!(scene.objects_with_material(m2.id()), vec![a, b]);
+
+        scene.remove(b);
+        assert!(scene.objects_with_tag(t2).is_empty());
+        assert!(scene.layer_objects(LAYER_TRANSPARENT).is_empty());
+        assert_eq!(scene.objects_with_material(m2.id()), vec![a]);
+    }
+
+    #[test]
+    fn spatial() {
+        let scene = Scene::new(RContext::new());
+        let m = material(&scene);
+        let a = scene.add(object(&m, "a", Vec3f::zeros(), 1f32));
+        let b = scene.add(object(&m, "b", Vec3f::new(20f32, 0f32, 0f32), 1f32));
+        // covers more than MAX_OBJECT_CELLS cells
+        let large = scene.add(object(&m, "large", Vec3f::new(100f32, 0f32, 0f32), 100f32));
+
+        let v = |x: f32, y: f32, z: f32| Vec3f::new(x, y, z);
+        assert_eq!(
+            scene.objects_in_bounds(&v(-1f32, -1f32, -1f32), &v(2f32, 2f32, 2f32)),
+            vec![a]
+        );
+        assert_eq!(
+            scene.objects_in_bounds(&v(150f32, 50f32, 50f32), &v(151f32, 51f32, 51f32)),
+            vec![large]
+        );
+        // more than MAX_QUERY_CELLS cells
+        assert_eq!(
+            scene.objects_in_bounds(
+                &v(-1000f32, -1000f32, -1000f32),
+                &v(1000f32, 1000f32, 1000f32)
+            ),
+            vec![a, b, large]
+        );
+
+        // the box corner is sqrt(3) away
+        assert_eq!(
+            scene.objects_in_sphere(&v(2f32, 2f32, 2f32), 1.8f32),
+            vec![a]
+        );
+        assert!(scene
+            .objects_in_sphere(&v(2f32, 2f32, 2f32), 1.7f32)
+            .is_empty());
+
+        let hits = scene.raycast(&v(-5f32, 0.5f32, 0.5f32), &Vec3f::x(), 1000f32);
+        let ids: Vec<_> = hits.iter().map(|v| v.0).collect();
+        assert_eq!(ids, vec![a, b, large]);
+        assert!((hits[0].1 - 5f32).abs() < 1e-4);
+        assert!((hits[2].1 - 105f32).abs() < 1e-4);
+        assert_eq!(
+            scene.raycast(&v(-5f32, 0.5f32, 0.5f32), &Vec3f::x(), 10f32),
+            vec![(a, 5f32)]
+        );
+        assert!(scene
+            .raycast(&v(-5f32, -5f32, 0.5f32), &Vec3f::x(), 1000f32)
+            .is_empty());
+
+        assert_eq!(
+            scene.bounds(),
+            Some((v(0f32, 0f32, 0f32), v(200f32, 100f32, 100f32)))
+        );
+        assert_eq!(
+            scene.bounds_of(&[a, b]),
+            Some((v(0f32, 0f32, 0f32), v(21f32, 1f32, 1f32)))
+        );
+
+        // moved objects leave their old cells
+        scene.modify(a, |w| {
+            w.object
+                .set_transform(Transform::from_translate(v(40f32, 0f32, 0f32)))
+        });
+        assert!(scene
+            .objects_in_bounds(&v(-1f32, -1f32, -1f32), &v(2f32, 2f32, 2f32))
+            .is_empty());
+        assert_eq!(
+            scene.objects_in_bounds(&v(39f32, -1f32, -1f32), &v(42f32, 2f32, 2f32)),
+            vec![a]
+        );
+    }
+}
@@ -1,46 +1,159 @@
-use dashmap::DashMap;
+use dashmap::{iter::Iter, mapref::one::Ref, DashMap};
 
 use crate::{
     context::{RContextRef, TagId},
-    material::MaterialArc,
+    graph::sg::{NodeAttachment, NodeId, SceneGraph},
+    material::{MaterialArc, MaterialId},
     mesh::Geometry,
-    types::{Size, Vec3f, Vec4f},
+    render::view::RenderTexture,
+    types::{Color, Size, Vec3f, Vec4f},
 };
 use std::{
     any::{Any, TypeId},
     collections::{BTreeMap, HashMap, HashSet},
     fmt::Debug,
-    sync::{atomic::AtomicBool, atomic::Ordering, Arc, Mutex},
+    ops::{Bound, RangeBounds},
+    sync::{atomic::AtomicBool, atomic::Ordering, Arc, Mutex, MutexGuard, RwLock},
 };
 
 use super::{
-    sort::{DistanceSorter, MaterialSorter, Sorter, UISceneSorter},
-    Camera,
+    event::{SceneEvent, SceneEventDispatcher, SceneEventReceiver},
+    layer::{
+        Layer, LayerId, LayerMask, LayerRegistry, LAYER_ALPHA_TEST, LAYER_NORMAL,
+        LAYER_TRANSPARENT, LAYER_UI,
+    },
+    occlusion::OcclusionCulling,
+    query::SceneIndex,
+    sort::{
+        DepthOrder, DistanceSorterFactory, MaterialSorterFactory, Sorter, SorterFactory,
+        UISorterFactory,
+    },
+    Camera, DepthConvention, Transform,
 };
 
-pub type LayerId = u32;
 pub type ObjectId = u64;
+pub type CameraId = u32;
 
-pub const LAYER_NORMAL: LayerId = 4_000;
-pub const LAYER_BACKGROUND: LayerId = 10_000;
-pub const LAYER_TRANSPARENT: LayerId = 20_000;
-pub const LAYER_ALPHA_TEST: LayerId = 30_000;
-pub const LAYER_UI: LayerId = 100_000;
 pub const UNKNOWN_OBJECT: ObjectId = 0;
 
-pub fn layer_str(id: LayerId) -> &'static str {
-    if id <= LAYER_NORMAL {
-        "normal"
-    } else if id <= LAYER_BACKGROUND {
-        "background"
-    } else if id <= LAYER_TRANSPARENT {
-        "transparent"
-    } else if id <= LAYER_ALPHA_TEST {
-        "alpha_test"
-    } else if id <= LAYER_UI {
-        "ui"
-    } else {
-        "invalid"
+#[derive(Debug, Clone, Default)]
+pub enum CameraTarget {
+    #[default]
+    Surface,
+    // the viewport is relative to the texture
+    Texture(Arc<RenderTexture>),
+}
+
+impl CameraTarget {
+    pub fn texture(&self) -> Option<&Arc<RenderTexture>> {
+        match self {
+            CameraTarget::Surface => None,
+            CameraTarget::Texture(t) => Some(t),
+        }
+    }
+}
+
+// how objects of LAYER_TRANSPARENT are blended
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TransparencyMode {
+    // blended back to front in the order of the layer sorter
+    #[default]
+    Sorted,
+    // weighted blended order independent transparency, needs no sorting and
+    // handles intersecting meshes, colors are approximated
+    WeightedBlended,
+}
+
+#[derive(Debug, Clone)]
+pub struct CameraSettings {
+    // normalized x, y, width, height of the render target, origin at top left
+    pub viewport: Vec4f,
+    // None keeps what the previous cameras drew
+    pub clear_color: Option<Color>,
+    pub clear_depth: bool,
+    // cameras with lower priority render first
+    pub priority: i32,
+    pub layer_mask: LayerMask,
+    pub active: bool,
+    // cameras rendering to textures run before the cameras on the surface
+    pub target: CameraTarget,
+}
+
+impl Default for CameraSettings {
+    fn default() -> Self {
+        Self {
+            viewport: Vec4f::new(0f32, 0f32, 1f32, 1f32),
+            clear_color: None,
+            clear_depth: true,
+            priority: 0,
+            layer_mask: LayerMask::ALL,
+            active: true,
+            target: CameraTarget::Surface,
+        }
+    }
+}
+
+impl CameraSettings {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn viewport(mut self, viewport: Vec4f) -> Self {
+        self.viewport = viewport;
+        self
+    }
+
+    pub fn clear_color(mut self, color: Option<Color>) -> Self {
+        self.clear_color = color;
+        self
+    }
+
+    pub fn clear_depth(mut self, clear: bool) -> Self {
+        self.clear_depth = clear;
+        self
+    }
+
+    pub fn priority(mut self, priority: i32) -> Self {
+        self.priority = priority;
+        self
+    }
+
+    pub fn layer_mask(mut self, mask: LayerMask) -> Self {
+        self.layer_mask = mask;
+        self
+    }
+
+    pub fn include_layer(mut self, layer: LayerId) -> Self {
+        self.layer_mask = self.layer_mask.with(layer);
+        self
+    }
+
+    pub fn exclude_layer(mut self, layer: LayerId) -> Self {
+        self.layer_mask = self.layer_mask.without(layer);
+        self
+    }
+
+    pub fn active(mut self, active: bool) -> Self {
+        self.active = active;
+        self
+    }
+
+    pub fn target(mut self, target: CameraTarget) -> Self {
+        self.target = target;
+        self
+    }
+
+    fn aspect(&self, view_size: &Size) -> f32 {
+        let size = self
+            .target
+            .texture()
+            .map(|v| v.size())
+            .unwrap_or(*view_size);
+        (size.x as f32 * self.viewport.z) / (size.y as f32 * self.viewport.w)
+    }
+
+    pub fn is_fullscreen(&self) -> bool {
+        self.viewport == Vec4f::new(0f32, 0f32, 1f32, 1f32)
     }
 }
 
@@ -61,23 +174,104 @@ impl ObjectWrapper {
 
 pub type SceneStorage = Arc<DashMap<u64, ObjectWrapper>>;
 
+// read only objects of a scene, see `Scene::read_container`
+#[derive(Clone)]
+pub struct SceneObjects {
+    storage: SceneStorage,
+}
+
+impl SceneObjects {
+    pub fn get(&self, id: &ObjectId) -> Option<Ref<'_, u64, ObjectWrapper>> {
+        self.storage.get(id)
+    }
+
+    pub fn iter(&self) -> Iter<'_, u64, ObjectWrapper> {
+        self.storage.iter()
+    }
+
+    pub fn contains_key(&self, id: &ObjectId) -> bool {
+        self.storage.contains_key(id)
+    }
+
+    pub fn len(&self) -> usize {
+        self.storage.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.storage.is_empty()
+    }
+}
+
+#[derive(Default)]
+pub struct SceneNode {
+    objects: Vec<ObjectId>,
+    attachments: Vec<Arc<dyn NodeAttachment>>,
+}
+
+impl SceneNode {
+    pub fn objects(&self) -> &[ObjectId] {
+        &self.objects
+    }
+}
+
+#[derive(Debug)]
+struct SceneCameraEntry {
+    id: CameraId,
+    camera: Arc<Camera>,
+    settings: CameraSettings,
+}
+
 #[derive(Debug, Default)]
 struct SceneCamera {
-    cameras: Vec<Arc<Camera>>,
+    // the first camera is the main camera
+    cameras: Vec<SceneCameraEntry>,
     ui_camera: Option<Arc<Camera>>,
+    last_id: CameraId,
 }
 
+impl SceneCamera {
+    fn push(&mut self, camera: Arc<Camera>, settings: CameraSettings) -> CameraId {
+        let id = self.last_id;
+        self.last_id += 1;
+        self.cameras.push(SceneCameraEntry {
+            id,
+            camera,
+            settings,
+        });
+        id
+    }
+
+    fn get_mut(&mut self, id: CameraId) -> Option<&mut SceneCameraEntry> {
+        self.cameras.iter_mut().find(|v| v.id == id)
+    }
+}
+
+type SorterRange = ((Bound<LayerId>, Bound<LayerId>), Arc<dyn SorterFactory>);
+
 pub struct Scene {
     context: RContextRef,
 
     storage: SceneStorage,
+    // lock after the storage entry when both are held
+    index: Mutex<SceneIndex>,
+    // objects may have been changed through `get_container`
+    index_dirty: AtomicBool,
 
     // reader layer -> objects
     queue: Mutex<BTreeMap<LayerId, Arc<Mutex<dyn Sorter>>>>,
+    // the last registered range containing a layer creates its sorter
+    sorters: Mutex<Vec<SorterRange>>,
+    layer_registry: RwLock<LayerRegistry>,
 
     cameras: Mutex<SceneCamera>,
 
+    graph: Mutex<SceneGraph<SceneNode>>,
+    object_nodes: DashMap<ObjectId, NodeId>,
+
     rebuild: AtomicBool,
+    events: SceneEventDispatcher,
+    transparency: Mutex<TransparencyMode>,
+    occlusion: Mutex<Option<Arc<OcclusionCulling>>>,
 
     attach_resources: Mutex<HashMap<TypeId, Arc<dyn Any + 'static + Send + Sync>>>,
 }
@@ -97,12 +291,22 @@ impl Scene {
         let mut s = Self {
             context,
             storage: SceneStorage::new(DashMap::new()),
+            index: Mutex::new(SceneIndex::default()),
+            index_dirty: AtomicBool::new(false),
 
             queue: Mutex::new(BTreeMap::new()),
+            sorters: Mutex::new(vec![]),
+            layer_registry: RwLock::new(LayerRegistry::new()),
 
             cameras: Mutex::new(SceneCamera::default()),
 
+            graph: Mutex::new(SceneGraph::new()),
+            object_nodes: DashMap::new(),
+
             rebuild: AtomicBool::new(true),
+            events: SceneEventDispatcher::default(),
+            transparency: Mutex::new(TransparencyMode::default()),
+            occlusion: Mutex::new(None),
 
             attach_resources: Mutex::new(HashMap::new()),
         };
@@ -110,6 +314,54 @@ impl Scene {
         s
     }
 
+    // objects of the layers in range are sorted by sorters of `factory`,
+    // layers that already have objects are sorted again
+    pub fn set_sorter<R: RangeBounds<LayerId>>(&self, layers: R, factory: Arc<dyn SorterFactory>) {
+        let range = (layers.start_bound().cloned(), layers.end_bound().cloned());
+        self.sorters.lock().unwrap().push((range, factory.clone()));
+
+        let camera = self.main_camera_ref();
+        let occlusion = self.occlusion_culling();
+        let mut q = self.queue.lock().unwrap();
+        for (layer, sorter) in q.iter_mut() {
+            if !range.contains(layer) {
+                continue;
+            }
+            let mut s = factory.create(self.storage.clone(), camera.clone());
+            s.set_occlusion(occlusion.clone());
+            for id in self.layer_objects(*layer) {
+                s.add(id);
+            }
+            *sorter = Arc::new(Mutex::new(s));
+        }
+    }
+
+    pub fn set_layer_sorter(&self, layer: LayerId, factory: Arc<dyn SorterFactory>) {
+        self.set_sorter(layer..=layer, factory);
+    }
+
+    // `set_sorter` first, then the sorter of the registered layer, then the default
+    fn sorter_factory(&self, layer: LayerId) -> Arc<dyn SorterFactory> {
+        let sorters = self.sorters.lock().unwrap();
+        if let Some((_, f)) = sorters
+            .iter()
+            .rev()
+            .find(|(range, _)| range.contains(&layer))
+        {
+            return f.clone();
+        }
+        if let Some(f) = self.find_layer(layer).and_then(|v| v.sorter) {
+            return f;
+        }
+        if layer >= LAYER_UI {
+            Arc::new(UISorterFactory)
+        } else if (LAYER_TRANSPARENT..LAYER_ALPHA_TEST).contains(&layer) {
+            Arc::new(DistanceSorterFactory(DepthOrder::BackToFront))
+        } else {
+            Arc::new(MaterialSorterFactory(DepthOrder::FrontToBack))
+        }
+    }
+
     fn add_default_ui_camera(&mut self) {
         let ui_camera = Arc::new(Camera::new());
         ui_camera.make_orthographic(Vec4f::new(0f32, 0f32, 1f32, 1f32), 0.1f32, 10f32);
@@ -133,18 +385,68 @@ impl Scene {
         self.rebuild.store(false, Ordering::SeqCst);
     }
 
+    // the render graph is rebuilt with the new mode
+    pub fn set_transparency_mode(&self, mode: TransparencyMode) {
+        let mut t = self.transparency.lock().unwrap();
+        if *t == mode {
+            return;
+        }
+        *t = mode;
+        drop(t);
+        self.set_rebuild_flag();
+    }
+
+    pub fn transparency_mode(&self) -> TransparencyMode {
+        *self.transparency.lock().unwrap()
+    }
+
+    // sorters skip objects hidden by the depth of the last frame. the sorters
+    // are shared by the cameras and only the depth of the main camera is read
+    // back, culling is off while more than one camera is active
+    pub fn set_occlusion_culling(&self, enable: bool) {
+        let mut o = self.occlusion.lock().unwrap();
+        if o.is_some() == enable {
+            return;
+        }
+        *o = enable.then(|| Arc::new(OcclusionCulling::new()));
+        let occlusion = o.clone();
+        drop(o);
+
+        let q = self.queue.lock().unwrap();
+        for sorter in q.values() {
+            sorter.lock().unwrap().set_occlusion(occlusion.clone());
+        }
+        drop(q);
+        self.set_rebuild_flag();
+    }
+
+    pub fn occlusion_culling(&self) -> Option<Arc<OcclusionCulling>> {
+        self.occlusion.lock().unwrap().clone()
+    }
+
+    // enabled and a single camera is active
+    pub fn occlusion_culling_active(&self) -> bool {
+        self.occlusion_culling().is_some() && self.active_cameras().len() == 1
+    }
+
     pub fn context(&self) -> RContextRef {
         self.context.clone()
     }
 
+    // object changes made after this call are queued on the receiver
+    pub fn subscribe(&self) -> SceneEventReceiver {
+        self.events.subscribe()
+    }
+
     pub fn set_main_camera(&self, camera: Arc<Camera>) {
         let mut c = self.cameras.lock().unwrap();
 
         if c.cameras.is_empty() {
-            c.cameras.push(camera.clone());
+            c.push(camera.clone(), CameraSettings::default());
         } else {
-            c.cameras[0] = camera.clone();
+            c.cameras[0].camera = camera.clone();
         }
+        drop(c);
 
         let q = self.queue.lock().unwrap();
         for sorter in q.values() {
@@ -154,7 +456,117 @@ impl Scene {
 
     pub fn main_camera_ref(&self) -> Option<Arc<Camera>> {
         let c = self.cameras.lock().unwrap();
-        c.cameras.get(0).cloned()
+        c.cameras.first().map(|v| v.camera.clone())
+    }
+
+    // the default depth target is cleared with the main camera's convention,
+    // every view draws with the convention of its own camera
+    pub fn depth_convention(&self) -> DepthConvention {
+        self.main_camera_ref()
+            .map(|v| v.depth_convention())
+            .unwrap_or_default()
+    }
+
+    pub fn main_camera_id(&self) -> Option<CameraId> {
+        let c = self.cameras.lock().unwrap();
+        c.cameras.first().map(|v| v.id)
+    }
+
+    pub fn add_camera(&self, camera: Arc<Camera>) -> CameraId {
+        self.add_camera_with(camera, CameraSettings::default())
+    }
+
+    pub fn add_camera_with(&self, camera: Arc<Camera>, settings: CameraSettings) -> CameraId {
+        if let Some(t) = settings.target.texture() {
+            camera.set_aspect(settings.aspect(&t.size()));
+        }
+        let mut c = self.cameras.lock().unwrap();
+        let empty = c.cameras.is_empty();
+        let id = c.push(camera.clone(), settings);
+        drop(c);
+
+        if empty {
+            let q = self.queue.lock().unwrap();
+            for sorter in q.values() {
+                sorter.lock().unwrap().set_camera(camera.clone());
+            }
+        }
+        self.set_rebuild_flag();
+        id
+    }
+
+    pub fn remove_camera(&self, id: CameraId) -> Option<Arc<Camera>> {
+        let mut c = self.cameras.lock().unwrap();
+        let index = c.cameras.iter().position(|v| v.id == id)?;
+        let entry = c.cameras.remove(index);
+        let main = c.cameras.first().map(|v| v.camera.clone());
+        drop(c);
+
+        if index == 0 {
+            if let Some(main) = main {
+                let q = self.queue.lock().unwrap();
+                for sorter in q.values() {
+                    sorter.lock().unwrap().set_camera(main.clone());
+                }
+            }
+        }
+        self.set_rebuild_flag();
+        Some(entry.camera)
+    }
+
+    pub fn camera_ref(&self, id: CameraId) -> Option<Arc<Camera>> {
+        let c = self.cameras.lock().unwrap();
+        c.cameras
+            .iter()
+            .find(|v| v.id == id)
+            .map(|v| v.camera.clone())
+    }
+
+    pub fn camera_settings(&self, id: CameraId) -> Option<CameraSettings> {
+        let c = self.cameras.lock().unwrap();
+        c.cameras
+            .iter()
+            .find(|v| v.id == id)
+            .map(|v| v.settings.clone())
+    }
+
+    // the render graph is rebuilt with the new settings
+    pub fn set_camera_settings(&self, id: CameraId, settings: CameraSettings) -> bool {
+        let mut c = self.cameras.lock().unwrap();
+        match c.get_mut(id) {
+            Some(entry) => entry.settings = settings,
+            None => return false,
+        }
+        drop(c);
+        self.set_rebuild_flag();
+        true
+    }
+
+    // every camera with its settings, the main camera first
+    pub fn camera_entries(&self) -> Vec<(CameraId, Arc<Camera>, CameraSettings)> {
+        let c = self.cameras.lock().unwrap();
+        c.cameras
+            .iter()
+            .map(|v| (v.id, v.camera.clone(), v.settings.clone()))
+            .collect()
+    }
+
+    pub fn cameras(&self) -> Vec<Arc<Camera>> {
+        let c = self.cameras.lock().unwrap();
+        c.cameras.iter().map(|v| v.camera.clone()).collect()
+    }
+
+    // active cameras in render order
+    pub fn active_cameras(&self) -> Vec<(CameraId, Arc<Camera>, CameraSettings)> {
+        let c = self.cameras.lock().unwrap();
+        let mut res: Vec<_> = c
+            .cameras
+            .iter()
+            .filter(|v| v.settings.active)
+            .map(|v| (v.id, v.camera.clone(), v.settings.clone()))
+            .collect();
+        res.sort_by_key(|v| (v.2.target.texture().is_none(), v.2.priority));
+        res
     }
 
     pub fn set_ui_camera(&mut self, camera: Arc<Camera>) {
@@ -203,28 +615,16 @@ impl Scene {
             object.set_name(&format!("Object {}", id));
         }
 
+        let material = object.material_arc().id();
         self.storage.insert(id, ObjectWrapper::new(layer, object));
-        let mut q = self.queue.lock().unwrap();
+        self.reindex(id);
+        self.add_to_sorter(id, layer);
 
-        let entry = q.entry(layer);
-        let entry = entry.or_insert_with(|| {
-            let camera = self.main_camera_ref();
-            if layer >= LAYER_UI {
-                Arc::new(Mutex::new(UISceneSorter::new()))
-            } else if layer > LAYER_TRANSPARENT {
-                Arc::new(Mutex::new(MaterialSorter::<DistanceSorter>::new(
-                    self.storage.clone(),
-                    camera,
-                )))
-            } else {
-                Arc::new(Mutex::new(MaterialSorter::<DistanceSorter>::new(
-                    self.storage.clone(),
-                    camera,
-                )))
-            }
+        self.events.send(SceneEvent::ObjectAdded {
+            id,
+            layer,
+            material,
         });
-        entry.lock().unwrap().add(id);
-
         id
     }
 
@@ -234,18 +634,76 @@ impl Scene {
         let store = &scene.storage;
         let keys: Vec<_> = store.iter().map(|k| *k.key()).collect();
 
+        let mut ids = HashMap::new();
         for id in keys {
             let (_, value) = store.remove(&id).unwrap();
-            self.add_with(value.object, value.layer);
+            ids.insert(id, self.add_with(value.object, value.layer));
+        }
+
+        // copy the node hierarchy below our root
+        let mut other = scene.graph.lock().unwrap();
+        let other_root = other.root();
+        let mut nodes = HashMap::new();
+        nodes.insert(other_root, self.root_node());
+        for node_id in other.subtree(other_root) {
+            let node = match other.get_mut(node_id) {
+                Some(v) => v,
+                None => continue,
+            };
+            let target = if node_id == other_root {
+                self.root_node()
+            } else {
+                let parent = node
+                    .parent()
+                    .and_then(|v| nodes.get(&v).cloned())
+                    .unwrap_or(self.root_node());
+                self.create_node(Some(parent), node.name(), node.local_transform().clone())
+            };
+            nodes.insert(node_id, target);
+
+            let data = std::mem::take(node.object_mut());
+            for object in data.objects {
+                if let Some(id) = ids.get(&object) {
+                    self.attach_object(*id, target);
+                }
+            }
+            for attachment in data.attachments {
+                self.attach_to_node(target, attachment);
+            }
         }
+        drop(other);
+        scene.object_nodes.clear();
+
         let mut t = self.attach_resources.lock().unwrap();
         let mut r = scene.attach_resources.lock().unwrap();
 
         std::mem::swap(&mut *t, &mut *r);
     }
 
+    fn add_to_sorter(&self, id: ObjectId, layer: LayerId) {
+        let mut q = self.queue.lock().unwrap();
+
+        let entry = q.entry(layer);
+        let entry = entry.or_insert_with(|| {
+            let camera = self.main_camera_ref();
+            let mut sorter = self
+                .sorter_factory(layer)
+                .create(self.storage.clone(), camera);
+            sorter.set_occlusion(self.occlusion_culling());
+            Arc::new(Mutex::new(sorter))
+        });
+        entry.lock().unwrap().add(id);
+    }
+
     fn clear_inner(&self) {
         self.queue.lock().unwrap().clear();
+        self.index.lock().unwrap().clear();
+    }
+
+    fn reindex(&self, id: ObjectId) {
+        if let Some(obj) = self.storage.get(&id) {
+            self.index.lock().unwrap().update(id, &obj);
+        }
     }
 
     pub fn remove(&self, id: u64) -> bool {
@@ -253,16 +711,32 @@ impl Scene {
             let q = self.queue.lock().unwrap();
             let sorter = q.get(&obj.layer).unwrap();
             sorter.lock().unwrap().remove(id);
+            drop(q);
 
+            let event = SceneEvent::ObjectRemoved {
+                id,
+                layer: obj.layer,
+                material: obj.o().material_arc().id(),
+            };
             drop(obj);
             self.storage.remove(&id);
+            self.index.lock().unwrap().remove(id);
+            if let Some((_, node)) = self.object_nodes.remove(&id) {
+                let mut graph = self.graph.lock().unwrap();
+                if let Some(node) = graph.get_mut(node) {
+                    node.object_mut().objects.retain(|v| *v != id);
+                }
+            }
+            self.events.send(event);
             return true;
         }
         false
     }
 
     pub fn remove_by_tag(&self, tag: TagId) {
-        self.remove_if(|v| v.o().has_tag(tag));
+        for id in self.objects_with_tag(tag) {
+            self.remove(id);
+        }
     }
 
     pub fn remove_all(&self) {
@@ -285,16 +759,194 @@ impl Scene {
     }
 
     pub fn modify_if<F: Fn(&mut ObjectWrapper)>(&self, f: F) {
+        let mut events = vec![];
         for mut v in self.storage.iter_mut() {
+            let id = *v.key();
             let obj = v.value_mut();
-            f(obj)
+            let before = ObjectState::new(obj);
+            f(obj);
+            self.index.lock().unwrap().update(id, obj);
+            before.diff(id, obj, &mut events);
+        }
+        self.dispatch(events);
+    }
+
+    // modify one object, returns false if it doesn't exist
+    pub fn modify<F: FnOnce(&mut ObjectWrapper)>(&self, id: ObjectId, f: F) -> bool {
+        let mut events = vec![];
+        match self.storage.get_mut(&id) {
+            Some(mut obj) => {
+                let before = ObjectState::new(&obj);
+                f(&mut obj);
+                self.index.lock().unwrap().update(id, &obj);
+                before.diff(id, &obj, &mut events);
+            }
+            None => return false,
+        }
+        self.dispatch(events);
+        true
+    }
+
+    // called without any storage entry held, sorters read the storage
+    fn dispatch(&self, events: Vec<SceneEvent>) {
+        for event in events {
+            match &event {
+                SceneEvent::MaterialChanged { id, layer, .. } => {
+                    let sorter = self.queue.lock().unwrap().get(layer).cloned();
+                    if let Some(sorter) = sorter {
+                        let mut sorter = sorter.lock().unwrap();
+                        sorter.remove(*id);
+                        sorter.add(*id);
+                    }
+                }
+                SceneEvent::LayerChanged { id, old, new, .. } => {
+                    let sorter = self.queue.lock().unwrap().get(old).cloned();
+                    if let Some(sorter) = sorter {
+                        sorter.lock().unwrap().remove(*id);
+                    }
+                    self.add_to_sorter(*id, *new);
+                }
+                _ => (),
+            }
+            self.events.send(event);
+        }
+    }
+
+    pub fn set_object_visible(&self, id: ObjectId, visible: bool) -> bool {
+        self.modify(id, |v| v.object.set_visible(visible))
+    }
+
+    pub fn set_object_z_order(&self, id: ObjectId, z_order: i8) -> bool {
+        self.modify(id, |v| v.object.set_z_order(z_order))
+    }
+
+    // returns false if the object doesn't exist
+    pub fn set_object_material(&self, id: ObjectId, material: MaterialArc) -> anyhow::Result<bool> {
+        let mut res = Ok(());
+        if !self.modify(id, |v| res = v.object.set_material(material)) {
+            return Ok(false);
         }
+        res.map(|_| true)
+    }
+
+    pub fn set_object_name(&self, id: ObjectId, name: &str) -> bool {
+        self.modify(id, |v| v.object.set_name(name))
+    }
+
+    pub fn add_object_tag(&self, id: ObjectId, tag: TagId) -> bool {
+        self.modify(id, |v| v.object.add_tag(tag))
+    }
+
+    pub fn remove_object_tag(&self, id: ObjectId, tag: TagId) -> bool {
+        self.modify(id, |v| v.object.remove_tag(tag))
+    }
+
+    pub fn objects_by_name(&self, name: &str) -> Vec<ObjectId> {
+        self.index().by_name(name)
+    }
+
+    pub fn objects_with_tag(&self, tag: TagId) -> Vec<ObjectId> {
+        self.index().by_tag(tag)
+    }
+
+    pub fn layer_objects(&self, layer: LayerId) -> Vec<ObjectId> {
+        self.index().by_layer(layer)
+    }
+
+    pub fn objects_with_material(&self, material: MaterialId) -> Vec<ObjectId> {
+        self.index().by_material(material)
+    }
+
+    // spatial queries only see objects whose geometry has an aabb boundary
+    pub fn objects_in_bounds(&self, min: &Vec3f, max: &Vec3f) -> Vec<ObjectId> {
+        self.index().in_bounds(min, max)
+    }
+
+    pub fn objects_in_sphere(&self, center: &Vec3f, radius: f32) -> Vec<ObjectId> {
+        self.index().in_sphere(center, radius)
     }
 
+    // world bounds of the objects, None without any aabb boundary
+    pub fn bounds(&self) -> Option<(Vec3f, Vec3f)> {
+        self.index().bounds(None)
+    }
+
+    pub fn bounds_of(&self, ids: &[ObjectId]) -> Option<(Vec3f, Vec3f)> {
+        self.index().bounds(Some(ids))
+    }
+
+    // objects hit by the ray with the distance to their bounds, nearest first
+    pub fn raycast(&self, origin: &Vec3f, dir: &Vec3f, max_distance: f32) -> Vec<(ObjectId, f32)> {
+        self.index().ray(origin, dir, max_distance)
+    }
+
+    // objects changed through the storage are re-indexed by the next query,
+    // prefer `modify` which keeps the index and events in sync
     pub fn get_container(&self) -> SceneStorage {
+        self.index_dirty.store(true, Ordering::Release);
         self.storage.clone()
     }
 
+    // for renderers and other readers, the index stays clean
+    pub fn read_container(&self) -> SceneObjects {
+        SceneObjects {
+            storage: self.storage.clone(),
+        }
+    }
+
+    fn index(&self) -> MutexGuard<'_, SceneIndex> {
+        if self.index_dirty.swap(false, Ordering::AcqRel) {
+            let entries = SceneIndex::snapshot(&self.storage);
+            let mut index = self.index.lock().unwrap();
+            index.sync(entries);
+            return index;
+        }
+        self.index.lock().unwrap()
+    }
+
+    // declare a layer or replace the settings of one, register layers before
+    // objects are added to them
+    pub fn register_layer(&self, layer: Layer) -> LayerId {
+        log::info!("register layer {:?}", layer);
+        let id = self.layer_registry.write().unwrap().register(layer);
+        self.set_rebuild_flag();
+        id
+    }
+
+    // the registered layer covering `id`
+    pub fn find_layer(&self, id: LayerId) -> Option<Layer> {
+        self.layer_registry.read().unwrap().find(id).cloned()
+    }
+
+    pub fn layer_by_name(&self, name: &str) -> Option<LayerId> {
+        self.layer_registry
+            .read()
+            .unwrap()
+            .by_name(name)
+            .map(|v| v.id)
+    }
+
+    pub fn registered_layers(&self) -> Vec<Layer> {
+        self.layer_registry
+            .read()
+            .unwrap()
+            .layers()
+            .cloned()
+            .collect()
+    }
+
+    pub fn layer_str(&self, id: LayerId) -> String {
+        self.find_layer(id)
+            .map(|v| v.name)
+            .unwrap_or_else(|| "invalid".to_owned())
+    }
+
+    // `layer` is drawn by views with the mask
+    pub fn layer_mask_contains(&self, mask: &LayerMask, layer: LayerId) -> bool {
+        let layers = self.layer_registry.read().unwrap();
+        mask.contains(layer, |v| layers.find(v).map(|l| l.id).unwrap_or(v))
+    }
+
     pub fn layers(&self) -> Vec<(LayerId, Arc<Mutex<dyn Sorter>>)> {
         self.queue
             .lock()
@@ -304,27 +956,195 @@ impl Scene {
             .collect()
     }
 
-    pub fn material_change(&self) -> bool {
-        let mut change = false;
-        for s in self.queue.lock().unwrap().values() {
-            if s.lock().unwrap().material_change() {
-                change = true;
+    pub fn clear_objects(&mut self) {
+        let events: Vec<_> = self
+            .storage
+            .iter()
+            .map(|v| SceneEvent::ObjectRemoved {
+                id: *v.key(),
+                layer: v.layer,
+                material: v.o().material_arc().id(),
+            })
+            .collect();
+        self.queue.lock().unwrap().clear();
+        self.storage.clear();
+        self.index.lock().unwrap().clear();
+        self.object_nodes.clear();
+        *self.graph.lock().unwrap() = SceneGraph::new();
+        for event in events {
+            self.events.send(event);
+        }
+    }
+
+    pub fn root_node(&self) -> NodeId {
+        self.graph.lock().unwrap().root()
+    }
+
+    pub fn create_node(&self, parent: Option<NodeId>, name: &str, transform: Transform) -> NodeId {
+        let mut graph = self.graph.lock().unwrap();
+        let parent = parent.unwrap_or(graph.root());
+        let id = graph.add_to_node(parent, SceneNode::default());
+        graph.set_name(id, name);
+        graph.set_local_transform(id, transform);
+        id
+    }
+
+    pub fn find_node(&self, name: &str) -> Option<NodeId> {
+        self.graph.lock().unwrap().find_by_name(name)
+    }
+
+    pub fn node_name(&self, node: NodeId) -> Option<String> {
+        let graph = self.graph.lock().unwrap();
+        graph.get(node).map(|v| v.name().to_owned())
+    }
+
+    pub fn set_node_name(&self, node: NodeId, name: &str) {
+        self.graph.lock().unwrap().set_name(node, name);
+    }
+
+    pub fn node_children(&self, node: NodeId) -> Vec<NodeId> {
+        let graph = self.graph.lock().unwrap();
+        graph
+            .get(node)
+            .map(|v| v.children().to_vec())
+            .unwrap_or_default()
+    }
+
+    pub fn node_parent(&self, node: NodeId) -> Option<NodeId> {
+        let graph = self.graph.lock().unwrap();
+        graph.get(node).and_then(|v| v.parent())
+    }
+
+    pub fn node_objects(&self, node: NodeId) -> Vec<ObjectId> {
+        let graph = self.graph.lock().unwrap();
+        graph
+            .get(node)
+            .map(|v| v.object().objects.clone())
+            .unwrap_or_default()
+    }
+
+    pub fn object_node(&self, id: ObjectId) -> Option<NodeId> {
+        self.object_nodes.get(&id).map(|v| *v)
+    }
+
+    pub fn set_node_transform(&self, node: NodeId, transform: Transform) {
+        self.graph
+            .lock()
+            .unwrap()
+            .set_local_transform(node, transform);
+    }
+
+    pub fn node_local_transform(&self, node: NodeId) -> Option<Transform> {
+        let graph = self.graph.lock().unwrap();
+        graph.get(node).map(|v| v.local_transform().clone())
+    }
+
+    // world transform as of the last `update_graph`
+    pub fn node_world_transform(&self, node: NodeId) -> Option<Transform> {
+        let graph = self.graph.lock().unwrap();
+        graph.get(node).map(|v| v.world_transform().clone())
+    }
+
+    pub fn reparent_node(&self, node: NodeId, parent: NodeId) -> bool {
+        self.graph.lock().unwrap().reparent(node, parent)
+    }
+
+    pub fn detach_node(&self, node: NodeId) -> bool {
+        self.graph.lock().unwrap().detach(node)
+    }
+
+    // remove the node, its children and all objects and attachments of them
+    pub fn remove_node(&self, node: NodeId) {
+        let data = self.graph.lock().unwrap().remove(node);
+        for n in data {
+            for id in n.objects {
+                self.object_nodes.remove(&id);
+                self.remove(id);
+            }
+            for attachment in n.attachments {
+                attachment.detach(self);
             }
         }
-        change
     }
 
-    pub fn clear_objects(&mut self) {
-        self.queue.lock().unwrap().clear();
-        self.storage.clear();
+    pub fn add_to_node(&self, object: RenderObject, node: NodeId) -> ObjectId {
+        let id = self.add(object);
+        self.attach_object(id, node);
+        id
+    }
+
+    pub fn add_with_to_node(&self, object: RenderObject, layer: LayerId, node: NodeId) -> ObjectId {
+        let id = self.add_with(object, layer);
+        self.attach_object(id, node);
+        id
+    }
+
+    pub fn set_object_transform(&self, id: ObjectId, transform: Transform) -> bool {
+        self.modify(id, |v| v.object.set_transform(transform))
+    }
+
+    pub fn object_transform(&self, id: ObjectId) -> Option<Transform> {
+        self.storage
+            .get(&id)
+            .map(|v| v.o().local_transform().clone())
+    }
+
+    pub fn attach_object(&self, id: ObjectId, node: NodeId) -> bool {
+        let mut graph = self.graph.lock().unwrap();
+        if graph.get(node).is_none() || !self.storage.contains_key(&id) {
+            return false;
+        }
+        if let Some(old) = self.object_nodes.insert(id, node) {
+            if let Some(old) = graph.get_mut(old) {
+                old.object_mut().objects.retain(|v| *v != id);
+            }
+        }
+        let n = graph.get_mut(node).unwrap();
+        n.object_mut().objects.push(id);
+        let world = n.world_transform().clone();
+        self.modify(id, |v| v.object.set_parent_transform(&world));
+        true
+    }
+
+    pub fn detach_object(&self, id: ObjectId) -> bool {
+        let root = self.root_node();
+        self.attach_object(id, root)
+    }
+
+    pub fn attach_to_node(&self, node: NodeId, attachment: Arc<dyn NodeAttachment>) -> bool {
+        let mut graph = self.graph.lock().unwrap();
+        let n = match graph.get_mut(node) {
+            Some(v) => v,
+            None => return false,
+        };
+        attachment.update_transform(n.world_transform());
+        n.object_mut().attachments.push(attachment);
+        true
+    }
+
+    // propagate node transforms to the attached objects
+    pub fn update_graph(&self) {
+        let mut graph = self.graph.lock().unwrap();
+        for node in graph.update() {
+            let n = match graph.get(node) {
+                Some(v) => v,
+                None => continue,
+            };
+            let world = n.world_transform();
+            for id in &n.object().objects {
+                self.modify(*id, |v| v.object.set_parent_transform(world));
+            }
+            for attachment in &n.object().attachments {
+                attachment.update_transform(world);
+            }
+        }
     }
 
     pub fn resize(&self, _logical: &Size, view_size: &Size) {
-        let aspect = view_size.x as f32 / view_size.y as f32;
         // self.ui_camera_ref().make_orthographic();
         let c = self.cameras.lock().unwrap();
-        for camera in c.cameras.iter() {
-            camera.set_aspect(aspect);
+        for entry in c.cameras.iter() {
+            entry.camera.set_aspect(entry.settings.aspect(view_size));
         }
     }
 
@@ -341,34 +1161,87 @@ impl Scene {
     }
 }
 
+// the parts of an object `SceneEvent`s report changes of
+struct ObjectState {
+    layer: LayerId,
+    material: MaterialId,
+    visible: bool,
+    transform: Transform,
+}
+
+impl ObjectState {
+    fn new(w: &ObjectWrapper) -> Self {
+        let o = w.o();
+        Self {
+            layer: w.layer,
+            material: o.material_arc().id(),
+            visible: o.visible(),
+            transform: o.transform().clone(),
+        }
+    }
+
+    fn diff(self, id: ObjectId, w: &ObjectWrapper, events: &mut Vec<SceneEvent>) {
+        let o = w.o();
+        let material = o.material_arc().id();
+        // moved first, a material change is then applied to the new layer's sorter
+        if w.layer != self.layer {
+            events.push(SceneEvent::LayerChanged {
+                id,
+                old: self.layer,
+                new: w.layer,
+                material,
+            });
+        }
+        if material != self.material {
+            events.push(SceneEvent::MaterialChanged {
+                id,
+                layer: w.layer,
+                old: self.material,
+                new: material,
+            });
+        }
+        if o.transform() != &self.transform {
+            events.push(SceneEvent::TransformChanged { id });
+        }
+        if o.visible() != self.visible {
+            events.push(SceneEvent::VisibilityChanged {
+                id,
+                visible: o.visible(),
+            });
+        }
+    }
+}
+
 #[derive(Debug)]
 pub struct RenderObject {
     geometry: Box<dyn Geometry>,
+    local: Transform,
+    parent: Transform,
+    world: Transform,
     material: MaterialArc,
     z_order: i8,
     visible: bool,
     case_shadow: bool,
     recv_shadow: bool,
     name: String,
+    source: Option<String>,
     tag: HashSet<TagId>,
 }
 
 impl RenderObject {
     pub fn new(geometry: Box<dyn Geometry>, material: MaterialArc) -> anyhow::Result<Self> {
-        let t = &geometry.mesh().properties;
-        if let Some(ins) = geometry.instance() {
-            let v = ins.data.lock().unwrap();
-            material.face().validate(t, Some(&v))?;
-        } else {
-            material.face().validate(t, None)?;
-        };
+        Self::validate(geometry.as_ref(), &material)?;
         Ok(Self {
+            local: geometry.transform().clone(),
+            parent: Transform::default(),
+            world: geometry.transform().clone(),
             geometry,
             material,
             z_order: 0,
             case_shadow: false,
             recv_shadow: false,
             name: String::default(),
+            source: None,
             visible: true,
             tag: HashSet::default(),
         })
@@ -406,10 +1279,27 @@ impl RenderObject {
         self.tag.insert(tag);
     }
 
+    pub fn remove_tag(&mut self, tag: TagId) {
+        self.tag.remove(&tag);
+    }
+
     pub fn has_tag(&self, tag: TagId) -> bool {
         self.tag.contains(&tag)
     }
 
+    pub fn tags(&self) -> impl Iterator<Item = TagId> + '_ {
+        self.tag.iter().cloned()
+    }
+
+    // where the geometry comes from, e.g. "gltf:model.glb#3", used when saving the scene
+    pub fn set_source(&mut self, source: &str) {
+        self.source = Some(source.to_owned());
+    }
+
+    pub fn source(&self) -> Option<&str> {
+        self.source.as_deref()
+    }
+
     // pub fn material(&self) -> &Material {
     //     self.material.as_ref()
     // }
@@ -418,6 +1308,23 @@ impl RenderObject {
         self.material.clone()
     }
 
+    pub fn set_material(&mut self, material: MaterialArc) -> anyhow::Result<()> {
+        Self::validate(self.geometry.as_ref(), &material)?;
+        self.material = material;
+        Ok(())
+    }
+
+    fn validate(geometry: &dyn Geometry, material: &MaterialArc) -> anyhow::Result<()> {
+        let t = &geometry.mesh().properties;
+        if let Some(ins) = geometry.instance() {
+            let v = ins.data.lock().unwrap();
+            material.face().validate(t, Some(&v))?;
+        } else {
+            material.face().validate(t, None)?;
+        };
+        Ok(())
+    }
+
     pub fn has_alpha_test(&self) -> bool {
         self.material.has_alpha_test()
     }
@@ -430,10 +1337,35 @@ impl RenderObject {
         self.geometry.as_ref()
     }
 
+    // geometry transform combined with the world transform of the owning node
+    pub fn transform(&self) -> &Transform {
+        &self.world
+    }
+
+    pub fn local_transform(&self) -> &Transform {
+        &self.local
+    }
+
+    // replace the geometry transform, the object uniform follows on the next frame
+    pub fn set_transform(&mut self, transform: Transform) {
+        self.local = transform;
+        self.world = &self.parent * &self.local;
+    }
+
+    pub(crate) fn set_parent_transform(&mut self, parent: &Transform) {
+        self.parent = parent.clone();
+        self.world = &self.parent * &self.local;
+    }
+
+    // sorters order by z_order before anything else, lower first
     pub fn z_order(&self) -> i8 {
         self.z_order
     }
 
+    pub fn set_z_order(&mut self, z_order: i8) {
+        self.z_order = z_order;
+    }
+
     pub fn visible(&self) -> bool {
         self.visible
     }
@@ -442,3 +1374,140 @@ impl RenderObject {
         self.visible = show;
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicU32, Ordering};
+
+    use crate::{
+        context::RContext,
+        material::{basic::BasicMaterialFaceBuilder, input::InputResourceBuilder, MaterialBuilder},
+        mesh::{builder::MeshBuilder, StaticGeometry},
+    };
+
+    use super::*;
+
+    #[derive(Default)]
+    struct Counter {
+        updated: AtomicU32,
+        detached: AtomicU32,
+    }
+
+    impl NodeAttachment for Counter {
+        fn update_transform(&self, _world: &Transform) {
+            self.updated.fetch_add(1, Ordering::Relaxed);
+        }
+
+        fn detach(&self, _scene: &Scene) {
+            self.detached.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    #[test]
+    fn remove_node_detaches() {
+        let scene = Scene::new(RContext::new());
+        let parent = scene.create_node(None, "parent", Transform::default());
+        let child = scene.create_node(Some(parent), "child", Transform::default());
+        let counter = Arc::new(Counter::default());
+        assert!(scene.attach_to_node(child, counter.clone()));
+
+        scene.remove_node(parent);
+        assert_eq!(counter.detached.load(Ordering::Relaxed), 1);
+        assert!(scene.find_node("child").is_none());
+
+        // no longer follows the graph
+        let updated = counter.updated.load(Ordering::Relaxed);
+        scene.update_graph();
+        assert_eq!(counter.updated.load(Ordering::Relaxed), updated);
+    }
+
+    #[test]
+    fn container_changes_reindex() {
+        let scene = Scene::new(RContext::new());
+        let mut builder = MeshBuilder::default();
+        builder.add_position_vertices3(&[Vec3f::zeros(), Vec3f::x(), Vec3f::y()]);
+        builder.add_indices32(&[0, 1, 2]);
+        let geometry = StaticGeometry::new(Arc::new(builder.build().unwrap()));
+        let mut color = InputResourceBuilder::new();
+        color.add_constant(Color::new(1f32, 1f32, 1f32, 1f32));
+        let material = MaterialBuilder::default()
+            .face(
+                BasicMaterialFaceBuilder::new()
+                    .texture(color.build())
+                    .build(),
+            )
+            .build(&scene.context());
+        let mut object = RenderObject::new(Box::new(geometry), material).unwrap();
+        object.set_name("a");
+        let id = scene.add(object);
+        assert_eq!(scene.objects_by_name("a"), vec![id]);
+
+        let storage = scene.get_container();
+        storage.get_mut(&id).unwrap().object.set_name("b");
+        assert!(scene.objects_by_name("a").is_empty());
+        assert_eq!(scene.objects_by_name("b"), vec![id]);
+
+        let storage = scene.get_container();
+        storage.remove(&id);
+        assert!(scene.objects_by_name("b").is_empty());
+    }
+
+    #[test]
+    fn read_container_keeps_index() {
+        let scene = Scene::new(RContext::new());
+        scene.get_container();
+        assert!(scene.objects_by_name("a").is_empty());
+        assert!(!scene.index_dirty.load(Ordering::Acquire));
+
+        let objects = scene.read_container();
+        assert!(objects.is_empty());
+        assert!(!scene.index_dirty.load(Ordering::Acquire));
+    }
+
+    #[test]
+    fn modify_moves_layer() {
+        let scene = Scene::new(RContext::new());
+        let mut builder = MeshBuilder::default();
+        builder.add_position_vertices3(&[Vec3f::zeros(), Vec3f::x(), Vec3f::y()]);
+        builder.add_indices32(&[0, 1, 2]);
+        let geometry = StaticGeometry::new(Arc::new(builder.build().unwrap()));
+        let mut color = InputResourceBuilder::new();
+        color.add_constant(Color::new(1f32, 1f32, 1f32, 1f32));
+        let material = MaterialBuilder::default()
+            .face(
+                BasicMaterialFaceBuilder::new()
+                    .texture(color.build())
+                    .build(),
+            )
+            .build(&scene.context());
+        let material_id = material.id();
+        let object = RenderObject::new(Box::new(geometry), material).unwrap();
+        let id = scene.add_with(object, LAYER_UI);
+        let events = scene.subscribe();
+
+        assert!(scene.modify(id, |v| v.layer = LAYER_UI + 1));
+        assert_eq!(
+            events.try_recv().unwrap(),
+            SceneEvent::LayerChanged {
+                id,
+                old: LAYER_UI,
+                new: LAYER_UI + 1,
+                material: material_id,
+            }
+        );
+        assert!(events.try_recv().is_err());
+
+        let layers: HashMap<_, _> = scene
+            .layers()
+            .into_iter()
+            .map(|(layer, sorter)| (layer, sorter.lock().unwrap().sort_and_cull()))
+            .collect();
+        assert!(layers[&LAYER_UI].is_empty());
+        assert_eq!(layers[&(LAYER_UI + 1)], vec![id]);
+        assert_eq!(scene.layer_objects(LAYER_UI + 1), vec![id]);
+
+        // unchanged layer, no event
+        assert!(scene.modify(id, |v| v.layer = LAYER_UI + 1));
+        assert!(events.try_recv().is_err());
+    }
+}
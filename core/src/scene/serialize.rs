@@ -0,0 +1,1141 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    path::Path,
+    sync::Arc,
+};
+
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+use bevy_reflect::Reflect;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+
+pub use ron::Value;
+
+use crate::{
+    context::{RContext, RContextRef, ResourceRef},
+    graph::sg::NodeId,
+    material::{
+        basic::BasicMaterialFaceSerializer, bind::ShaderBindingResource, Material, MaterialArc,
+        MaterialBuilder,
+    },
+    mesh::{
+        binary, DynamicGeometry, Geometry, InstanceProperties, Mesh, StaticGeometry, TransformType,
+    },
+    render::view::RenderTexture,
+    types::{Quaternion, Size, Vec2f, Vec3f, Vec4f},
+};
+
+use super::{
+    Camera, CameraSettings, CameraTarget, LayerId, LayerMask, RenderObject, Scene, Transform,
+};
+
+// bump when the file layout changes
+pub const SCENE_VERSION: u32 = 2;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Reflect)]
+pub enum BindingValue {
+    // textures and samplers are saved by their source, see `RContext::set_resource_source`
+    Resource(String),
+    Int32(i32),
+    Int64(i64),
+    Float(f32),
+    Double(f64),
+    Float2([f32; 2]),
+    Float3([f32; 3]),
+    Float4([f32; 4]),
+}
+
+impl BindingValue {
+    pub fn from_resource(res: &ShaderBindingResource, context: &RContext) -> Option<Self> {
+        match res {
+            ShaderBindingResource::Resource(r) => match context.resource_source(r.id()) {
+                Some(source) => Some(Self::Resource(source)),
+                None => {
+                    log::warn!("resource {} has no source, it is not saved", r.id());
+                    None
+                }
+            },
+            _ => Self::from_value(res),
+        }
+    }
+
+    // None for resources and missing bindings
+    pub fn from_value(res: &ShaderBindingResource) -> Option<Self> {
+        Some(match res {
+            ShaderBindingResource::Nothing | ShaderBindingResource::Resource(_) => return None,
+            ShaderBindingResource::Int32(v) => Self::Int32(*v),
+            ShaderBindingResource::Int64(v) => Self::Int64(*v),
+            ShaderBindingResource::Float(v) => Self::Float(*v),
+            ShaderBindingResource::Double(v) => Self::Double(*v),
+            ShaderBindingResource::Float2(v) => Self::Float2((*v).into()),
+            ShaderBindingResource::Float3(v) => Self::Float3((*v).into()),
+            ShaderBindingResource::Float4(v) => Self::Float4((*v).into()),
+        })
+    }
+
+    pub fn to_resource(&self, resources: &LoadedResources) -> ShaderBindingResource {
+        match self {
+            Self::Resource(source) => match resources.get(source) {
+                Some(r) => ShaderBindingResource::Resource(r.clone()),
+                None => ShaderBindingResource::Nothing,
+            },
+            _ => self.to_value(),
+        }
+    }
+
+    // `Nothing` for resources
+    pub fn to_value(&self) -> ShaderBindingResource {
+        match self {
+            Self::Resource(_) => ShaderBindingResource::Nothing,
+            Self::Int32(v) => ShaderBindingResource::Int32(*v),
+            Self::Int64(v) => ShaderBindingResource::Int64(*v),
+            Self::Float(v) => ShaderBindingResource::Float(*v),
+            Self::Double(v) => ShaderBindingResource::Double(*v),
+            Self::Float2(v) => ShaderBindingResource::Float2(Vec2f::from(*v)),
+            Self::Float3(v) => ShaderBindingResource::Float3(Vec3f::from(*v)),
+            Self::Float4(v) => ShaderBindingResource::Float4(Vec4f::from(*v)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TransformDesc {
+    pub translate: [f32; 3],
+    // x, y, z, w
+    pub rotate: [f32; 4],
+    pub scale: [f32; 3],
+}
+
+impl From<&Transform> for TransformDesc {
+    fn from(t: &Transform) -> Self {
+        let q = t.rotate().quaternion();
+        Self {
+            translate: (*t.translate()).into(),
+            rotate: [q.i, q.j, q.k, q.w],
+            scale: (*t.scale()).into(),
+        }
+    }
+}
+
+impl From<&TransformDesc> for Transform {
+    fn from(t: &TransformDesc) -> Self {
+        let r = t.rotate;
+        Transform::new(
+            t.translate.into(),
+            Quaternion::from_quaternion(nalgebra::Quaternion::new(r[3], r[0], r[1], r[2])),
+            t.scale.into(),
+        )
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum ProjectionDesc {
+    Perspective {
+        aspect: f32,
+        fovy: f32,
+        near: f32,
+        far: f32,
+    },
+    Orthographic {
+        rect: [f32; 4],
+        near: f32,
+        far: f32,
+    },
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CameraDesc {
+    pub projection: ProjectionDesc,
+    pub from: [f32; 3],
+    pub to: [f32; 3],
+    pub up: [f32; 3],
+    // None for the ui camera and version 1 files
+    #[serde(default)]
+    pub settings: Option<CameraSettingsDesc>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RenderTextureDesc {
+    // the texture is loaded like material resources
+    pub source: String,
+    pub size: [u32; 2],
+    pub format: wgpu::TextureFormat,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CameraSettingsDesc {
+    pub viewport: [f32; 4],
+    pub clear_color: Option<[f32; 4]>,
+    pub clear_depth: bool,
+    pub priority: i32,
+    pub layer_mask: LayerMask,
+    pub active: bool,
+    // None draws to the surface
+    pub target: Option<RenderTextureDesc>,
+}
+
+impl CameraSettingsDesc {
+    pub fn new(settings: &CameraSettings, context: &RContext) -> Self {
+        let target = settings.target.texture().and_then(|t| {
+            match context.resource_source(t.texture().id()) {
+                Some(source) => Some(RenderTextureDesc {
+                    source,
+                    size: [t.size().x, t.size().y],
+                    format: t.format(),
+                }),
+                None => {
+                    log::warn!("camera target has no source, it is saved drawing to the surface");
+                    None
+                }
+            }
+        });
+        Self {
+            viewport: settings.viewport.into(),
+            clear_color: settings.clear_color.map(|v| v.into()),
+            clear_depth: settings.clear_depth,
+            priority: settings.priority,
+            layer_mask: settings.layer_mask.clone(),
+            active: settings.active,
+            target,
+        }
+    }
+
+    pub fn settings(&self, resources: &LoadedResources) -> CameraSettings {
+        let target = match &self.target {
+            Some(t) => match resources.get(&t.source) {
+                Some(texture) => CameraTarget::Texture(Arc::new(RenderTexture::from_texture(
+                    texture.clone(),
+                    Size::new(t.size[0], t.size[1]),
+                    t.format,
+                ))),
+                None => CameraTarget::Surface,
+            },
+            None => CameraTarget::Surface,
+        };
+        CameraSettings {
+            viewport: Vec4f::from(self.viewport),
+            clear_color: self.clear_color.map(Vec4f::from),
+            clear_depth: self.clear_depth,
+            priority: self.priority,
+            layer_mask: self.layer_mask.clone(),
+            active: self.active,
+            target,
+        }
+    }
+}
+
+impl From<&Camera> for CameraDesc {
+    fn from(c: &Camera) -> Self {
+        let projection = match c.orthographic_rect() {
+            Some(rect) => ProjectionDesc::Orthographic {
+                rect: rect.into(),
+                near: c.near(),
+                far: c.far(),
+            },
+            None => ProjectionDesc::Perspective {
+                aspect: c.aspect(),
+                fovy: c.fovy(),
+                near: c.near(),
+                far: c.far(),
+            },
+        };
+        Self {
+            projection,
+            from: c.from().into(),
+            to: c.to().into(),
+            up: c.up().into(),
+            settings: None,
+        }
+    }
+}
+
+impl CameraDesc {
+    pub fn apply(&self, c: &Camera) {
+        match &self.projection {
+            ProjectionDesc::Perspective {
+                aspect,
+                fovy,
+                near,
+                far,
+            } => c.make_perspective(*aspect, *fovy, *near, *far),
+            ProjectionDesc::Orthographic { rect, near, far } => {
+                c.make_orthographic(Vec4f::from(*rect), *near, *far)
+            }
+        }
+        c.look_at(self.from.into(), self.to.into(), self.up.into());
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NodeDesc {
+    pub id: u64,
+    // None for nodes directly below the root
+    pub parent: Option<u64>,
+    pub name: String,
+    pub transform: TransformDesc,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MaterialDesc {
+    pub name: String,
+    pub face: String,
+    pub variants: Vec<String>,
+    pub params: BTreeMap<String, BindingValue>,
+    pub primitive: wgpu::PrimitiveState,
+    pub blend: Option<wgpu::BlendState>,
+}
+
+impl MaterialDesc {
+    // the resource saved with `key`, loaded by `SceneSerializer`
+    pub fn resource(&self, key: &str, resources: &LoadedResources) -> Option<ResourceRef> {
+        match self.params.get(key)? {
+            BindingValue::Resource(source) => resources.get(source).cloned(),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GeometryDesc {
+    pub source: Option<String>,
+    // index into `SceneDesc::meshes`
+    pub mesh: Option<usize>,
+    pub dynamic: bool,
+    // base64 encoded binary instance frame
+    pub instance: Option<String>,
+    pub instance_dynamic: bool,
+    #[serde(default = "instance_transform_v1")]
+    pub instance_transform: TransformType,
+}
+
+// version 1 files always had a matrix per instance
+fn instance_transform_v1() -> TransformType {
+    TransformType::Mat4x4
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ObjectDesc {
+    pub name: String,
+    pub layer: LayerId,
+    pub tags: Vec<String>,
+    pub node: Option<u64>,
+    pub transform: TransformDesc,
+    pub geometry: GeometryDesc,
+    pub material: usize,
+    pub visible: bool,
+    pub cast_shadow: bool,
+    pub recv_shadow: bool,
+    #[serde(default)]
+    pub z_order: i8,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SceneDesc {
+    pub version: u32,
+    pub cameras: Vec<CameraDesc>,
+    pub ui_camera: Option<CameraDesc>,
+    pub nodes: Vec<NodeDesc>,
+    // base64 encoded binary meshes
+    pub meshes: Vec<String>,
+    pub materials: Vec<MaterialDesc>,
+    pub objects: Vec<ObjectDesc>,
+    pub resources: BTreeMap<String, Value>,
+}
+
+#[derive(Deserialize)]
+struct SceneHeader {
+    #[serde(default)]
+    version: u32,
+}
+
+pub trait MaterialFaceSerializer: Send + Sync {
+    // same as `MaterialFace::name`
+    fn name(&self) -> &str;
+
+    // binding keys saved into `MaterialDesc::params`
+    fn keys(&self) -> &[&str];
+
+    fn load(
+        &self,
+        desc: &MaterialDesc,
+        resources: &LoadedResources,
+        builder: MaterialBuilder,
+    ) -> anyhow::Result<MaterialBuilder>;
+}
+
+// resources of the loaded materials by source
+pub type LoadedResources = HashMap<String, ResourceRef>;
+
+// resolves the source of a saved texture or sampler, e.g. a file path or an asset id
+pub trait ResourceSourceLoader: Send + Sync {
+    fn load(&self, context: &RContextRef, source: &str) -> anyhow::Result<ResourceRef>;
+}
+
+// scene resources attached with `Scene::attach`, e.g. lights
+pub trait SceneResourceSerializer: Send + Sync {
+    fn name(&self) -> &str;
+
+    fn save(&self, scene: &Scene) -> anyhow::Result<Option<Value>>;
+
+    fn load(&self, scene: &Scene, value: Value) -> anyhow::Result<()>;
+}
+
+// resolves `RenderObject::source` when loading, objects with a known source are not embedded
+pub trait GeometrySourceLoader: Send + Sync {
+    fn load(&self, source: &str) -> anyhow::Result<Arc<Mesh>>;
+}
+
+pub fn to_value<T: Serialize>(v: &T) -> anyhow::Result<Value> {
+    Ok(ron::from_str(&ron::to_string(v)?)?)
+}
+
+pub fn from_value<T: DeserializeOwned>(v: Value) -> anyhow::Result<T> {
+    Ok(v.into_rust()?)
+}
+
+fn decode_base64(s: &str) -> anyhow::Result<Vec<u8>> {
+    BASE64
+        .decode(s)
+        .map_err(|e| anyhow::anyhow!("invalid base64 data: {}", e))
+}
+
+// source scheme is the part before the first ':'
+fn source_scheme(source: &str) -> &str {
+    source.split(':').next().unwrap_or_default()
+}
+
+pub struct SceneSerializer {
+    faces: HashMap<String, Arc<dyn MaterialFaceSerializer>>,
+    resources: Vec<Arc<dyn SceneResourceSerializer>>,
+    sources: HashMap<String, Arc<dyn GeometrySourceLoader>>,
+    resource_sources: HashMap<String, Arc<dyn ResourceSourceLoader>>,
+}
+
+impl Default for SceneSerializer {
+    fn default() -> Self {
+        Self::new().with_face(BasicMaterialFaceSerializer)
+    }
+}
+
+impl SceneSerializer {
+    pub fn new() -> Self {
+        Self {
+            faces: HashMap::new(),
+            resources: vec![],
+            sources: HashMap::new(),
+            resource_sources: HashMap::new(),
+        }
+    }
+
+    pub fn with_face<F: MaterialFaceSerializer + 'static>(mut self, face: F) -> Self {
+        self.faces.insert(face.name().to_owned(), Arc::new(face));
+        self
+    }
+
+    pub fn with_resource<R: SceneResourceSerializer + 'static>(mut self, resource: R) -> Self {
+        self.resources.push(Arc::new(resource));
+        self
+    }
+
+    pub fn with_source<L: GeometrySourceLoader + 'static>(
+        mut self,
+        scheme: &str,
+        loader: L,
+    ) -> Self {
+        self.sources.insert(scheme.to_owned(), Arc::new(loader));
+        self
+    }
+
+    pub fn with_resource_source<L: ResourceSourceLoader + 'static>(
+        mut self,
+        scheme: &str,
+        loader: L,
+    ) -> Self {
+        self.resource_sources
+            .insert(scheme.to_owned(), Arc::new(loader));
+        self
+    }
+
+    fn save_material(
+        &self,
+        context: &RContext,
+        material: &Material,
+    ) -> anyhow::Result<MaterialDesc> {
+        let face = material.face();
+        let serializer = self
+            .faces
+            .get(face.name())
+            .ok_or_else(|| anyhow::anyhow!("no serializer for material face {}", face.name()))?;
+        let mut params = BTreeMap::new();
+        for key in serializer.keys() {
+            if let Some(v) = BindingValue::from_resource(&face.query_resource(key), context) {
+                params.insert(key.to_string(), v);
+            }
+        }
+        Ok(MaterialDesc {
+            name: material.name().to_owned(),
+            face: face.name().to_owned(),
+            variants: face.variants().flags().to_vec(),
+            params,
+            primitive: *material.primitive(),
+            blend: material.blend().cloned(),
+        })
+    }
+
+    pub fn save(&self, scene: &Scene) -> anyhow::Result<String> {
+        let context = scene.context();
+
+        let cameras = scene
+            .camera_entries()
+            .iter()
+            .map(|(_, camera, settings)| CameraDesc {
+                settings: Some(CameraSettingsDesc::new(settings, &context)),
+                ..CameraDesc::from(camera.as_ref())
+            })
+            .collect();
+        let ui_camera = Some(CameraDesc::from(scene.ui_camera_ref().as_ref()));
+
+        // parents come before children
+        let mut nodes = vec![];
+        let mut stack: Vec<(NodeId, Option<u64>)> = scene
+            .node_children(scene.root_node())
+            .into_iter()
+            .rev()
+            .map(|v| (v, None))
+            .collect();
+        while let Some((node, parent)) = stack.pop() {
+            nodes.push(NodeDesc {
+                id: node,
+                parent,
+                name: scene.node_name(node).unwrap_or_default(),
+                transform: TransformDesc::from(
+                    &scene.node_local_transform(node).unwrap_or_default(),
+                ),
+            });
+            for child in scene.node_children(node).into_iter().rev() {
+                stack.push((child, Some(node)));
+            }
+        }
+
+        let mut meshes = vec![];
+        let mut mesh_index: HashMap<*const Mesh, usize> = HashMap::new();
+        let mut materials = vec![];
+        let mut material_index: HashMap<u64, usize> = HashMap::new();
+        let mut objects = vec![];
+
+        let storage = scene.read_container();
+        let mut ids: Vec<_> = storage.iter().map(|v| *v.key()).collect();
+        ids.sort();
+
+        for id in ids {
+            let w = match storage.get(&id) {
+                Some(v) => v,
+                None => continue,
+            };
+            let o = w.o();
+            let geometry = o.geometry();
+
+            let material = o.material_arc();
+            let material = match material_index.get(&material.id().id()) {
+                Some(v) => *v,
+                None => {
+                    materials.push(self.save_material(&context, &material)?);
+                    material_index.insert(material.id().id(), materials.len() - 1);
+                    materials.len() - 1
+                }
+            };
+
+            let source = o.source().map(|v| v.to_owned());
+            let has_loader = source
+                .as_deref()
+                .is_some_and(|v| self.sources.contains_key(source_scheme(v)));
+            let mesh = if has_loader {
+                None
+            } else {
+                let mesh = geometry.mesh();
+                let key = Arc::as_ptr(&mesh);
+                Some(match mesh_index.get(&key) {
+                    Some(v) => *v,
+                    None => {
+                        meshes.push(BASE64.encode(binary::write_mesh(&mesh)));
+                        mesh_index.insert(key, meshes.len() - 1);
+                        meshes.len() - 1
+                    }
+                })
+            };
+            let (instance, instance_dynamic, instance_transform) = match geometry.instance() {
+                Some(ins) => {
+                    let data = ins.data.lock().unwrap();
+                    (
+                        Some(BASE64.encode(binary::write_instance(&data))),
+                        ins.dynamic,
+                        ins.transform_type,
+                    )
+                }
+                None => (None, false, TransformType::Mat4x4),
+            };
+
+            let mut tags: Vec<_> = o.tags().filter_map(|v| context.tag_name(v)).collect();
+            tags.sort();
+
+            objects.push(ObjectDesc {
+                name: o.name().to_owned(),
+                layer: w.layer,
+                tags,
+                node: scene.object_node(id).filter(|v| *v != scene.root_node()),
+                transform: TransformDesc::from(o.local_transform()),
+                geometry: GeometryDesc {
+                    source,
+                    mesh,
+                    dynamic: !geometry.info().is_static && geometry.instance().is_none(),
+                    instance,
+                    instance_dynamic,
+                    instance_transform,
+                },
+                material,
+                visible: o.visible(),
+                cast_shadow: o.cast_shadow(),
+                recv_shadow: o.recv_shadow(),
+                z_order: o.z_order(),
+            });
+        }
+
+        let mut resources = BTreeMap::new();
+        for r in &self.resources {
+            if let Some(v) = r.save(scene)? {
+                resources.insert(r.name().to_owned(), v);
+            }
+        }
+
+        let desc = SceneDesc {
+            version: SCENE_VERSION,
+            cameras,
+            ui_camera,
+            nodes,
+            meshes,
+            materials,
+            objects,
+            resources,
+        };
+        Ok(ron::ser::to_string_pretty(
+            &desc,
+            ron::ser::PrettyConfig::default(),
+        )?)
+    }
+
+    pub fn save_to_file<P: AsRef<Path>>(&self, scene: &Scene, path: P) -> anyhow::Result<()> {
+        let s = self.save(scene)?;
+        std::fs::write(path, s)?;
+        Ok(())
+    }
+
+    pub fn parse(&self, s: &str) -> anyhow::Result<SceneDesc> {
+        let header: SceneHeader =
+            ron::from_str(s).map_err(|e| anyhow::anyhow!("invalid scene file: {}", e))?;
+        match header.version {
+            0 => anyhow::bail!("scene file has no version"),
+            // version 1 has no camera settings, z order and instance
+            // transform type, they take the defaults
+            1 | SCENE_VERSION => {}
+            v if v > SCENE_VERSION => anyhow::bail!(
+                "scene file version {} is newer than the supported version {}",
+                v,
+                SCENE_VERSION
+            ),
+            v => anyhow::bail!("unknown scene file version {}", v),
+        }
+        ron::from_str(s).map_err(|e| anyhow::anyhow!("invalid scene file: {}", e))
+    }
+
+    fn load_resources(&self, scene: &Scene, desc: &SceneDesc) -> anyhow::Result<LoadedResources> {
+        let context = scene.context();
+        let mut resources = LoadedResources::new();
+        // materials may sample the textures cameras draw into
+        let targets = desc
+            .cameras
+            .iter()
+            .filter_map(|c| c.settings.as_ref()?.target.as_ref())
+            .map(|t| &t.source);
+        let params = desc
+            .materials
+            .iter()
+            .flat_map(|m| m.params.values())
+            .filter_map(|v| match v {
+                BindingValue::Resource(source) => Some(source),
+                _ => None,
+            });
+        for source in targets.chain(params) {
+            if resources.contains_key(source) {
+                continue;
+            }
+            let loader = self
+                .resource_sources
+                .get(source_scheme(source))
+                .ok_or_else(|| anyhow::anyhow!("no loader for resource source {}", source))?;
+            let res = loader.load(&context, source)?;
+            // saved again with the same source
+            context.set_resource_source(&res, source);
+            resources.insert(source.clone(), res);
+        }
+        Ok(resources)
+    }
+
+    fn load_material(
+        &self,
+        scene: &Scene,
+        desc: &MaterialDesc,
+        resources: &LoadedResources,
+    ) -> anyhow::Result<MaterialArc> {
+        let serializer = self
+            .faces
+            .get(&desc.face)
+            .ok_or_else(|| anyhow::anyhow!("no serializer for material face {}", desc.face))?;
+        let mut builder = MaterialBuilder::default()
+            .name(&desc.name)
+            .primitive(desc.primitive);
+        if let Some(blend) = desc.blend {
+            builder = builder.blend(blend);
+        }
+        let builder = serializer.load(desc, resources, builder)?;
+        Ok(builder.build(&scene.context()))
+    }
+
+    fn load_mesh(
+        &self,
+        desc: &SceneDesc,
+        geometry: &GeometryDesc,
+        cache: &mut HashMap<usize, Arc<Mesh>>,
+    ) -> anyhow::Result<Arc<Mesh>> {
+        if let Some(source) = &geometry.source {
+            if let Some(loader) = self.sources.get(source_scheme(source)) {
+                return loader.load(source);
+            }
+        }
+        let index = match geometry.mesh {
+            Some(v) => v,
+            None => anyhow::bail!(
+                "geometry source {:?} has no loader and no embedded mesh",
+                geometry.source
+            ),
+        };
+        if let Some(mesh) = cache.get(&index) {
+            return Ok(mesh.clone());
+        }
+        let data = desc
+            .meshes
+            .get(index)
+            .ok_or_else(|| anyhow::anyhow!("mesh index {} out of range", index))?;
+        let mesh = Arc::new(binary::read_mesh(&decode_base64(data)?)?);
+        cache.insert(index, mesh.clone());
+        Ok(mesh)
+    }
+
+    // objects, nodes, cameras and resources are added to `scene`
+    pub fn load(&self, scene: &Scene, s: &str) -> anyhow::Result<()> {
+        let desc = self.parse(s)?;
+        let context = scene.context();
+        let resources = self.load_resources(scene, &desc)?;
+
+        for (index, c) in desc.cameras.iter().enumerate() {
+            let settings = c.settings.as_ref().map(|v| v.settings(&resources));
+            let main = if index == 0 {
+                scene.main_camera_id()
+            } else {
+                None
+            };
+            match main {
+                Some(id) => {
+                    if let Some(camera) = scene.camera_ref(id) {
+                        c.apply(&camera);
+                    }
+                    if let Some(settings) = settings {
+                        scene.set_camera_settings(id, settings);
+                    }
+                }
+                None => {
+                    let camera = Camera::new();
+                    c.apply(&camera);
+                    scene.add_camera_with(Arc::new(camera), settings.unwrap_or_default());
+                }
+            }
+        }
+        if let Some(c) = &desc.ui_camera {
+            c.apply(&scene.ui_camera_ref());
+        }
+
+        let mut nodes = HashMap::new();
+        for n in &desc.nodes {
+            let parent =
+                match n.parent {
+                    Some(p) => Some(*nodes.get(&p).ok_or_else(|| {
+                        anyhow::anyhow!("node {} has unknown parent {}", n.id, p)
+                    })?),
+                    None => None,
+                };
+            let node = scene.create_node(parent, &n.name, Transform::from(&n.transform));
+            nodes.insert(n.id, node);
+        }
+
+        let mut materials = vec![];
+        for m in &desc.materials {
+            materials.push(self.load_material(scene, m, &resources)?);
+        }
+
+        let mut meshes = HashMap::new();
+        for o in &desc.objects {
+            let mesh = self.load_mesh(&desc, &o.geometry, &mut meshes)?;
+            let material = materials
+                .get(o.material)
+                .ok_or_else(|| anyhow::anyhow!("material index {} out of range", o.material))?
+                .clone();
+
+            let instance = match &o.geometry.instance {
+                Some(data) => Some(InstanceProperties {
+                    data: binary::read_instance(&decode_base64(data)?)?.into(),
+                    transform_type: o.geometry.instance_transform,
+                    dynamic: o.geometry.instance_dynamic,
+                }),
+                None => None,
+            };
+            let transform = Transform::from(&o.transform);
+            let geometry: Box<dyn Geometry> = if o.geometry.dynamic {
+                let mut g = DynamicGeometry::new(mesh).with_transform(transform);
+                if let Some(instance) = instance {
+                    g = g.with_instance(instance);
+                }
+                Box::new(g)
+            } else {
+                let mut g = StaticGeometry::new(mesh).with_transform(transform);
+                if let Some(instance) = instance {
+                    g = g.with_instance(instance);
+                }
+                Box::new(g)
+            };
+
+            let mut object = RenderObject::new(geometry, material)?;
+            object.set_name(&o.name);
+            if let Some(source) = &o.geometry.source {
+                object.set_source(source);
+            }
+            if o.cast_shadow {
+                object.set_cast_shadow();
+            }
+            if o.recv_shadow {
+                object.set_recv_shadow();
+            }
+            object.set_visible(o.visible);
+            object.set_z_order(o.z_order);
+            for tag in &o.tags {
+                object.add_tag(context.new_tag(tag));
+            }
+
+            let id = scene.add_with(object, o.layer);
+            if let Some(node) = o.node {
+                let node = nodes.get(&node).ok_or_else(|| {
+                    anyhow::anyhow!("object {} has unknown node {}", o.name, node)
+                })?;
+                scene.attach_object(id, *node);
+            }
+        }
+
+        for (name, value) in desc.resources {
+            match self.resources.iter().find(|v| v.name() == name) {
+                Some(r) => r.load(scene, value)?,
+                None => log::warn!("no serializer for scene resource {}, skipped", name),
+            }
+        }
+
+        Ok(())
+    }
+
+    pub fn load_from_file<P: AsRef<Path>>(&self, scene: &Scene, path: P) -> anyhow::Result<()> {
+        let s = std::fs::read_to_string(path)?;
+        self.load(scene, &s)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        context::RContext,
+        material::{basic::BasicMaterialFaceBuilder, input::InputResourceBuilder},
+        mesh::builder::{
+            InstancePropertiesBuilder, InstancePropertyType, MeshBuilder, MeshPropertiesBuilder,
+            MeshPropertyType,
+        },
+        testing::{gpu, TestGpu},
+        types::Color,
+    };
+
+    use super::*;
+
+    fn triangle(texture: bool) -> Arc<Mesh> {
+        let mut builder = MeshBuilder::default();
+        builder.add_position_vertices3(&[
+            Vec3f::new(0f32, 0f32, 0f32),
+            Vec3f::new(1f32, 0f32, 0f32),
+            Vec3f::new(0f32, 1f32, 0f32),
+        ]);
+        builder.add_indices32(&[0, 1, 2]);
+        let mut properties = MeshPropertiesBuilder::default();
+        if texture {
+            let property = MeshPropertyType::new::<Vec2f>("texture");
+            properties.add_property(property);
+            properties.add_property_data(
+                property,
+                &[
+                    Vec2f::new(0f32, 0f32),
+                    Vec2f::new(1f32, 0f32),
+                    Vec2f::new(0f32, 1f32),
+                ],
+            );
+        }
+        builder.set_properties(properties.build());
+        Arc::new(builder.build().unwrap())
+    }
+
+    fn add_object(scene: &Scene, mesh: Arc<Mesh>, material: MaterialArc, node: NodeId) {
+        let transform = Transform::new(
+            Vec3f::new(1f32, 2f32, 3f32),
+            Quaternion::identity(),
+            Vec3f::new(2f32, 2f32, 2f32),
+        );
+        let geometry = StaticGeometry::new(mesh).with_transform(transform);
+        let mut object = RenderObject::new(Box::new(geometry), material).unwrap();
+        object.set_name("triangle");
+        object.add_tag(scene.context().new_tag("saved"));
+        scene.add_to_node(object, node);
+    }
+
+    #[test]
+    fn round_trip() {
+        let scene = Scene::new(RContext::new());
+        let camera = Camera::new();
+        camera.make_perspective(1.5f32, 1f32, 0.1f32, 100f32);
+        camera.look_at(
+            Vec3f::new(0f32, 1f32, 5f32),
+            Vec3f::zeros(),
+            Vec3f::new(0f32, 1f32, 0f32),
+        );
+        scene.add_camera(Arc::new(camera));
+        let overlay = Camera::new();
+        overlay.make_orthographic(Vec4f::new(-1f32, -1f32, 1f32, 1f32), 0.1f32, 10f32);
+        let settings = CameraSettings {
+            viewport: Vec4f::new(0.5f32, 0f32, 0.5f32, 0.5f32),
+            clear_color: Some(Color::new(0f32, 0f32, 1f32, 1f32)),
+            clear_depth: false,
+            priority: 3,
+            layer_mask: LayerMask::only(&[4_000, 10_000]),
+            active: false,
+            target: CameraTarget::Surface,
+        };
+        scene.add_camera_with(Arc::new(overlay), settings.clone());
+
+        let parent = scene.create_node(None, "parent", Transform::default());
+        let child = scene.create_node(
+            Some(parent),
+            "child",
+            Transform::new(
+                Vec3f::new(0f32, 1f32, 0f32),
+                Quaternion::identity(),
+                Vec3f::new(1f32, 1f32, 1f32),
+            ),
+        );
+
+        let mut color = InputResourceBuilder::new();
+        color.add_constant(Color::new(1f32, 0f32, 0f32, 1f32));
+        let material = MaterialBuilder::default()
+            .name("red")
+            .face(
+                BasicMaterialFaceBuilder::new()
+                    .texture(color.build())
+                    .alpha_test(0.5f32)
+                    .build(),
+            )
+            .build(&scene.context());
+
+        // both objects share the mesh and the material
+        let mesh = triangle(false);
+        add_object(&scene, mesh.clone(), material.clone(), child);
+        add_object(&scene, mesh.clone(), material.clone(), scene.root_node());
+
+        // instance colors without an instance transform
+        let mut input = InputResourceBuilder::new();
+        input.add_instance();
+        let instanced = MaterialBuilder::default()
+            .name("instanced")
+            .face(
+                BasicMaterialFaceBuilder::new()
+                    .texture(input.build())
+                    .build(),
+            )
+            .build(&scene.context());
+        let mut instance = InstancePropertiesBuilder::default();
+        let color = InstancePropertyType::new::<Color>("color");
+        instance.add_property(color);
+        instance.add_property_data(color, &[Color::zeros(), Color::new(1f32, 0f32, 0f32, 1f32)]);
+        let geometry = StaticGeometry::new(mesh).with_instance(InstanceProperties {
+            data: instance.build().into(),
+            transform_type: TransformType::None,
+            dynamic: false,
+        });
+        let mut object = RenderObject::new(Box::new(geometry), instanced).unwrap();
+        object.set_z_order(-2);
+        scene.add(object);
+
+        let serializer = SceneSerializer::default();
+        let saved = serializer.save(&scene).unwrap();
+        let desc = serializer.parse(&saved).unwrap();
+        assert_eq!(desc.nodes.len(), 2);
+        assert_eq!(desc.meshes.len(), 1);
+        assert_eq!(desc.materials.len(), 2);
+        assert_eq!(desc.objects.len(), 3);
+        assert_eq!(
+            desc.materials[0].params.get("const_color"),
+            Some(&BindingValue::Float4([1f32, 0f32, 0f32, 1f32]))
+        );
+
+        let loaded = Scene::new(RContext::new());
+        serializer.load(&loaded, &saved).unwrap();
+        assert_eq!(loaded.read_container().len(), 3);
+        assert!(loaded.find_node("child").is_some());
+        assert_eq!(serializer.save(&loaded).unwrap(), saved);
+
+        let cameras = loaded.camera_entries();
+        assert_eq!(cameras.len(), 2);
+        let s = &cameras[1].2;
+        assert_eq!(s.viewport, settings.viewport);
+        assert_eq!(s.clear_color, settings.clear_color);
+        assert!(!s.clear_depth && !s.active);
+        assert_eq!(s.priority, 3);
+        assert_eq!(s.layer_mask, settings.layer_mask);
+        assert!(cameras[1].1.orthographic_rect().is_some());
+
+        let storage = loaded.read_container();
+        let instanced = storage
+            .iter()
+            .find(|v| v.o().geometry().instance().is_some())
+            .unwrap();
+        assert_eq!(instanced.o().z_order(), -2);
+        assert_eq!(
+            instanced.o().geometry().instance().unwrap().transform_type,
+            TransformType::None
+        );
+    }
+
+    #[test]
+    fn version_1_defaults() {
+        let serializer = SceneSerializer::default();
+        let scene = Scene::new(RContext::new());
+        let material = MaterialBuilder::default()
+            .face(BasicMaterialFaceBuilder::new().build())
+            .build(&scene.context());
+        add_object(&scene, triangle(false), material, scene.root_node());
+        let saved = serializer.save(&scene).unwrap();
+
+        // what a version 1 writer produced
+        let mut desc = serializer.parse(&saved).unwrap();
+        desc.version = 1;
+        let v1 = ron::ser::to_string(&desc)
+            .unwrap()
+            .replace(",settings:None", "")
+            .replace(",z_order:0", "")
+            .replace(",instance_transform:Mat4x4", "");
+        assert!(!v1.contains("z_order") && !v1.contains("instance_transform"));
+
+        let loaded = Scene::new(RContext::new());
+        serializer.load(&loaded, &v1).unwrap();
+        assert_eq!(loaded.read_container().len(), 1);
+    }
+
+    // "texture:<r>" is a 1x1 texture, "sampler:" a default sampler
+    #[derive(Clone)]
+    struct GpuResources(Arc<TestGpu>);
+
+    impl ResourceSourceLoader for GpuResources {
+        fn load(&self, context: &RContextRef, source: &str) -> anyhow::Result<ResourceRef> {
+            let gpu = &self.0;
+            match source.split_once(':') {
+                Some(("texture", r)) => {
+                    let texture = gpu.device.create_texture(&wgpu::TextureDescriptor {
+                        label: Some(source),
+                        size: wgpu::Extent3d {
+                            width: 1,
+                            height: 1,
+                            depth_or_array_layers: 1,
+                        },
+                        mip_level_count: 1,
+                        sample_count: 1,
+                        dimension: wgpu::TextureDimension::D2,
+                        format: wgpu::TextureFormat::Rgba8Unorm,
+                        usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST,
+                        view_formats: &[],
+                    });
+                    gpu.queue.write_texture(
+                        texture.as_image_copy(),
+                        &[r.parse()?, 0, 0, 255],
+                        wgpu::ImageDataLayout::default(),
+                        texture.size(),
+                    );
+                    Ok(context.register_texture(texture))
+                }
+                Some(("sampler", _)) => Ok(context.register_sampler(
+                    gpu.device
+                        .create_sampler(&wgpu::SamplerDescriptor::default()),
+                )),
+                _ => anyhow::bail!("unknown resource {}", source),
+            }
+        }
+    }
+
+    #[test]
+    fn texture_round_trip() {
+        let gpu = match gpu() {
+            Some(v) => GpuResources(Arc::new(v)),
+            None => return,
+        };
+        let serializer = SceneSerializer::default()
+            .with_resource_source("texture", gpu.clone())
+            .with_resource_source("sampler", gpu.clone());
+
+        let scene = Scene::new(RContext::new());
+        let context = scene.context();
+        let texture = gpu.load(&context, "texture:255").unwrap();
+        let sampler = gpu.load(&context, "sampler:").unwrap();
+        context.set_resource_source(&texture, "texture:255");
+        context.set_resource_source(&sampler, "sampler:");
+
+        let mut input = InputResourceBuilder::new();
+        input.add_texture(texture);
+        let material = MaterialBuilder::default()
+            .name("textured")
+            .face(
+                BasicMaterialFaceBuilder::new()
+                    .texture(input.build())
+                    .sampler(sampler)
+                    .build(),
+            )
+            .build(&context);
+        add_object(&scene, triangle(true), material, scene.root_node());
+
+        let saved = serializer.save(&scene).unwrap();
+        let desc = serializer.parse(&saved).unwrap();
+        assert_eq!(
+            desc.materials[0].params.get("texture_color"),
+            Some(&BindingValue::Resource("texture:255".to_owned()))
+        );
+
+        let loaded = Scene::new(RContext::new());
+        serializer.load(&loaded, &saved).unwrap();
+        let storage = loaded.read_container();
+        let object = storage.iter().next().unwrap();
+        let material = object.o().material_arc();
+        match material.face().query_resource("texture_color") {
+            ShaderBindingResource::Resource(r) => assert_eq!(
+                loaded.context().resource_source(r.id()).as_deref(),
+                Some("texture:255")
+            ),
+            _ => panic!("texture is not loaded"),
+        }
+        assert_eq!(serializer.save(&loaded).unwrap(), saved);
+    }
+}
@@ -1,44 +1,90 @@
 use std::{
-    any::TypeId,
+    cmp::Reverse,
     collections::{HashMap, HashSet},
     sync::Arc,
 };
 
-use crate::material::MaterialId;
+use ordered_float::OrderedFloat;
 
-use super::{Camera, SceneStorage, UNKNOWN_OBJECT};
+use crate::{
+    material::MaterialId,
+    types::{Boundary, Vec3f, Vec4f},
+};
+
+use super::{occlusion::OcclusionCulling, Camera, RenderObject, SceneStorage, UNKNOWN_OBJECT};
 
 pub trait Sorter: Send + Sync {
     fn set_camera(&mut self, camera: Arc<Camera>);
     fn add(&mut self, object: u64);
     fn sort_and_cull(&mut self) -> Vec<u64>;
     fn remove(&mut self, object: u64);
-    fn material_change(&mut self) -> bool;
+    // see `Scene::set_occlusion_culling`, sorters ignoring depth keep every object
+    fn set_occlusion(&mut self, _occlusion: Option<Arc<OcclusionCulling>>) {}
+}
+
+impl Sorter for Box<dyn Sorter> {
+    fn set_camera(&mut self, camera: Arc<Camera>) {
+        self.as_mut().set_camera(camera)
+    }
+
+    fn add(&mut self, object: u64) {
+        self.as_mut().add(object)
+    }
+
+    fn sort_and_cull(&mut self) -> Vec<u64> {
+        self.as_mut().sort_and_cull()
+    }
+
+    fn remove(&mut self, object: u64) {
+        self.as_mut().remove(object)
+    }
+
+    fn set_occlusion(&mut self, occlusion: Option<Arc<OcclusionCulling>>) {
+        self.as_mut().set_occlusion(occlusion)
+    }
+}
+
+// creates the sorter of a layer, see `Scene::set_sorter`
+pub trait SorterFactory: Send + Sync {
+    fn create(&self, storage: SceneStorage, camera: Option<Arc<Camera>>) -> Box<dyn Sorter>;
+}
+
+impl<F> SorterFactory for F
+where
+    F: Fn(SceneStorage, Option<Arc<Camera>>) -> Box<dyn Sorter> + Send + Sync,
+{
+    fn create(&self, storage: SceneStorage, camera: Option<Arc<Camera>>) -> Box<dyn Sorter> {
+        self(storage, camera)
+    }
 }
 
-pub trait SorterFactory {
-    fn create(objects: SceneStorage) -> Self;
+// squared distance from the camera to the world space center of the object
+fn depth(o: &RenderObject, camera: &Camera) -> OrderedFloat<f32> {
+    let c = match o.geometry().boundary() {
+        Boundary::AABB(v) if !v.is_empty() => v.center(),
+        _ => Vec3f::zeros(),
+    };
+    let c = o.transform().mat() * Vec4f::new(c.x, c.y, c.z, 1f32);
+    OrderedFloat((c.xyz() - camera.from()).norm_squared())
 }
 
+// draw in insertion order, objects with a lower z_order first
 pub struct UISceneSorter {
     objects: Vec<u64>,
     object_position: HashMap<u64, usize>,
-    new_material: bool,
+    storage: SceneStorage,
 }
 
 impl Sorter for UISceneSorter {
     fn add(&mut self, object: u64) {
         let pos = self.objects.len();
-        if pos == 0 {
-            self.new_material = true;
-        }
         self.objects.push(object);
         self.object_position.insert(object, pos);
     }
 
     #[profiling::function]
     fn sort_and_cull(&mut self) -> Vec<u64> {
-        let res: Vec<_> = self
+        let mut res: Vec<_> = self
             .objects
             .iter()
             .cloned()
@@ -50,6 +96,8 @@ impl Sorter for UISceneSorter {
         }
         self.objects = res.clone();
 
+        // stable, egui relies on the insertion order
+        res.sort_by_key(|v| self.storage.get(v).map(|o| o.o().z_order()));
         res
     }
 
@@ -61,30 +109,45 @@ impl Sorter for UISceneSorter {
     }
 
     fn set_camera(&mut self, _camera: Arc<Camera>) {}
-
-    fn material_change(&mut self) -> bool {
-        if self.new_material {
-            self.new_material = false;
-            return true;
-        }
-        false
-    }
 }
 
 impl UISceneSorter {
-    pub fn new() -> Self {
+    pub fn new(storage: SceneStorage) -> Self {
         Self {
             object_position: HashMap::new(),
             objects: Vec::new(),
-            new_material: false,
+            storage,
         }
     }
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DepthOrder {
+    // opaque geometry, lets early-z reject hidden fragments
+    FrontToBack,
+    // blended geometry
+    BackToFront,
+}
+
+// sort by z_order, then by the distance to the main camera
 pub struct DistanceSorter {
     objects: HashSet<u64>,
     storage: SceneStorage,
     camera: Option<Arc<Camera>>,
+    order: DepthOrder,
+    occlusion: Option<Arc<OcclusionCulling>>,
+}
+
+impl DistanceSorter {
+    pub fn new(storage: SceneStorage, camera: Option<Arc<Camera>>, order: DepthOrder) -> Self {
+        Self {
+            objects: HashSet::new(),
+            storage,
+            camera,
+            order,
+            occlusion: None,
+        }
+    }
 }
 
 impl Sorter for DistanceSorter {
@@ -98,80 +161,111 @@ impl Sorter for DistanceSorter {
 
     #[profiling::function]
     fn sort_and_cull(&mut self) -> Vec<u64> {
-        if let Some(c) = &self.camera {
-            let camera_pos = c.from().into();
-            // cull first
-            let _frustum = c.frustum_worldspace();
-
-            let mut res: Vec<_> = self
-                .objects
-                .iter()
-                .cloned()
-                .filter_map(|v| {
-                    let o = self.storage.get(&v).unwrap();
-                    let o = o.o();
-                    if !o.visible() {
-                        None
-                    } else {
-                        // if let Some(aabb) = o.geometry().boundary() {
-                        //     if !aabb.in_frustum(&frustum) {
-                        //         return None;
-                        //     }
-                        // }
-                        Some((o.geometry().boundary().distance(&camera_pos), v))
-                    }
-                })
-                .collect();
-            res.sort_by(|a, b| b.0.cmp(&a.0));
-
-            return res.iter().map(|v| v.1).collect();
+        let camera = self.camera.as_ref();
+        let mut res: Vec<_> = self
+            .objects
+            .iter()
+            .cloned()
+            .filter_map(|v| {
+                let o = self.storage.get(&v).unwrap();
+                let o = o.o();
+                if !o.visible() || self.occlusion.as_ref().is_some_and(|c| c.is_occluded(o)) {
+                    return None;
+                }
+                let d = camera.map(|c| depth(o, c)).unwrap_or_default();
+                Some((o.z_order(), d, v))
+            })
+            .collect();
+        match self.order {
+            DepthOrder::FrontToBack => res.sort(),
+            DepthOrder::BackToFront => res.sort_by_key(|v| (v.0, Reverse(v.1), v.2)),
         }
-        self.objects.iter().cloned().collect()
+
+        res.iter().map(|v| v.2).collect()
     }
 
     fn remove(&mut self, object: u64) {
         self.objects.remove(&object);
     }
 
-    fn material_change(&mut self) -> bool {
-        false
+    fn set_occlusion(&mut self, occlusion: Option<Arc<OcclusionCulling>>) {
+        self.occlusion = occlusion;
     }
 }
 
-impl SorterFactory for DistanceSorter {
-    fn create(objects: SceneStorage) -> Self {
+// sort by z_order then by object id, no camera dependency
+pub struct ZOrderSorter {
+    objects: HashSet<u64>,
+    storage: SceneStorage,
+}
+
+impl ZOrderSorter {
+    pub fn new(storage: SceneStorage) -> Self {
         Self {
             objects: HashSet::new(),
-            storage: objects,
-            camera: None,
+            storage,
         }
     }
 }
 
-pub struct MaterialSorter<T> {
-    map: HashMap<MaterialId, (T, u64)>,
+impl Sorter for ZOrderSorter {
+    fn set_camera(&mut self, _camera: Arc<Camera>) {}
+
+    fn add(&mut self, object: u64) {
+        self.objects.insert(object);
+    }
+
+    #[profiling::function]
+    fn sort_and_cull(&mut self) -> Vec<u64> {
+        let mut res: Vec<_> = self
+            .objects
+            .iter()
+            .cloned()
+            .filter_map(|v| {
+                let o = self.storage.get(&v).unwrap();
+                let o = o.o();
+                o.visible().then(|| (o.z_order(), v))
+            })
+            .collect();
+        res.sort();
+        res.iter().map(|v| v.1).collect()
+    }
+
+    fn remove(&mut self, object: u64) {
+        self.objects.remove(&object);
+    }
+}
+
+// order objects by z_order, then group them by material in the order of the
+// material sort key, each group is sorted by a sorter of `inner`
+pub struct MaterialSorter {
+    map: HashMap<MaterialId, (Box<dyn Sorter>, u64)>,
     storage: SceneStorage,
-    materials: HashMap<TypeId, u64>,
-    new_material: bool,
+    // material the object was sorted with, the storage may already hold a new one
+    objects: HashMap<u64, MaterialId>,
     camera: Option<Arc<Camera>>,
+    inner: Arc<dyn SorterFactory>,
+    occlusion: Option<Arc<OcclusionCulling>>,
 }
 
-impl<T> MaterialSorter<T> {
-    pub fn new(storage: SceneStorage, camera: Option<Arc<Camera>>) -> Self {
+impl MaterialSorter {
+    pub fn new(
+        storage: SceneStorage,
+        camera: Option<Arc<Camera>>,
+        inner: Arc<dyn SorterFactory>,
+    ) -> Self {
         Self {
             map: HashMap::new(),
             storage,
-            materials: HashMap::new(),
-            new_material: false,
+            objects: HashMap::new(),
             camera,
+            inner,
+            occlusion: None,
         }
     }
 }
 
-impl<T> Sorter for MaterialSorter<T>
-where
-    T: Sorter + SorterFactory,
-{
+impl Sorter for MaterialSorter {
     fn set_camera(&mut self, camera: Arc<Camera>) {
         self.camera = Some(camera.clone());
         for t in &mut self.map.values_mut() {
@@ -185,67 +279,87 @@ where
 
         let material = obj.material_arc();
         let material_id = material.id();
-        let face_id = material.face_id();
 
         let t = self.map.entry(material_id).or_insert_with(|| {
-            let mut t = T::create(self.storage.clone());
-            if let Some(c) = &self.camera {
-                t.set_camera(c.clone());
-            }
+            let mut t = self.inner.create(self.storage.clone(), self.camera.clone());
+            t.set_occlusion(self.occlusion.clone());
             (t, material.face().sort_key())
         });
         t.0.add(object);
-
-        let v = self
-            .materials
-            .entry(face_id)
-            .and_modify(|v| *v += 1)
-            .or_insert(1);
-        if *v == 1 {
-            log::info!("add new material trigger {} {}", object, obj.name());
-            self.new_material = true;
-        }
+        self.objects.insert(object, material_id);
     }
 
     #[profiling::function]
     fn sort_and_cull(&mut self) -> Vec<u64> {
         let mut res = vec![];
-        let mut material_list = vec![];
-        material_list.reserve(self.map.len());
+        let mut material_list = Vec::with_capacity(self.map.len());
 
         for (material_id, (_t, sort_key)) in &self.map {
-            material_list.push((*sort_key, *material_id));
+            material_list.push((*sort_key, material_id.id(), *material_id));
         }
-        material_list.sort_by(|a, b| a.0.cmp(&b.0));
+        material_list.sort_by_key(|v| (v.0, v.1));
 
-        for (_, material_id) in material_list {
+        for (_, _, material_id) in material_list {
             let t = self.map.get_mut(&material_id).unwrap();
             let res2 = t.0.sort_and_cull();
             res.extend(res2);
         }
+        // a sorter holds a single layer, z_order goes before the material.
+        // the sort is stable, materials stay grouped inside a z_order
+        res.sort_by_key(|v| self.storage.get(v).map(|o| o.o().z_order()));
 
         res
     }
 
     fn remove(&mut self, object: u64) {
-        if let Some(obj) = self.storage.get(&object) {
-            let material = obj.o().material_arc();
-            let material_id = material.id();
-            let face_id = material.face_id();
-
-            self.materials.entry(face_id).and_modify(|v| *v -= 1);
-
+        if let Some(material_id) = self.objects.remove(&object) {
             self.map
                 .entry(material_id)
                 .and_modify(|v| v.0.remove(object));
         }
     }
 
-    fn material_change(&mut self) -> bool {
-        if self.new_material {
-            self.new_material = false;
-            return true;
+    fn set_occlusion(&mut self, occlusion: Option<Arc<OcclusionCulling>>) {
+        for t in self.map.values_mut() {
+            t.0.set_occlusion(occlusion.clone());
         }
-        false
+        self.occlusion = occlusion;
+    }
+}
+
+pub struct UISorterFactory;
+
+impl SorterFactory for UISorterFactory {
+    fn create(&self, storage: SceneStorage, _camera: Option<Arc<Camera>>) -> Box<dyn Sorter> {
+        Box::new(UISceneSorter::new(storage))
+    }
+}
+
+pub struct DistanceSorterFactory(pub DepthOrder);
+
+impl SorterFactory for DistanceSorterFactory {
+    fn create(&self, storage: SceneStorage, camera: Option<Arc<Camera>>) -> Box<dyn Sorter> {
+        Box::new(DistanceSorter::new(storage, camera, self.0))
+    }
+}
+
+pub struct ZOrderSorterFactory;
+
+impl SorterFactory for ZOrderSorterFactory {
+    fn create(&self, storage: SceneStorage, _camera: Option<Arc<Camera>>) -> Box<dyn Sorter> {
+        Box::new(ZOrderSorter::new(storage))
+    }
+}
+
+// material first, then by distance in `order`
+pub struct MaterialSorterFactory(pub DepthOrder);
+
+impl SorterFactory for MaterialSorterFactory {
+    fn create(&self, storage: SceneStorage, camera: Option<Arc<Camera>>) -> Box<dyn Sorter> {
+        Box::new(MaterialSorter::new(
+            storage,
+            camera,
+            Arc::new(DistanceSorterFactory(self.0)),
+        ))
     }
 }
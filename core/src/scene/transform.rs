@@ -1,10 +1,18 @@
 use std::{fmt::Debug, ops::Mul};
 
-use crate::types::*;
+use bevy_reflect::Reflect;
 
+use crate::{
+    reflect::{ReflectProxy, Rotation},
+    types::*,
+};
+
+// translate * rotate * scale, the matrix is kept in sync with the components.
+// composition keeps the exact matrix, components are decomposed from it
 #[derive(Clone)]
 pub struct Transform {
     mat: Mat4x4f,
+    normal_mat: Mat3x3f,
     translate: Vec3f,
     scale: Vec3f,
     rotate: Quaternion,
@@ -20,10 +28,22 @@ impl Debug for Transform {
     }
 }
 
+impl PartialEq for Transform {
+    fn eq(&self, other: &Self) -> bool {
+        self.mat == other.mat
+    }
+}
+
 pub struct TransformBuilder {
     inner: Transform,
 }
 
+impl Default for TransformBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl TransformBuilder {
     pub fn new() -> Self {
         Self {
@@ -32,13 +52,8 @@ impl TransformBuilder {
     }
 
     pub fn build(self) -> Transform {
-        let mut t = self.inner;
-
-        t.mat.append_nonuniform_scaling_mut(&t.scale);
-        t.mat *= t.rotate.to_homogeneous();
-        t.mat.append_translation_mut(&t.translate);
-
-        t
+        let t = self.inner;
+        Transform::new(t.translate, t.rotate, t.scale)
     }
 
     pub fn translate(mut self, offset: Vec3f) -> Self {
@@ -53,34 +68,179 @@ impl TransformBuilder {
         self.inner.rotate *= rotate;
         self
     }
+    pub fn look_at(mut self, target: Vec3f, up: Vec3f) -> Self {
+        self.inner.rotate = look_rotation(target - self.inner.translate, up);
+        self
+    }
+}
+
+fn look_rotation(dir: Vec3f, up: Vec3f) -> Quaternion {
+    if dir.norm_squared() <= f32::EPSILON {
+        return Quaternion::identity();
+    }
+    // -z points to the target, same as cameras and gltf lights
+    Quaternion::face_towards(&-dir, &up)
+}
+
+fn normal_matrix(mat: &Mat4x4f) -> Mat3x3f {
+    mat.fixed_view::<3, 3>(0, 0)
+        .try_inverse()
+        .map(|v| v.transpose())
+        .unwrap_or_else(Mat3x3f::identity)
 }
 
 impl Transform {
+    pub fn new(translate: Vec3f, rotate: Quaternion, scale: Vec3f) -> Self {
+        let mut t = Self {
+            mat: Mat4x4f::identity(),
+            normal_mat: Mat3x3f::identity(),
+            translate,
+            scale,
+            rotate,
+        };
+        t.rebuild();
+        t
+    }
+
+    pub fn from_translate(translate: Vec3f) -> Self {
+        Self::new(
+            translate,
+            Quaternion::identity(),
+            Vec3f::new(1f32, 1f32, 1f32),
+        )
+    }
+
+    pub fn from_rotate(rotate: Quaternion) -> Self {
+        Self::new(Vec3f::zeros(), rotate, Vec3f::new(1f32, 1f32, 1f32))
+    }
+
+    pub fn from_scale(scale: Vec3f) -> Self {
+        Self::new(Vec3f::zeros(), Quaternion::identity(), scale)
+    }
+
+    // decompose an affine matrix, shear is kept in the matrix only
+    pub fn from_mat(mat: Mat4x4f) -> Self {
+        let m3: Mat3x3f = mat.fixed_view::<3, 3>(0, 0).into();
+        let mut scale = Vec3f::new(
+            m3.column(0).norm(),
+            m3.column(1).norm(),
+            m3.column(2).norm(),
+        );
+        if m3.determinant() < 0f32 {
+            scale.x = -scale.x;
+        }
+        let mut r = m3;
+        for i in 0..3 {
+            if scale[i].abs() > f32::EPSILON {
+                r.set_column(i, &(m3.column(i) / scale[i]));
+            }
+        }
+        let rotate = Quaternion::from_rotation_matrix(&Rotation3::from_matrix(&r));
+
+        Self {
+            normal_mat: normal_matrix(&mat),
+            translate: mat.fixed_view::<3, 1>(0, 3).into(),
+            mat,
+            scale,
+            rotate,
+        }
+    }
+
+    // an object placed at `eye` with -z facing `target`
+    pub fn look_at(eye: Vec3f, target: Vec3f, up: Vec3f) -> Self {
+        Self::new(
+            eye,
+            look_rotation(target - eye, up),
+            Vec3f::new(1f32, 1f32, 1f32),
+        )
+    }
+
     pub fn builder(self) -> TransformBuilder {
         TransformBuilder { inner: self }
     }
 
+    fn rebuild(&mut self) {
+        self.mat = Mat4x4f::new_translation(&self.translate)
+            * self.rotate.to_homogeneous()
+            * Mat4x4f::new_nonuniform_scaling(&self.scale);
+        self.normal_mat = normal_matrix(&self.mat);
+    }
+
+    pub fn translate(&self) -> &Vec3f {
+        &self.translate
+    }
+    pub fn rotate(&self) -> &Quaternion {
+        &self.rotate
+    }
+    pub fn scale(&self) -> &Vec3f {
+        &self.scale
+    }
+
+    pub fn set_translate(&mut self, translate: Vec3f) {
+        self.translate = translate;
+        self.rebuild();
+    }
+    pub fn set_rotate(&mut self, rotate: Quaternion) {
+        self.rotate = rotate;
+        self.rebuild();
+    }
+    pub fn set_scale(&mut self, scale: Vec3f) {
+        self.scale = scale;
+        self.rebuild();
+    }
+    pub fn set_look_at(&mut self, target: Vec3f, up: Vec3f) {
+        self.rotate = look_rotation(target - self.translate, up);
+        self.rebuild();
+    }
+
     pub fn apply(&self, vertex: Vec3f) -> Vec3f {
-        let v = Vec4f::new(vertex.x, vertex.y, vertex.z, 0f32);
-        let v4 = self.mat * v;
-        Vec3f::new(v4.x, v4.y, v4.z)
+        self.mat.transform_point(&vertex.into()).coords
+    }
+    pub fn apply_vector(&self, vector: Vec3f) -> Vec3f {
+        self.mat.transform_vector(&vector)
+    }
+    pub fn apply_normal(&self, normal: Vec3f) -> Vec3f {
+        let n = self.normal_mat * normal;
+        n.try_normalize(1e-12).unwrap_or(n)
     }
     pub fn apply_batch(
         &self,
         vertices: impl Iterator<Item = Vec3f>,
     ) -> impl Iterator<Item = Vec3f> {
         let mat = self.mat;
-        vertices.map(move |vertex| {
-            let v = Vec4f::new(vertex.x, vertex.y, vertex.z, 0f32);
-            let v4 = mat * v;
-            Vec3f::new(v4.x, v4.y, v4.z)
-        })
+        vertices.map(move |vertex| mat.transform_point(&vertex.into()).coords)
     }
+
     pub fn mat(&self) -> &Mat4x4f {
         &self.mat
     }
+    // inverse transpose of the upper 3x3
+    pub fn normal_mat(&self) -> &Mat3x3f {
+        &self.normal_mat
+    }
+
+    pub fn inverse(&self) -> Option<Transform> {
+        self.mat.try_inverse().map(Self::from_mat)
+    }
+
+    // apply `t` after self
     pub fn mul_mut(&mut self, t: &Transform) {
-        self.mat = t.mat * self.mat;
+        *self = t * &*self;
+    }
+
+    // component wise interpolation, rotation uses slerp
+    pub fn lerp(&self, other: &Transform, t: f32) -> Transform {
+        Self::new(
+            self.translate.lerp(&other.translate, t),
+            self.slerp_rotate(other, t),
+            self.scale.lerp(&other.scale, t),
+        )
+    }
+
+    fn slerp_rotate(&self, other: &Transform, t: f32) -> Quaternion {
+        self.rotate
+            .try_slerp(&other.rotate, t, 1e-6)
+            .unwrap_or_else(|| self.rotate.nlerp(&other.rotate, t))
     }
 }
 
@@ -88,6 +248,7 @@ impl Default for Transform {
     fn default() -> Self {
         Self {
             mat: Mat4x4f::identity(),
+            normal_mat: Mat3x3f::identity(),
             translate: Vec3f::default(),
             scale: Vec3f::new(1f32, 1f32, 1f32),
             rotate: Quaternion::identity(),
@@ -95,17 +256,181 @@ impl Default for Transform {
     }
 }
 
+// `a * b` applies b first, then a
 impl Mul<&Transform> for &Transform {
     type Output = Transform;
 
     fn mul(self, rhs: &Transform) -> Self::Output {
-        let t = self.translate + rhs.translate;
-        let r = self.rotate * rhs.rotate;
-        let s = self.scale.component_mul(&rhs.scale);
-        TransformBuilder::new()
-            .translate(t)
-            .rotate(r)
-            .scale(s)
-            .build()
+        Transform::from_mat(self.mat * rhs.mat)
+    }
+}
+
+impl Mul<Transform> for Transform {
+    type Output = Transform;
+
+    fn mul(self, rhs: Transform) -> Self::Output {
+        &self * &rhs
+    }
+}
+
+#[derive(Debug, Clone, Reflect)]
+pub struct TransformProxy {
+    pub translate: [f32; 3],
+    // x, y, z, w
+    #[reflect(@Rotation)]
+    pub rotate: [f32; 4],
+    pub scale: [f32; 3],
+}
+
+impl ReflectProxy for Transform {
+    type Proxy = TransformProxy;
+
+    fn reflect_proxy(&self) -> TransformProxy {
+        TransformProxy {
+            translate: self.translate.into(),
+            rotate: self.rotate.quaternion().coords.into(),
+            scale: self.scale.into(),
+        }
+    }
+
+    fn apply_proxy(&mut self, proxy: &TransformProxy) {
+        let r = proxy.rotate;
+        self.set_translate(proxy.translate.into());
+        if r.iter().any(|v| *v != 0f32) {
+            self.set_rotate(Quaternion::from_quaternion(nalgebra::Quaternion::new(
+                r[3], r[0], r[1], r[2],
+            )));
+        }
+        self.set_scale(proxy.scale.into());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn near(a: Vec3f, b: Vec3f) -> bool {
+        (a - b).norm() < 1e-4
+    }
+
+    fn near_mat(a: &Mat4x4f, b: &Mat4x4f) -> bool {
+        (a - b).abs().max() < 1e-4
+    }
+
+    fn sample() -> Transform {
+        Transform::new(
+            Vec3f::new(1f32, 2f32, 3f32),
+            Quaternion::from_axis_angle(&Vec3f::y_axis(), 0.7f32),
+            Vec3f::new(2f32, 0.5f32, 3f32),
+        )
+    }
+
+    #[test]
+    fn mul_order() {
+        let t = Transform::from_translate(Vec3f::new(1f32, 0f32, 0f32));
+        let s = Transform::from_scale(Vec3f::new(2f32, 2f32, 2f32));
+        let p = Vec3f::new(1f32, 1f32, 1f32);
+        // the right hand side is applied first
+        assert!(near((&t * &s).apply(p), Vec3f::new(3f32, 2f32, 2f32)));
+        assert!(near((&s * &t).apply(p), Vec3f::new(4f32, 2f32, 2f32)));
+
+        let mut m = s.clone();
+        m.mul_mut(&t);
+        assert!(near(m.apply(p), Vec3f::new(3f32, 2f32, 2f32)));
+    }
+
+    #[test]
+    fn inverse() {
+        let t = sample();
+        let inv = t.inverse().unwrap();
+        assert!(near_mat(&(&t * &inv).mat, &Mat4x4f::identity()));
+        let p = Vec3f::new(-1f32, 4f32, 0.5f32);
+        assert!(near(inv.apply(t.apply(p)), p));
+
+        assert!(Transform::from_scale(Vec3f::new(1f32, 0f32, 1f32))
+            .inverse()
+            .is_none());
+    }
+
+    #[test]
+    fn from_mat_non_uniform() {
+        let t = sample();
+        let d = Transform::from_mat(*t.mat());
+        assert!(near(*d.translate(), *t.translate()));
+        assert!(near(*d.scale(), *t.scale()));
+        assert!(d.rotate().angle_to(t.rotate()) < 1e-4);
+        // recomposing the components gives the same matrix
+        let r = Transform::new(*d.translate(), *d.rotate(), *d.scale());
+        assert!(near_mat(r.mat(), t.mat()));
+    }
+
+    #[test]
+    fn from_mat_mirrored() {
+        let t = Transform::new(
+            Vec3f::new(0f32, 1f32, 0f32),
+            Quaternion::from_axis_angle(&Vec3f::z_axis(), 0.3f32),
+            Vec3f::new(1f32, -2f32, 1f32),
+        );
+        let d = Transform::from_mat(*t.mat());
+        assert!(d.scale().x * d.scale().y * d.scale().z < 0f32);
+        let r = Transform::new(*d.translate(), *d.rotate(), *d.scale());
+        assert!(near_mat(r.mat(), t.mat()));
+    }
+
+    #[test]
+    fn normal_mat() {
+        // a plane x = y squashed along y, its normal must stay perpendicular
+        let t = Transform::from_scale(Vec3f::new(1f32, 0.25f32, 1f32));
+        let n = Vec3f::new(1f32, -1f32, 0f32).normalize();
+        let tangent = t.apply_vector(Vec3f::new(1f32, 1f32, 0f32));
+        let tn = t.apply_normal(n);
+        assert!(tn.dot(&tangent).abs() < 1e-5);
+        assert!((tn.norm() - 1f32).abs() < 1e-5);
+        assert!(
+            (t.normal_mat() - Mat3x3f::from_diagonal(&Vec3f::new(1f32, 4f32, 1f32)))
+                .abs()
+                .max()
+                < 1e-5
+        );
+    }
+
+    #[test]
+    fn lerp() {
+        let a = Transform::new(
+            Vec3f::zeros(),
+            Quaternion::identity(),
+            Vec3f::new(1f32, 1f32, 1f32),
+        );
+        let b = Transform::new(
+            Vec3f::new(2f32, 0f32, 0f32),
+            Quaternion::from_axis_angle(&Vec3f::y_axis(), std::f32::consts::FRAC_PI_2),
+            Vec3f::new(3f32, 3f32, 3f32),
+        );
+        let m = a.lerp(&b, 0.5f32);
+        assert!(near(*m.translate(), Vec3f::new(1f32, 0f32, 0f32)));
+        assert!(near(*m.scale(), Vec3f::new(2f32, 2f32, 2f32)));
+        assert!((m.rotate().angle() - std::f32::consts::FRAC_PI_4).abs() < 1e-5);
+        assert!(near_mat(a.lerp(&b, 0f32).mat(), a.mat()));
+        assert!(near_mat(a.lerp(&b, 1f32).mat(), b.mat()));
+    }
+
+    #[test]
+    fn proxy_round_trip() {
+        let rotate = Quaternion::from_euler_angles(0.3f32, 0.2f32, 0.1f32);
+        let t = Transform::new(
+            Vec3f::new(1f32, 2f32, 3f32),
+            rotate,
+            Vec3f::new(2f32, 2f32, 2f32),
+        );
+        let mut proxy = t.reflect_proxy();
+        let mut edited = Transform::default();
+        edited.apply_proxy(&proxy);
+        assert!(near_mat(edited.mat(), t.mat()));
+
+        // the matrix follows the edited fields
+        proxy.translate = [0f32, 0f32, 0f32];
+        edited.apply_proxy(&proxy);
+        assert!(near(edited.apply(Vec3f::zeros()), Vec3f::zeros()));
+        assert!(near(*edited.scale(), Vec3f::new(2f32, 2f32, 2f32)));
     }
 }
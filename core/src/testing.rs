@@ -0,0 +1,19 @@
+// a device for tests touching gpu resources, None without an adapter
+pub struct TestGpu {
+    pub device: wgpu::Device,
+    pub queue: wgpu::Queue,
+}
+
+pub fn gpu() -> Option<TestGpu> {
+    let instance = wgpu::Instance::new(wgpu::InstanceDescriptor::default());
+    let adapter = match pollster::block_on(instance.request_adapter(&Default::default())) {
+        Some(v) => v,
+        None => {
+            log::warn!("no gpu adapter, test skipped");
+            return None;
+        }
+    };
+    let (device, queue) =
+        pollster::block_on(adapter.request_device(&Default::default(), None)).ok()?;
+    Some(TestGpu { device, queue })
+}
@@ -91,6 +91,9 @@ impl BoundBox {
             val: Some((Vec3f::new(minx, miny, minz), Vec3f::new(maxx, maxy, maxz))),
         }
     }
+    pub fn is_empty(&self) -> bool {
+        self.val.is_none()
+    }
     pub fn min(&self) -> &Vec3f {
         &self.val.as_ref().unwrap().0
     }
@@ -0,0 +1,286 @@
+use core::{
+    reflect::{
+        bevy_reflect::{
+            attributes::CustomAttributes, Enum, ReflectMut, ReflectRef, TypeInfo, VariantInfo,
+        },
+        set_variant, Angle, ColorEdit, Logarithmic, Reflect, ReflectProxy, Rotation,
+    },
+    types::Quaternion,
+    util::{angle2rad, rad2angle},
+};
+use std::ops::RangeInclusive;
+
+// editable fields for any reflected value, returns true if anything changed
+pub fn inspect(ui: &mut egui::Ui, value: &mut dyn Reflect) -> bool {
+    inspect_value(ui, value, None)
+}
+
+// edits the proxy of a value, it is written back when anything changed
+pub fn inspect_proxy<T: ReflectProxy>(ui: &mut egui::Ui, value: &mut T) -> bool {
+    let mut proxy = value.reflect_proxy();
+    let changed = inspect(ui, &mut proxy);
+    if changed {
+        value.apply_proxy(&proxy);
+    }
+    changed
+}
+
+fn has<T: Reflect>(attributes: Option<&CustomAttributes>) -> bool {
+    attributes.map(|v| v.contains::<T>()).unwrap_or_default()
+}
+
+// values edited in a single row
+fn is_inline(value: &dyn Reflect) -> bool {
+    match value.reflect_ref() {
+        ReflectRef::Value(_) => true,
+        ReflectRef::Array(a) => a
+            .iter()
+            .all(|v| matches!(v.reflect_ref(), ReflectRef::Value(_))),
+        ReflectRef::Enum(_) => match value.get_represented_type_info() {
+            Some(TypeInfo::Enum(info)) => info.iter().all(|v| matches!(v, VariantInfo::Unit(_))),
+            _ => false,
+        },
+        _ => false,
+    }
+}
+
+fn field(
+    ui: &mut egui::Ui,
+    name: &str,
+    value: &mut dyn Reflect,
+    attributes: Option<&CustomAttributes>,
+) -> bool {
+    ui.push_id(name, |ui| {
+        if is_inline(value) {
+            ui.horizontal(|ui| {
+                ui.label(name);
+                inspect_value(ui, value, attributes)
+            })
+            .inner
+        } else {
+            egui::CollapsingHeader::new(name)
+                .default_open(true)
+                .show(ui, |ui| inspect_value(ui, value, attributes))
+                .body_returned
+                .unwrap_or_default()
+        }
+    })
+    .inner
+}
+
+fn inspect_value(
+    ui: &mut egui::Ui,
+    value: &mut dyn Reflect,
+    attributes: Option<&CustomAttributes>,
+) -> bool {
+    if has::<Rotation>(attributes) {
+        if let Some(q) = value.downcast_mut::<[f32; 4]>() {
+            return inspect_rotation(ui, q);
+        }
+    }
+    if has::<ColorEdit>(attributes) {
+        if let Some(c) = value.downcast_mut::<[f32; 4]>() {
+            return ui.color_edit_button_rgba_unmultiplied(c).changed();
+        }
+        if let Some(c) = value.downcast_mut::<[f32; 3]>() {
+            return ui.color_edit_button_rgb(c).changed();
+        }
+    }
+
+    let inline = is_inline(value);
+    let info = value.get_represented_type_info();
+    let mut changed = false;
+    match value.reflect_mut() {
+        ReflectMut::Struct(s) => {
+            let info = match info {
+                Some(TypeInfo::Struct(info)) => Some(info),
+                _ => None,
+            };
+            for i in 0..s.field_len() {
+                let name = s.name_at(i).unwrap_or_default().to_owned();
+                let attributes = info
+                    .and_then(|v| v.field_at(i))
+                    .map(|v| v.custom_attributes());
+                if let Some(f) = s.field_at_mut(i) {
+                    changed |= field(ui, &name, f, attributes);
+                }
+            }
+        }
+        ReflectMut::TupleStruct(s) => {
+            let info = match info {
+                Some(TypeInfo::TupleStruct(info)) => Some(info),
+                _ => None,
+            };
+            for i in 0..s.field_len() {
+                let attributes = info
+                    .and_then(|v| v.field_at(i))
+                    .map(|v| v.custom_attributes());
+                if let Some(f) = s.field_mut(i) {
+                    changed |= field(ui, &i.to_string(), f, attributes);
+                }
+            }
+        }
+        ReflectMut::Tuple(t) => {
+            for i in 0..t.field_len() {
+                if let Some(f) = t.field_mut(i) {
+                    changed |= field(ui, &i.to_string(), f, None);
+                }
+            }
+        }
+        ReflectMut::List(l) => {
+            for i in 0..l.len() {
+                if let Some(f) = l.get_mut(i) {
+                    changed |= field(ui, &i.to_string(), f, attributes);
+                }
+            }
+        }
+        ReflectMut::Array(a) => {
+            for i in 0..a.len() {
+                if let Some(f) = a.get_mut(i) {
+                    if inline {
+                        // item attributes (e.g. ranges) come from the array field
+                        changed |= ui.push_id(i, |ui| inspect_value(ui, f, attributes)).inner;
+                    } else {
+                        changed |= field(ui, &i.to_string(), f, attributes);
+                    }
+                }
+            }
+        }
+        ReflectMut::Map(m) => {
+            for i in 0..m.len() {
+                if let Some((k, v)) = m.get_at_mut(i) {
+                    let name = k
+                        .downcast_ref::<String>()
+                        .cloned()
+                        .unwrap_or_else(|| format!("{:?}", k));
+                    changed |= field(ui, &name, v, None);
+                }
+            }
+        }
+        ReflectMut::Enum(e) => {
+            if let Some(TypeInfo::Enum(info)) = info {
+                changed |= inspect_enum(ui, e, info.variant_names());
+                let variant = info.variant(e.variant_name());
+                for i in 0..e.field_len() {
+                    let name = e
+                        .name_at(i)
+                        .map(|v| v.to_owned())
+                        .unwrap_or_else(|| i.to_string());
+                    let attributes = match variant {
+                        Some(VariantInfo::Struct(v)) => {
+                            v.field_at(i).map(|v| v.custom_attributes())
+                        }
+                        Some(VariantInfo::Tuple(v)) => v.field_at(i).map(|v| v.custom_attributes()),
+                        _ => None,
+                    };
+                    if let Some(f) = e.field_at_mut(i) {
+                        changed |= field(ui, &name, f, attributes);
+                    }
+                }
+            }
+        }
+        ReflectMut::Value(v) => {
+            changed |= inspect_primitive(ui, v, attributes);
+        }
+    }
+    changed
+}
+
+fn inspect_enum(ui: &mut egui::Ui, e: &mut dyn Enum, variants: &[&'static str]) -> bool {
+    let current = e.variant_name().to_owned();
+    let mut selected = current.clone();
+    egui::ComboBox::from_id_salt("variant")
+        .selected_text(&current)
+        .show_ui(ui, |ui| {
+            for name in variants {
+                ui.selectable_value(&mut selected, name.to_string(), *name);
+            }
+        });
+    if selected != current {
+        if set_variant(e, &selected) {
+            return true;
+        }
+        log::warn!("can not switch {} to {}", current, selected);
+    }
+    false
+}
+
+fn inspect_rotation(ui: &mut egui::Ui, q: &mut [f32; 4]) -> bool {
+    let rotate = if q.iter().all(|v| *v == 0f32) {
+        Quaternion::identity()
+    } else {
+        Quaternion::from_quaternion((*q).into())
+    };
+    let (roll, pitch, yaw) = rotate.euler_angles();
+    let mut angles = [rad2angle(roll), rad2angle(pitch), rad2angle(yaw)];
+
+    let mut changed = false;
+    for v in &mut angles {
+        changed |= ui.add(egui::DragValue::new(v).suffix("°")).changed();
+    }
+    if changed {
+        let rotate = Quaternion::from_euler_angles(
+            angle2rad(angles[0]),
+            angle2rad(angles[1]),
+            angle2rad(angles[2]),
+        );
+        *q = rotate.quaternion().coords.into();
+    }
+    changed
+}
+
+fn inspect_primitive(
+    ui: &mut egui::Ui,
+    value: &mut dyn Reflect,
+    attributes: Option<&CustomAttributes>,
+) -> bool {
+    let range = attributes
+        .and_then(|v| v.get::<RangeInclusive<f32>>())
+        .cloned();
+    if let Some(v) = value.downcast_mut::<f32>() {
+        if has::<Angle>(attributes) {
+            let mut angle = rad2angle(*v);
+            let changed = match range {
+                Some(r) => ui
+                    .add(
+                        egui::Slider::new(&mut angle, rad2angle(*r.start())..=rad2angle(*r.end()))
+                            .suffix("°"),
+                    )
+                    .changed(),
+                None => ui
+                    .add(egui::DragValue::new(&mut angle).suffix("°"))
+                    .changed(),
+            };
+            if changed {
+                *v = angle2rad(angle);
+            }
+            return changed;
+        }
+        return match range {
+            Some(r) => ui
+                .add(egui::Slider::new(v, r).logarithmic(has::<Logarithmic>(attributes)))
+                .changed(),
+            None => ui.add(egui::DragValue::new(v).speed(0.01f32)).changed(),
+        };
+    }
+    if let Some(v) = value.downcast_mut::<bool>() {
+        return ui.checkbox(v, "").changed();
+    }
+    if let Some(v) = value.downcast_mut::<String>() {
+        return ui.text_edit_singleline(v).changed();
+    }
+
+    macro_rules! drag {
+        ($($t: ty),*) => {
+            $(
+                if let Some(v) = value.downcast_mut::<$t>() {
+                    return ui.add(egui::DragValue::new(v)).changed();
+                }
+            )*
+        };
+    }
+    drag!(f64, i32, i64, u32, u64, usize);
+
+    ui.label(format!("{:?}", value));
+    false
+}
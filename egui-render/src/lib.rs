@@ -2,7 +2,7 @@ mod mesh;
 
 use core::{
     backends::wgpu_backend::WGPUResource,
-    context::{RContext, TagId},
+    context::{RContext, ResourceRef, TagId},
     event::{EventSender, EventSource, InputEvent, ProcessEventResult},
     material::{Material, MaterialBuilder},
     mesh::StaticGeometry,
@@ -26,13 +26,16 @@ use egui::FontFamily;
 use material::{EguiMaterialFace, EguiMaterialFaceBuilder};
 use mesh::{UIMesh, UITextures};
 use rust_fontconfig::FcFontCache;
+use user_texture::UserTextures;
 use util::load_font;
 use window::WindowSize;
 
 use crate::material_render::EguiMaterialRendererFactory;
 
+pub mod inspector;
 pub mod material;
 pub mod material_render;
+pub mod user_texture;
 mod util;
 
 pub use egui;
@@ -42,6 +45,9 @@ pub struct EguiRenderer {
     ui_mesh: UIMesh,
 
     ui_materials: Option<HashMap<egui::TextureId, Arc<Material>>>,
+    user_textures: Arc<UserTextures>,
+    // materials of user textures with the resource id they were built for
+    user_materials: HashMap<u64, (u64, Arc<Material>)>,
     ui_tag: TagId,
 
     ctx: Arc<egui::Context>,
@@ -64,6 +70,8 @@ impl EguiRenderer {
             ui_mesh: UIMesh::new(ctx.clone()),
             ui_textures: Some(UITextures::default()),
             ui_materials: Some(HashMap::new()),
+            user_textures: Arc::new(UserTextures::new()),
+            user_materials: HashMap::new(),
             ui_tag,
 
             ctx: Arc::new(ctx),
@@ -226,13 +234,33 @@ impl EguiRenderer {
             ui_materials.remove(&texture_id);
         }
 
+        let build_material = |t: ResourceRef| {
+            MaterialBuilder::default()
+                .face(
+                    EguiMaterialFaceBuilder::default()
+                        .with_sampler(gpu.default_sampler())
+                        .with_texture(t)
+                        .build(),
+                )
+                .build(&scene.context())
+        };
+
         for (mesh, texture_id) in meshes {
-            let material = ui_materials.entry(texture_id).or_insert_with(|| {
-                let t = ui_textures.get(texture_id);
-                MaterialBuilder::default()
-                    .face(EguiMaterialFaceBuilder::default().with_sampler(gpu.default_sampler()).with_texture(t).build())
-                    .build(&scene.context())
-            });
+            let material = if let egui::TextureId::User(id) = texture_id {
+                let t = match self.user_textures.get(id) {
+                    Some(t) => t,
+                    None => continue,
+                };
+                let res_id = t.id();
+                if !matches!(self.user_materials.get(&id), Some(v) if v.0 == res_id) {
+                    self.user_materials.insert(id, (res_id, build_material(t)));
+                }
+                &self.user_materials.get(&id).unwrap().1
+            } else {
+                &*ui_materials
+                    .entry(texture_id)
+                    .or_insert_with(|| build_material(ui_textures.get(texture_id)))
+            };
 
             let mut object = RenderObject::new(
                 Box::new(StaticGeometry::new(Arc::new(mesh))),
@@ -409,6 +437,7 @@ impl EguiPlugin {
 
         let ctx = s.r.ctx.clone();
         container.register_arc(ctx);
+        container.register_arc(s.r.user_textures.clone());
         s
     }
 }
@@ -45,6 +45,10 @@ impl BindingResourceProvider for EguiMaterialFace {
     fn bind_group(&self) -> core::render::pso::BindGroupType {
         self.resource.bind_group()
     }
+
+    fn uses_resource(&self, id: u64) -> bool {
+        self.resource.uses_resource(id)
+    }
 }
 
 #[derive(Debug, Default)]
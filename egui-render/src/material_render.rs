@@ -13,9 +13,10 @@ use core::{
         collection::ShaderBindGroupCollection,
         material::{take_rs, MaterialRendererFactory, RenderMaterialPsoBuilder, SetupResource},
         pso::{BindGroupType, ColorTargetBuilder, RenderDescriptorObject},
-        tech::ShaderTechCollection,
+        tech::{layer_instance_id, ShaderTechCollection},
+        view::ViewId,
     },
-    scene::LayerId,
+    scene::{Layer, LayerId},
     types::Rectu,
     wgpu,
 };
@@ -53,6 +54,7 @@ impl<'a> BindingResourceProvider for EguiMaterialShaderResourceProvider<'a> {
 pub struct EguiMaterialHardwareRenderer {
     inner: EguiMaterialHardwareRendererInner,
     layer: LayerId,
+    view: ViewId,
 }
 
 impl EguiMaterialHardwareRenderer {}
@@ -67,12 +69,12 @@ impl RenderPassExecutor for EguiMaterialHardwareRenderer {
         let inner = &mut self.inner;
         inner.main_buffers.recall();
         let rs = take_rs::<EguiMaterialFace>(&context)?;
-        let c = rs.scene.get_container();
+        let c = rs.scene.read_container();
 
         // copy vertices and indices
         let gpu_ref = engine.gpu_ref();
 
-        let layer = rs.layer(self.layer);
+        let layer = rs.layer(self.view, self.layer)?;
 
         for indirect in &layer.material {
             let objects = layer.objects(indirect);
@@ -118,11 +120,17 @@ impl RenderPassExecutor for EguiMaterialHardwareRenderer {
         let inner = &mut self.inner;
 
         let rs = take_rs::<EguiMaterialFace>(&context).unwrap();
-        let layer = rs.layer(self.layer);
+        let layer = match rs.layer(self.view, self.layer) {
+            Some(v) => v,
+            None => return,
+        };
         for indirect in &layer.material {
-            let pso = inner
-                .material_shader_collector
-                .get("egui", indirect.material.face().variants(), indirect.material.id().id(), "egui");
+            let pso = inner.material_shader_collector.get(
+                "egui",
+                indirect.material.face().variants(),
+                layer_instance_id(&indirect.material, self.layer),
+                "egui",
+            );
 
             let rp = EguiMaterialShaderResourceProvider {
                 mat: &indirect.material,
@@ -146,13 +154,19 @@ impl RenderPassExecutor for EguiMaterialHardwareRenderer {
         let inner = &mut self.inner;
 
         let rs = take_rs::<EguiMaterialFace>(&context).unwrap();
-        let layer = rs.layer(self.layer);
-        let mut pass = engine.begin(layer.layer);
+        let layer = match rs.layer(self.view, self.layer) {
+            Some(v) => v,
+            None => return,
+        };
+        let mut pass = layer.begin(engine);
 
         for indirect in &layer.material {
-            let pso = inner
-                .material_shader_collector
-                .get("egui", indirect.material.face().variants(), indirect.material.id().id(), "egui");
+            let pso = inner.material_shader_collector.get(
+                "egui",
+                indirect.material.face().variants(),
+                layer_instance_id(&indirect.material, self.layer),
+                "egui",
+            );
 
             pass.set_pipeline(pso.render());
             pass.set_bind_group(0, &layer.main_camera.bind_group, &[0]);
@@ -189,6 +203,51 @@ impl RenderPassExecutor for EguiMaterialHardwareRenderer {
     }
 }
 
+fn setup_pso(
+    materials: &[Arc<Material>],
+    layer: LayerId,
+    gpu: &WGPUResource,
+    setup_resource: &SetupResource,
+) {
+    let depth_format = wgpu::TextureFormat::Depth32Float;
+    let settings = setup_resource
+        .scene
+        .find_layer(layer)
+        .unwrap_or_else(|| Layer::new(layer, "invalid"));
+    for material in materials {
+        setup_resource
+            .shader_tech_collection
+            .setup(
+                gpu.device(),
+                "egui",
+                material.face().variants(),
+                layer_instance_id(material, layer),
+                |_| {
+                    let mut target = ColorTargetBuilder::new(gpu.surface_format());
+                    target = match material.blend().or(settings.blend.as_ref()) {
+                        Some(blend) => target.set_blender(*blend),
+                        None => target.set_append_blender(),
+                    };
+                    let mut rdo = RenderDescriptorObject::new();
+                    rdo = rdo
+                        .set_depth(depth_format, |depth: &mut _| {
+                            depth.depth_compare = settings.depth_compare(setup_resource.depth);
+                            depth.depth_write_enabled = settings.writes_depth(true);
+                        })
+                        .vertex_no_split()
+                        .set_primitive(|primitive: &mut _| {
+                            primitive.cull_mode = None;
+                        })
+                        .set_msaa(setup_resource.msaa)
+                        .add_target(target.build());
+
+                    rdo
+                },
+            )
+            .unwrap();
+    }
+}
+
 #[derive(Default)]
 pub struct EguiMaterialRendererFactory {}
 
@@ -219,31 +278,8 @@ impl MaterialRendererFactory for EguiMaterialRendererFactory {
         });
         let sampler = ctx.register_sampler(sampler);
 
-        let depth_format = wgpu::TextureFormat::Depth32Float;
-
         for (layer, materials) in &materials_map.map {
-            setup_resource
-                .shader_tech_collection
-                .setup_materials(gpu.device(), materials, "egui", |material, _| {
-                    let mut rdo = RenderDescriptorObject::new();
-                    rdo = rdo
-                        .set_depth(depth_format, |depth: &mut _| {
-                            depth.depth_compare = wgpu::CompareFunction::LessEqual;
-                        })
-                        .vertex_no_split()
-                        .set_primitive(|primitive: &mut _| {
-                            primitive.cull_mode = None;
-                        })
-                        .set_msaa(setup_resource.msaa)
-                        .add_target(
-                            ColorTargetBuilder::new(gpu.surface_format())
-                                .set_append_blender()
-                                .build(),
-                        );
-
-                    rdo
-                })
-                .unwrap();
+            setup_pso(materials, *layer, gpu, setup_resource);
 
             let r = Arc::new(Mutex::new(EguiMaterialHardwareRenderer {
                 inner: EguiMaterialHardwareRendererInner {
@@ -258,26 +294,23 @@ impl MaterialRendererFactory for EguiMaterialRendererFactory {
                     ),
                 },
                 layer: *layer,
+                view: setup_resource.view,
             }));
 
-            let mut pass = RenderPassBuilder::new(format!("egui pass layer {}", layer));
+            let mut pass = RenderPassBuilder::new(format!(
+                "egui pass view {} layer {}",
+                setup_resource.view, layer
+            ));
+            let target = &setup_resource.target;
             pass.render_target(RenderTargetDescriptor {
-                colors: smallvec::smallvec![ColorRenderTargetDescriptor {
-                    prefer_attachment: PreferAttachment::Default,
-                    resolve_attachment: PreferAttachment::Default,
-                    ops: ResourceOps {
-                        load: None,
-                        store: true,
-                    },
-                }],
-                depth: Some(DepthRenderTargetDescriptor {
-                    prefer_attachment: PreferAttachment::Default,
-                    depth_ops: Some(ResourceOps {
-                        load: Some(ClearValue::Depth(1.0f32)),
-                        store: true,
-                    }),
-                    stencil_ops: None,
-                }),
+                colors: smallvec::smallvec![target.color_target(ResourceOps {
+                    load: None,
+                    store: true,
+                })],
+                depth: Some(target.depth_target(Some(ResourceOps {
+                    load: Some(ClearValue::Depth(1.0f32)),
+                    store: true,
+                }))),
             });
 
             pass.add_constraint(PassConstraint::Last);
@@ -287,4 +320,16 @@ impl MaterialRendererFactory for EguiMaterialRendererFactory {
             g.add_render_pass(pass);
         }
     }
+
+    fn setup_materials(
+        &self,
+        materials_map: &RenderMaterialPsoBuilder,
+        gpu: &WGPUResource,
+        setup_resource: &SetupResource,
+    ) -> bool {
+        for materials in materials_map.map.values() {
+            setup_pso(materials, *layer, gpu, setup_resource);
+        }
+        true
+    }
 }
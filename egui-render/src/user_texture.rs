@@ -0,0 +1,32 @@
+use core::context::ResourceRef;
+use std::{collections::HashMap, sync::Mutex};
+
+// textures of the app shown with `egui::TextureId::User(id)`, the texture
+// bound to an id can change every frame
+#[derive(Debug, Default)]
+pub struct UserTextures {
+    textures: Mutex<HashMap<u64, ResourceRef>>,
+}
+
+impl UserTextures {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn set(&self, id: u64, texture: Option<ResourceRef>) -> egui::TextureId {
+        let mut textures = self.textures.lock().unwrap();
+        match texture {
+            Some(t) => {
+                textures.insert(id, t);
+            }
+            None => {
+                textures.remove(&id);
+            }
+        }
+        egui::TextureId::User(id)
+    }
+
+    pub fn get(&self, id: u64) -> Option<ResourceRef> {
+        self.textures.lock().unwrap().get(&id).cloned()
+    }
+}
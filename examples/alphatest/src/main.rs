@@ -75,7 +75,11 @@ impl AppEventProcessor for MainLogic {
                 }
             }
         } else if let Some(ev) = event.downcast_ref::<core::event::Event>() {
-            if let core::event::Event::Input(input) = &ev {
+            if let core::event::Event::Update(delta) = &ev {
+                if let Some(ct) = &self.ct {
+                    ct.borrow_mut().on_update(*delta as f32);
+                }
+            } else if let core::event::Event::Input(input) = &ev {
                 if let Some(ct) = &mut self.ct {
                     ct.borrow_mut().on_input(input);
                 }
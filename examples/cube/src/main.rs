@@ -1,28 +1,32 @@
 use core::{
+    backends::wgpu_backend::WGPUResource,
     context::RContext,
     material::{
         basic::BasicMaterialFaceBuilder, input::InputResourceBuilder, MaterialBuilder
     },
     mesh::StaticGeometry,
+    render::view::RenderTexture,
     scene::{
         controller::{orbit::OrbitCameraController, CameraController},
-        Camera, RenderObject, Scene,
+        Camera, CameraId, CameraSettings, CameraTarget, LayerMask, RenderObject, Scene,
+        TransformBuilder, LAYER_BACKGROUND, LAYER_OVERLAY_GIZMO,
     },
-    types::{Color, Size, Vec3f},
+    types::{Color, Quaternion, Size, Vec3f, Vec4f},
 };
 use std::{any::Any, cell::RefCell, sync::Arc};
 
 use app::{App, AppEventProcessor};
-use geometry::mesh::CubeMeshBuilder;
+use geometry::mesh::{CubeMeshBuilder, PlaneMeshBuilder};
 use window::{HardwareRenderPluginFactory, Msaa, MsaaResource, WindowPluginFactory};
 
 #[derive(Default)]
 pub struct MainLogic {
     ct: Option<Box<RefCell<dyn CameraController>>>,
+    pip: Option<CameraId>,
 }
 
 impl MainLogic {
-    fn on_startup(&mut self, scene: &core::scene::Scene) {
+    fn on_startup(&mut self, scene: &core::scene::Scene, gpu: &WGPUResource) {
         let mesh = CubeMeshBuilder::default()
             .enable_color(Color::new(1.0f32, 1.0f32, 1.0f32, 1.0f32))
             .set_color_front_face(Color::new(0.8f32, 0.8f32, 0.8f32, 1f32))
@@ -43,6 +47,25 @@ impl MainLogic {
         let obj = RenderObject::new(Box::new(geometry), material).unwrap();
         scene.add(obj);
 
+        // a marker inside the cube, the overlay gizmo layer ignores depth
+        let mesh = CubeMeshBuilder::default().build();
+        let geometry = StaticGeometry::new(Arc::new(mesh)).with_transform(
+            TransformBuilder::new()
+                .scale(Vec3f::new(0.2f32, 0.2f32, 0.2f32))
+                .build(),
+        );
+        let material = MaterialBuilder::default()
+            .face(
+                BasicMaterialFaceBuilder::new()
+                    .texture(InputResourceBuilder::only_constant(Color::new(
+                        1f32, 0.5f32, 0f32, 1f32,
+                    )))
+                    .build(),
+            )
+            .build(&scene.context());
+        let obj = RenderObject::new(Box::new(geometry), material).unwrap();
+        scene.add_with(obj, LAYER_OVERLAY_GIZMO);
+
         let camera = Camera::new();
         camera.make_perspective(1f32, std::f32::consts::PI / 2f32, 0.01f32, 100f32);
 
@@ -58,6 +81,61 @@ impl MainLogic {
             camera.clone(),
         ))));
         scene.set_main_camera(camera);
+
+        // picture in picture, looking from the top
+        let top = Camera::new();
+        top.make_perspective(1f32, std::f32::consts::PI / 2f32, 0.01f32, 100f32);
+        top.look_at(
+            Vec3f::new(0f32, 2f32, 0.01f32),
+            Vec3f::zeros(),
+            Vec3f::new(0f32, 1f32, 0f32),
+        );
+        let settings = CameraSettings::new()
+            .viewport(Vec4f::new(0.7f32, 0.05f32, 0.25f32, 0.25f32))
+            .clear_color(Some(Color::new(0.1f32, 0.1f32, 0.1f32, 1f32)))
+            .priority(1);
+        self.pip = Some(scene.add_camera_with(Arc::new(top), settings));
+
+        // a security camera rendering into a texture shown on a monitor,
+        // the monitor is in the background layer which the camera skips
+        let texture = Arc::new(RenderTexture::new(
+            gpu,
+            Some("monitor"),
+            Size::new(256, 256),
+        ));
+        let security = Camera::new();
+        security.make_perspective(1f32, std::f32::consts::PI / 3f32, 0.01f32, 100f32);
+        security.look_at(
+            Vec3f::new(2f32, 1.5f32, 2f32),
+            Vec3f::zeros(),
+            Vec3f::new(0f32, 1f32, 0f32),
+        );
+        let settings = CameraSettings::new()
+            .clear_color(Some(Color::new(0.1f32, 0.1f32, 0.3f32, 1f32)))
+            .layer_mask(LayerMask::ALL.without(LAYER_BACKGROUND))
+            .target(CameraTarget::Texture(texture.clone()));
+        scene.add_camera_with(Arc::new(security), settings);
+
+        let mesh = PlaneMeshBuilder::default().enable_texture().build();
+        let geometry = StaticGeometry::new(Arc::new(mesh)).with_transform(
+            TransformBuilder::new()
+                .translate(Vec3f::new(1.2f32, 0.6f32, -1f32))
+                .rotate(Quaternion::from_axis_angle(
+                    &Vec3f::x_axis(),
+                    std::f32::consts::FRAC_PI_2,
+                ))
+                .build(),
+        );
+        let material = MaterialBuilder::default()
+            .face(
+                BasicMaterialFaceBuilder::new()
+                    .texture(InputResourceBuilder::only_texture(texture.texture()))
+                    .sampler(gpu.default_sampler())
+                    .build(),
+            )
+            .build(&scene.context());
+        let obj = RenderObject::new(Box::new(geometry), material).unwrap();
+        scene.add_with(obj, LAYER_BACKGROUND);
     }
 }
 
@@ -67,11 +145,16 @@ impl AppEventProcessor for MainLogic {
             match ev {
                 app::Event::Startup => {
                     let scene = context.container.get::<Scene>().unwrap();
-                    self.on_startup(&scene);
+                    let gpu = context.container.get::<WGPUResource>().unwrap();
+                    self.on_startup(&scene, &gpu);
                 }
             }
         } else if let Some(ev) = event.downcast_ref::<core::event::Event>() {
-            if let core::event::Event::Input(input) = &ev {
+            if let core::event::Event::Update(delta) = &ev {
+                if let Some(ct) = &self.ct {
+                    ct.borrow_mut().on_update(*delta as f32);
+                }
+            } else if let core::event::Event::Input(input) = &ev {
                 if let Some(ct) = &mut self.ct {
                     ct.borrow_mut().on_input(input);
                 }
@@ -84,6 +167,16 @@ impl AppEventProcessor for MainLogic {
                             core::event::VirtualKeyCode::F8 => Some(8),
                             _ => None,
                         };
+                        if key.vk == core::event::VirtualKeyCode::P
+                            && key.state == core::event::ElementState::Pressed
+                        {
+                            let scene = context.container.get::<Scene>().unwrap();
+                            if let Some(id) = self.pip {
+                                let settings = scene.camera_settings(id).unwrap();
+                                let active = !settings.active;
+                                scene.set_camera_settings(id, settings.active(active));
+                            }
+                        }
                         if let Some(s) = sampler_count {
                             context
                                 .container
@@ -107,7 +200,7 @@ fn main() {
 
     let mut app = App::new(context);
     app.register_plugin(WindowPluginFactory::new(
-        "Cube Msaa-F1-F2-F4-F8",
+        "Cube Msaa-F1-F2-F4-F8 PiP-P",
         Size::new(600, 600),
     ));
     app.register_plugin(HardwareRenderPluginFactory);
@@ -1,19 +1,26 @@
 use core::{
     context::RContext,
+    graph::rdg::inspect::RenderGraphInspector,
+    material::MaterialArc,
     scene::{
-        controller::{CameraController, CameraControllerFactory},
+        controller::{
+            path::{CameraKeyframe, CameraPath, CameraPathController},
+            CameraController, CameraControllerFactory,
+        },
         Camera, Scene,
     },
-    types::{Size, Vec3f, Vec4f},
-    util::{angle2rad, rad2angle},
+    types::{Size, Vec3f},
+    util::angle2rad,
 };
 use std::{any::Any, cell::RefCell, sync::Arc};
 
 use app::{container::Container, App, AppEventProcessor};
 use egui_render::egui;
+use egui_render::inspector::{inspect, inspect_proxy};
+use egui_render::user_texture::UserTextures;
 use egui_render::EguiPluginFactory;
 use gltfloader::{GltfPluginFactory, Loader};
-use phong_render::PhongPluginFactory;
+use phong_render::{light::SceneLights, PhongPluginFactory};
 use rfd::{FileDialog, MessageDialog};
 use window::{
     HardwareRenderPluginFactory, MainWindowHandle, StatisticsResource, WindowPluginFactory,
@@ -21,25 +28,24 @@ use window::{
 
 #[derive(Default)]
 struct CameraSideState {
-    perspective: bool,
-    fov: f32,
-    aspect: f32,
-
-    rect: Vec4f,
-
-    near: f32,
-    far: f32,
-
     controller: String,
 }
 
+// user texture id of the render graph preview
+const GRAPH_PREVIEW_TEXTURE: u64 = 1;
+
 #[derive(Default)]
 pub struct MainLogic {
     reset_camera: Option<Camera>,
     cur_camera: Option<Arc<Camera>>,
     controller: Option<Box<RefCell<dyn CameraController>>>,
     show_camera_side: bool,
+    show_graph_side: bool,
+    show_scene_side: bool,
     camera_state: CameraSideState,
+    // physical size of the window
+    size: Size,
+    path: Option<CameraPathController>,
 }
 
 impl MainLogic {
@@ -55,18 +61,6 @@ impl MainLogic {
 
         scene.set_main_camera(Arc::new(camera));
         self.cur_camera = scene.main_camera_ref();
-        self.fill_camera_state();
-    }
-
-    fn fill_camera_state(&mut self) {
-        if let Some(camera) = &self.cur_camera {
-            self.camera_state.perspective = camera.is_perspective();
-            self.camera_state.aspect = camera.aspect();
-            self.camera_state.far = camera.far();
-            self.camera_state.near = camera.near();
-            self.camera_state.fov = camera.fovy();
-        }
-        // self.camera_state.rect = ca
     }
 }
 
@@ -81,9 +75,24 @@ impl AppEventProcessor for MainLogic {
             }
         } else if let Some(ev) = event.downcast_ref::<core::event::Event>() {
             match ev {
-                core::event::Event::Update(_) => {
+                core::event::Event::Update(delta) => {
                     let ctx = context.container.get::<egui::Context>().unwrap();
                     self.draw_egui(&ctx, context.container);
+                    // a playing path owns the camera
+                    match &mut self.path {
+                        Some(p) if p.is_playing() => p.on_update(*delta as f32),
+                        _ => {
+                            if let Some(c) = &mut self.controller {
+                                c.borrow_mut().on_update(*delta as f32);
+                            }
+                        }
+                    }
+                }
+                core::event::Event::Resized { physical, .. } => {
+                    self.size = *physical;
+                    if let Some(c) = &mut self.controller {
+                        c.borrow_mut().on_resize(*physical);
+                    }
                 }
                 core::event::Event::Input(input) => {
                     if let Some(c) = &mut self.controller {
@@ -108,8 +117,6 @@ impl AppEventProcessor for MainLogic {
                     self.reset_camera = Some((*c).clone());
                     s.set_main_camera(c.clone());
                     self.cur_camera = Some(c);
-
-                    self.fill_camera_state();
                 } else {
                     let main_window = context.container.get::<MainWindowHandle>().unwrap();
                     MessageDialog::new()
@@ -140,6 +147,203 @@ impl MainLogic {
             }
         }
     }
+    #[cfg(not(target_arch = "wasm32"))]
+    fn path_file(container: &Container, save: bool) -> Option<std::path::PathBuf> {
+        let main_window = container.get::<MainWindowHandle>().unwrap();
+        let dialog = FileDialog::new()
+            .set_parent(&*main_window)
+            .add_filter("camera path", &["ron"]);
+        if save {
+            dialog.set_title("save camera path").save_file()
+        } else {
+            dialog.set_title("load camera path").pick_file()
+        }
+    }
+
+    #[cfg(target_arch = "wasm32")]
+    fn path_file(_container: &Container, _save: bool) -> Option<std::path::PathBuf> {
+        None
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    fn graph_file(container: &Container, ext: &str) -> Option<std::path::PathBuf> {
+        let main_window = container.get::<MainWindowHandle>().unwrap();
+        FileDialog::new()
+            .set_parent(&*main_window)
+            .add_filter(ext, &[ext])
+            .set_file_name(format!("graph.{}", ext))
+            .set_title("export render graph")
+            .save_file()
+    }
+
+    #[cfg(target_arch = "wasm32")]
+    fn graph_file(_container: &Container, _ext: &str) -> Option<std::path::PathBuf> {
+        None
+    }
+
+    fn graph_side(ui: &mut egui::Ui, container: &Container) {
+        let inspector = container.get::<RenderGraphInspector>().unwrap();
+        let graph = match inspector.graph() {
+            Some(v) => v,
+            None => {
+                ui.label("no render graph");
+                return;
+            }
+        };
+        let stats = inspector.pool_stats();
+        ui.label(format!(
+            "allocated {} KB, pooled {} KB, aliased {} KB",
+            stats.allocated_bytes / 1024,
+            stats.pooled_bytes / 1024,
+            stats.aliased_bytes / 1024
+        ));
+        ui.label(format!(
+            "pool holds {} resources, {} KB",
+            stats.resident_count,
+            stats.resident_bytes / 1024
+        ));
+        ui.horizontal(|ui| {
+            if ui.button("export dot").clicked() {
+                if let Some(file) = Self::graph_file(container, "dot") {
+                    if let Err(e) = std::fs::write(&file, inspector.dot()) {
+                        log::error!("export render graph {:?} fail {}", file, e);
+                    }
+                }
+            }
+            if ui.button("export json").clicked() {
+                if let Some(file) = Self::graph_file(container, "json") {
+                    let res = graph.to_json().and_then(|s| Ok(std::fs::write(&file, s)?));
+                    if let Err(e) = res {
+                        log::error!("export render graph {:?} fail {}", file, e);
+                    }
+                }
+            }
+        });
+
+        ui.separator();
+        egui::CollapsingHeader::new("Passes").show(ui, |ui| {
+            for pass in &graph.passes {
+                let title = match pass.order {
+                    Some(order) => format!("{}: {}", order, pass.name),
+                    None => format!("{} (not run)", pass.name),
+                };
+                ui.collapsing(title, |ui| {
+                    for (id, usage) in pass.reads.iter().chain(pass.writes.iter()) {
+                        let name = graph.resource(*id).map(|v| v.name.as_str());
+                        ui.label(format!("{:?} {} #{}", usage, name.unwrap_or("?"), id));
+                    }
+                    if !pass.after.is_empty() {
+                        ui.label(format!("after {}", pass.after.join(", ")));
+                    }
+                });
+            }
+        });
+
+        // textures are captured after their last pass
+        let mut capture = inspector.capture();
+        egui::CollapsingHeader::new("Resources")
+            .default_open(true)
+            .show(ui, |ui| {
+                for res in &graph.resources {
+                    let lifetime = res
+                        .lifetime
+                        .map(|(beg, end)| format!("[{}, {}]", beg, end))
+                        .unwrap_or_default();
+                    let text = format!("#{} {} {} {}", res.id, res.name, res.detail, lifetime);
+                    if res.kind == "texture" {
+                        ui.selectable_value(&mut capture, Some(res.id), text);
+                    } else {
+                        ui.label(text);
+                    }
+                }
+            });
+        if capture != inspector.capture() {
+            inspector.set_capture(capture);
+        }
+
+        let user_textures = container.get::<UserTextures>().unwrap();
+        if capture.is_none() {
+            user_textures.set(GRAPH_PREVIEW_TEXTURE, None);
+            return;
+        }
+        ui.separator();
+        match inspector.captured() {
+            Some(texture) => {
+                let size = texture.texture_ref().size();
+                let id = user_textures.set(GRAPH_PREVIEW_TEXTURE, Some(texture));
+                let w = ui.available_width().min(size.width as f32);
+                let h = w * size.height as f32 / size.width as f32;
+                ui.image(egui::load::SizedTexture::new(id, egui::vec2(w, h)));
+            }
+            None => {
+                user_textures.set(GRAPH_PREVIEW_TEXTURE, None);
+                ui.label("only single sampled 2d textures can be previewed");
+            }
+        }
+    }
+
+    fn path_side(
+        ui: &mut egui::Ui,
+        container: &Container,
+        camera: &Arc<Camera>,
+        path: &mut Option<CameraPathController>,
+    ) {
+        ui.horizontal(|ui| {
+            if ui.button("load").clicked() {
+                if let Some(file) = Self::path_file(container, false) {
+                    match CameraPath::load_from_file(&file) {
+                        Ok(v) => *path = Some(CameraPathController::new(camera.clone(), v)),
+                        Err(e) => log::error!("load camera path {:?} fail {}", file, e),
+                    }
+                }
+            }
+            if ui.button("add keyframe").clicked() {
+                let p = path.get_or_insert_with(|| {
+                    CameraPathController::new(camera.clone(), CameraPath::new())
+                });
+                let time = if p.path().keyframes.is_empty() {
+                    0f32
+                } else {
+                    p.duration() + 2f32
+                };
+                p.path_mut()
+                    .add_keyframe(CameraKeyframe::from_camera(time, camera));
+            }
+            if let Some(p) = path {
+                if ui.button("save").clicked() {
+                    if let Some(file) = Self::path_file(container, true) {
+                        if let Err(e) = p.path().save_to_file(&file) {
+                            log::error!("save camera path {:?} fail {}", file, e);
+                        }
+                    }
+                }
+            }
+        });
+
+        let p = match path {
+            Some(v) => v,
+            None => return,
+        };
+        ui.horizontal(|ui| {
+            let text = if p.is_playing() { "pause" } else { "play" };
+            if ui.button(text).clicked() {
+                if p.is_playing() {
+                    p.pause();
+                } else {
+                    p.play();
+                }
+            }
+            ui.checkbox(&mut p.path_mut().looped, "loop");
+        });
+        let mut time = p.time();
+        if ui
+            .add(egui::Slider::new(&mut time, 0f32..=p.duration()).text("time"))
+            .changed()
+        {
+            p.seek(time);
+        }
+    }
+
     fn main_side(
         &mut self,
         _ctx: &egui::Context,
@@ -167,102 +371,106 @@ impl MainLogic {
                     ui.close_menu();
                 }
             });
+            ui.menu_button("Scene", |ui| {
+                if ui.button("show").clicked() {
+                    self.show_scene_side = true;
+                    ui.close_menu();
+                }
+            });
             ui.menu_button("Camera", |ui| {
                 if ui.button("show").clicked() {
                     self.show_camera_side = true;
                     ui.close_menu();
                 }
             });
+            ui.menu_button("Debug", |ui| {
+                if ui.button("render graph").clicked() {
+                    self.show_graph_side = true;
+                    ui.close_menu();
+                }
+            });
         });
 
         ui.label(format!("fps {}", fps));
     }
 
-    fn camera_view(ui: &mut egui::Ui, camera: &Camera) {
-        ui.horizontal(|ui| {
-            ui.label("from: ");
-            ui.label(format!("{:?}", camera.from()));
-        });
-        ui.horizontal(|ui| {
-            ui.label("to: ");
-            ui.label(format!("{:?}", camera.to()));
-        });
-        ui.horizontal(|ui| {
-            ui.label("up: ");
-            ui.label(format!("{:?}", camera.up()));
-        });
-        ui.horizontal(|ui| {
-            ui.label("distance: ");
-            ui.label(format!(
-                "{}",
-                nalgebra::distance(&camera.from().into(), &camera.to().into())
-            ));
-        });
+    fn layers_inspect(ui: &mut egui::Ui, scene: &Scene) {
+        for mut layer in scene.registered_layers() {
+            let id = layer.id;
+            egui::CollapsingHeader::new(layer.name.clone())
+                .id_salt(id)
+                .show(ui, |ui| {
+                    if inspect(ui, &mut layer) {
+                        layer.id = id;
+                        scene.register_layer(layer);
+                    }
+                });
+        }
     }
 
-    fn camera_project(ui: &mut egui::Ui, state: &mut CameraSideState, camera: &Camera) {
-        if ui
-            .horizontal(|ui| {
-                ui.selectable_value(&mut state.perspective, true, "Perspective");
-                ui.selectable_value(&mut state.perspective, false, "Orthographic")
-            })
-            .response
-            .changed()
-        {
-            if state.perspective {
-                camera.make_perspective(state.aspect, state.fov, state.near, state.far);
-            } else {
-                camera.make_orthographic(state.rect, state.near, state.far);
+    fn lights_inspect(ui: &mut egui::Ui, scene: &Scene) {
+        let lights = match scene.get_resource::<SceneLights>() {
+            Some(v) => v,
+            None => {
+                ui.label("no lights");
+                return;
             }
+        };
+        let all = lights
+            .direct_light()
+            .into_iter()
+            .chain(lights.extra_lights());
+        for (index, light) in all.enumerate() {
+            let mut edited = light.as_ref().clone();
+            egui::CollapsingHeader::new(format!("light {}", index))
+                .id_salt(index)
+                .show(ui, |ui| {
+                    if inspect_proxy(ui, &mut edited)
+                        && lights.replace_light(&light, edited).is_none()
+                    {
+                        log::warn!("light {} can't change its kind", index);
+                    }
+                });
         }
+    }
 
-        if state.perspective {
-            ui.horizontal_wrapped(|ui| {
-                ui.label("fov");
-                let mut fov = rad2angle(state.fov);
-                if ui
-                    .add(egui::Slider::new(&mut fov, 40f32..=120f32).suffix("°"))
-                    .changed()
-                {
-                    state.fov = angle2rad(fov);
-                    camera.set_fov(state.fov);
-                }
-            });
-            ui.horizontal_wrapped(|ui| {
-                ui.label("aspect");
-                if ui
-                    .add(egui::Slider::new(&mut state.aspect, 0.01f32..=100f32).logarithmic(true))
-                    .changed()
-                {
-                    camera.set_aspect(state.aspect);
-                }
-            });
+    fn materials_inspect(ui: &mut egui::Ui, scene: &Scene) {
+        let mut materials: Vec<MaterialArc> = vec![];
+        for w in scene.read_container().iter() {
+            let material = w.o().material_arc();
+            if !materials.iter().any(|v| v.id() == material.id()) {
+                materials.push(material);
+            }
         }
+        materials.sort_by_key(|v| v.id().id());
+        for mut material in materials {
+            let id = material.id().id();
+            egui::CollapsingHeader::new(format!("{} {}", material.name(), id))
+                .id_salt(id)
+                .show(ui, |ui| inspect_proxy(ui, &mut material));
+        }
+    }
 
-        ui.horizontal_wrapped(|ui| {
-            ui.label("near");
-            if ui
-                .add(
-                    egui::Slider::new(&mut state.near, 0.00001f32..=(state.far - 0.1f32))
-                        .logarithmic(true),
-                )
-                .changed()
-            {
-                camera.set_near(state.near);
-            }
-        });
+    fn scene_side(ui: &mut egui::Ui, container: &Container) {
+        let scene = container.get::<Scene>().unwrap();
+        ui.collapsing("Layers", |ui| Self::layers_inspect(ui, &scene));
+        ui.separator();
+        ui.collapsing("Lights", |ui| Self::lights_inspect(ui, &scene));
+        ui.separator();
+        ui.collapsing("Materials", |ui| Self::materials_inspect(ui, &scene));
+    }
 
-        ui.horizontal_wrapped(|ui| {
-            ui.label("far");
-            if ui
-                .add(
-                    egui::Slider::new(&mut state.far, (state.near + 0.1f32)..=100000f32)
-                        .logarithmic(true),
-                )
-                .changed()
-            {
-                camera.set_near(state.far);
-            }
+    fn camera_inspect(ui: &mut egui::Ui, camera: &Camera) {
+        let mut edited = camera.clone();
+        if inspect_proxy(ui, &mut edited) {
+            camera.copy_from(&edited);
+        }
+        ui.horizontal(|ui| {
+            ui.label("distance: ");
+            ui.label(format!(
+                "{}",
+                nalgebra::distance(&camera.from().into(), &camera.to().into())
+            ));
         });
     }
 
@@ -272,6 +480,8 @@ impl MainLogic {
         camera: &Arc<Camera>,
         controller_factory: &CameraControllerFactory,
         controller: &mut Option<Box<RefCell<dyn CameraController>>>,
+        scene: &Scene,
+        size: Size,
     ) {
         let c = state.controller.clone();
         if egui::ComboBox::from_label("Controller")
@@ -301,8 +511,17 @@ impl MainLogic {
                 *controller = None;
             } else {
                 *controller = controller_factory.create(&state.controller, camera.clone());
+                if let Some(c) = controller {
+                    c.borrow_mut().on_resize(size);
+                }
             }
         };
+
+        if ui.button("frame scene").clicked() {
+            if let (Some(c), Some((min, max))) = (controller.as_ref(), scene.bounds()) {
+                c.borrow_mut().frame(&min, &max);
+            }
+        }
     }
 
     fn camera_side(
@@ -311,24 +530,22 @@ impl MainLogic {
         state: &mut CameraSideState,
         camera: Option<Arc<Camera>>,
         controller: &mut Option<Box<RefCell<dyn CameraController>>>,
+        size: Size,
     ) -> bool {
         if camera.is_none() {
             ui.label("no camera");
             return false;
         }
         let factory = container.get::<_>().unwrap();
+        let scene = container.get::<Scene>().unwrap();
         let camera = camera.unwrap();
-        let res = ui.collapsing("Projection", |ui| Self::camera_project(ui, state, &camera));
-        res.body_returned.unwrap_or_default();
-
-        ui.separator();
-        ui.collapsing("View", |ui| Self::camera_view(ui, &camera));
+        ui.collapsing("Camera", |ui| Self::camera_inspect(ui, &camera));
 
         ui.separator();
         egui::CollapsingHeader::new("Controller")
             .default_open(true)
             .show(ui, |ui| {
-                Self::camera_control(ui, state, &camera, &factory, controller)
+                Self::camera_control(ui, state, &camera, &factory, controller, &scene, size)
             });
 
         ui.separator();
@@ -354,15 +571,41 @@ impl MainLogic {
         let reset = egui::Window::new("Camera")
             .open(&mut self.show_camera_side)
             .show(ctx, |ui| {
-                Self::camera_side(
+                let reset = Self::camera_side(
                     ui,
                     container,
                     &mut self.camera_state,
                     self.cur_camera.clone(),
                     &mut self.controller,
-                )
+                    self.size,
+                );
+                if let Some(camera) = &self.cur_camera {
+                    ui.separator();
+                    egui::CollapsingHeader::new("Path").show(ui, |ui| {
+                        Self::path_side(ui, container, camera, &mut self.path)
+                    });
+                }
+                reset
             });
 
+        egui::Window::new("Scene")
+            .open(&mut self.show_scene_side)
+            .vscroll(true)
+            .show(ctx, |ui| Self::scene_side(ui, container));
+
+        egui::Window::new("Render Graph")
+            .open(&mut self.show_graph_side)
+            .default_width(320f32)
+            .vscroll(true)
+            .show(ctx, |ui| Self::graph_side(ui, container));
+        // stop copying the previewed texture
+        if !self.show_graph_side {
+            let inspector = container.get::<RenderGraphInspector>().unwrap();
+            if inspector.capture().is_some() {
+                inspector.set_capture(None);
+            }
+        }
+
         if let Some(reset) = reset {
             if let Some(r) = reset.inner {
                 // reset camera
@@ -390,9 +633,29 @@ fn do_main() {
     app.register_plugin(GltfPluginFactory);
     app.register_plugin(PhongPluginFactory {});
     app.add_event_processor(Box::new(MainLogic::default()));
+    #[cfg(not(target_arch = "wasm32"))]
+    input_args(&mut app);
     app.run();
 }
 
+// --record <file> writes the input of the session, --replay <file> plays one back
+#[cfg(not(target_arch = "wasm32"))]
+fn input_args(app: &mut App) {
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match (arg.as_str(), args.next()) {
+            ("--record", Some(path)) => app.record_input(path),
+            ("--replay", Some(path)) => {
+                match core::event::record::InputPlayer::load_from_file(&path) {
+                    Ok(player) => app.replay_input(player),
+                    Err(e) => log::error!("load input recording {} fail {}", path, e),
+                }
+            }
+            (arg, _) => log::warn!("unknown argument {}", arg),
+        }
+    }
+}
+
 fn main() {
     #[cfg(feature = "profile-with-tracy")]
     {
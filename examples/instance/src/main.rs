@@ -127,6 +127,9 @@ impl AppEventProcessor for MainLogic {
         } else if let Some(ev) = event.downcast_ref::<core::event::Event>() {
             match &ev {
                 core::event::Event::Update(delta) => {
+                    if let Some(ct) = &self.ct {
+                        ct.borrow_mut().on_update(*delta as f32);
+                    }
                     let scene = context.container.get::<Scene>().unwrap();
                     if let Some(id) = &self.object_id {
                         let dt = *delta as f32 * 0.01f32;
@@ -275,6 +275,9 @@ impl AppEventProcessor for MainLogic {
         } else if let Some(ev) = event.downcast_ref::<core::event::Event>() {
             match ev {
                 core::event::Event::Update(delta) => {
+                    if let Some(ct) = &self.ct {
+                        ct.borrow_mut().on_update(*delta as f32);
+                    }
                     let scene = context.container.get::<Scene>().unwrap();
                     self.update(*delta as f32, &scene);
                 }
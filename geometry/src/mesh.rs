@@ -7,8 +7,10 @@ pub mod icosahedron;
 pub mod lathe;
 pub mod octahedron;
 pub mod plane;
+pub mod polygon;
 pub mod ring;
 pub mod skybox;
+pub mod terrain;
 pub mod tetrahedron;
 pub mod torus;
 pub mod torusknot;
@@ -18,4 +20,6 @@ pub mod uvsphere;
 pub use circle::CircleMeshBuilder;
 pub use cube::CubeMeshBuilder;
 pub use plane::PlaneMeshBuilder;
+pub use polygon::PolygonMeshBuilder;
+pub use terrain::TerrainMeshBuilder;
 pub use uvsphere::UVSphereBuilder;
@@ -3,12 +3,13 @@ use core::{
         builder::{MeshBuilder, MeshPropertiesBuilder, MeshPropertyType},
         Mesh,
     },
-    types::{Color, Vec3f},
+    types::{Color, Vec2f, Vec3f},
 };
 
 pub struct PlaneMeshBuilder {
     normal: bool,
     color: bool,
+    texture: bool,
     segments_x: u32,
     segments_z: u32,
     colors: Vec<Color>,
@@ -20,6 +21,7 @@ impl Default for PlaneMeshBuilder {
         Self {
             normal: false,
             color: false,
+            texture: false,
             segments_x: 1,
             segments_z: 1,
             colors: vec![],
@@ -40,6 +42,11 @@ impl PlaneMeshBuilder {
         self
     }
 
+    pub fn enable_texture(mut self) -> Self {
+        self.texture = true;
+        self
+    }
+
     pub fn set_color_face_at_index(mut self, index: usize, color: Color) -> Self {
         if self.colors.len() <= index {
             self.colors.resize(index + 1, self.default_color);
@@ -65,6 +72,10 @@ impl PlaneMeshBuilder {
         if self.color {
             properties_builder.add_property(color_property);
         }
+        let texture_property = MeshPropertyType::new::<Vec2f>("texture");
+        if self.texture {
+            properties_builder.add_property(texture_property);
+        }
 
         let dx = 1f32 / self.segments_x as f32;
         let dz = 1f32 / self.segments_z as f32;
@@ -114,6 +125,15 @@ impl PlaneMeshBuilder {
             properties_builder.add_property_data(color_property, &self.colors);
         }
 
+        if self.texture {
+            // u along x, v along z
+            let uvs: Vec<Vec2f> = vertices
+                .iter()
+                .map(|v| Vec2f::new(v.x - x_beg, v.z - z_beg))
+                .collect();
+            properties_builder.add_property_data(texture_property, &uvs);
+        }
+
         builder.set_properties(properties_builder.build());
 
         builder.build().unwrap()
@@ -0,0 +1,525 @@
+use core::{
+    mesh::{
+        builder::{MeshBuilder, MeshPropertiesBuilder, MeshPropertyType},
+        Mesh,
+    },
+    types::{Color, Vec2f, Vec3f},
+};
+
+fn cross2(a: Vec2f, b: Vec2f) -> f32 {
+    a.x * b.y - a.y * b.x
+}
+
+fn signed_area(points: &[Vec2f], contour: &[usize]) -> f32 {
+    let mut area = 0f32;
+    for i in 0..contour.len() {
+        let a = points[contour[i]];
+        let b = points[contour[(i + 1) % contour.len()]];
+        area += cross2(a, b);
+    }
+    area * 0.5f32
+}
+
+fn point_in_triangle(p: Vec2f, a: Vec2f, b: Vec2f, c: Vec2f) -> bool {
+    let d0 = cross2(b - a, p - a);
+    let d1 = cross2(c - b, p - b);
+    let d2 = cross2(a - c, p - c);
+    let has_neg = d0 < 0f32 || d1 < 0f32 || d2 < 0f32;
+    let has_pos = d0 > 0f32 || d1 > 0f32 || d2 > 0f32;
+    !(has_neg && has_pos)
+}
+
+// outline is CCW and holes are CW after this, so the solid side is always on the left of an edge
+fn contours(
+    outline: &[Vec2f],
+    holes: &[Vec<Vec2f>],
+) -> anyhow::Result<(Vec<Vec2f>, Vec<Vec<usize>>)> {
+    let mut points = vec![];
+    let mut loops = vec![];
+
+    for (index, contour) in std::iter::once(outline)
+        .chain(holes.iter().map(|v| v.as_slice()))
+        .enumerate()
+    {
+        let beg = points.len();
+        points.extend_from_slice(contour);
+        let mut len = contour.len();
+        if len > 1 && contour[0] == contour[len - 1] {
+            len -= 1;
+        }
+        if len < 3 {
+            if index == 0 {
+                anyhow::bail!("polygon outline needs 3 points at least, got {}", len);
+            }
+            continue;
+        }
+        let mut l: Vec<usize> = (beg..beg + len).collect();
+        let area = signed_area(&points, &l);
+        if (index == 0 && area < 0f32) || (index != 0 && area > 0f32) {
+            l.reverse();
+        }
+        loops.push(l);
+    }
+
+    Ok((points, loops))
+}
+
+fn bridge_hole(points: &[Vec2f], outer: &mut Vec<usize>, hole: &[usize]) -> anyhow::Result<()> {
+    let mut m_pos = 0;
+    for (i, v) in hole.iter().enumerate() {
+        if points[*v].x > points[hole[m_pos]].x {
+            m_pos = i;
+        }
+    }
+    let mp = points[hole[m_pos]];
+    let n = outer.len();
+
+    // cast a ray to +x and find the nearest edge of the outer polygon
+    let mut best: Option<(f32, usize)> = None;
+    for i in 0..n {
+        let j = (i + 1) % n;
+        let a = points[outer[i]];
+        let b = points[outer[j]];
+        if (a.y > mp.y) == (b.y > mp.y) {
+            continue;
+        }
+        let x = a.x + (mp.y - a.y) * (b.x - a.x) / (b.y - a.y);
+        if x < mp.x {
+            continue;
+        }
+        if best.map_or(true, |(bx, _)| x < bx) {
+            let p = if a.y == mp.y {
+                i
+            } else if b.y == mp.y {
+                j
+            } else if a.x > b.x {
+                i
+            } else {
+                j
+            };
+            best = Some((x, p));
+        }
+    }
+    let (ix, mut p) = match best {
+        Some(v) => v,
+        None => anyhow::bail!("polygon hole at {:?} is outside of the outline", mp),
+    };
+
+    let ip = Vec2f::new(ix, mp.y);
+    let pp = points[outer[p]];
+    if pp != ip {
+        // a reflex vertex inside (M, I, P) may hide P, pick the one closest in angle to the ray
+        let mut best_cos = -1f32;
+        let mut best_len = f32::MAX;
+        for k in 0..n {
+            if k == p {
+                continue;
+            }
+            let prev = points[outer[(k + n - 1) % n]];
+            let r = points[outer[k]];
+            let next = points[outer[(k + 1) % n]];
+            if cross2(r - prev, next - r) > 0f32 {
+                continue;
+            }
+            if !point_in_triangle(r, mp, ip, pp) {
+                continue;
+            }
+            let d = r - mp;
+            let len = d.norm();
+            if len == 0f32 {
+                continue;
+            }
+            let cos = d.x / len;
+            if cos > best_cos || (cos == best_cos && len < best_len) {
+                best_cos = cos;
+                best_len = len;
+                p = k;
+            }
+        }
+    }
+
+    let mut merged = Vec::with_capacity(n + hole.len() + 2);
+    merged.extend_from_slice(&outer[..=p]);
+    for k in 0..=hole.len() {
+        merged.push(hole[(m_pos + k) % hole.len()]);
+    }
+    merged.push(outer[p]);
+    merged.extend_from_slice(&outer[p + 1..]);
+    *outer = merged;
+    Ok(())
+}
+
+fn ear_clip(points: &[Vec2f], mut poly: Vec<usize>, indices: &mut Vec<u32>) {
+    let mut start = 0;
+    while poly.len() > 3 {
+        let n = poly.len();
+        let mut ear = None;
+        let mut fallback = (f32::MIN, 0);
+
+        for step in 0..n {
+            let i = (start + step) % n;
+            let pa = points[poly[(i + n - 1) % n]];
+            let pb = points[poly[i]];
+            let pc = points[poly[(i + 1) % n]];
+            let c = cross2(pb - pa, pc - pb);
+            if c > fallback.0 {
+                fallback = (c, i);
+            }
+            if c <= 0f32 {
+                continue;
+            }
+            let blocked = poly.iter().any(|k| {
+                let p = points[*k];
+                p != pa && p != pb && p != pc && point_in_triangle(p, pa, pb, pc)
+            });
+            if !blocked {
+                ear = Some(i);
+                break;
+            }
+        }
+
+        // no ear found means the input is degenerate or self intersecting, clip the most convex vertex
+        let i = ear.unwrap_or(fallback.1);
+        let a = poly[(i + n - 1) % n];
+        let b = poly[i];
+        let c = poly[(i + 1) % n];
+        if cross2(points[b] - points[a], points[c] - points[b]) > 0f32 {
+            indices.extend_from_slice(&[a as u32, b as u32, c as u32]);
+        }
+        poly.remove(i);
+        start = if i == 0 { 0 } else { i - 1 };
+    }
+
+    if poly.len() == 3 {
+        let (a, b, c) = (poly[0], poly[1], poly[2]);
+        if cross2(points[b] - points[a], points[c] - points[b]) > 0f32 {
+            indices.extend_from_slice(&[a as u32, b as u32, c as u32]);
+        }
+    }
+}
+
+fn triangulate_contours(points: &[Vec2f], loops: &[Vec<usize>]) -> anyhow::Result<Vec<u32>> {
+    let mut indices = vec![];
+    if loops.is_empty() {
+        return Ok(indices);
+    }
+    let mut outer = loops[0].clone();
+    let mut holes: Vec<&Vec<usize>> = loops[1..].iter().collect();
+    let max_x = |l: &Vec<usize>| {
+        l.iter()
+            .map(|v| points[*v].x)
+            .fold(f32::MIN, |a, b| a.max(b))
+    };
+    holes.sort_by(|a, b| max_x(b).total_cmp(&max_x(a)));
+
+    for hole in holes {
+        bridge_hole(points, &mut outer, hole)?;
+    }
+    ear_clip(points, outer, &mut indices);
+    Ok(indices)
+}
+
+// returns CCW triangles indexing into outline followed by all holes
+pub fn triangulate(outline: &[Vec2f], holes: &[Vec<Vec2f>]) -> anyhow::Result<Vec<u32>> {
+    let (points, loops) = contours(outline, holes)?;
+    triangulate_contours(&points, &loops)
+}
+
+fn miter_offsets(points: &[Vec2f], loops: &[Vec<usize>]) -> Vec<Vec2f> {
+    let mut offsets = vec![Vec2f::zeros(); points.len()];
+    for l in loops {
+        let n = l.len();
+        for k in 0..n {
+            let prev = points[l[(k + n - 1) % n]];
+            let cur = points[l[k]];
+            let next = points[l[(k + 1) % n]];
+            let e0 = (cur - prev).try_normalize(1e-6).unwrap_or_default();
+            let e1 = (next - cur).try_normalize(1e-6).unwrap_or_default();
+            let n0 = Vec2f::new(-e0.y, e0.x);
+            let n1 = Vec2f::new(-e1.y, e1.x);
+            let d = 1f32 + n0.dot(&n1);
+            offsets[l[k]] = if d > 1e-3 { (n0 + n1) / d } else { n0 };
+        }
+    }
+    offsets
+}
+
+fn to3(p: Vec2f, y: f32) -> Vec3f {
+    Vec3f::new(p.x, y, -p.y)
+}
+
+pub struct PolygonMeshBuilder {
+    normal: bool,
+    color: bool,
+    default_color: Color,
+    outline: Vec<Vec2f>,
+    holes: Vec<Vec<Vec2f>>,
+    depth: f32,
+    bevel_size: f32,
+    bevel_segments: u32,
+    front_cap: bool,
+    back_cap: bool,
+}
+
+impl Default for PolygonMeshBuilder {
+    fn default() -> Self {
+        Self {
+            normal: false,
+            color: false,
+            default_color: Color::default(),
+            outline: vec![],
+            holes: vec![],
+            depth: 0f32,
+            bevel_size: 0f32,
+            bevel_segments: 1,
+            front_cap: true,
+            back_cap: true,
+        }
+    }
+}
+
+impl PolygonMeshBuilder {
+    pub fn enable_normal(mut self) -> Self {
+        self.normal = true;
+        self
+    }
+
+    pub fn enable_color(mut self, default_color: Color) -> Self {
+        self.color = true;
+        self.default_color = default_color;
+        self
+    }
+
+    pub fn set_outline(mut self, outline: Vec<Vec2f>) -> Self {
+        self.outline = outline;
+        self
+    }
+
+    pub fn add_hole(mut self, hole: Vec<Vec2f>) -> Self {
+        self.holes.push(hole);
+        self
+    }
+
+    pub fn set_extrude(mut self, depth: f32) -> Self {
+        self.depth = depth.max(0f32);
+        self
+    }
+
+    pub fn set_bevel(mut self, size: f32, segments: u32) -> Self {
+        self.bevel_size = size.max(0f32);
+        self.bevel_segments = segments.max(1);
+        self
+    }
+
+    pub fn set_caps(mut self, front: bool, back: bool) -> Self {
+        self.front_cap = front;
+        self.back_cap = back;
+        self
+    }
+
+    pub fn build(self) -> anyhow::Result<Mesh> {
+        let mut builder = MeshBuilder::default();
+        let mut properties_builder = MeshPropertiesBuilder::default();
+        let property = MeshPropertyType::new::<Vec3f>("normal_vertex");
+        if self.normal {
+            properties_builder.add_property(property);
+        }
+        let color_property = MeshPropertyType::new::<Color>("color");
+        if self.color {
+            properties_builder.add_property(color_property);
+        }
+
+        let (points, loops) = contours(&self.outline, &self.holes)?;
+        let triangles = triangulate_contours(&points, &loops)?;
+
+        let mut vertices = vec![];
+        let mut normals = vec![];
+        let mut indices = vec![];
+
+        if self.depth <= 0f32 {
+            for p in &points {
+                vertices.push(to3(*p, 0f32));
+                normals.push(Vec3f::new(0f32, 1f32, 0f32));
+            }
+            indices.extend_from_slice(&triangles);
+        } else {
+            let offsets = miter_offsets(&points, &loops);
+            let bevel = self.bevel_size.min(self.depth * 0.5f32);
+
+            // (y, inset, outward weight, up weight) from back to front
+            let mut rings = vec![];
+            if bevel > 0f32 {
+                let segments = self.bevel_segments;
+                for k in 0..=segments {
+                    let theta =
+                        (segments - k) as f32 / segments as f32 * std::f32::consts::FRAC_PI_2;
+                    let (s, c) = theta.sin_cos();
+                    rings.push((bevel - bevel * s, bevel * (1f32 - c), c, -s));
+                }
+                for k in 0..=segments {
+                    let theta = k as f32 / segments as f32 * std::f32::consts::FRAC_PI_2;
+                    let (s, c) = theta.sin_cos();
+                    rings.push((self.depth - bevel + bevel * s, bevel * (1f32 - c), c, s));
+                }
+            } else {
+                rings.push((0f32, 0f32, 1f32, 0f32));
+                rings.push((self.depth, 0f32, 1f32, 0f32));
+            }
+
+            for l in &loops {
+                let n = l.len();
+                for k in 0..n {
+                    let p0 = l[k];
+                    let p1 = l[(k + 1) % n];
+                    let e = (points[p1] - points[p0])
+                        .try_normalize(1e-6)
+                        .unwrap_or_default();
+                    let out = to3(Vec2f::new(e.y, -e.x), 0f32);
+
+                    let base = vertices.len() as u32;
+                    for (y, inset, wo, wu) in &rings {
+                        let normal = (out * *wo + Vec3f::new(0f32, *wu, 0f32)).normalize();
+                        vertices.push(to3(points[p0] + offsets[p0] * *inset, *y));
+                        vertices.push(to3(points[p1] + offsets[p1] * *inset, *y));
+                        normals.push(normal);
+                        normals.push(normal);
+                    }
+                    for r in 0..rings.len() as u32 - 1 {
+                        let a = base + r * 2;
+                        let b = a + 1;
+                        let d = a + 2;
+                        let c = d + 1;
+                        indices.extend_from_slice(&[a, b, c, a, c, d]);
+                    }
+                }
+            }
+
+            if self.front_cap {
+                let base = vertices.len() as u32;
+                for (p, o) in points.iter().zip(offsets.iter()) {
+                    vertices.push(to3(p + o * bevel, self.depth));
+                    normals.push(Vec3f::new(0f32, 1f32, 0f32));
+                }
+                indices.extend(triangles.iter().map(|v| v + base));
+            }
+            if self.back_cap {
+                let base = vertices.len() as u32;
+                for (p, o) in points.iter().zip(offsets.iter()) {
+                    vertices.push(to3(p + o * bevel, 0f32));
+                    normals.push(Vec3f::new(0f32, -1f32, 0f32));
+                }
+                for t in triangles.chunks(3) {
+                    indices.extend_from_slice(&[t[0] + base, t[2] + base, t[1] + base]);
+                }
+            }
+        }
+
+        builder.add_position_vertices3(&vertices);
+        builder.add_indices32(&indices);
+
+        if self.normal {
+            properties_builder.add_property_data(property, &normals);
+        }
+
+        if self.color {
+            let mut colors = vec![];
+            colors.resize(vertices.len(), self.default_color);
+            properties_builder.add_property_data(color_property, &colors);
+        }
+
+        builder.set_properties(properties_builder.build());
+
+        builder.build()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn square(c: Vec2f, half: f32) -> Vec<Vec2f> {
+        vec![
+            c + Vec2f::new(-half, -half),
+            c + Vec2f::new(half, -half),
+            c + Vec2f::new(half, half),
+            c + Vec2f::new(-half, half),
+        ]
+    }
+
+    fn points(outline: &[Vec2f], holes: &[Vec<Vec2f>]) -> Vec<Vec2f> {
+        let mut points = outline.to_vec();
+        for hole in holes {
+            points.extend_from_slice(hole);
+        }
+        points
+    }
+
+    // every triangle must be CCW, returns the covered area
+    fn area(points: &[Vec2f], indices: &[u32]) -> f32 {
+        assert_eq!(indices.len() % 3, 0);
+        let mut area = 0f32;
+        for t in indices.chunks(3) {
+            let (a, b, c) = (
+                points[t[0] as usize],
+                points[t[1] as usize],
+                points[t[2] as usize],
+            );
+            let s = cross2(b - a, c - a) * 0.5f32;
+            assert!(s > 0f32);
+            area += s;
+        }
+        area
+    }
+
+    #[test]
+    fn triangulate_square() {
+        let outline = square(Vec2f::zeros(), 1f32);
+        let indices = triangulate(&outline, &[]).unwrap();
+        assert_eq!(indices.len(), 6);
+        assert!((area(&outline, &indices) - 4f32).abs() < 1e-4);
+    }
+
+    #[test]
+    fn triangulate_cw_outline() {
+        let mut outline = square(Vec2f::zeros(), 1f32);
+        outline.reverse();
+        let indices = triangulate(&outline, &[]).unwrap();
+        assert!((area(&outline, &indices) - 4f32).abs() < 1e-4);
+    }
+
+    #[test]
+    fn triangulate_with_hole() {
+        let outline = square(Vec2f::zeros(), 2f32);
+        let holes = vec![square(Vec2f::zeros(), 1f32)];
+        let indices = triangulate(&outline, &holes).unwrap();
+        let points = points(&outline, &holes);
+        assert!((area(&points, &indices) - 12f32).abs() < 1e-4);
+    }
+
+    #[test]
+    fn triangulate_with_holes() {
+        let outline = square(Vec2f::zeros(), 4f32);
+        let holes = vec![
+            square(Vec2f::new(-2f32, 0f32), 1f32),
+            square(Vec2f::new(2f32, 1f32), 1f32),
+            square(Vec2f::new(0f32, -2.5f32), 0.5f32),
+        ];
+        let indices = triangulate(&outline, &holes).unwrap();
+        let points = points(&outline, &holes);
+        assert!((area(&points, &indices) - (64f32 - 4f32 - 4f32 - 1f32)).abs() < 1e-3);
+    }
+
+    #[test]
+    fn outline_too_small() {
+        let outline = vec![Vec2f::zeros(), Vec2f::new(1f32, 0f32)];
+        let holes = vec![square(Vec2f::zeros(), 1f32)];
+        assert!(triangulate(&outline, &holes).is_err());
+    }
+
+    #[test]
+    fn hole_outside() {
+        let outline = square(Vec2f::zeros(), 1f32);
+        let holes = vec![square(Vec2f::new(5f32, 0f32), 1f32)];
+        assert!(triangulate(&outline, &holes).is_err());
+    }
+}
@@ -0,0 +1,451 @@
+use core::{
+    mesh::{
+        builder::{MeshBuilder, MeshPropertiesBuilder, MeshPropertyType},
+        Mesh,
+    },
+    types::{BoundBox, Color, Vec2f, Vec3f},
+};
+
+pub enum HeightSource {
+    Flat,
+    // normalized luma values, row major
+    Image {
+        width: u32,
+        height: u32,
+        data: Vec<f32>,
+    },
+    Function(Box<dyn Fn(f32, f32) -> f32>),
+}
+
+impl HeightSource {
+    pub fn from_image(image: &image::DynamicImage) -> Self {
+        let luma = image.to_luma32f();
+        Self::Image {
+            width: luma.width(),
+            height: luma.height(),
+            data: luma.into_raw(),
+        }
+    }
+
+    // u, v in [0, 1], x, z in world space
+    fn sample(&self, u: f32, v: f32, x: f32, z: f32) -> f32 {
+        match self {
+            HeightSource::Flat => 0f32,
+            HeightSource::Image {
+                width,
+                height,
+                data,
+            } => {
+                if *width == 0 || *height == 0 {
+                    return 0f32;
+                }
+                let fx = u.clamp(0f32, 1f32) * (*width - 1) as f32;
+                let fy = v.clamp(0f32, 1f32) * (*height - 1) as f32;
+                let x0 = fx.floor() as u32;
+                let y0 = fy.floor() as u32;
+                let x1 = (x0 + 1).min(*width - 1);
+                let y1 = (y0 + 1).min(*height - 1);
+                let tx = fx - x0 as f32;
+                let ty = fy - y0 as f32;
+                let at = |x: u32, y: u32| data[(y * *width + x) as usize];
+
+                let top = at(x0, y0) * (1f32 - tx) + at(x1, y0) * tx;
+                let bottom = at(x0, y1) * (1f32 - tx) + at(x1, y1) * tx;
+                top * (1f32 - ty) + bottom * ty
+            }
+            HeightSource::Function(f) => f(x, z),
+        }
+    }
+}
+
+pub struct TerrainChunk {
+    pub mesh: Mesh,
+    pub boundary: BoundBox,
+    pub x: u32,
+    pub z: u32,
+}
+
+pub struct TerrainMeshBuilder {
+    normal: bool,
+    color: bool,
+    texture: bool,
+    default_color: Color,
+    bands: Vec<(f32, Color)>,
+    source: HeightSource,
+    size: Vec2f,
+    height_scale: f32,
+    segments_x: u32,
+    segments_z: u32,
+    chunk_segments: u32,
+}
+
+impl Default for TerrainMeshBuilder {
+    fn default() -> Self {
+        Self {
+            normal: false,
+            color: false,
+            texture: false,
+            default_color: Color::default(),
+            bands: vec![],
+            source: HeightSource::Flat,
+            size: Vec2f::new(1f32, 1f32),
+            height_scale: 1f32,
+            segments_x: 64,
+            segments_z: 64,
+            chunk_segments: 32,
+        }
+    }
+}
+
+impl TerrainMeshBuilder {
+    pub fn enable_normal(mut self) -> Self {
+        self.normal = true;
+        self
+    }
+
+    pub fn enable_color(mut self, default_color: Color) -> Self {
+        self.color = true;
+        self.default_color = default_color;
+        self
+    }
+
+    pub fn enable_texture(mut self) -> Self {
+        self.texture = true;
+        self
+    }
+
+    // vertices with height below `max_height` (before scaling) take the color of the first matching band
+    pub fn add_color_band(mut self, max_height: f32, color: Color) -> Self {
+        self.bands.push((max_height, color));
+        self.bands
+            .sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap_or(std::cmp::Ordering::Equal));
+        self
+    }
+
+    pub fn set_height_image(mut self, image: &image::DynamicImage) -> Self {
+        self.source = HeightSource::from_image(image);
+        self
+    }
+
+    pub fn set_height_fn<F: Fn(f32, f32) -> f32 + 'static>(mut self, f: F) -> Self {
+        self.source = HeightSource::Function(Box::new(f));
+        self
+    }
+
+    pub fn set_size(mut self, x: f32, z: f32) -> Self {
+        self.size = Vec2f::new(x, z);
+        self
+    }
+
+    pub fn set_height_scale(mut self, scale: f32) -> Self {
+        self.height_scale = scale;
+        self
+    }
+
+    pub fn set_segments(mut self, x: u32, z: u32) -> Self {
+        self.segments_x = x.max(1);
+        self.segments_z = z.max(1);
+        self
+    }
+
+    pub fn set_chunk_segments(mut self, segments: u32) -> Self {
+        self.chunk_segments = segments.max(1);
+        self
+    }
+
+    fn band_color(&self, h: f32) -> Color {
+        for (max_height, color) in &self.bands {
+            if h <= *max_height {
+                return *color;
+            }
+        }
+        self.bands.last().map(|v| v.1).unwrap_or(self.default_color)
+    }
+
+    pub fn build(self) -> anyhow::Result<Vec<TerrainChunk>> {
+        let nx = self.segments_x as usize + 1;
+        let nz = self.segments_z as usize + 1;
+        let dx = self.size.x / self.segments_x as f32;
+        let dz = self.size.y / self.segments_z as f32;
+        let x_beg = -self.size.x * 0.5f32;
+        let z_beg = -self.size.y * 0.5f32;
+
+        // raw heights are kept for color bands, positions use the scaled one
+        let mut heights = Vec::with_capacity(nx * nz);
+        for j in 0..nz {
+            for i in 0..nx {
+                let u = i as f32 / self.segments_x as f32;
+                let v = j as f32 / self.segments_z as f32;
+                let x = x_beg + i as f32 * dx;
+                let z = z_beg + j as f32 * dz;
+                heights.push(self.source.sample(u, v, x, z));
+            }
+        }
+        let height_at = |i: usize, j: usize| heights[j * nx + i] * self.height_scale;
+
+        // central differences over the whole grid so chunk seams share normals
+        let normal_at = |i: usize, j: usize| {
+            let l = height_at(i.saturating_sub(1), j);
+            let r = height_at((i + 1).min(nx - 1), j);
+            let b = height_at(i, j.saturating_sub(1));
+            let t = height_at(i, (j + 1).min(nz - 1));
+            let sx = ((i + 1).min(nx - 1) - i.saturating_sub(1)) as f32 * dx;
+            let sz = ((j + 1).min(nz - 1) - j.saturating_sub(1)) as f32 * dz;
+            Vec3f::new(-(r - l) / sx, 1f32, -(t - b) / sz).normalize()
+        };
+
+        let normal_property = MeshPropertyType::new::<Vec3f>("normal_vertex");
+        let color_property = MeshPropertyType::new::<Color>("color");
+        let texture_property = MeshPropertyType::new::<Vec2f>("texture");
+
+        let chunk = self.chunk_segments as usize;
+        let mut chunks = vec![];
+
+        let mut cz = 0;
+        while cz * chunk < nz - 1 {
+            let mut cx = 0;
+            while cx * chunk < nx - 1 {
+                let i_beg = cx * chunk;
+                let i_end = ((cx + 1) * chunk).min(nx - 1);
+                let j_beg = cz * chunk;
+                let j_end = ((cz + 1) * chunk).min(nz - 1);
+                let w = i_end - i_beg + 1;
+
+                let mut builder = MeshBuilder::default();
+                let mut properties_builder = MeshPropertiesBuilder::default();
+                if self.normal {
+                    properties_builder.add_property(normal_property);
+                }
+                if self.color {
+                    properties_builder.add_property(color_property);
+                }
+                if self.texture {
+                    properties_builder.add_property(texture_property);
+                }
+
+                let mut vertices = vec![];
+                let mut normals = vec![];
+                let mut colors = vec![];
+                let mut uvs = vec![];
+                let mut boundary = BoundBox::default();
+
+                for j in j_beg..=j_end {
+                    for i in i_beg..=i_end {
+                        let p = Vec3f::new(
+                            x_beg + i as f32 * dx,
+                            height_at(i, j),
+                            z_beg + j as f32 * dz,
+                        );
+                        boundary = &boundary + &p;
+                        vertices.push(p);
+                        if self.normal {
+                            normals.push(normal_at(i, j));
+                        }
+                        if self.color {
+                            colors.push(self.band_color(heights[j * nx + i]));
+                        }
+                        if self.texture {
+                            uvs.push(Vec2f::new(
+                                i as f32 / self.segments_x as f32,
+                                j as f32 / self.segments_z as f32,
+                            ));
+                        }
+                    }
+                }
+
+                let mut indices = vec![];
+                for j in 0..(j_end - j_beg) {
+                    for i in 0..(i_end - i_beg) {
+                        let a = (j * w + i) as u32;
+                        let b = a + 1;
+                        let c = a + w as u32;
+                        let d = c + 1;
+                        indices.extend_from_slice(&[c, d, a, b, a, d]);
+                    }
+                }
+
+                builder.add_position_vertices3(&vertices);
+                builder.add_indices32(&indices);
+
+                if self.normal {
+                    properties_builder.add_property_data(normal_property, &normals);
+                }
+                if self.color {
+                    properties_builder.add_property_data(color_property, &colors);
+                }
+                if self.texture {
+                    properties_builder.add_property_data(texture_property, &uvs);
+                }
+
+                builder.set_properties(properties_builder.build());
+
+                chunks.push(TerrainChunk {
+                    mesh: builder.build()?,
+                    boundary,
+                    x: cx as u32,
+                    z: cz as u32,
+                });
+                cx += 1;
+            }
+            cz += 1;
+        }
+
+        Ok(chunks)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn floats(bytes: &[u8]) -> Vec<f32> {
+        bytes
+            .chunks(4)
+            .map(|v| f32::from_ne_bytes([v[0], v[1], v[2], v[3]]))
+            .collect()
+    }
+
+    fn positions(mesh: &Mesh) -> Vec<Vec3f> {
+        floats(mesh.vertices_view().unwrap())
+            .chunks(3)
+            .map(|v| Vec3f::new(v[0], v[1], v[2]))
+            .collect()
+    }
+
+    // normal and color properties, in this order
+    fn normals_colors(mesh: &Mesh) -> Vec<(Vec3f, Color)> {
+        floats(mesh.properties_view())
+            .chunks(7)
+            .map(|v| {
+                (
+                    Vec3f::new(v[0], v[1], v[2]),
+                    Color::new(v[3], v[4], v[5], v[6]),
+                )
+            })
+            .collect()
+    }
+
+    fn near(a: Vec3f, b: Vec3f) -> bool {
+        (a - b).norm() < 1e-5
+    }
+
+    #[test]
+    fn chunk_layout() {
+        let chunks = TerrainMeshBuilder::default()
+            .set_size(5f32, 3f32)
+            .set_segments(5, 3)
+            .set_chunk_segments(2)
+            .build()
+            .unwrap();
+        // 3 chunks along x (2, 2, 1 segments), 2 along z (2, 1)
+        assert_eq!(chunks.len(), 6);
+        let chunk = |x: u32, z: u32| chunks.iter().find(|c| c.x == x && c.z == z).unwrap();
+        assert_eq!(chunk(0, 0).mesh.vertex_count(), 9);
+        assert_eq!(chunk(0, 0).mesh.index_count(), Some(24));
+        assert_eq!(chunk(2, 1).mesh.vertex_count(), 4);
+        assert_eq!(chunk(2, 1).mesh.index_count(), Some(6));
+
+        for c in &chunks {
+            for p in positions(&c.mesh) {
+                assert!(p.x >= c.boundary.min().x - 1e-5 && p.x <= c.boundary.max().x + 1e-5);
+                assert!(p.z >= c.boundary.min().z - 1e-5 && p.z <= c.boundary.max().z + 1e-5);
+            }
+        }
+    }
+
+    #[test]
+    fn seams_match() {
+        let chunks = TerrainMeshBuilder::default()
+            .enable_normal()
+            .enable_color(Color::new(1f32, 1f32, 1f32, 1f32))
+            .set_height_fn(|x, z| (x * 1.3f32).sin() * (z * 0.7f32).cos())
+            .set_size(4f32, 4f32)
+            .set_segments(8, 8)
+            .set_chunk_segments(4)
+            .build()
+            .unwrap();
+        assert_eq!(chunks.len(), 4);
+        let chunk = |x: u32, z: u32| chunks.iter().find(|c| c.x == x && c.z == z).unwrap();
+
+        // the last column of chunk (0, 0) is the first column of chunk (1, 0)
+        let (left, right) = (chunk(0, 0), chunk(1, 0));
+        let (lp, rp) = (positions(&left.mesh), positions(&right.mesh));
+        let (lv, rv) = (normals_colors(&left.mesh), normals_colors(&right.mesh));
+        for j in 0..5 {
+            let (a, b) = (j * 5 + 4, j * 5);
+            assert!(near(lp[a], rp[b]));
+            assert!(near(lv[a].0, rv[b].0));
+        }
+
+        // the last row of chunk (0, 0) is the first row of chunk (0, 1)
+        let (bottom, top) = (chunk(0, 0), chunk(0, 1));
+        let (bp, tp) = (positions(&bottom.mesh), positions(&top.mesh));
+        let (bv, tv) = (normals_colors(&bottom.mesh), normals_colors(&top.mesh));
+        for i in 0..5 {
+            let (a, b) = (20 + i, i);
+            assert!(near(bp[a], tp[b]));
+            assert!(near(bv[a].0, tv[b].0));
+        }
+    }
+
+    #[test]
+    fn normals() {
+        let flat = TerrainMeshBuilder::default()
+            .enable_normal()
+            .enable_color(Color::default())
+            .set_segments(2, 2)
+            .build()
+            .unwrap();
+        for (n, _) in normals_colors(&flat[0].mesh) {
+            assert!(near(n, Vec3f::new(0f32, 1f32, 0f32)));
+        }
+
+        // a 45 degree slope rising along x
+        let slope = TerrainMeshBuilder::default()
+            .enable_normal()
+            .enable_color(Color::default())
+            .set_height_fn(|x, _| x)
+            .set_size(2f32, 2f32)
+            .set_segments(4, 4)
+            .build()
+            .unwrap();
+        let expect = Vec3f::new(-1f32, 1f32, 0f32).normalize();
+        for (n, _) in normals_colors(&slope[0].mesh) {
+            assert!(near(n, expect));
+        }
+    }
+
+    #[test]
+    fn height_bands() {
+        let low = Color::new(0f32, 0f32, 1f32, 1f32);
+        let mid = Color::new(0f32, 1f32, 0f32, 1f32);
+        let high = Color::new(1f32, 1f32, 1f32, 1f32);
+        // bands are sorted, the scale doesn't change the band of a vertex
+        let chunks = TerrainMeshBuilder::default()
+            .enable_normal()
+            .enable_color(Color::default())
+            .add_color_band(0.5f32, mid)
+            .add_color_band(0f32, low)
+            .add_color_band(1f32, high)
+            .set_height_fn(|x, _| x + 0.5f32)
+            .set_size(2f32, 1f32)
+            .set_segments(4, 1)
+            .set_height_scale(10f32)
+            .build()
+            .unwrap();
+        let mesh = &chunks[0].mesh;
+        let p = positions(mesh);
+        let v = normals_colors(mesh);
+        for (p, (_, color)) in p.iter().zip(v) {
+            let raw = p.y / 10f32;
+            let expect = if raw <= 0f32 {
+                low
+            } else if raw <= 0.5f32 {
+                mid
+            } else {
+                high
+            };
+            assert_eq!(color, expect);
+        }
+    }
+}
@@ -12,8 +12,9 @@ use core::wgpu;
 use std::any::Any;
 
 use core::context::{RContext, RContextRef, ResourceRef, TagId};
+use core::graph::sg::NodeId;
 use core::mesh::StaticGeometry;
-use core::scene::{Camera, RenderObject, Scene, Transform, TransformBuilder};
+use core::scene::{Camera, RenderObject, Scene, Transform};
 use core::types::{BoundBox, Size, Vec3f, Vec4f};
 use core::util::any_as_x_slice_array;
 use std::cell::RefCell;
@@ -242,6 +243,7 @@ impl<'a> ParseContext<'a> {
         buf_view: &mut GltfBufferView<'a>,
         mesh: gltf::Mesh,
         transform: &Transform,
+        node_id: NodeId,
     ) -> anyhow::Result<BoundBox> {
         self.info.total_meshes += 1;
 
@@ -268,9 +270,8 @@ impl<'a> ParseContext<'a> {
 
             mesh_builder.set_properties(mesh_properties_builder.build());
 
-            let mut g = StaticGeometry::new(Arc::new(mesh_builder.build()?));
+            let g = StaticGeometry::new(Arc::new(mesh_builder.build()?));
 
-            g = g.with_transform(transform.clone());
             let mut obj = RenderObject::new(Box::new(g), material.clone()).unwrap();
             obj.set_cast_shadow();
             obj.set_recv_shadow();
@@ -278,7 +279,7 @@ impl<'a> ParseContext<'a> {
 
             obj.add_tag(tag_id);
 
-            self.scene.add(obj);
+            self.scene.add_to_node(obj, node_id);
         }
         log::info!("load mesh {:?} transform {:?}", mesh.name(), transform);
 
@@ -291,31 +292,34 @@ impl<'a> ParseContext<'a> {
         tag_id: TagId,
         buf: &mut GltfBufferView<'a>,
         transform: &Transform,
+        parent: NodeId,
     ) -> anyhow::Result<()> {
         let d = node.transform().decomposed();
         let q = nalgebra::Quaternion::from(Vec4f::new(d.1[0], d.1[1], d.1[2], d.1[3]));
         let q = Unit::new_unchecked(q);
 
-        let mut transform_node = TransformBuilder::new()
-            .translate(d.0.into())
-            .rotate(q)
-            .scale(d.2.into())
-            .build();
+        let local = Transform::new(d.0.into(), q, d.2.into());
+        let node_id =
+            self.scene
+                .create_node(Some(parent), node.name().unwrap_or_default(), local.clone());
+
+        // world transform is only used for the bound box here, objects follow their node
+        let mut transform_node = local;
         transform_node.mul_mut(transform);
 
         if let Some(mesh) = node.mesh() {
-            let bb = self.parse_mesh(tag_id, buf, mesh, &transform_node)?;
+            let bb = self.parse_mesh(tag_id, buf, mesh, &transform_node, node_id)?;
             self.info.aabb = &self.info.aabb + &bb;
         }
         self.info.total_nodes += 1;
 
         for node in node.children() {
-            self.parse_node(node, tag_id, buf, &transform_node)?;
+            self.parse_node(node, tag_id, buf, &transform_node, node_id)?;
         }
         if let Some(light) = node.light() {
             self.material_loader
                 .borrow()
-                .load_light(&light, &self.scene)?;
+                .load_light(&light, &self.scene, node_id)?;
         }
         Ok(())
     }
@@ -445,8 +449,9 @@ impl<'a> ParseContext<'a> {
             let tag_id = self.scene.context().new_tag(name);
 
             let transform = Transform::default();
+            let scene_node = self.scene.create_node(None, name, transform.clone());
             for node in s.nodes() {
-                self.parse_node(node, tag_id, buf_view, &transform)?;
+                self.parse_node(node, tag_id, buf_view, &transform, scene_node)?;
             }
             log::info!(
                 "model scene {} nodes {}",
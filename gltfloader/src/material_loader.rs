@@ -1,5 +1,6 @@
 use core::{
     context::ResourceRef,
+    graph::sg::NodeId,
     material::Material,
     mesh::builder::{MeshBuilder, MeshPropertiesBuilder},
     scene::Scene,
@@ -28,6 +29,7 @@ pub trait MaterialLoader {
         &self,
         light: &gltf::khr_lights_punctual::Light,
         scene: &Scene,
+        node: NodeId,
     ) -> anyhow::Result<()>;
     fn post_load(&mut self, scene: &Scene, info: &GltfSceneInfo) -> anyhow::Result<()> {
         Ok(())
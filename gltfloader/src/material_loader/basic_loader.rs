@@ -295,6 +295,7 @@ impl MaterialLoader for BasicMaterialLoader {
         &self,
         light: &gltf::khr_lights_punctual::Light,
         scene: &core::scene::Scene,
+        node: core::graph::sg::NodeId,
     ) -> anyhow::Result<()> {
         log::info!("ignore light {}", light.name().unwrap_or_default());
         Ok(())
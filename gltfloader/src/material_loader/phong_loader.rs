@@ -377,6 +377,7 @@ impl MaterialLoader for PhongMaterialLoader {
         &self,
         light: &gltf::khr_lights_punctual::Light,
         scene: &core::scene::Scene,
+        node: core::graph::sg::NodeId,
     ) -> anyhow::Result<()> {
         log::info!("load light {}", light.name().unwrap_or_default());
         if scene.get_resource::<SceneLights>().is_none() {
@@ -392,18 +393,20 @@ impl MaterialLoader for PhongMaterialLoader {
             ..Default::default()
         };
 
-        match light.kind() {
+        let l = match light.kind() {
             gltf::khr_lights_punctual::Kind::Directional => {
+                // gltf directional lights point down -z of their node
                 let dlight = DirectLightBuilder::default()
                     .color(color)
+                    .position(Vec3f::new(0f32, 0f32, 1f32))
                     .cast_shadow(shadow_config);
-                sl.set_direct_light(dlight.build());
+                sl.set_direct_light(dlight.build())
             }
             gltf::khr_lights_punctual::Kind::Point => {
                 let plight = PointLightBuilder::default()
                     .color(color)
                     .cast_shadow(shadow_config);
-                sl.add_point_light(plight.build());
+                sl.add_point_light(plight.build())
             }
             gltf::khr_lights_punctual::Kind::Spot {
                 inner_cone_angle,
@@ -412,10 +415,12 @@ impl MaterialLoader for PhongMaterialLoader {
                 let plight = SpotLightBuilder::default()
                     .color(color)
                     .cutoff(inner_cone_angle, outer_cone_angle)
+                    .direction(Vec3f::new(0f32, 0f32, -1f32))
                     .cast_shadow(shadow_config);
-                sl.add_spot_light(plight.build());
+                sl.add_spot_light(plight.build())
             }
-        }
+        };
+        scene.attach_to_node(node, l);
 
         Ok(())
     }
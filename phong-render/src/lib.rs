@@ -1,4 +1,4 @@
-use core::scene::Scene;
+use gcore::scene::Scene;
 use std::any::TypeId;
 
 use app::{
@@ -53,6 +53,6 @@ impl Plugin for PhongPlugin {
 
 impl AppEventProcessor for PhongPlugin {
     fn on_event(&mut self, context: &app::AppEventContext, event: &dyn std::any::Any) {
-        if let Some(ev) = event.downcast_ref::<core::event::Event>() {}
+        if let Some(ev) = event.downcast_ref::<gcore::event::Event>() {}
     }
 }
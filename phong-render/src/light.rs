@@ -1,10 +1,17 @@
-use core::{
+use bevy_reflect::Reflect;
+use gcore::{
+    graph::sg::NodeAttachment,
     material::bind::{BindingResourceMap, BindingResourceProvider, ShaderBindingResource},
+    reflect::{Angle, ColorEdit, Logarithmic, ReflectProxy},
     render::pso::BindGroupType,
-    scene::Camera,
+    scene::{
+        serialize::{from_value, to_value, SceneResourceSerializer, Value},
+        Camera, Scene, Transform,
+    },
     types::{Color, Mat4x4f, Vec2f, Vec3f, Vec4f},
     util::{angle2rad, any_as_u8_slice},
 };
+use serde::{Deserialize, Serialize};
 use std::sync::{Arc, Mutex};
 
 #[derive(Debug, Clone)]
@@ -26,6 +33,35 @@ impl Default for ShadowConfig {
     }
 }
 
+#[derive(Debug, Clone, Reflect)]
+pub struct ShadowConfigProxy {
+    pub cast_shadow: bool,
+    pub size: [f32; 2],
+    #[reflect(@0.01f32..=100f32, @Logarithmic)]
+    pub bias_factor: f32,
+    pub pcf: bool,
+}
+
+impl ReflectProxy for ShadowConfig {
+    type Proxy = ShadowConfigProxy;
+
+    fn reflect_proxy(&self) -> ShadowConfigProxy {
+        ShadowConfigProxy {
+            cast_shadow: self.cast_shadow,
+            size: self.size.into(),
+            bias_factor: self.bias_factor,
+            pcf: self.pcf,
+        }
+    }
+
+    fn apply_proxy(&mut self, proxy: &ShadowConfigProxy) {
+        self.cast_shadow = proxy.cast_shadow;
+        self.size = proxy.size.into();
+        self.bias_factor = proxy.bias_factor;
+        self.pcf = proxy.pcf;
+    }
+}
+
 pub trait TLight {
     fn light_cameras(&self) -> &[Camera];
     fn light_uniform(&self) -> Vec<u8>;
@@ -34,12 +70,47 @@ pub trait TLight {
     fn shadow_config(&self) -> &ShadowConfig;
 }
 
+#[derive(Clone)]
 pub enum Light {
     Direct(DirectLight),
     Spot(SpotLight),
     Point(PointLight),
 }
 
+impl NodeAttachment for Light {
+    fn update_transform(&self, world: &Transform) {
+        match self {
+            Light::Direct(d) => {
+                let (from, to, up) = d.local;
+                d.camera[0].look_at(
+                    world.apply(from),
+                    world.apply(to),
+                    world.apply_vector(up).normalize(),
+                );
+            }
+            Light::Spot(s) => {
+                let pos = world.apply(s.pos);
+                let dir = world.apply_vector(s.dir).normalize();
+                s.camera[0].look_at(
+                    pos,
+                    pos + dir * 100f32,
+                    world.apply_vector(s.up).normalize(),
+                );
+                *s.world.lock().unwrap() = (pos, dir);
+            }
+            Light::Point(p) => {
+                *p.world_pos.lock().unwrap() = world.apply(p.pos);
+            }
+        }
+    }
+
+    fn detach(&self, scene: &Scene) {
+        if let Some(lights) = scene.get_resource::<SceneLights>() {
+            lights.remove_light(self);
+        }
+    }
+}
+
 impl TLight for Light {
     fn light_cameras(&self) -> &[Camera] {
         match self {
@@ -83,6 +154,7 @@ impl TLight for Light {
 }
 
 #[repr(C)]
+#[derive(Debug, Clone, Serialize, Deserialize, Reflect)]
 pub struct Attenuation {
     pub constant: f32,
     pub linear: f32,
@@ -158,12 +230,27 @@ impl BaseLightUniform {
 
 pub struct DirectLight {
     color: Color,
+    // from, to, up in node space
+    local: (Vec3f, Vec3f, Vec3f),
     camera: [Camera; 1],
     shadow: ShadowConfig,
     attenuation: Attenuation,
     intensity: f32,
 }
 
+impl Clone for DirectLight {
+    fn clone(&self) -> Self {
+        Self {
+            color: self.color,
+            local: self.local,
+            camera: self.camera.clone(),
+            shadow: self.shadow.clone(),
+            attenuation: self.attenuation.clone(),
+            intensity: self.intensity,
+        }
+    }
+}
+
 impl TLight for DirectLight {
     fn light_cameras(&self) -> &[Camera] {
         &self.camera
@@ -207,12 +294,27 @@ impl TLight for DirectLight {
 pub struct PointLight {
     color: Color,
     pos: Vec3f,
+    world_pos: Mutex<Vec3f>,
     camera: [Camera; 6],
     shadow: ShadowConfig,
     attenuation: Attenuation,
     intensity: f32,
 }
 
+impl Clone for PointLight {
+    fn clone(&self) -> Self {
+        Self {
+            color: self.color,
+            pos: self.pos,
+            world_pos: Mutex::new(*self.world_pos.lock().unwrap()),
+            camera: self.camera.clone(),
+            shadow: self.shadow.clone(),
+            attenuation: self.attenuation.clone(),
+            intensity: self.intensity,
+        }
+    }
+}
+
 impl TLight for PointLight {
     fn light_cameras(&self) -> &[Camera] {
         &self.camera
@@ -223,7 +325,7 @@ impl TLight for PointLight {
         let u = PointLightUniform {
             color: Vec3f::new(self.color.x, self.color.y, self.color.z),
             size_x: self.shadow.size.x,
-            pos: self.pos,
+            pos: *self.world_pos.lock().unwrap(),
             size_y: self.shadow.size.y,
             vp: camera.vp(),
             attenuation: Vec4f::new(
@@ -255,6 +357,9 @@ pub struct SpotLight {
     color: Color,
     pos: Vec3f,
     dir: Vec3f,
+    up: Vec3f,
+    // position and direction in world space
+    world: Mutex<(Vec3f, Vec3f)>,
     cutoff: f32,
     cutoff_outer: f32,
     camera: [Camera; 1],
@@ -263,6 +368,24 @@ pub struct SpotLight {
     intensity: f32,
 }
 
+impl Clone for SpotLight {
+    fn clone(&self) -> Self {
+        Self {
+            color: self.color,
+            pos: self.pos,
+            dir: self.dir,
+            up: self.up,
+            world: Mutex::new(*self.world.lock().unwrap()),
+            cutoff: self.cutoff,
+            cutoff_outer: self.cutoff_outer,
+            camera: self.camera.clone(),
+            shadow: self.shadow.clone(),
+            attenuation: self.attenuation.clone(),
+            intensity: self.intensity,
+        }
+    }
+}
+
 impl TLight for SpotLight {
     fn light_cameras(&self) -> &[Camera] {
         &self.camera
@@ -270,13 +393,14 @@ impl TLight for SpotLight {
 
     fn light_uniform(&self) -> Vec<u8> {
         let camera = &self.camera[0];
+        let (pos, dir) = *self.world.lock().unwrap();
         let u = SpotLightUniform {
             color: Vec3f::new(self.color.x, self.color.y, self.color.z),
             size_x: self.shadow.size.x,
             size_y: self.shadow.size.y,
-            pos: self.pos,
+            pos,
             cutoff: self.cutoff,
-            dir: self.dir,
+            dir,
             cutoff_outer: self.cutoff_outer,
             vp: camera.vp(),
             bias_factor: self.shadow.bias_factor,
@@ -345,18 +469,69 @@ impl SceneLights {
         let mut base = inner.base.lock().unwrap();
         base.ambient = ambient;
     }
-    pub fn set_direct_light(&self, light: DirectLight) {
+    pub fn set_direct_light(&self, light: DirectLight) -> Arc<Light> {
         let mut inner = self.inner.lock().unwrap();
-        inner.direct_light = Some(Arc::new(Light::Direct(light)));
+        let light = Arc::new(Light::Direct(light));
+        inner.direct_light = Some(light.clone());
+        light
     }
 
-    pub fn add_point_light(&self, light: PointLight) {
+    pub fn add_point_light(&self, light: PointLight) -> Arc<Light> {
         let mut inner = self.inner.lock().unwrap();
-        inner.extra_lights.push(Arc::new(Light::Point(light)));
+        let light = Arc::new(Light::Point(light));
+        inner.extra_lights.push(light.clone());
+        light
     }
-    pub fn add_spot_light(&self, light: SpotLight) {
+    pub fn add_spot_light(&self, light: SpotLight) -> Arc<Light> {
         let mut inner = self.inner.lock().unwrap();
-        inner.extra_lights.push(Arc::new(Light::Spot(light)));
+        let light = Arc::new(Light::Spot(light));
+        inner.extra_lights.push(light.clone());
+        light
+    }
+
+    pub fn remove_light(&self, light: &Light) -> bool {
+        let mut inner = self.inner.lock().unwrap();
+        if inner
+            .direct_light
+            .as_ref()
+            .is_some_and(|v| std::ptr::eq(v.as_ref(), light))
+        {
+            inner.direct_light = None;
+            return true;
+        }
+        let len = inner.extra_lights.len();
+        inner
+            .extra_lights
+            .retain(|v| !std::ptr::eq(v.as_ref(), light));
+        inner.extra_lights.len() != len
+    }
+
+    // keeps the place of `old`. returns None if `old` is not a light of the
+    // scene, or if a direct light would be swapped with another kind
+    pub fn replace_light(&self, old: &Light, light: Light) -> Option<Arc<Light>> {
+        let mut inner = self.inner.lock().unwrap();
+        let direct = matches!(light, Light::Direct(_));
+        if direct != matches!(old, Light::Direct(_)) {
+            return None;
+        }
+        let light = Arc::new(light);
+        if direct {
+            if !inner
+                .direct_light
+                .as_ref()
+                .is_some_and(|v| std::ptr::eq(v.as_ref(), old))
+            {
+                return None;
+            }
+            inner.direct_light = Some(light.clone());
+            return Some(light);
+        }
+        let v = inner
+            .extra_lights
+            .iter_mut()
+            .find(|v| std::ptr::eq(v.as_ref(), old))?;
+        *v = light.clone();
+        Some(light)
     }
 
     pub fn has_direct_light(&self) -> bool {
@@ -454,6 +629,8 @@ pub struct DirectLightBuilder {
     color: Color,
     position: Vec3f,
     dir: Vec3f,
+    // target and up of the shadow camera
+    look_at: Option<(Vec3f, Vec3f)>,
     shadow_rect: Vec4f,
     near: f32,
     far: f32,
@@ -468,6 +645,7 @@ impl DirectLightBuilder {
             color: Color::new(0.5f32, 0.5f32, 0.5f32, 1f32),
             position: Vec3f::new(0f32, 0f32, 0f32),
             dir: Vec3f::new(1f32, 0f32, 0f32),
+            look_at: None,
             shadow_rect: Vec4f::new(-5f32, -5f32, 5f32, 5f32),
             near: 0.0001f32,
             far: 12f32,
@@ -491,6 +669,12 @@ impl DirectLightBuilder {
         self
     }
 
+    // the light looks at the origin by default
+    pub fn look_at(mut self, to: Vec3f, up: Vec3f) -> Self {
+        self.look_at = Some((to, up.normalize()));
+        self
+    }
+
     pub fn cast_shadow(mut self, config: ShadowConfig) -> Self {
         self.shadow = config;
         self
@@ -511,10 +695,13 @@ impl DirectLightBuilder {
 
         c.make_orthographic(self.shadow_rect, self.near, self.far);
         // let to = self.position + self.dir * 10f32;
-        let to = Vec3f::default();
-        c.look_at(self.position, to, Vec3f::new(1f32, 1f32, 0f32).normalize());
+        let (to, up) = self
+            .look_at
+            .unwrap_or((Vec3f::default(), Vec3f::new(1f32, 1f32, 0f32).normalize()));
+        c.look_at(self.position, to, up);
         DirectLight {
             color: self.color,
+            local: (self.position, to, up),
             camera: [c],
             shadow: self.shadow,
             attenuation: self.attenuation,
@@ -580,6 +767,7 @@ impl PointLightBuilder {
         PointLight {
             color: self.color,
             pos: self.position,
+            world_pos: Mutex::new(self.position),
             camera: [
                 c.clone(),
                 c.clone(),
@@ -665,11 +853,14 @@ impl SpotLightBuilder {
         let c = Camera::new();
         c.make_perspective(1.0f32, angle2rad(90f32), 0.1f32, 40f32);
         let to = self.position + self.dir * 100f32;
-        c.look_at(self.position, to, Vec3f::new(0f32, 1f32, 1f32).normalize());
+        let up = Vec3f::new(0f32, 1f32, 1f32).normalize();
+        c.look_at(self.position, to, up);
         SpotLight {
             color: self.color,
             pos: self.position,
             dir: self.dir,
+            up,
+            world: Mutex::new((self.position, self.dir)),
             cutoff: self.cutoff,
             cutoff_outer: self.cutoff_outer,
             camera: [c],
@@ -685,3 +876,428 @@ impl Default for SpotLightBuilder {
         Self::new()
     }
 }
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ShadowDesc {
+    pub cast_shadow: bool,
+    pub size: [f32; 2],
+    pub bias_factor: f32,
+    pub pcf: bool,
+}
+
+impl From<&ShadowConfig> for ShadowDesc {
+    fn from(s: &ShadowConfig) -> Self {
+        Self {
+            cast_shadow: s.cast_shadow,
+            size: s.size.into(),
+            bias_factor: s.bias_factor,
+            pcf: s.pcf,
+        }
+    }
+}
+
+impl From<&ShadowDesc> for ShadowConfig {
+    fn from(s: &ShadowDesc) -> Self {
+        Self {
+            cast_shadow: s.cast_shadow,
+            size: s.size.into(),
+            bias_factor: s.bias_factor,
+            pcf: s.pcf,
+        }
+    }
+}
+
+// lights are saved in world space
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum LightDesc {
+    Direct {
+        color: [f32; 4],
+        position: [f32; 3],
+        // from the position to the target of the shadow camera
+        direction: [f32; 3],
+        up: [f32; 3],
+        shadow: ShadowDesc,
+        attenuation: Attenuation,
+        intensity: f32,
+    },
+    Point {
+        color: [f32; 4],
+        position: [f32; 3],
+        shadow: ShadowDesc,
+        attenuation: Attenuation,
+        intensity: f32,
+    },
+    Spot {
+        color: [f32; 4],
+        position: [f32; 3],
+        direction: [f32; 3],
+        cutoff: f32,
+        cutoff_outer: f32,
+        shadow: ShadowDesc,
+        attenuation: Attenuation,
+        intensity: f32,
+    },
+}
+
+impl From<&Light> for LightDesc {
+    fn from(light: &Light) -> Self {
+        match light {
+            Light::Direct(d) => LightDesc::Direct {
+                color: d.color.into(),
+                position: d.camera[0].from().into(),
+                direction: (d.camera[0].to() - d.camera[0].from()).into(),
+                up: d.camera[0].up().into(),
+                shadow: (&d.shadow).into(),
+                attenuation: d.attenuation.clone(),
+                intensity: d.intensity,
+            },
+            Light::Point(p) => LightDesc::Point {
+                color: p.color.into(),
+                position: (*p.world_pos.lock().unwrap()).into(),
+                shadow: (&p.shadow).into(),
+                attenuation: p.attenuation.clone(),
+                intensity: p.intensity,
+            },
+            Light::Spot(s) => {
+                let (pos, dir) = *s.world.lock().unwrap();
+                LightDesc::Spot {
+                    color: s.color.into(),
+                    position: pos.into(),
+                    direction: dir.into(),
+                    cutoff: s.cutoff,
+                    cutoff_outer: s.cutoff_outer,
+                    shadow: (&s.shadow).into(),
+                    attenuation: s.attenuation.clone(),
+                    intensity: s.intensity,
+                }
+            }
+        }
+    }
+}
+
+impl LightDesc {
+    pub fn build(&self) -> Light {
+        match self {
+            LightDesc::Direct {
+                color,
+                position,
+                direction,
+                up,
+                shadow,
+                attenuation,
+                intensity,
+            } => Light::Direct(
+                DirectLightBuilder::new()
+                    .color(Color::from(*color))
+                    .position(Vec3f::from(*position))
+                    .look_at(
+                        Vec3f::from(*position) + Vec3f::from(*direction),
+                        Vec3f::from(*up),
+                    )
+                    .cast_shadow(shadow.into())
+                    .attenuation(attenuation.clone())
+                    .intensity(*intensity)
+                    .build(),
+            ),
+            LightDesc::Point {
+                color,
+                position,
+                shadow,
+                attenuation,
+                intensity,
+            } => Light::Point(
+                PointLightBuilder::new()
+                    .color(Color::from(*color))
+                    .position(Vec3f::from(*position))
+                    .cast_shadow(shadow.into())
+                    .attenuation(attenuation.clone())
+                    .intensity(*intensity)
+                    .build(),
+            ),
+            LightDesc::Spot {
+                color,
+                position,
+                direction,
+                cutoff,
+                cutoff_outer,
+                shadow,
+                attenuation,
+                intensity,
+            } => Light::Spot(
+                SpotLightBuilder::new()
+                    .color(Color::from(*color))
+                    .position(Vec3f::from(*position))
+                    .direction(Vec3f::from(*direction))
+                    .cutoff(*cutoff, *cutoff_outer)
+                    .cast_shadow(shadow.into())
+                    .attenuation(attenuation.clone())
+                    .intensity(*intensity)
+                    .build(),
+            ),
+        }
+    }
+
+    pub fn add_to(&self, lights: &SceneLights) -> Arc<Light> {
+        match self.build() {
+            Light::Direct(d) => lights.set_direct_light(d),
+            Light::Point(p) => lights.add_point_light(p),
+            Light::Spot(s) => lights.add_spot_light(s),
+        }
+    }
+}
+
+// lights are edited in world space like they are saved
+#[derive(Debug, Clone, Reflect)]
+pub enum LightProxy {
+    Direct {
+        #[reflect(@ColorEdit)]
+        color: [f32; 4],
+        position: [f32; 3],
+        // from the position to the target of the shadow camera
+        direction: [f32; 3],
+        up: [f32; 3],
+        shadow: ShadowConfigProxy,
+        attenuation: Attenuation,
+        intensity: f32,
+    },
+    Point {
+        #[reflect(@ColorEdit)]
+        color: [f32; 4],
+        position: [f32; 3],
+        shadow: ShadowConfigProxy,
+        attenuation: Attenuation,
+        intensity: f32,
+    },
+    Spot {
+        #[reflect(@ColorEdit)]
+        color: [f32; 4],
+        position: [f32; 3],
+        direction: [f32; 3],
+        #[reflect(@Angle)]
+        cutoff: f32,
+        #[reflect(@Angle)]
+        cutoff_outer: f32,
+        shadow: ShadowConfigProxy,
+        attenuation: Attenuation,
+        intensity: f32,
+    },
+}
+
+impl ReflectProxy for Light {
+    type Proxy = LightProxy;
+
+    fn reflect_proxy(&self) -> LightProxy {
+        match self {
+            Light::Direct(d) => LightProxy::Direct {
+                color: d.color.into(),
+                position: d.camera[0].from().into(),
+                direction: (d.camera[0].to() - d.camera[0].from()).into(),
+                up: d.camera[0].up().into(),
+                shadow: d.shadow.reflect_proxy(),
+                attenuation: d.attenuation.clone(),
+                intensity: d.intensity,
+            },
+            Light::Point(p) => LightProxy::Point {
+                color: p.color.into(),
+                position: (*p.world_pos.lock().unwrap()).into(),
+                shadow: p.shadow.reflect_proxy(),
+                attenuation: p.attenuation.clone(),
+                intensity: p.intensity,
+            },
+            Light::Spot(s) => {
+                let (pos, dir) = *s.world.lock().unwrap();
+                LightProxy::Spot {
+                    color: s.color.into(),
+                    position: pos.into(),
+                    direction: dir.into(),
+                    cutoff: s.cutoff,
+                    cutoff_outer: s.cutoff_outer,
+                    shadow: s.shadow.reflect_proxy(),
+                    attenuation: s.attenuation.clone(),
+                    intensity: s.intensity,
+                }
+            }
+        }
+    }
+
+    // switching the kind builds a new light
+    fn apply_proxy(&mut self, proxy: &LightProxy) {
+        match (self, proxy) {
+            (
+                Light::Direct(d),
+                LightProxy::Direct {
+                    color,
+                    position,
+                    direction,
+                    up,
+                    shadow,
+                    attenuation,
+                    intensity,
+                },
+            ) => {
+                let from = Vec3f::from(*position);
+                let to = from + Vec3f::from(*direction);
+                let up = Vec3f::from(*up).normalize();
+                d.local = (from, to, up);
+                d.camera[0].look_at(from, to, up);
+                d.color = Color::from(*color);
+                d.shadow.apply_proxy(shadow);
+                d.attenuation = attenuation.clone();
+                d.intensity = *intensity;
+            }
+            (
+                Light::Point(p),
+                LightProxy::Point {
+                    color,
+                    position,
+                    shadow,
+                    attenuation,
+                    intensity,
+                },
+            ) => {
+                p.pos = Vec3f::from(*position);
+                *p.world_pos.lock().unwrap() = p.pos;
+                p.color = Color::from(*color);
+                p.shadow.apply_proxy(shadow);
+                p.attenuation = attenuation.clone();
+                p.intensity = *intensity;
+            }
+            (
+                Light::Spot(s),
+                LightProxy::Spot {
+                    color,
+                    position,
+                    direction,
+                    cutoff,
+                    cutoff_outer,
+                    shadow,
+                    attenuation,
+                    intensity,
+                },
+            ) => {
+                s.pos = Vec3f::from(*position);
+                s.dir = Vec3f::from(*direction).normalize();
+                *s.world.lock().unwrap() = (s.pos, s.dir);
+                s.camera[0].look_at(s.pos, s.pos + s.dir * 100f32, s.up);
+                s.color = Color::from(*color);
+                s.cutoff = *cutoff;
+                s.cutoff_outer = *cutoff_outer;
+                s.shadow.apply_proxy(shadow);
+                s.attenuation = attenuation.clone();
+                s.intensity = *intensity;
+            }
+            (light, proxy) => *light = LightDesc::from(proxy).build(),
+        }
+    }
+}
+
+impl From<&LightProxy> for LightDesc {
+    fn from(proxy: &LightProxy) -> Self {
+        let shadow = |s: &ShadowConfigProxy| ShadowDesc {
+            cast_shadow: s.cast_shadow,
+            size: s.size,
+            bias_factor: s.bias_factor,
+            pcf: s.pcf,
+        };
+        match proxy {
+            LightProxy::Direct {
+                color,
+                position,
+                direction,
+                up,
+                shadow: s,
+                attenuation,
+                intensity,
+            } => LightDesc::Direct {
+                color: *color,
+                position: *position,
+                direction: *direction,
+                up: *up,
+                shadow: shadow(s),
+                attenuation: attenuation.clone(),
+                intensity: *intensity,
+            },
+            LightProxy::Point {
+                color,
+                position,
+                shadow: s,
+                attenuation,
+                intensity,
+            } => LightDesc::Point {
+                color: *color,
+                position: *position,
+                shadow: shadow(s),
+                attenuation: attenuation.clone(),
+                intensity: *intensity,
+            },
+            LightProxy::Spot {
+                color,
+                position,
+                direction,
+                cutoff,
+                cutoff_outer,
+                shadow: s,
+                attenuation,
+                intensity,
+            } => LightDesc::Spot {
+                color: *color,
+                position: *position,
+                direction: *direction,
+                cutoff: *cutoff,
+                cutoff_outer: *cutoff_outer,
+                shadow: shadow(s),
+                attenuation: attenuation.clone(),
+                intensity: *intensity,
+            },
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SceneLightsDesc {
+    pub ambient: [f32; 4],
+    pub direct: Option<LightDesc>,
+    pub extra: Vec<LightDesc>,
+}
+
+pub struct SceneLightsSerializer;
+
+impl SceneResourceSerializer for SceneLightsSerializer {
+    fn name(&self) -> &str {
+        "phong_lights"
+    }
+
+    fn save(&self, scene: &Scene) -> anyhow::Result<Option<Value>> {
+        let lights = match scene.get_resource::<SceneLights>() {
+            Some(v) => v,
+            None => return Ok(None),
+        };
+        let ambient = lights.base_uniform().lock().unwrap().ambient;
+        let desc = SceneLightsDesc {
+            ambient: ambient.into(),
+            direct: lights.direct_light().map(|v| LightDesc::from(v.as_ref())),
+            extra: lights
+                .extra_lights()
+                .iter()
+                .map(|v| LightDesc::from(v.as_ref()))
+                .collect(),
+        };
+        Ok(Some(to_value(&desc)?))
+    }
+
+    fn load(&self, scene: &Scene, value: Value) -> anyhow::Result<()> {
+        let desc: SceneLightsDesc = from_value(value)?;
+        if scene.get_resource::<SceneLights>().is_none() {
+            scene.attach(Arc::new(SceneLights::default()));
+        }
+        let lights = scene.get_resource::<SceneLights>().unwrap();
+        lights.set_ambient(Color::from(desc.ambient));
+        if let Some(direct) = &desc.direct {
+            direct.add_to(&lights);
+        }
+        for light in &desc.extra {
+            light.add_to(&lights);
+        }
+        Ok(())
+    }
+}
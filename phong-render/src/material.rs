@@ -1,5 +1,15 @@
-use core::{
-    backends::wgpu_backend::uniform_alignment, context::ResourceRef, material::{bind::{BindingResourceMap, BindingResourceProvider, ShaderBindingResource}, input::{InputResource, InputResourceIterItem}, MaterialFace}, render::pso::BindGroupType, types::{Color, Vec3f, Vec4f}, util::any_as_u8_slice
+use gcore::{
+    backends::wgpu_backend::uniform_alignment,
+    context::ResourceRef,
+    material::{
+        bind::{BindingResourceMap, BindingResourceProvider, ShaderBindingResource},
+        input::{InputResource, InputResourceBuilder, InputResourceIterItem},
+        MaterialBuilder, MaterialFace,
+    },
+    render::pso::BindGroupType,
+    scene::serialize::{BindingValue, LoadedResources, MaterialDesc, MaterialFaceSerializer},
+    types::{Color, Vec3f, Vec4f},
+    util::any_as_u8_slice,
 };
 use std::{hash::Hasher, io::Write, panic::panic_any};
 
@@ -40,7 +50,20 @@ impl MaterialFace for PhongMaterialFace {
     fn variants(&self) -> &tshader::VariantFlags {
         &self.variants_base
     } 
- 
+
+    fn params(&self) -> &[&str] {
+        &[
+            "diffuse_color",
+            "specular_color",
+            "emissive_color",
+            "emissive_strength",
+            "shininess",
+        ]
+    }
+
+    fn set_param(&self, key: &str, value: ShaderBindingResource) -> bool {
+        self.params().contains(&key) && self.resource.update(key, value)
+    }
 }
 
 impl BindingResourceProvider for PhongMaterialFace {
@@ -51,6 +74,14 @@ impl BindingResourceProvider for PhongMaterialFace {
     fn bind_group(&self) -> BindGroupType {
         self.resource.bind_group()
     }
+
+    fn uses_resource(&self, id: u64) -> bool {
+        self.resource.uses_resource(id)
+    }
+
+    fn version(&self) -> u64 {
+        self.resource.version()
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -270,3 +301,119 @@ impl Default for PhongMaterialFaceBuilder {
         Self::new()
     }
 }
+
+// the sampler is shared by all loaded materials
+pub struct PhongMaterialFaceSerializer {
+    sampler: ResourceRef,
+}
+
+impl PhongMaterialFaceSerializer {
+    pub fn new(sampler: ResourceRef) -> Self {
+        Self { sampler }
+    }
+}
+
+fn load_color_input(
+    desc: &MaterialDesc,
+    resources: &LoadedResources,
+    prefix: &str,
+    key: &str,
+    texture_key: &str,
+) -> anyhow::Result<InputResource<Color>> {
+    let has = |flag: &str| {
+        desc.variants
+            .iter()
+            .any(|v| *v == format!("{}_{}", prefix, flag))
+    };
+    let mut input = InputResourceBuilder::new();
+    if has("CONSTANT") {
+        match desc.params.get(key) {
+            Some(BindingValue::Float4(c)) => input.add_constant(Color::from(*c)),
+            _ => anyhow::bail!("material {} missing {}", desc.name, key),
+        }
+    }
+    if has("VERTEX") {
+        input.add_pre_vertex();
+    }
+    if has("TEXTURE") {
+        match desc.resource(texture_key, resources) {
+            Some(t) => input.add_texture(t),
+            None => anyhow::bail!("material {} missing {}", desc.name, texture_key),
+        }
+    }
+    Ok(input.build())
+}
+
+impl MaterialFaceSerializer for PhongMaterialFaceSerializer {
+    fn name(&self) -> &str {
+        "phong"
+    }
+
+    fn keys(&self) -> &[&str] {
+        &[
+            "diffuse_color",
+            "diffuse_texture",
+            "specular_color",
+            "specular_texture",
+            "normal_texture",
+            "emissive_color",
+            "emissive_texture",
+            "emissive_strength",
+            "shininess",
+        ]
+    }
+
+    fn load(
+        &self,
+        desc: &MaterialDesc,
+        resources: &LoadedResources,
+        builder: MaterialBuilder,
+    ) -> anyhow::Result<MaterialBuilder> {
+        let mut face = PhongMaterialFaceBuilder::new()
+            .diffuse(load_color_input(
+                desc,
+                resources,
+                "DIFFUSE",
+                "diffuse_color",
+                "diffuse_texture",
+            )?)
+            .specular(load_color_input(
+                desc,
+                resources,
+                "SPECULAR",
+                "specular_color",
+                "specular_texture",
+            )?)
+            .emissive(load_color_input(
+                desc,
+                resources,
+                "EMISSIVE",
+                "emissive_color",
+                "emissive_texture",
+            )?)
+            .sampler(self.sampler.clone());
+
+        let has = |flag: &str| desc.variants.iter().any(|v| v == flag);
+        if has("NORMAL_VERTEX") || has("NORMAL_TEXTURE") {
+            let mut normal = InputResourceBuilder::new();
+            if has("NORMAL_VERTEX") {
+                normal.add_pre_vertex();
+            }
+            if has("NORMAL_TEXTURE") {
+                match desc.resource("normal_texture", resources) {
+                    Some(t) => normal.add_texture(t),
+                    None => anyhow::bail!("material {} missing normal_texture", desc.name),
+                }
+            }
+            face = face.normal(normal.build());
+        }
+        if let Some(BindingValue::Float(v)) = desc.params.get("emissive_strength") {
+            face = face.emissive_strength(*v);
+        }
+        if let Some(BindingValue::Float(v)) = desc.params.get("shininess") {
+            face = face.shininess(*v);
+        }
+
+        Ok(builder.face(face.build()))
+    }
+}
@@ -1,4 +1,4 @@
-use core::{
+use gcore::{
     backends::wgpu_backend::{ClearValue, ResourceOps},
     graph::rdg::{
         pass::{DepthRenderTargetDescriptor, PreferAttachment, RenderTargetDescriptor},
@@ -8,9 +8,11 @@ use core::{
         collection::ShaderBindGroupCollection,
         collector::MeshBufferCollector,
         material::{take_rs, MaterialRendererFactory, RenderMaterialPsoBuilder},
-        pso::{PipelineStateObject, RenderDescriptorObject},
+        oit::{oit_variants, OitTarget},
+        pso::{ColorTargetBuilder, PipelineStateObject, RenderDescriptorObject},
         tech::ShaderTechCollection,
     },
+    scene::Layer,
     types::Vec3u,
     wgpu,
 };
@@ -43,13 +45,13 @@ pub struct PhongMaterialSharedData {
 
 fn copy_vertex_data(
     shared: &mut PhongMaterialSharedData,
-    context: core::graph::rdg::pass::RenderPassContext<'_>,
+    context: gcore::graph::rdg::pass::RenderPassContext<'_>,
     device: &wgpu::Device,
 ) -> Option<()> {
     shared.mesh_buffer_collector.recall();
 
     let rs = take_rs::<PhongMaterialFace>(&context)?;
-    let c = rs.scene.get_container();
+    let c = rs.scene.read_container();
 
     for layer in &rs.list {
         for indirect in &layer.material {
@@ -71,7 +73,7 @@ impl PhongMaterialRendererFactory {
         &self,
         t: Arc<Light>,
         shared: Arc<Mutex<PhongMaterialSharedData>>,
-        g: &mut core::graph::rdg::RenderGraphBuilder,
+        g: &mut gcore::graph::rdg::RenderGraphBuilder,
     ) -> Option<u32> {
         let config = t.shadow_config();
         if !config.cast_shadow {
@@ -110,9 +112,9 @@ impl MaterialRendererFactory for PhongMaterialRendererFactory {
     fn setup(
         &self,
         materials_map: &RenderMaterialPsoBuilder,
-        gpu: &core::backends::wgpu_backend::WGPUResource,
-        g: &mut core::graph::rdg::RenderGraphBuilder,
-        setup_resource: &core::render::material::SetupResource,
+        gpu: &gcore::backends::wgpu_backend::WGPUResource,
+        g: &mut gcore::graph::rdg::RenderGraphBuilder,
+        setup_resource: &gcore::render::material::SetupResource,
     ) {
         let shadow_sampler = Arc::new(gpu.device().create_sampler(&wgpu::SamplerDescriptor {
             label: Some("shadow_sampler"),
@@ -208,38 +210,81 @@ impl MaterialRendererFactory for PhongMaterialRendererFactory {
 
         let shared = Arc::new(Mutex::new(shared));
 
-        setup_resource
-            .shader_tech_collection
-            .setup(
-                gpu.device(),
-                "phong",
-                &VariantFlags::default(),
-                0,
-                |pass_name| {
-                    let mut rdo = RenderDescriptorObject::new();
-                    let depth_format = wgpu::TextureFormat::Depth32Float;
-
-                    if pass_name == "phong-forward-base" {
-                        rdo = rdo.set_depth(depth_format, |depth: &mut _| {
-                            depth.depth_compare = wgpu::CompareFunction::Less;
-                            depth.depth_write_enabled = true;
-                        });
-                    } else {
-                        // add
-                        rdo = rdo.set_depth(depth_format, |depth: &mut _| {
-                            depth.depth_compare = wgpu::CompareFunction::Equal;
-                            depth.depth_write_enabled = false;
-                        });
-                    }
-                    rdo
-                },
-            )
-            .unwrap();
+        // pipelines of a layer follow its depth and blend settings
+        for layer in materials_map.map.keys() {
+            let settings = setup_resource
+                .scene
+                .find_layer(*layer)
+                .unwrap_or_else(|| Layer::new(*layer, "invalid"));
+            setup_resource
+                .shader_tech_collection
+                .setup(
+                    gpu.device(),
+                    "phong",
+                    &VariantFlags::default(),
+                    *layer as u64,
+                    |pass_name| {
+                        let mut rdo = RenderDescriptorObject::new();
+                        let depth_format = wgpu::TextureFormat::Depth32Float;
+
+                        if pass_name == "phong-forward-base" {
+                            if let Some(blend) = &settings.blend {
+                                rdo = rdo.add_target(
+                                    ColorTargetBuilder::new(gpu.surface_format())
+                                        .set_blender(*blend)
+                                        .build(),
+                                );
+                            }
+                            rdo = rdo.set_depth(depth_format, |depth: &mut _| {
+                                depth.depth_compare = settings.depth_compare(setup_resource.depth);
+                                depth.depth_write_enabled = settings.writes_depth(false);
+                            });
+                        } else {
+                            // add, lights the pixels the base pass wrote
+                            rdo = rdo.set_depth(depth_format, |depth: &mut _| {
+                                depth.depth_compare = if settings.writes_depth(false) {
+                                    wgpu::CompareFunction::Equal
+                                } else {
+                                    settings.depth_compare(setup_resource.depth)
+                                };
+                                depth.depth_write_enabled = false;
+                            });
+                        }
+                        rdo
+                    },
+                )
+                .unwrap();
+
+            if setup_resource.oit_target(*layer).is_some() {
+                setup_resource
+                    .shader_tech_collection
+                    .setup(
+                        gpu.device(),
+                        "phong",
+                        &oit_variants(&VariantFlags::default(), setup_resource.depth),
+                        *layer as u64,
+                        |_| {
+                            let rdo = OitTarget::add_targets(RenderDescriptorObject::new());
+                            rdo.set_depth(wgpu::TextureFormat::Depth32Float, |depth: &mut _| {
+                                depth.depth_compare = settings.depth_compare(setup_resource.depth);
+                                depth.depth_write_enabled = false;
+                            })
+                        },
+                    )
+                    .unwrap();
+            }
+        }
 
         for (layer, _) in &materials_map.map {
-            let mut base_pass =
-                RenderPassBuilder::new(format!("phong forward base pass layer {}", layer));
-            base_pass.default_color_depth_render_target();
+            let oit = setup_resource.oit_target(*layer);
+            let mut base_pass = RenderPassBuilder::new(format!(
+                "phong forward base pass view {} layer {}",
+                setup_resource.view, layer
+            ));
+            match oit {
+                Some(oit) => base_pass.render_target(oit.render_target()),
+                None => base_pass.render_target(setup_resource.target.render_target()),
+            }
 
             let mut shadow_map_id = None;
             if has_direct_light {
@@ -264,15 +309,21 @@ impl MaterialRendererFactory for PhongMaterialRendererFactory {
                 has_direct_light,
                 shadow_map_id,
                 layer: *layer,
+                view: setup_resource.view,
                 lights: lights.clone(),
             })));
             g.add_render_pass(base_pass);
             shadow_map_id = None;
 
+            // only the base lights are accumulated for transparent objects
+            if oit.is_some() {
+                continue;
+            }
+
             for (index, light) in lights.extra_lights().iter().enumerate() {
                 let mut add_pass = RenderPassBuilder::new(format!(
-                    "phong forward add pass {} layer {}",
-                    index, layer
+                    "phong forward add pass {} view {} layer {}",
+                    index, setup_resource.view, layer
                 ));
                 let res = self.add_shadow_pass_for_light(light.clone(), shared.clone(), g);
                 if let Some(res) = res {
@@ -281,7 +332,7 @@ impl MaterialRendererFactory for PhongMaterialRendererFactory {
                 }
 
                 // add pass
-                add_pass.default_color_depth_render_target();
+                add_pass.render_target(setup_resource.target.render_target());
 
                 add_pass.async_execute(Arc::new(Mutex::new(base::PhongMaterialAddRenderer {
                     shared: shared.clone(),
@@ -291,6 +342,7 @@ impl MaterialRendererFactory for PhongMaterialRendererFactory {
                     shadow_map_id,
                     has_shadow_pass: light.shadow_config().cast_shadow,
                     layer: *layer,
+                    view: setup_resource.view,
                     lights: lights.clone(),
                 })));
 
@@ -298,6 +350,16 @@ impl MaterialRendererFactory for PhongMaterialRendererFactory {
             }
         }
     }
+
+    // phong pipelines don't depend on the material
+    fn setup_materials(
+        &self,
+        _materials_map: &RenderMaterialPsoBuilder,
+        _gpu: &gcore::backends::wgpu_backend::WGPUResource,
+        _setup_resource: &gcore::render::material::SetupResource,
+    ) -> bool {
+        true
+    }
 }
 
 // struct PhongMaterialBufferInstantiation {
@@ -309,9 +371,9 @@ impl MaterialRendererFactory for PhongMaterialRendererFactory {
 // impl MaterialBufferInstantiation for PhongMaterialBufferInstantiation {
 //     fn create_pipeline(
 //         &self,
-//         material: &core::material::Material,
+//         material: &gcore::material::Material,
 //         global_layout: &wgpu::BindGroupLayout,
-//         gpu: &core::backends::wgpu_backend::WGPUResource,
+//         gpu: &gcore::backends::wgpu_backend::WGPUResource,
 //     ) -> PipelinePassResource {
 //         let mut variants = material.face_by::<PhongMaterialFace>().variants.clone();
 //         let variants_add = material.face_by::<PhongMaterialFace>().variants_add.clone();
@@ -394,7 +456,7 @@ impl MaterialRendererFactory for PhongMaterialRendererFactory {
 
 //     fn create_bind_group(
 //         &self,
-//         material: &core::material::Material,
+//         material: &gcore::material::Material,
 //         buffers: &[wgpu::Buffer],
 //         pipeline: &PipelinePassResource,
 //         device: &wgpu::Device,
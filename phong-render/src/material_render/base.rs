@@ -1,6 +1,11 @@
-use core::{
-    backends::wgpu_backend::WGPUResource, graph::rdg::pass::RenderPassExecutor,
-    render::{material::take_rs, pso::BindGroupType}, scene::LayerId, types::Mat4x4f, util::any_as_u8_slice_array, wgpu,
+use gcore::{
+    backends::wgpu_backend::WGPUResource,
+    graph::rdg::pass::RenderPassExecutor,
+    render::{material::take_rs, pso::BindGroupType, view::ViewId},
+    scene::{LayerId, Transform},
+    types::Mat4x4f,
+    util::any_as_u8_slice_array,
+    wgpu,
 };
 use std::{
     io::Write,
@@ -13,22 +18,15 @@ use crate::{light::{SceneLights, TLight}, material::PhongMaterialFace};
 
 use super::{copy_vertex_data, PhongMaterialSharedData};
 
-fn get_object_constant(to_world: &Mat4x4f) -> Vec<u8> {
+fn get_object_constant(to_world: &Transform) -> Vec<u8> {
     let mut constant = vec![];
-    let _ = constant.write_all(any_as_u8_slice_array(to_world.as_slice()));
-    let to_world3 = to_world.fixed_view::<3, 3>(0, 0);
-
-    if let Some(inv) = to_world3.try_inverse() {
-        let p = inv.transpose();
-        let p = Mat4x4f::new(
-            p.m11, p.m12, p.m13, 0f32, p.m21, p.m22, p.m23, 0f32, p.m31, p.m32, p.m33, 0f32, 0f32,
-            0f32, 0f32, 0f32,
-        );
-        let _ = constant.write_all(any_as_u8_slice_array(p.as_slice()));
-    } else {
-        log::warn!("inverse object fail");
-        let _ = constant.write_all(any_as_u8_slice_array(Mat4x4f::identity().as_slice()));
-    }
+    let _ = constant.write_all(any_as_u8_slice_array(to_world.mat().as_slice()));
+    let p = to_world.normal_mat();
+    let p = Mat4x4f::new(
+        p.m11, p.m12, p.m13, 0f32, p.m21, p.m22, p.m23, 0f32, p.m31, p.m32, p.m33, 0f32, 0f32,
+        0f32, 0f32, 0f32,
+    );
+    let _ = constant.write_all(any_as_u8_slice_array(p.as_slice()));
     constant
 }
 
@@ -40,6 +38,7 @@ pub struct PhongMaterialBaseRenderer {
     pub shadow_map_sampler: Arc<wgpu::Sampler>,
     pub shadow_map_id: Option<u32>,
     pub layer: LayerId,
+    pub view: ViewId,
     pub lights: Arc<SceneLights>,
 }
 
@@ -47,9 +46,11 @@ impl RenderPassExecutor for PhongMaterialBaseRenderer {
     #[profiling::function]
     fn prepare<'b>(
         &'b mut self,
-        context: core::graph::rdg::pass::RenderPassContext<'b>,
-        engine: &mut core::graph::rdg::backend::GraphCopyEngine,
+        context: gcore::graph::rdg::pass::RenderPassContext<'b>,
+        engine: &mut gcore::graph::rdg::backend::GraphCopyEngine,
     ) -> Option<()> {
+        // nothing of the layer left after culling
+        take_rs::<PhongMaterialFace>(&context)?.layer(self.view, self.layer)?;
         let mut shared = self.shared.lock().unwrap();
         if !self.has_shadow_pass {
             copy_vertex_data(&mut shared, context, engine.device())?;
@@ -67,13 +68,16 @@ impl RenderPassExecutor for PhongMaterialBaseRenderer {
     #[profiling::function]
     fn queue<'b>(
         &'b mut self,
-        context: core::graph::rdg::pass::RenderPassContext<'b>,
+        context: gcore::graph::rdg::pass::RenderPassContext<'b>,
         device: &wgpu::Device,
     ) {
         let rs = take_rs::<PhongMaterialFace>(&context).unwrap();
         let mut shared = self.shared.lock().unwrap();
 
-        let layer = rs.layer(self.layer);
+        let layer = match rs.layer(self.view, self.layer) {
+            Some(v) => v,
+            None => return,
+        };
 
         for indirect in &layer.material {
             let material = indirect.material.as_ref();
@@ -95,7 +99,9 @@ impl RenderPassExecutor for PhongMaterialBaseRenderer {
 
                 for indirect in &layer.material {
                     let material = indirect.material.as_ref();
-                    let pso = shared.material_shader_collector.get(material, 0);
+                    let pso = shared
+                        .material_shader_collector
+                        .get(material, self.layer as u64);
                     layout = Some(pso.get_bind_group_layout(3));
                     break;
                 }
@@ -124,21 +130,26 @@ impl RenderPassExecutor for PhongMaterialBaseRenderer {
     #[profiling::function]
     fn render<'b>(
         &'b mut self,
-        context: core::graph::rdg::pass::RenderPassContext<'b>,
-        engine: &mut core::graph::rdg::backend::GraphRenderEngine,
+        context: gcore::graph::rdg::pass::RenderPassContext<'b>,
+        engine: &mut gcore::graph::rdg::backend::GraphRenderEngine,
     ) {
         let rs = take_rs::<PhongMaterialFace>(&context).unwrap();
-        let c = rs.scene.get_container();
+        let c = rs.scene.read_container();
         let shared = self.shared.lock().unwrap();
-        let layer = rs.layer(self.layer);
+        let layer = match rs.layer(self.view, self.layer) {
+            Some(v) => v,
+            None => return,
+        };
 
-        let mut pass = engine.begin(layer.layer);
+        let mut pass = layer.begin(engine);
 
         for indirect in &layer.material {
             let objects = layer.objects(indirect);
             let material = indirect.material.as_ref();
 
-            let pso = shared.material_shader_collector.get(material, 0);
+            let pso = shared
+                .material_shader_collector
+                .get(material, self.layer as u64);
 
             pass.set_pipeline(pso.render());
             pass.set_bind_group(0, &layer.main_camera.bind_group, &[]); // camera bind group
@@ -158,9 +169,9 @@ impl RenderPassExecutor for PhongMaterialBaseRenderer {
                 let obj = obj.o();
                 pass.push_debug_group(&format!("object {}", obj.name()));
                 let mesh = obj.geometry().mesh();
-                let object_uniform = obj.geometry().transform();
+                let object_uniform = obj.transform();
 
-                let constant = get_object_constant(object_uniform.mat());
+                let constant = get_object_constant(object_uniform);
                 pass.set_push_constants(
                     wgpu::ShaderStages::VERTEX | wgpu::ShaderStages::FRAGMENT,
                     0,
@@ -175,7 +186,7 @@ impl RenderPassExecutor for PhongMaterialBaseRenderer {
         }
     }
 
-    fn cleanup<'b>(&'b mut self, _context: core::graph::rdg::pass::RenderPassContext<'b>) {}
+    fn cleanup<'b>(&'b mut self, _context: gcore::graph::rdg::pass::RenderPassContext<'b>) {}
 }
 
 pub struct PhongMaterialAddRenderer {
@@ -186,6 +197,7 @@ pub struct PhongMaterialAddRenderer {
     pub shadow_map_id: Option<u32>,
     pub has_shadow_pass: bool,
     pub layer: LayerId,
+    pub view: ViewId,
     pub lights: Arc<SceneLights>,
 }
 
@@ -193,9 +205,10 @@ impl RenderPassExecutor for PhongMaterialAddRenderer {
     #[profiling::function]
     fn prepare<'b>(
         &'b mut self,
-        _context: core::graph::rdg::pass::RenderPassContext<'b>,
-        engine: &mut core::graph::rdg::backend::GraphCopyEngine,
+        context: gcore::graph::rdg::pass::RenderPassContext<'b>,
+        engine: &mut gcore::graph::rdg::backend::GraphCopyEngine,
     ) -> Option<()> {
+        take_rs::<PhongMaterialFace>(&context)?.layer(self.view, self.layer)?;
         let shared = self.shared.lock().unwrap();
         copy_light_uniform(
             &shared.scene_shared.lights_uniforms[self.index + 1],
@@ -208,12 +221,15 @@ impl RenderPassExecutor for PhongMaterialAddRenderer {
     #[profiling::function]
     fn queue<'b>(
         &'b mut self,
-        context: core::graph::rdg::pass::RenderPassContext<'b>,
+        context: gcore::graph::rdg::pass::RenderPassContext<'b>,
         device: &wgpu::Device,
     ) {
         let rs = take_rs::<PhongMaterialFace>(&context).unwrap();
         let shared = self.shared.lock().unwrap();
-        let layer = rs.layer(self.layer);
+        let layer = match rs.layer(self.view, self.layer) {
+            Some(v) => v,
+            None => return,
+        };
 
         if let Some(res_id) = &self.shadow_map_id {
             let mut layout = None;
@@ -249,16 +265,19 @@ impl RenderPassExecutor for PhongMaterialAddRenderer {
     #[profiling::function]
     fn render<'b>(
         &'b mut self,
-        context: core::graph::rdg::pass::RenderPassContext<'b>,
-        engine: &mut core::graph::rdg::backend::GraphRenderEngine,
+        context: gcore::graph::rdg::pass::RenderPassContext<'b>,
+        engine: &mut gcore::graph::rdg::backend::GraphRenderEngine,
     ) {
         let rs = take_rs::<PhongMaterialFace>(&context).unwrap();
-        let c = rs.scene.get_container();
+        let c = rs.scene.read_container();
         let shared = self.shared.lock().unwrap();
 
-        let layer = rs.layer(self.layer);
+        let layer = match rs.layer(self.view, self.layer) {
+            Some(v) => v,
+            None => return,
+        };
 
-        let mut pass = engine.begin(layer.layer);
+        let mut pass = layer.begin(engine);
 
         for indirect in &layer.material {
             let objects = layer.objects(indirect);
@@ -290,8 +309,8 @@ impl RenderPassExecutor for PhongMaterialAddRenderer {
                 let obj = obj.o();
                 pass.push_debug_group(&format!("object {}", obj.name()));
                 let mesh = obj.geometry().mesh();
-                let object_uniform = obj.geometry().transform();
-                let constant = get_object_constant(object_uniform.mat());
+                let object_uniform = obj.transform();
+                let constant = get_object_constant(object_uniform);
                 pass.set_push_constants(
                     wgpu::ShaderStages::VERTEX | wgpu::ShaderStages::FRAGMENT,
                     0,
@@ -306,5 +325,5 @@ impl RenderPassExecutor for PhongMaterialAddRenderer {
         }
     }
 
-    fn cleanup<'b>(&'b mut self, _context: core::graph::rdg::pass::RenderPassContext<'b>) {}
+    fn cleanup<'b>(&'b mut self, _context: gcore::graph::rdg::pass::RenderPassContext<'b>) {}
 }
@@ -1,4 +1,4 @@
-use core::{
+use gcore::{
     graph::rdg::pass::RenderPassExecutor,
     render::{material::take_rs, pso::PipelineStateObject, tech::ShaderTechCollection},
     types::Vec2f,
@@ -24,8 +24,8 @@ impl RenderPassExecutor for ShadowRenderer {
     #[profiling::function]
     fn prepare<'b>(
         &'b mut self,
-        context: core::graph::rdg::pass::RenderPassContext<'b>,
-        engine: &mut core::graph::rdg::backend::GraphCopyEngine,
+        context: gcore::graph::rdg::pass::RenderPassContext<'b>,
+        engine: &mut gcore::graph::rdg::backend::GraphCopyEngine,
     ) -> Option<()> {
         let mut shared = self.shared.lock().unwrap();
         copy_vertex_data(&mut shared, context, engine.device())?;
@@ -43,7 +43,7 @@ impl RenderPassExecutor for ShadowRenderer {
     #[profiling::function]
     fn queue<'b>(
         &'b mut self,
-        _context: core::graph::rdg::pass::RenderPassContext<'b>,
+        _context: gcore::graph::rdg::pass::RenderPassContext<'b>,
         device: &wgpu::Device,
     ) {
         if self.cameras_bind_group.is_empty() {
@@ -56,7 +56,9 @@ impl RenderPassExecutor for ShadowRenderer {
             });
             let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
                 label: None,
-                layout: self.pso.get_bind_group_layout(core::render::pso::BindGroupType::Camera),
+                layout: self
+                    .pso
+                    .get_bind_group_layout(gcore::render::pso::BindGroupType::Camera),
                 entries: &[wgpu::BindGroupEntry {
                     binding: 0,
                     resource: wgpu::BindingResource::Buffer(wgpu::BufferBinding {
@@ -73,11 +75,11 @@ impl RenderPassExecutor for ShadowRenderer {
     #[profiling::function]
     fn render<'b>(
         &'b mut self,
-        context: core::graph::rdg::pass::RenderPassContext<'b>,
-        engine: &mut core::graph::rdg::backend::GraphRenderEngine,
+        context: gcore::graph::rdg::pass::RenderPassContext<'b>,
+        engine: &mut gcore::graph::rdg::backend::GraphRenderEngine,
     ) {
         let rs = take_rs::<PhongMaterialFace>(&context).unwrap();
-        let c = rs.scene.get_container();
+        let c = rs.scene.read_container();
         let shared = self.shared.lock().unwrap();
 
         for layer in &rs.list {
@@ -99,7 +101,7 @@ impl RenderPassExecutor for ShadowRenderer {
                     let obj = obj.o();
                     pass.push_debug_group(&format!("object {}", obj.name()));
                     let mesh = obj.geometry().mesh();
-                    let object_uniform = obj.geometry().transform();
+                    let object_uniform = obj.transform();
                     pass.set_push_constants(
                         wgpu::ShaderStages::VERTEX,
                         0,
@@ -117,5 +119,5 @@ impl RenderPassExecutor for ShadowRenderer {
     }
 
     #[profiling::function]
-    fn cleanup<'b>(&'b mut self, _context: core::graph::rdg::pass::RenderPassContext<'b>) {}
+    fn cleanup<'b>(&'b mut self, _context: gcore::graph::rdg::pass::RenderPassContext<'b>) {}
 }
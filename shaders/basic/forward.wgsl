@@ -1,5 +1,8 @@
 ///#include "camera.wgsl"
 ///#include "object.wgsl"
+///#if OIT
+///#include "oit.wgsl"
+///#endif
 ///#if TEXTURE
 ///#decl VERTEX_TEX
 ///#endif
@@ -95,7 +98,11 @@ fn vs_main(input: VertexInput) -> VertexOutput{
 }
 
 @fragment
+///#if OIT
+fn fs_main(input: VertexOutput) -> OitOutput {
+///#else
 fn fs_main(input: VertexOutput) -> @location(0) vec4<f32>{
+///#endif
     var color = input.color;
 ///#if TEXTURE
     color *= textureSample(texture_color, sampler_tex, input.uv);
@@ -106,5 +113,9 @@ fn fs_main(input: VertexOutput) -> @location(0) vec4<f32>{
         discard;
     }
 ///#endif
+///#if OIT
+    return oit_output(color, input.position.z);
+///#else
     return color;
+///#endif
 }
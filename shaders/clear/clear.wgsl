@@ -0,0 +1,27 @@
+struct VertexOutput {
+    @loc_struct(VertexOutput) @builtin(position) position: vec4<f32>,
+};
+
+struct Clear {
+    color: vec4<f32>,
+}
+
+@loc_global(ObjectUniform) var<push_constant> clear: Clear;
+
+// a triangle covering the whole viewport on the far plane
+@vertex
+fn vs_main(@builtin(vertex_index) index: u32) -> VertexOutput {
+    var output: VertexOutput;
+    let uv = vec2<f32>(f32((index << 1u) & 2u), f32(index & 2u));
+///#if REVERSE_Z
+    output.position = vec4<f32>(uv * 2.0 - 1.0, 0.0, 1.0);
+///#else
+    output.position = vec4<f32>(uv * 2.0 - 1.0, 1.0, 1.0);
+///#endif
+    return output;
+}
+
+@fragment
+fn fs_main(input: VertexOutput) -> @location(0) vec4<f32> {
+    return clear.color;
+}
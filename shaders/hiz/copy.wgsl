@@ -0,0 +1,30 @@
+///#if MSAA
+@loc_global(MaterialUniform) var depth_texture: texture_depth_multisampled_2d;
+///#else
+@loc_global(MaterialUniform) var depth_texture: texture_depth_2d;
+///#endif
+@loc_global(MaterialUniform) var output_texture: texture_storage_2d<r32uint, write>;
+
+// depth is stored as bits, the order of positive floats is kept
+@compute @workgroup_size(8, 8)
+fn cs_main(@builtin(global_invocation_id) id: vec3<u32>) {
+    let size = textureDimensions(output_texture);
+    if (id.x >= size.x || id.y >= size.y) {
+        return;
+    }
+    let pos = vec2<i32>(id.xy);
+///#if MSAA
+    // farthest depth of the samples (min with reversed depth)
+    var depth = textureLoad(depth_texture, pos, 0);
+    for (var i = 1; i < i32(textureNumSamples(depth_texture)); i++) {
+///#if REVERSE_Z
+        depth = min(depth, textureLoad(depth_texture, pos, i));
+///#else
+        depth = max(depth, textureLoad(depth_texture, pos, i));
+///#endif
+    }
+///#else
+    let depth = textureLoad(depth_texture, pos, 0);
+///#endif
+    textureStore(output_texture, pos, vec4<u32>(bitcast<u32>(depth), 0u, 0u, 0u));
+}
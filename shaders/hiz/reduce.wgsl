@@ -0,0 +1,34 @@
+@loc_global(MaterialUniform) var input_texture: texture_2d<u32>;
+@loc_global(MaterialUniform) var output_texture: texture_storage_2d<r32uint, write>;
+
+// farthest depth of the 2x2 texels (min with reversed depth), the last texel of an odd
+// size takes the extra row or column
+@compute @workgroup_size(8, 8)
+fn cs_main(@builtin(global_invocation_id) id: vec3<u32>) {
+    let size = textureDimensions(output_texture);
+    if (id.x >= size.x || id.y >= size.y) {
+        return;
+    }
+    let input_size = vec2<i32>(textureDimensions(input_texture));
+    let pos = vec2<i32>(id.xy);
+    let base = pos * 2;
+    let odd = (input_size & vec2<i32>(1)) == vec2<i32>(1);
+    let last = pos == vec2<i32>(size) - 1;
+    let end = min(select(base + 1, base + 2, odd & last), input_size - 1);
+
+///#if REVERSE_Z
+    var depth = 0xffffffffu;
+///#else
+    var depth = 0u;
+///#endif
+    for (var y = base.y; y <= end.y; y++) {
+        for (var x = base.x; x <= end.x; x++) {
+///#if REVERSE_Z
+            depth = min(depth, textureLoad(input_texture, vec2<i32>(x, y), 0).r);
+///#else
+            depth = max(depth, textureLoad(input_texture, vec2<i32>(x, y), 0).r);
+///#endif
+        }
+    }
+    textureStore(output_texture, pos, vec4<u32>(depth, 0u, 0u, 0u));
+}
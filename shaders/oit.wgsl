@@ -0,0 +1,25 @@
+// weighted blended order independent transparency (McGuire and Bavoil 2013)
+struct OitOutput {
+    @location(0) accum: vec4<f32>,
+    @location(1) reveal: f32,
+}
+
+// weight by coverage and depth, near fragments dominate the average
+fn oit_weight(depth: f32, alpha: f32) -> f32 {
+    let a = min(1.0, alpha * 10.0) + 0.01;
+///#if REVERSE_Z
+    // the near plane is 1
+    let d = depth;
+///#else
+    let d = 1.0 - depth * 0.9;
+///#endif
+    return clamp(a * a * a * 1e8 * d * d * d, 1e-2, 3e3);
+}
+
+fn oit_output(color: vec4<f32>, depth: f32) -> OitOutput {
+    var output: OitOutput;
+    let w = oit_weight(depth, color.a);
+    output.accum = vec4<f32>(color.rgb * color.a, color.a) * w;
+    output.reveal = color.a;
+    return output;
+}
@@ -0,0 +1,27 @@
+struct VertexOutput {
+    @loc_struct(VertexOutput) @builtin(position) position: vec4<f32>,
+};
+
+@loc_global(MaterialUniform) var accum_texture: texture_2d<f32>;
+@loc_global(MaterialUniform) var reveal_texture: texture_2d<f32>;
+
+@vertex
+fn vs_main(@builtin(vertex_index) index: u32) -> VertexOutput {
+    var output: VertexOutput;
+    let uv = vec2<f32>(f32((index << 1u) & 2u), f32(index & 2u));
+    output.position = vec4<f32>(uv * 2.0 - 1.0, 1.0, 1.0);
+    return output;
+}
+
+// blended over the target with src alpha, the alpha is the total coverage
+@fragment
+fn fs_main(input: VertexOutput) -> @location(0) vec4<f32> {
+    let pos = vec2<i32>(input.position.xy);
+    let reveal = textureLoad(reveal_texture, pos, 0).r;
+    if (reveal >= 1.0) {
+        discard;
+    }
+    let accum = textureLoad(accum_texture, pos, 0);
+    let color = accum.rgb / clamp(accum.a, 1e-4, 5e4);
+    return vec4<f32>(color, 1.0 - reveal);
+}
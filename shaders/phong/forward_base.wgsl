@@ -2,6 +2,9 @@
 ///#include "camera.wgsl"
 ///#include "./light.wgsl"
 ///#include "./material.wgsl"
+///#if OIT
+///#include "oit.wgsl"
+///#endif
 ///#if DIFFUSE_TEXTURE || NORMAL_TEXTURE || SPECULAR_TEXTURE || EMISSIVE_TEXTURE 
 ///#decl UV
 ///#endif
@@ -36,6 +39,9 @@ struct VertexOutput {
 ///#endif
 ///#if DIFFUSE_VERTEX
     @loc_struct(VertexOutput) diffuse: vec3<f32>,
+///#if OIT
+    @loc_struct(VertexOutput) alpha: f32,
+///#endif
 ///#endif
 ///#if EMISSIVE_VERTEX
     @loc_struct(VertexOutput) emissive: vec3<f32>,
@@ -109,6 +115,9 @@ fn vs_main(input: VertexInput) -> VertexOutput{
 ///#endif
 ///#if DIFFUSE_VERTEX
     output.diffuse = input.diffuse.xyz;
+///#if OIT
+    output.alpha = input.diffuse.w;
+///#endif
 ///#endif
 ///#if EMISSIVE_VERTEX
     output.emissive = input.emissive.xyz;
@@ -127,7 +136,11 @@ fn vs_main(input: VertexInput) -> VertexOutput{
 }
 
 @fragment
+///#if OIT
+fn fs_main(input: VertexOutput) -> OitOutput {
+///#else
 fn fs_main(input: VertexOutput) -> @location(0) vec4<f32>{
+///#endif
     var obj: ObjectInfo;
 ///#if DIFFUSE_CONSTANT
 
@@ -206,5 +219,17 @@ fn fs_main(input: VertexOutput) -> @location(0) vec4<f32>{
     color = color + ambient_color;
 ///#endif
 
+///#if OIT
+    // opacity of the diffuse inputs
+    var alpha = 1.0;
+///#if DIFFUSE_VERTEX
+    alpha *= input.alpha;
+///#endif
+///#if DIFFUSE_TEXTURE
+    alpha *= textureSample(texture_diffuse, sampler_tex, input.uv).a;
+///#endif
+    return oit_output(vec4<f32>(color.xyz, alpha), input.position.z);
+///#else
     return vec4<f32>(color.xyz, 1.0);
+///#endif
 }
@@ -41,6 +41,14 @@ impl VariantFlags {
     pub fn hash_key(&self) -> u64 {
         self.hash_key
     }
+
+    pub fn flags(&self) -> &[String] {
+        &self.view
+    }
+
+    pub fn has_flag(&self, flag: &str) -> bool {
+        self.view.iter().any(|v| v == flag)
+    }
 }
 
 #[derive(Debug, Default)]
@@ -3,7 +3,8 @@ use std::collections::{HashMap, HashSet};
 use std::sync::Arc;
 
 use crate::tech::{
-    Builtin, GlobalVariable, InputBinding, Pass, PushConstant, Shader, UniformSampler, UniformStruct, UniformSubVariable, UniformTexture
+    Builtin, GlobalVariable, InputBinding, Pass, PushConstant, Shader, UniformSampler,
+    UniformStorageBuffer, UniformStorageTexture, UniformStruct, UniformSubVariable, UniformTexture,
 };
 use crate::VariantFlags;
 use tshader_builder::compiler::ShaderTechCompiler;
@@ -52,9 +53,13 @@ impl ShaderPassReflection {
     ) -> anyhow::Result<(wgpu::TextureSampleType, bool)> {
         let mut multisampled = false;
         let sample_type = match class {
-            naga::ImageClass::Sampled { kind: _, multi } => {
+            naga::ImageClass::Sampled { kind, multi } => {
                 multisampled = multi;
-                wgpu::TextureSampleType::Float { filterable: true }
+                match kind {
+                    naga::ScalarKind::Uint => wgpu::TextureSampleType::Uint,
+                    naga::ScalarKind::Sint => wgpu::TextureSampleType::Sint,
+                    _ => wgpu::TextureSampleType::Float { filterable: true },
+                }
             }
             naga::ImageClass::Depth { multi } => {
                 multisampled = multi;
@@ -70,6 +75,29 @@ impl ShaderPassReflection {
         Ok((sample_type, multisampled))
     }
 
+    fn storage_to_format(
+        format: naga::StorageFormat,
+        access: naga::StorageAccess,
+    ) -> anyhow::Result<(wgpu::TextureFormat, wgpu::StorageTextureAccess)> {
+        let format = match format {
+            naga::StorageFormat::R32Uint => wgpu::TextureFormat::R32Uint,
+            naga::StorageFormat::R32Sint => wgpu::TextureFormat::R32Sint,
+            naga::StorageFormat::R32Float => wgpu::TextureFormat::R32Float,
+            naga::StorageFormat::Rgba8Unorm => wgpu::TextureFormat::Rgba8Unorm,
+            naga::StorageFormat::Rgba16Float => wgpu::TextureFormat::Rgba16Float,
+            naga::StorageFormat::Rgba32Float => wgpu::TextureFormat::Rgba32Float,
+            _ => anyhow::bail!("storage format not supported {:?}", format),
+        };
+        let load = access.contains(naga::StorageAccess::LOAD);
+        let store = access.contains(naga::StorageAccess::STORE);
+        let access = match (load, store) {
+            (true, true) => wgpu::StorageTextureAccess::ReadWrite,
+            (true, false) => wgpu::StorageTextureAccess::ReadOnly,
+            _ => wgpu::StorageTextureAccess::WriteOnly,
+        };
+        Ok((format, access))
+    }
+
     fn to_vertex_format2(ty: &naga::ScalarKind, width: u8) -> anyhow::Result<wgpu::VertexFormat> {
         let res = match ty {
             naga::ScalarKind::Sint => {
@@ -361,6 +389,27 @@ impl ShaderPassReflection {
                                 .ok_or(anyhow::anyhow!("no binding in global image/sampler"))?;
                             let ty = module.types.get_handle(var.ty)?;
                             match ty.inner {
+                                naga::TypeInner::Image {
+                                    dim,
+                                    arrayed,
+                                    class: naga::ImageClass::Storage { format, access },
+                                } => {
+                                    let dimension = Self::image_to_dimension(dim, arrayed)?;
+                                    let (format, access) = Self::storage_to_format(format, access)?;
+
+                                    res.insert(
+                                        name.clone(),
+                                        GlobalVariable::StorageTexture(UniformStorageTexture {
+                                            group: bind.group,
+                                            binding: bind.binding,
+                                            group_name,
+                                            dimension,
+                                            format,
+                                            access,
+                                        }),
+                                    );
+                                    found.insert(name);
+                                }
                                 naga::TypeInner::Image {
                                     dim,
                                     arrayed,
@@ -398,6 +447,26 @@ impl ShaderPassReflection {
                                 _ => (),
                             }
                         }
+                        naga::AddressSpace::Storage { access } => {
+                            let ty = module.types.get_handle(var.ty)?;
+                            let size = ty.inner.size(module.to_ctx());
+                            let bind = var
+                                .binding
+                                .as_ref()
+                                .ok_or(anyhow::anyhow!("no binding in storage buffer"))?;
+
+                            res.insert(
+                                name.clone(),
+                                GlobalVariable::StorageBuffer(UniformStorageBuffer {
+                                    group: bind.group,
+                                    binding: bind.binding,
+                                    group_name,
+                                    size,
+                                    read_only: !access.contains(naga::StorageAccess::STORE),
+                                }),
+                            );
+                            found.insert(name);
+                        }
                         naga::AddressSpace::PushConstant => {
                             let ty = module.types.get_handle(var.ty)?;
                             let size = ty.inner.size(module.to_ctx());
@@ -580,6 +649,7 @@ impl ShaderPassReflection {
                         device_module: shader_module.clone(),
                         global_reference: reference,
                     });
+                    pass.workgroup_size = entry.workgroup_size;
                     Self::take_input(&entry.function, &module, &mut pass.local_variables)?;
                 }
             };
@@ -46,6 +46,26 @@ pub struct UniformTexture {
     pub sample_type: wgpu::TextureSampleType,
 }
 
+#[derive(Debug, Clone)]
+pub struct UniformStorageTexture {
+    pub group: u32,
+    pub binding: u32,
+    pub group_name: String,
+    pub dimension: wgpu::TextureViewDimension,
+    pub format: wgpu::TextureFormat,
+    pub access: wgpu::StorageTextureAccess,
+}
+
+#[derive(Debug, Clone)]
+pub struct UniformStorageBuffer {
+    pub group: u32,
+    pub binding: u32,
+    pub group_name: String,
+    // size of the fixed part, or of one element of a runtime sized array
+    pub size: u32,
+    pub read_only: bool,
+}
+
 #[derive(Debug, Clone)]
 pub struct UniformSampler {
     pub group: u32,
@@ -64,6 +84,8 @@ pub struct PushConstant {
 pub enum GlobalVariable {
     Struct(UniformStruct),
     Texture(UniformTexture),
+    StorageTexture(UniformStorageTexture),
+    StorageBuffer(UniformStorageBuffer),
     Sampler(UniformSampler),
     PushConstant(PushConstant),
 }
@@ -86,6 +108,8 @@ pub struct Pass {
     pub vs: Option<Shader>,
     pub fs: Option<Shader>,
     pub cs: Option<Shader>,
+    // of the compute entry
+    pub workgroup_size: [u32; 3],
 
     // pub constants: Vec<wgpu::PushConstantRange>,
     // pub input_layout: BTreeMap<ResourcePosition, (bool, wgpu::VertexFormat)>,
@@ -1,16 +1,22 @@
 use core::backends::wgpu_backend::WGPUResource;
 use core::context::{RContext, ResourceRef};
 use core::event::EventProcessor;
+use core::graph::rdg::inspect::RenderGraphInspector;
+use core::graph::rdg::pool::ResourcePool;
 use core::graph::rdg::resource::RT_COLOR_RESOURCE_ID;
 use core::graph::rdg::{RenderGraph, RenderGraphBuilder};
 use core::render::{HardwareRenderer, ModuleRenderer, RenderParameter, SetupConfig};
-use core::scene::controller::CameraControllerFactory;
-use core::scene::Scene;
+use core::scene::controller::{
+    editor::EditorControllerFactory, fly::FlyControllerFactory, CameraControllerFactory,
+    ControllerFactory,
+};
+use core::scene::{Scene, SceneEventReceiver};
 use core::types::{Color, Size, Vec4f};
 use std::any::Any;
 use std::cell::RefCell;
+use std::collections::HashMap;
 use std::rc::Rc;
-use std::sync::{Arc, Mutex};
+use std::sync::{Arc, Mutex, Weak};
 
 pub mod looper;
 pub mod statistics;
@@ -30,8 +36,12 @@ pub type CEvent = core::event::Event;
 
 struct HardwareRenderPlugin {
     rdg: Option<RenderGraph>,
+    // transient resources of the dropped graph, for the next one
+    pool: Option<ResourcePool>,
     renderer: HardwareRenderer,
     cc_factory: Option<Arc<CameraControllerFactory>>,
+    // the scene subscribed to, the container may hold a new one
+    scene_events: Option<(Weak<Scene>, SceneEventReceiver)>,
     first_update: bool,
 }
 
@@ -39,11 +49,21 @@ impl HardwareRenderPlugin {
     pub fn new() -> Self {
         Self {
             rdg: None,
+            pool: None,
             renderer: HardwareRenderer::new(),
             first_update: true,
             cc_factory: None,
+            scene_events: None,
         }
     }
+    fn reset_graph(&mut self, context: &RContext) {
+        if let Some(mut rdg) = self.rdg.take() {
+            // the next graph captures again
+            rdg.set_capture(None, context);
+            self.pool = Some(rdg.take_resource_pool());
+        }
+    }
+
     fn update(&mut self, _delta: f32) {
         if self.first_update {
             self.first_update = false;
@@ -57,13 +77,25 @@ impl HardwareRenderPlugin {
 
         let clear_color = container.get::<ClearColor>().unwrap().get();
         let scene = container.get::<Scene>().unwrap();
-        if scene.material_change() {
-            self.rdg = None;
-            log::info!("rebuild scene because material changed");
+        scene.update_graph();
+        let subscribed = self
+            .scene_events
+            .as_ref()
+            .is_some_and(|(s, _)| std::ptr::eq(s.as_ptr(), Arc::as_ptr(&scene)));
+        if !subscribed {
+            if self.scene_events.is_some() {
+                log::info!("rebuild scene because the scene is replaced");
+                self.reset_graph(gpu.context());
+            }
+            self.scene_events = Some((Arc::downgrade(&scene), scene.subscribe()));
+        }
+        let events: Vec<_> = self.scene_events.as_ref().unwrap().1.try_iter().collect();
+        if self.rdg.is_some() && self.renderer.update(gpu.clone(), &scene, &events) {
+            self.reset_graph(gpu.context());
         }
         if scene.has_rebuild_flag() {
             log::info!("rebuild scene because flag enabled");
-            self.rdg = None;
+            self.reset_graph(gpu.context());
         }
 
         scene.ui_camera_ref().make_orthographic(
@@ -77,28 +109,40 @@ impl HardwareRenderPlugin {
             let mut graph_builder = RenderGraphBuilder::new("main graph");
             let aa = msaa.get().0;
             graph_builder.set_msaa(aa);
+            graph_builder.set_depth_convention(scene.depth_convention());
+            if let Some(pool) = self.pool.take() {
+                graph_builder.set_resource_pool(pool);
+            }
 
-            let config = SetupConfig { msaa: aa };
+            let real_size = Size::new(
+                texture.texture_ref().width(),
+                texture.texture_ref().height(),
+            );
+            let config = SetupConfig {
+                msaa: aa,
+                size: real_size,
+            };
 
             self.renderer
                 .setup(&mut graph_builder, gpu.clone(), &scene, &config);
             log::info!("rebuild render graph with view size {:?}", view_size);
             // container.get::<RContext>().unwrap();
-            let real_size = Size::new(
-                texture.texture_ref().width(),
-                texture.texture_ref().height(),
-            );
 
             graph_builder.set_present_target(real_size, gpu.surface_format(), Some(clear_color));
-            self.rdg = Some(graph_builder.compile());
+            let rdg = graph_builder.compile();
+            if let Some(inspector) = container.get::<RenderGraphInspector>() {
+                inspector.set_graph(&rdg);
+            }
+            self.rdg = Some(rdg);
             scene.clear_rebuild_flag();
         }
 
-        self.rdg
-            .as_mut()
-            .unwrap()
-            .registry()
-            .import(RT_COLOR_RESOURCE_ID, texture);
+        let inspector = container.get::<RenderGraphInspector>();
+        let rdg = self.rdg.as_mut().unwrap();
+        rdg.registry().import(RT_COLOR_RESOURCE_ID, texture);
+        if let Some(inspector) = &inspector {
+            rdg.set_capture(inspector.capture(), gpu.context());
+        }
 
         let p = RenderParameter {
             gpu: gpu.clone(),
@@ -107,10 +151,26 @@ impl HardwareRenderPlugin {
         };
 
         self.renderer.render(p);
+
+        if let Some(inspector) = &inspector {
+            inspector.update(self.rdg.as_ref().unwrap());
+        }
     }
 }
 
 impl Plugin for HardwareRenderPlugin {
+    fn load_factory(&self) -> app::plugin::CoreFactoryList {
+        let fly = FlyControllerFactory::default();
+        let editor = EditorControllerFactory::default();
+        let mut camera_controllers: HashMap<String, Box<dyn ControllerFactory>> = HashMap::new();
+        camera_controllers.insert(fly.name(), Box::new(fly));
+        camera_controllers.insert(editor.name(), Box::new(editor));
+        app::plugin::CoreFactoryList {
+            camera_controllers,
+            ..Default::default()
+        }
+    }
+
     fn install_factory(
         &mut self,
         container: &Container,
@@ -146,7 +206,8 @@ impl AppEventProcessor for HardwareRenderPlugin {
                     self.render(texture.clone(), context.container);
                 }
                 core::event::Event::Resized { logical, physical } => {
-                    self.rdg = None;
+                    let gpu = context.container.get::<WGPUResource>().unwrap();
+                    self.reset_graph(gpu.context());
                     context.container.get::<WindowSize>().unwrap().set((
                         Size::new(physical.x, physical.y),
                         Size::new(logical.x, logical.y),
@@ -162,7 +223,8 @@ impl AppEventProcessor for HardwareRenderPlugin {
 pub struct HardwareRenderPluginFactory;
 
 impl PluginFactory for HardwareRenderPluginFactory {
-    fn create(&self, _container: &Container) -> Box<dyn Plugin> {
+    fn create(&self, container: &Container) -> Box<dyn Plugin> {
+        container.register(RenderGraphInspector::new());
         Box::new(HardwareRenderPlugin::new())
     }
 